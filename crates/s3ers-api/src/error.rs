@@ -0,0 +1,493 @@
+//! This module contains types for all kinds of errors that can occur when
+//! converting between http requests / responses and s3ers's representation
+//! of S3 API requests / responses.
+
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    error::Error as StdError,
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use http::{
+    header::{DATE, RETRY_AFTER},
+    StatusCode,
+};
+use quick_xml::{events::Event, Reader};
+
+use crate::header::HttpDate;
+
+/// An error when converting one of s3ers's endpoint-specific request or
+/// response types to the corresponding http type.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum IntoHttpError {
+    /// A header value couldn't be built.
+    Header(http::header::InvalidHeaderValue),
+
+    /// The request or response couldn't be built.
+    Http(http::Error),
+
+    /// The request couldn't be signed.
+    Signature(s3ers_signature::Error),
+}
+
+impl fmt::Display for IntoHttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Header(err) => {
+                write!(f, "header serialization failed: {}", err)
+            }
+            Self::Http(err) => write!(f, "http error: {}", err),
+            Self::Signature(err) => write!(f, "signing failed: {}", err),
+        }
+    }
+}
+
+impl StdError for IntoHttpError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Header(err) => Some(err),
+            Self::Http(err) => Some(err),
+            Self::Signature(err) => Some(err),
+        }
+    }
+}
+
+impl From<http::header::InvalidHeaderValue> for IntoHttpError {
+    fn from(err: http::header::InvalidHeaderValue) -> Self {
+        Self::Header(err)
+    }
+}
+
+impl From<http::Error> for IntoHttpError {
+    fn from(err: http::Error) -> Self {
+        Self::Http(err)
+    }
+}
+
+impl From<s3ers_signature::Error> for IntoHttpError {
+    fn from(err: s3ers_signature::Error) -> Self {
+        Self::Signature(err)
+    }
+}
+
+/// An error when converting an http request to one of s3ers's
+/// endpoint-specific request types, on a server.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FromHttpRequestError {
+    /// The path, query, headers or body of the request couldn't be
+    /// deserialized.
+    Deserialization(DeserializationError),
+}
+
+impl fmt::Display for FromHttpRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deserialization(err) => {
+                write!(f, "deserialization failed: {}", err)
+            }
+        }
+    }
+}
+
+impl StdError for FromHttpRequestError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Deserialization(err) => Some(err),
+        }
+    }
+}
+
+impl From<DeserializationError> for FromHttpRequestError {
+    fn from(err: DeserializationError) -> Self {
+        Self::Deserialization(err)
+    }
+}
+
+/// An error when converting an http response to one of s3ers's
+/// endpoint-specific response types.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FromHttpResponseError {
+    /// The server returned a successful response that couldn't be
+    /// deserialized.
+    Deserialization(DeserializationError),
+
+    /// The server returned an error.
+    Server(Box<S3Error>),
+}
+
+impl fmt::Display for FromHttpResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deserialization(err) => {
+                write!(f, "deserialization failed: {}", err)
+            }
+            Self::Server(err) => {
+                write!(f, "the server returned an error: {}", err)
+            }
+        }
+    }
+}
+
+impl StdError for FromHttpResponseError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Deserialization(err) => Some(err),
+            Self::Server(err) => Some(err),
+        }
+    }
+}
+
+impl From<DeserializationError> for FromHttpResponseError {
+    fn from(err: DeserializationError) -> Self {
+        Self::Deserialization(err)
+    }
+}
+
+impl From<S3Error> for FromHttpResponseError {
+    fn from(err: S3Error) -> Self {
+        Self::Server(Box::new(err))
+    }
+}
+
+/// An error when deserializing the body or headers of a response.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DeserializationError {
+    /// The XML body is invalid.
+    Xml(quick_xml::Error),
+
+    /// A header value isn't valid visible ASCII.
+    Header(http::header::ToStrError),
+
+    /// A required header or element is missing.
+    Missing(String),
+
+    /// A header or element has an invalid value.
+    Invalid(String),
+}
+
+impl fmt::Display for DeserializationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Xml(err) => write!(f, "invalid XML: {}", err),
+            Self::Header(err) => write!(f, "invalid header value: {}", err),
+            Self::Missing(name) => write!(f, "missing `{}`", name),
+            Self::Invalid(name) => write!(f, "invalid value of `{}`", name),
+        }
+    }
+}
+
+impl StdError for DeserializationError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Xml(err) => Some(err),
+            Self::Header(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<quick_xml::Error> for DeserializationError {
+    fn from(err: quick_xml::Error) -> Self {
+        Self::Xml(err)
+    }
+}
+
+impl From<http::header::ToStrError> for DeserializationError {
+    fn from(err: http::header::ToStrError) -> Self {
+        Self::Header(err)
+    }
+}
+
+/// An error returned by an S3 server.
+///
+/// Most errors come with an XML body describing them. Responses without a
+/// body, like the ones to `HEAD` requests, only have a status code, which is
+/// then turned into the code, like `NotFound` for `404 Not Found`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct S3Error {
+    /// The status code of the response.
+    pub status: StatusCode,
+
+    /// The error code, like `NoSuchKey` or `AccessDenied`.
+    pub code: String,
+
+    /// A human-readable description of the error.
+    pub message: Option<String>,
+
+    /// The bucket or object the error is about.
+    pub resource: Option<String>,
+
+    /// The ID of the request, from the body or the `x-amz-request-id`
+    /// header.
+    pub request_id: Option<String>,
+
+    /// The extended request ID, from the body or the `x-amz-id-2` header.
+    pub host_id: Option<String>,
+
+    /// The time of the server, from the `Date` header.
+    pub date: Option<HttpDate>,
+
+    /// How long the server asks to wait before retrying, from the
+    /// `Retry-After` header.
+    pub retry_after: Option<Duration>,
+
+    /// The region of the bucket of the request, from the
+    /// `x-amz-bucket-region` header, when it is in another region than the
+    /// one the request was sent to.
+    pub bucket_region: Option<String>,
+
+    /// The other elements of the error body, which depend on the code, like
+    /// `Region` for `AuthorizationHeaderMalformed` or `StringToSign` for
+    /// `SignatureDoesNotMatch`.
+    pub details: BTreeMap<String, String>,
+}
+
+impl S3Error {
+    /// Creates an error with the given status and code, like
+    /// `404 Not Found` and `NoSuchKey`, for a server to return.
+    pub fn new(status: StatusCode, code: impl Into<String>) -> Self {
+        Self {
+            status,
+            code: code.into(),
+            message: None,
+            resource: None,
+            request_id: None,
+            host_id: None,
+            date: None,
+            retry_after: None,
+            bucket_region: None,
+            details: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the human-readable description of the error.
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Sets the bucket or object the error is about.
+    pub fn with_resource(mut self, resource: impl Into<String>) -> Self {
+        self.resource = Some(resource.into());
+        self
+    }
+
+    /// Reads the error returned by the server in a response.
+    ///
+    /// This never fails: bodies that aren't a valid S3 error, like the HTML
+    /// pages of proxies, are ignored.
+    pub fn from_http_response<T: AsRef<[u8]>>(
+        response: &http::Response<T>,
+    ) -> Self {
+        let status = response.status();
+        let headers = response.headers();
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(ToOwned::to_owned)
+        };
+
+        let mut error = Self {
+            status,
+            code: status
+                .canonical_reason()
+                .unwrap_or("Unknown")
+                .replace(' ', ""),
+            message: None,
+            resource: None,
+            request_id: header("x-amz-request-id"),
+            host_id: header("x-amz-id-2"),
+            date: headers
+                .get(DATE)
+                .and_then(|value| HttpDate::try_from(value).ok()),
+            retry_after: None,
+            bucket_region: header("x-amz-bucket-region"),
+            details: BTreeMap::new(),
+        };
+        error.retry_after = headers
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| retry_after(value, error.date));
+
+        let elements = match error_elements(response.body().as_ref()) {
+            Ok(elements) => elements,
+            Err(_) => return error,
+        };
+        for (name, value) in elements {
+            match name.as_str() {
+                "Code" => error.code = value,
+                "Message" => error.message = Some(value),
+                "Resource" => error.resource = Some(value),
+                "RequestId" => error.request_id = Some(value),
+                "HostId" => error.host_id = Some(value),
+                _ => {
+                    error.details.insert(name, value);
+                }
+            }
+        }
+        error
+    }
+}
+
+impl fmt::Display for S3Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.code, self.status)?;
+        if let Some(message) = &self.message {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for S3Error {}
+
+/// Unboxes errors, for the functions of servers returning boxed errors to
+/// keep their results small.
+impl From<Box<S3Error>> for S3Error {
+    fn from(err: Box<S3Error>) -> Self {
+        *err
+    }
+}
+
+/// Parses a `Retry-After` value, either a number of seconds or a date,
+/// relative to the date of the response if it has one.
+fn retry_after(value: &str, date: Option<HttpDate>) -> Option<Duration> {
+    if let Ok(secs) = value.trim().parse() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let retry_at = HttpDate::parse(value).ok()?.to_system_time();
+    let now = date.map_or_else(SystemTime::now, HttpDate::to_system_time);
+    Some(retry_at.duration_since(now).unwrap_or_default())
+}
+
+/// Collects the leaf elements of an `Error` XML document.
+fn error_elements(
+    xml: &[u8],
+) -> Result<Vec<(String, String)>, DeserializationError> {
+    let mut reader = Reader::from_reader(xml);
+    reader.trim_text(true);
+
+    let mut elements = Vec::new();
+    let mut current = None;
+    let mut in_error = false;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) => {
+                let name = start.local_name();
+                if !in_error {
+                    if name.as_ref() != b"Error" {
+                        return Err(DeserializationError::Missing(
+                            "Error".to_owned(),
+                        ));
+                    }
+                    in_error = true;
+                } else {
+                    current = Some(
+                        String::from_utf8_lossy(name.as_ref()).into_owned(),
+                    );
+                }
+            }
+            Event::Text(text) => {
+                if let Some(name) = current.take() {
+                    elements.push((name, text.unescape()?.into_owned()));
+                }
+            }
+            Event::End(_) => current = None,
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if in_error {
+        Ok(elements)
+    } else {
+        Err(DeserializationError::Missing("Error".to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::{Response, StatusCode};
+
+    use super::S3Error;
+
+    #[test]
+    fn parse_error_body() {
+        let response = Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .header("Date", "Fri, 24 May 2013 00:00:00 GMT")
+            .header("x-amz-request-id", "from-header")
+            .body(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<Error>
+  <Code>SignatureDoesNotMatch</Code>
+  <Message>The request signature we calculated does not match the signature you provided.</Message>
+  <StringToSign>AWS4-HMAC-SHA256&#10;20130524T000000Z</StringToSign>
+  <RequestId>4442587FB7D0A2F9</RequestId>
+</Error>"#,
+            )
+            .unwrap();
+
+        let error = S3Error::from_http_response(&response);
+        assert_eq!(error.status, StatusCode::FORBIDDEN);
+        assert_eq!(error.code, "SignatureDoesNotMatch");
+        assert!(error.message.unwrap().starts_with("The request signature"));
+        assert_eq!(error.request_id.as_deref(), Some("4442587FB7D0A2F9"));
+        assert_eq!(
+            error.details.get("StringToSign").map(String::as_str),
+            Some("AWS4-HMAC-SHA256\n20130524T000000Z")
+        );
+        assert_eq!(
+            error.date.unwrap().to_string(),
+            "Fri, 24 May 2013 00:00:00 GMT"
+        );
+    }
+
+    #[test]
+    fn error_without_body() {
+        let response = Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("x-amz-request-id", "0A49CE4060975EAC")
+            .body(Vec::new())
+            .unwrap();
+
+        let error = S3Error::from_http_response(&response);
+        assert_eq!(error.code, "NotFound");
+        assert_eq!(error.message, None);
+        assert_eq!(error.request_id.as_deref(), Some("0A49CE4060975EAC"));
+        assert_eq!(error.retry_after, None);
+    }
+
+    #[test]
+    fn retry_after() {
+        let response = |retry_after: &str| {
+            Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header("Date", "Fri, 24 May 2013 00:00:00 GMT")
+                .header("Retry-After", retry_after)
+                .body(Vec::new())
+                .unwrap()
+        };
+
+        let error = S3Error::from_http_response(&response("120"));
+        assert_eq!(error.retry_after, Some(Duration::from_secs(120)));
+
+        let error = S3Error::from_http_response(&response(
+            "Fri, 24 May 2013 00:00:30 GMT",
+        ));
+        assert_eq!(error.retry_after, Some(Duration::from_secs(30)));
+    }
+}
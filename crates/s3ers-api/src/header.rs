@@ -0,0 +1,678 @@
+//! Typed values for HTTP headers used by S3 endpoints.
+
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    error::Error as StdError,
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use http::header::{HeaderMap, HeaderName, HeaderValue, InvalidHeaderValue};
+use time::{
+    format_description::{well_known::Rfc3339, FormatItem},
+    macros::format_description,
+    Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset,
+};
+
+use crate::error::DeserializationError;
+
+const IMF_FIXDATE: &[FormatItem<'static>] = format_description!(
+    "[weekday repr:short], [day] [month repr:short] [year] \
+     [hour]:[minute]:[second] GMT"
+);
+
+const ISO_8601: &[FormatItem<'static>] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second].000Z");
+
+/// A point in time as carried by HTTP date headers such as `Date`,
+/// `Last-Modified` or `If-Modified-Since`.
+///
+/// Parsing accepts the three formats allowed by RFC 7231 (IMF-fixdate,
+/// RFC 850 and asctime) as well as the deviations S3-compatible servers are
+/// known to emit: numeric UTC offsets, `UTC` instead of `GMT`, missing
+/// weekdays and RFC 3339 timestamps. Formatting always produces IMF-fixdate.
+///
+/// HTTP dates have a one second resolution, so sub-second precision is
+/// dropped on construction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HttpDate(SystemTime);
+
+impl HttpDate {
+    /// Returns the current time as an `HttpDate`.
+    pub fn now() -> Self {
+        SystemTime::now().into()
+    }
+
+    /// Parses an HTTP date, accepting any of the formats listed in the type
+    /// documentation.
+    pub fn parse(s: &str) -> Result<Self, ParseHttpDateError> {
+        let s = s.trim();
+
+        let datetime = OffsetDateTime::parse(s, &Rfc3339)
+            .ok()
+            .or_else(|| parse_loose(s))
+            .ok_or(ParseHttpDateError(()))?;
+
+        let secs = datetime.unix_timestamp();
+        if secs < 0 {
+            return Err(ParseHttpDateError(()));
+        }
+
+        Ok(Self(UNIX_EPOCH + Duration::from_secs(secs as u64)))
+    }
+
+    /// Formats the date like the timestamps of XML documents, like
+    /// `2009-10-12T17:50:30.000Z`.
+    pub fn to_iso8601(&self) -> String {
+        // Dates after the epoch always format.
+        OffsetDateTime::from(self.0).format(ISO_8601).unwrap()
+    }
+
+    /// Returns the `SystemTime` this date represents.
+    pub fn to_system_time(self) -> SystemTime {
+        self.0
+    }
+}
+
+impl From<SystemTime> for HttpDate {
+    fn from(time: SystemTime) -> Self {
+        // Dates before the epoch can't be represented in headers anyway.
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+}
+
+impl From<HttpDate> for SystemTime {
+    fn from(date: HttpDate) -> Self {
+        date.0
+    }
+}
+
+impl FromStr for HttpDate {
+    type Err = ParseHttpDateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for HttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let datetime = OffsetDateTime::from(self.0);
+        let formatted = datetime.format(IMF_FIXDATE).map_err(|_| fmt::Error)?;
+        f.write_str(&formatted)
+    }
+}
+
+impl TryFrom<&HeaderValue> for HttpDate {
+    type Error = ParseHttpDateError;
+
+    fn try_from(value: &HeaderValue) -> Result<Self, Self::Error> {
+        value
+            .to_str()
+            .map_err(|_| ParseHttpDateError(()))
+            .and_then(Self::parse)
+    }
+}
+
+impl From<HttpDate> for HeaderValue {
+    fn from(date: HttpDate) -> Self {
+        // IMF-fixdate only contains visible ASCII characters.
+        HeaderValue::from_str(&date.to_string()).unwrap()
+    }
+}
+
+/// An error returned when a string isn't a valid HTTP date.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseHttpDateError(());
+
+impl fmt::Display for ParseHttpDateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid HTTP date")
+    }
+}
+
+impl StdError for ParseHttpDateError {}
+
+/// The value of an `Expires` header.
+///
+/// RFC 7234 asks recipients to treat invalid values, most commonly `0`, as a
+/// time in the past rather than rejecting the response. S3 stores whatever
+/// the uploader sent verbatim, so the raw value is kept when it can't be
+/// parsed as a date.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Expires {
+    /// A valid HTTP date.
+    Date(HttpDate),
+
+    /// A value that isn't a valid HTTP date.
+    Invalid(String),
+}
+
+impl Expires {
+    /// Parses an `Expires` header value. This never fails, see
+    /// [`Expires::Invalid`].
+    pub fn parse(s: &str) -> Self {
+        match HttpDate::parse(s) {
+            Ok(date) => Self::Date(date),
+            Err(_) => Self::Invalid(s.to_owned()),
+        }
+    }
+
+    /// Returns the expiry date, if the value is a valid date.
+    pub fn date(&self) -> Option<HttpDate> {
+        match self {
+            Self::Date(date) => Some(*date),
+            Self::Invalid(_) => None,
+        }
+    }
+
+    /// Whether the value denotes a time at or before `now`. Invalid values
+    /// are always considered expired.
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        match self {
+            Self::Date(date) => date.to_system_time() <= now,
+            Self::Invalid(_) => true,
+        }
+    }
+}
+
+impl From<HttpDate> for Expires {
+    fn from(date: HttpDate) -> Self {
+        Self::Date(date)
+    }
+}
+
+impl FromStr for Expires {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+
+impl fmt::Display for Expires {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Date(date) => date.fmt(f),
+            Self::Invalid(raw) => f.write_str(raw),
+        }
+    }
+}
+
+impl TryFrom<&Expires> for HeaderValue {
+    type Error = InvalidHeaderValue;
+
+    fn try_from(expires: &Expires) -> Result<Self, Self::Error> {
+        match expires {
+            Expires::Date(date) => Ok((*date).into()),
+            Expires::Invalid(raw) => HeaderValue::from_str(raw),
+        }
+    }
+}
+
+/// A key provided by the customer for the server to encrypt an object
+/// with, and to decrypt it when it is retrieved, known as SSE-C.
+///
+/// The server doesn't keep the key, only its MD5 digest to check the key of
+/// later requests, so losing the key loses the object. Requests carrying it
+/// must be sent over HTTPS.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SseCustomerKey([u8; 32]);
+
+impl SseCustomerKey {
+    /// The encryption algorithm of customer-provided keys.
+    pub const ALGORITHM: &'static str = "AES256";
+
+    /// Creates a customer-provided key from its 256 bits.
+    pub fn new(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    /// Returns the base64-encoded key.
+    pub fn key(&self) -> String {
+        STANDARD.encode(self.0)
+    }
+
+    /// Returns the base64-encoded MD5 digest of the key.
+    pub fn key_md5(&self) -> String {
+        s3ers_signature::md5::content_md5(&self.0)
+    }
+
+    /// Reads the key of a request received by a server, if it has one,
+    /// checking it against its MD5 digest.
+    pub fn from_headers(
+        headers: &HeaderMap,
+    ) -> Result<Option<Self>, DeserializationError> {
+        Self::from_headers_with_prefix(
+            headers,
+            "x-amz-server-side-encryption-customer-",
+        )
+    }
+
+    /// Reads the key of the source of a copy received by a server, if it
+    /// has one, like [`from_headers`](Self::from_headers).
+    pub fn from_copy_source_headers(
+        headers: &HeaderMap,
+    ) -> Result<Option<Self>, DeserializationError> {
+        Self::from_headers_with_prefix(
+            headers,
+            "x-amz-copy-source-server-side-encryption-customer-",
+        )
+    }
+
+    fn from_headers_with_prefix(
+        headers: &HeaderMap,
+        prefix: &str,
+    ) -> Result<Option<Self>, DeserializationError> {
+        let header = |name: &str| -> Result<_, DeserializationError> {
+            let name = format!("{}{}", prefix, name);
+            match headers.get(name.as_str()) {
+                Some(value) => {
+                    let value = value.to_str()?.to_owned();
+                    Ok(Some((name, value)))
+                }
+                None => Ok(None),
+            }
+        };
+        let (name, key) = match header("key")? {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+        let key = STANDARD
+            .decode(key)
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .map(Self)
+            .ok_or(DeserializationError::Invalid(name))?;
+        match header("algorithm")? {
+            Some((_, algorithm)) if algorithm == Self::ALGORITHM => {}
+            Some((name, _)) => return Err(DeserializationError::Invalid(name)),
+            None => {
+                return Err(DeserializationError::Missing(format!(
+                    "{}algorithm",
+                    prefix
+                )))
+            }
+        }
+        if let Some((name, key_md5)) = header("key-md5")? {
+            if key_md5 != key.key_md5() {
+                return Err(DeserializationError::Invalid(name));
+            }
+        }
+        Ok(Some(key))
+    }
+
+    /// Returns the headers of a request encrypting or decrypting an object
+    /// with the key.
+    pub fn headers(&self) -> [(HeaderName, HeaderValue); 3] {
+        self.headers_with_prefix("x-amz-server-side-encryption-customer-")
+    }
+
+    /// Returns the headers of a copy whose source is encrypted with the
+    /// key.
+    pub fn copy_source_headers(&self) -> [(HeaderName, HeaderValue); 3] {
+        self.headers_with_prefix(
+            "x-amz-copy-source-server-side-encryption-customer-",
+        )
+    }
+
+    fn headers_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> [(HeaderName, HeaderValue); 3] {
+        let header = |name: &str, value: String| {
+            // The names are lowercase and the values base64 or ASCII.
+            (
+                HeaderName::try_from(format!("{}{}", prefix, name)).unwrap(),
+                HeaderValue::try_from(value).unwrap(),
+            )
+        };
+        [
+            header("algorithm", Self::ALGORITHM.to_owned()),
+            header("key", self.key()),
+            header("key-md5", self.key_md5()),
+        ]
+    }
+}
+
+impl fmt::Debug for SseCustomerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseCustomerKey")
+            .field("key_md5", &self.key_md5())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The encryption of objects with keys managed by AWS KMS, known as
+/// SSE-KMS, on the endpoints writing objects.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SseKms {
+    /// The ID, ARN or alias of the KMS key, the AWS managed key of S3 if
+    /// there is none.
+    pub key_id: Option<String>,
+
+    /// The encryption context, additional data authenticated along with the
+    /// object.
+    pub context: BTreeMap<String, String>,
+
+    /// Whether an S3 bucket key is used, reducing the requests to KMS, the
+    /// setting of the bucket if there is none.
+    pub bucket_key_enabled: Option<bool>,
+}
+
+impl SseKms {
+    /// The value of the `x-amz-server-side-encryption` header of SSE-KMS.
+    pub const ALGORITHM: &'static str = "aws:kms";
+
+    /// Encrypts with the AWS managed key of S3.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the ID, ARN or alias of the KMS key.
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Adds a pair to the encryption context.
+    pub fn with_context(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.context.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets whether an S3 bucket key is used.
+    pub fn with_bucket_key_enabled(mut self, enabled: bool) -> Self {
+        self.bucket_key_enabled = Some(enabled);
+        self
+    }
+
+    /// Reads the SSE-KMS encryption of a request received by a server, if
+    /// it is encrypted with a KMS key.
+    pub fn from_headers(
+        headers: &HeaderMap,
+    ) -> Result<Option<Self>, DeserializationError> {
+        let header = |name: &str| -> Result<_, DeserializationError> {
+            headers
+                .get(name)
+                .map(|value| Ok(value.to_str()?.to_owned()))
+                .transpose()
+        };
+        match header("x-amz-server-side-encryption")? {
+            Some(algorithm) if algorithm == Self::ALGORITHM => {}
+            _ => return Ok(None),
+        }
+
+        let mut sse_kms = Self {
+            key_id: header("x-amz-server-side-encryption-aws-kms-key-id")?,
+            ..Self::default()
+        };
+        let name = "x-amz-server-side-encryption-context";
+        if let Some(context) = header(name)? {
+            let invalid = || DeserializationError::Invalid(name.to_owned());
+            let context = STANDARD.decode(context).map_err(|_| invalid())?;
+            let context: serde_json::Value =
+                serde_json::from_slice(&context).map_err(|_| invalid())?;
+            for (key, value) in context.as_object().ok_or_else(invalid)? {
+                let value = value.as_str().ok_or_else(invalid)?;
+                sse_kms.context.insert(key.clone(), value.to_owned());
+            }
+        }
+        let name = "x-amz-server-side-encryption-bucket-key-enabled";
+        if let Some(enabled) = header(name)? {
+            sse_kms.bucket_key_enabled = Some(
+                enabled
+                    .parse()
+                    .map_err(|_| DeserializationError::Invalid(name.into()))?,
+            );
+        }
+        Ok(Some(sse_kms))
+    }
+
+    /// Returns the headers of a request writing an object encrypted this
+    /// way, the encryption context being base64-encoded JSON.
+    pub fn headers(
+        &self,
+    ) -> Result<Vec<(HeaderName, HeaderValue)>, InvalidHeaderValue> {
+        let mut headers = vec![(
+            HeaderName::from_static("x-amz-server-side-encryption"),
+            HeaderValue::from_static(Self::ALGORITHM),
+        )];
+        if let Some(key_id) = &self.key_id {
+            headers.push((
+                HeaderName::from_static(
+                    "x-amz-server-side-encryption-aws-kms-key-id",
+                ),
+                HeaderValue::try_from(key_id)?,
+            ));
+        }
+        if !self.context.is_empty() {
+            // Maps of strings always serialize.
+            let context = serde_json::to_string(&self.context).unwrap();
+            headers.push((
+                HeaderName::from_static("x-amz-server-side-encryption-context"),
+                HeaderValue::try_from(STANDARD.encode(context))?,
+            ));
+        }
+        if let Some(enabled) = self.bucket_key_enabled {
+            headers.push((
+                HeaderName::from_static(
+                    "x-amz-server-side-encryption-bucket-key-enabled",
+                ),
+                HeaderValue::from_static(if enabled {
+                    "true"
+                } else {
+                    "false"
+                }),
+            ));
+        }
+        Ok(headers)
+    }
+}
+
+/// Parses the RFC 7231 formats and their common variations by classifying
+/// whitespace, comma and dash separated tokens instead of matching a fixed
+/// layout.
+fn parse_loose(s: &str) -> Option<OffsetDateTime> {
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+    let mut time = None;
+    let mut offset = UtcOffset::UTC;
+
+    // Dashes also separate the date parts of RFC 850 dates, so negative
+    // offsets have to be taken off before tokenizing.
+    let mut s = s;
+    if let Some((rest, zone)) = s.rsplit_once(' ') {
+        if let Some(zone) = zone.strip_prefix('-') {
+            offset = parse_offset(zone, -1)?;
+            s = rest;
+        }
+    }
+
+    let tokens = s
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '-')
+        .filter(|t| !t.is_empty());
+
+    for (i, token) in tokens.enumerate() {
+        // Offsets like `+05:30` contain colons too, so they go before times.
+        if let Some(zone) = token.strip_prefix('+') {
+            offset = parse_offset(zone, 1)?;
+        } else if token.contains(':') {
+            time = Some(parse_time(token)?);
+        } else if token.bytes().all(|b| b.is_ascii_digit()) {
+            let value: i32 = token.parse().ok()?;
+            if token.len() == 4 || day.is_some() {
+                year = Some(if token.len() <= 2 {
+                    // RFC 7231 §7.1.1.1: two digit years more than 50 years
+                    // in the future refer to the past century.
+                    if value < 70 {
+                        2000 + value
+                    } else {
+                        1900 + value
+                    }
+                } else {
+                    value
+                });
+            } else {
+                day = Some(u8::try_from(value).ok()?);
+            }
+        } else if let Some(m) = parse_month(token) {
+            month = Some(m);
+        } else {
+            // Weekday names are redundant, don't bother validating them.
+            let is_zone = matches!(token, "GMT" | "UTC" | "UT" | "Z");
+            let is_weekday =
+                i == 0 && token.chars().all(|c| c.is_ascii_alphabetic());
+            if !is_zone && !is_weekday {
+                return None;
+            }
+        }
+    }
+
+    let date = Date::from_calendar_date(year?, month?, day?).ok()?;
+    Some(PrimitiveDateTime::new(date, time?).assume_offset(offset))
+}
+
+fn parse_time(s: &str) -> Option<Time> {
+    // `u8::from_str` accepts a leading `+`, which times never have.
+    let mut parts = s.split(':').map(|p| {
+        match !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()) {
+            true => p.parse::<u8>().ok(),
+            false => None,
+        }
+    });
+    let hour = parts.next()??;
+    let minute = parts.next()??;
+    let second = parts.next().unwrap_or(Some(0))?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Time::from_hms(hour, minute, second).ok()
+}
+
+fn parse_month(s: &str) -> Option<Month> {
+    let month = match s.get(..3)?.to_ascii_lowercase().as_str() {
+        "jan" => Month::January,
+        "feb" => Month::February,
+        "mar" => Month::March,
+        "apr" => Month::April,
+        "may" => Month::May,
+        "jun" => Month::June,
+        "jul" => Month::July,
+        "aug" => Month::August,
+        "sep" => Month::September,
+        "oct" => Month::October,
+        "nov" => Month::November,
+        "dec" => Month::December,
+        _ => return None,
+    };
+    Some(month)
+}
+
+fn parse_offset(s: &str, sign: i8) -> Option<UtcOffset> {
+    let s = s.replace(':', "");
+    if s.len() != 4 || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i8 = s[..2].parse().ok()?;
+    let minutes: i8 = s[2..].parse().ok()?;
+    UtcOffset::from_hms(sign * hours, sign * minutes, 0).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::TryFrom,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use http::HeaderValue;
+
+    use super::{Expires, HttpDate};
+
+    // Sun, 06 Nov 1994 08:49:37 GMT
+    const SECS: u64 = 784_111_777;
+
+    fn date(secs: u64) -> HttpDate {
+        (UNIX_EPOCH + Duration::from_secs(secs)).into()
+    }
+
+    #[test]
+    fn parse_dates() {
+        let dates = [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "06 Nov 1994 08:49:37 GMT",
+            "1994-11-06T08:49:37Z",
+            "1994-11-06T08:49:37.000Z",
+        ];
+        for s in dates {
+            assert_eq!(HttpDate::parse(s), Ok(date(SECS)), "{}", s);
+        }
+        assert_eq!(date(SECS).to_string(), dates[0]);
+        assert_eq!(date(SECS).to_iso8601(), dates[6]);
+    }
+
+    #[test]
+    fn parse_offsets() {
+        let offsets = [
+            ("Sun, 06 Nov 1994 08:49:37 +0000", SECS),
+            ("Sun, 06 Nov 1994 08:49:37 +05:30", SECS - 19_800),
+            ("Sun, 06 Nov 1994 08:49:37 +0530", SECS - 19_800),
+            ("Sun, 06 Nov 1994 08:49:37 -0800", SECS + 28_800),
+            ("Sun, 06-Nov-94 08:49:37 -08:00", SECS + 28_800),
+        ];
+        for (s, secs) in offsets {
+            assert_eq!(HttpDate::parse(s), Ok(date(secs)), "{}", s);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_dates() {
+        let invalid = [
+            "",
+            "0",
+            "yesterday",
+            "Sun, 06 Nov 1994 GMT",
+            "Sun, 32 Nov 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 +08:49:37 GMT",
+            "Sun, 06 Nov 1994 08:49:37 +5",
+            "Sun, 06 Nov 1994 08:49:37 PST",
+            "Wed, 31 Dec 1969 23:59:59 GMT",
+        ];
+        for s in invalid {
+            assert!(HttpDate::parse(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn parse_expires() {
+        let expires: Expires = "Sun, 06 Nov 1994 08:49:37 GMT".parse().unwrap();
+        assert_eq!(expires, Expires::Date(date(SECS)));
+        assert_eq!(expires.date(), Some(date(SECS)));
+        assert!(!expires.is_expired_at(date(SECS - 1).into()));
+        assert!(expires.is_expired_at(date(SECS).into()));
+
+        let invalid = Expires::parse("0");
+        assert_eq!(invalid, Expires::Invalid("0".to_owned()));
+        assert_eq!(invalid.date(), None);
+        assert!(invalid.is_expired_at(UNIX_EPOCH));
+        assert_eq!(invalid.to_string(), "0");
+        assert_eq!(HeaderValue::try_from(&invalid).unwrap(), "0");
+    }
+}
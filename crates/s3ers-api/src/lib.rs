@@ -0,0 +1,128 @@
+//! Core types and traits used to describe S3 API endpoints.
+
+#![warn(missing_docs)]
+
+use std::time::Duration;
+
+use bytes::BufMut;
+use http::Uri;
+use s3ers_signature::{Credentials, SigningParams};
+
+pub mod error;
+pub mod header;
+mod metadata;
+pub mod uri;
+pub mod xml;
+
+pub use metadata::{AuthScheme, Metadata, Scope};
+
+use error::{FromHttpRequestError, FromHttpResponseError, IntoHttpError};
+
+/// A request type for an S3 API endpoint, used for sending requests.
+pub trait OutgoingRequest: Sized {
+    /// Metadata about the endpoint.
+    const METADATA: Metadata;
+
+    /// The response type returned when the request is successful.
+    type IncomingResponse: IncomingResponse;
+
+    /// Tries to convert this request into an `http::Request`.
+    ///
+    /// `base_url` is the URL of the S3 service, like
+    /// `https://s3.eu-west-1.amazonaws.com`. The request isn't signed.
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError>;
+
+    /// Tries to convert this request into a presigned URL, which can be used
+    /// to perform the request without the credentials until `expires_in`
+    /// has elapsed.
+    ///
+    /// The request body isn't part of the URL, and its headers must be sent
+    /// unchanged along with it.
+    fn try_into_presigned_uri(
+        self,
+        base_url: &str,
+        credentials: &Credentials,
+        params: &SigningParams<'_>,
+        expires_in: Duration,
+    ) -> Result<Uri, IntoHttpError> {
+        let mut request = self.try_into_http_request::<Vec<u8>>(base_url)?;
+        s3ers_signature::presign_request(
+            &mut request,
+            credentials,
+            params,
+            expires_in,
+        )?;
+        Ok(request.uri().clone())
+    }
+}
+
+/// A response type for an S3 API endpoint, used for receiving responses.
+pub trait IncomingResponse: Sized {
+    /// Tries to convert the given `http::Response` into this response type.
+    ///
+    /// Error responses are returned as [`FromHttpResponseError::Server`],
+    /// usually read with [`S3Error::from_http_response`].
+    ///
+    /// [`S3Error::from_http_response`]: error::S3Error::from_http_response
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError>;
+}
+
+/// A response type whose body can be read as a stream rather than by
+/// [`try_from_http_response`](IncomingResponse::try_from_http_response),
+/// like the content of an object.
+pub trait IncomingStreamingResponse: IncomingResponse {
+    /// Tries to convert the head of a successful `http::Response` into this
+    /// response type, without its body.
+    fn try_from_http_response_head(
+        response: http::Response<()>,
+    ) -> Result<Self, FromHttpResponseError>;
+}
+
+/// A request type for an S3 API endpoint, used for receiving requests on a
+/// server.
+pub trait IncomingRequest: Sized {
+    /// Metadata about the endpoint.
+    const METADATA: Metadata;
+
+    /// The response type sent back when the request is successful.
+    type OutgoingResponse: OutgoingResponse;
+
+    /// Tries to convert the given `http::Request` into this request type.
+    ///
+    /// `path_args` are the percent-decoded values of the parameters of the
+    /// path of the endpoint, like the bucket and the key of
+    /// `/:bucket/*key`, in order. The signature of the request isn't
+    /// verified.
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError>;
+}
+
+/// A response type for an S3 API endpoint, used for sending responses from
+/// a server.
+pub trait OutgoingResponse {
+    /// Tries to convert this response into an `http::Response`.
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError>;
+}
+
+/// A request to an endpoint returning its results in pages, like the list
+/// endpoints.
+pub trait Paginated: OutgoingRequest + Clone {
+    /// The type of the results.
+    type Item;
+
+    /// Returns the request for the page following the given response, or
+    /// `None` if it was the last page.
+    fn next_page(self, response: &Self::IncomingResponse) -> Option<Self>;
+
+    /// Returns the results of a page.
+    fn into_items(response: Self::IncomingResponse) -> Vec<Self::Item>;
+}
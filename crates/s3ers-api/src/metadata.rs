@@ -0,0 +1,97 @@
+use http::Method;
+
+/// Metadata about an API endpoint.
+#[derive(Clone, Debug)]
+pub struct Metadata {
+    /// A human-readable description of the endpoint.
+    pub description: &'static str,
+
+    /// The HTTP method used by this endpoint.
+    pub method: Method,
+
+    /// A unique identifier for this endpoint, like `GetObject`.
+    pub name: &'static str,
+
+    /// The path of this endpoint's URL, with variable names where path
+    /// parameters should be filled in during a request.
+    ///
+    /// Parameters like `:bucket` are a single path segment, and greedy ones
+    /// like `*key`, which end the path, take the rest of it, slashes
+    /// included, as in `/:bucket/*key`. The path may end with the constant
+    /// query of the endpoint, like `?uploads` or `?list-type=2`, which
+    /// tells it apart from the other endpoints with the same path.
+    pub path: &'static str,
+
+    /// What the endpoint acts on: the service, a bucket or an object.
+    pub scope: Scope,
+
+    /// How requests to this endpoint are authenticated.
+    pub authentication: AuthScheme,
+
+    /// Whether requests to this endpoint must have a `Content-MD5` header,
+    /// which clients compute from the body when it isn't set.
+    pub requires_content_md5: bool,
+
+    /// Whether requests to this endpoint can carry a checksum of their
+    /// body in an `x-amz-checksum-*` header, and responses one of the
+    /// content they return.
+    pub flexible_checksums: bool,
+}
+
+impl Metadata {
+    /// Returns the constant query parameters of the path of the endpoint,
+    /// with the value of the ones that have one, like `("uploads", None)`
+    /// for `/:bucket?uploads` or `("list-type", Some("2"))` for
+    /// `/:bucket?list-type=2`.
+    pub fn query(
+        &self,
+    ) -> impl Iterator<Item = (&'static str, Option<&'static str>)> {
+        let path: &'static str = self.path;
+        let query = path.split_once('?').map_or("", |(_, query)| query);
+        query
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .map(|param| match param.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (param, None),
+            })
+    }
+}
+
+/// What an endpoint acts on, which tells clients where the bucket and the
+/// key of its requests are, to address them in the hostname or in the path,
+/// and servers what kind of resource requests are for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// The service as a whole, with a path without bucket, like
+    /// `ListBuckets`.
+    Service,
+
+    /// A bucket, with a path starting with the bucket, like `/:bucket`.
+    Bucket,
+
+    /// An object, with a path starting with the bucket followed by the key,
+    /// like `/:bucket/*key`.
+    Object,
+}
+
+/// The authentication scheme used by an endpoint.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AuthScheme {
+    /// No authentication is performed.
+    None,
+
+    /// Requests are signed with AWS Signature Version 4, in the
+    /// `Authorization` header.
+    AwsSignatureV4,
+
+    /// Requests are signed with AWS Signature Version 4, in the query
+    /// string, as for presigned URLs.
+    AwsSignatureV4QueryParams,
+
+    /// Requests are signed with the legacy AWS Signature Version 2, in the
+    /// `Authorization` header.
+    ///
+    /// Clients only sign them with the `sigv2` feature of `s3ers-client`.
+    AwsSignatureV2,
+}
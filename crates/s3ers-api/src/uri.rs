@@ -0,0 +1,198 @@
+//! Building the URIs of endpoint requests.
+
+use std::fmt::Write;
+
+use percent_encoding::{
+    percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC,
+};
+
+use crate::Metadata;
+
+/// The characters that are percent-encoded in path segments and query
+/// components, that is everything except the unreserved characters of
+/// RFC 3986.
+const ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Returns the path-style URL of a bucket, like
+/// `https://s3.amazonaws.com/bucket`.
+pub fn bucket_url(base_url: &str, bucket: &str) -> String {
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        utf8_percent_encode(bucket, ENCODE_SET)
+    )
+}
+
+/// Returns the path-style URL of an object, like
+/// `https://s3.amazonaws.com/bucket/photos/2006/February/sample.jpg`.
+///
+/// The slashes of the key are kept, its other reserved characters are
+/// percent-encoded.
+pub fn object_url(base_url: &str, bucket: &str, key: &str) -> String {
+    let mut url = bucket_url(base_url, bucket);
+    for segment in key.split('/') {
+        url.push('/');
+        url.extend(utf8_percent_encode(segment, ENCODE_SET));
+    }
+    url
+}
+
+/// A query string.
+#[derive(Clone, Debug, Default)]
+pub struct Query(String);
+
+impl Query {
+    /// Creates an empty query string.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the query string of a request to an endpoint, with the
+    /// constant query parameters of its path, like `list-type=2`.
+    pub fn for_endpoint(metadata: &Metadata) -> Self {
+        metadata
+            .query()
+            .fold(Self::new(), |query, (name, value)| match value {
+                Some(value) => query.param(name, value),
+                None => query.flag(name),
+            })
+    }
+
+    /// Adds a parameter without value, like the `uploads` subresource.
+    pub fn flag(mut self, name: &str) -> Self {
+        self.separator();
+        self.0.extend(utf8_percent_encode(name, ENCODE_SET));
+        self
+    }
+
+    /// Adds a parameter.
+    pub fn param(mut self, name: &str, value: impl ToString) -> Self {
+        self.separator();
+        write!(
+            self.0,
+            "{}={}",
+            utf8_percent_encode(name, ENCODE_SET),
+            utf8_percent_encode(&value.to_string(), ENCODE_SET)
+        )
+        .unwrap();
+        self
+    }
+
+    /// Adds a parameter if it has a value.
+    pub fn param_opt(self, name: &str, value: Option<impl ToString>) -> Self {
+        match value {
+            Some(value) => self.param(name, value),
+            None => self,
+        }
+    }
+
+    /// Appends the query string to a URL.
+    pub fn append_to(&self, mut url: String) -> String {
+        if !self.0.is_empty() {
+            url.push('?');
+            url.push_str(&self.0);
+        }
+        url
+    }
+
+    fn separator(&mut self) {
+        if !self.0.is_empty() {
+            self.0.push('&');
+        }
+    }
+}
+
+/// Returns the percent-decoded value of a path segment or a query
+/// component, with invalid UTF-8 replaced.
+pub fn decode(component: &str) -> String {
+    percent_decode_str(component)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// Returns the percent-decoded parameters of a query string, in order, with
+/// an empty value for parameters without one, like the `uploads`
+/// subresource.
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let (name, value) = param.split_once('=').unwrap_or((param, ""));
+            (decode(name), decode(value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Method;
+
+    use super::{object_url, parse_query, Query};
+    use crate::{AuthScheme, Metadata, Scope};
+
+    #[test]
+    fn build_object_url() {
+        let url = Query::new()
+            .param("uploadId", "a b+c")
+            .param_opt("max-parts", Some(10))
+            .param_opt("part-number-marker", None::<u32>)
+            .append_to(object_url(
+                "https://s3.amazonaws.com/",
+                "bucket",
+                "photos/2006/Feb ruary/sample.jpg",
+            ));
+
+        assert_eq!(
+            url,
+            "https://s3.amazonaws.com/bucket/photos/2006/Feb%20ruary/\
+             sample.jpg?uploadId=a%20b%2Bc&max-parts=10"
+        );
+    }
+
+    #[test]
+    fn build_endpoint_query() {
+        let mut metadata = Metadata {
+            description: "Test endpoint",
+            method: Method::GET,
+            name: "Test",
+            path: "/:bucket?list-type=2&versions",
+            scope: Scope::Bucket,
+            authentication: AuthScheme::AwsSignatureV4,
+            requires_content_md5: false,
+            flexible_checksums: false,
+        };
+        assert_eq!(
+            metadata.query().collect::<Vec<_>>(),
+            [("list-type", Some("2")), ("versions", None)]
+        );
+        let query = Query::for_endpoint(&metadata).param("prefix", "a/");
+        assert_eq!(
+            query.append_to("/bucket".to_owned()),
+            "/bucket?list-type=2&versions&prefix=a%2F"
+        );
+
+        metadata.path = "/:bucket/*key";
+        assert_eq!(metadata.query().count(), 0);
+        assert_eq!(
+            Query::for_endpoint(&metadata).append_to("/".to_owned()),
+            "/"
+        );
+    }
+
+    #[test]
+    fn parse_query_string() {
+        assert_eq!(
+            parse_query("uploads&uploadId=a%20b%2Bc&&prefix="),
+            [
+                ("uploads".to_owned(), String::new()),
+                ("uploadId".to_owned(), "a b+c".to_owned()),
+                ("prefix".to_owned(), String::new()),
+            ]
+        );
+    }
+}
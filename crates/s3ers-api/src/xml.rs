@@ -0,0 +1,221 @@
+//! A minimal XML document model for the bodies of S3 requests and
+//! responses.
+//!
+//! S3 documents are small trees of elements that contain either other
+//! elements or text, without attributes of interest, so they are read into
+//! [`Element`]s and queried by name, and written from them.
+
+use std::str::FromStr;
+
+use quick_xml::{escape::escape, events::Event, Reader};
+
+use crate::error::DeserializationError;
+
+/// An XML element.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Element {
+    /// The local name of the element, without its namespace prefix.
+    pub name: String,
+
+    /// The text of the element, empty if it only contains other elements.
+    pub text: String,
+
+    /// The child elements.
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    /// Parses an XML document and returns its root element.
+    pub fn parse(xml: &[u8]) -> Result<Self, DeserializationError> {
+        let mut reader = Reader::from_reader(xml);
+        reader.trim_text(true);
+
+        let mut stack: Vec<Element> = Vec::new();
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(start) => stack.push(Self::new(
+                    String::from_utf8_lossy(start.local_name().as_ref()),
+                )),
+                Event::Empty(empty) => {
+                    let element = Self::new(String::from_utf8_lossy(
+                        empty.local_name().as_ref(),
+                    ));
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                Event::Text(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text.unescape()?);
+                    }
+                }
+                Event::CData(data) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&String::from_utf8_lossy(&data));
+                    }
+                }
+                Event::End(_) => {
+                    // quick-xml checks that end tags match start tags.
+                    let element = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                Event::Eof => {
+                    return Err(DeserializationError::Missing(
+                        "root element".to_owned(),
+                    ))
+                }
+                _ => {}
+            }
+            buf.clear();
+        }
+    }
+
+    /// Creates an element without text or children.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Creates an element containing text.
+    pub fn with_text(name: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            text: text.into(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Adds a child element.
+    pub fn with_child(mut self, child: Element) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Writes the element as an XML document.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        self.write(&mut xml);
+        xml
+    }
+
+    fn write(&self, xml: &mut String) {
+        xml.push('<');
+        xml.push_str(&self.name);
+        xml.push('>');
+        xml.push_str(&escape(&self.text));
+        for child in &self.children {
+            child.write(xml);
+        }
+        xml.push_str("</");
+        xml.push_str(&self.name);
+        xml.push('>');
+    }
+
+    /// Returns the first child element with the given name.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Returns the child elements with the given name.
+    pub fn children<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// Returns the text of the first child element with the given name.
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.as_str())
+    }
+
+    /// Returns the text of the first child element with the given name, as
+    /// an owned string.
+    pub fn child_string(&self, name: &str) -> Option<String> {
+        self.child_text(name).map(ToOwned::to_owned)
+    }
+
+    /// Returns the text of the first child element with the given name,
+    /// failing if there is none.
+    pub fn required_text(
+        &self,
+        name: &str,
+    ) -> Result<&str, DeserializationError> {
+        self.child_text(name)
+            .ok_or_else(|| DeserializationError::Missing(name.to_owned()))
+    }
+
+    /// Parses the text of the first child element with the given name, if
+    /// there is one.
+    pub fn parse_child<T: FromStr>(
+        &self,
+        name: &str,
+    ) -> Result<Option<T>, DeserializationError> {
+        self.child_text(name)
+            .map(|text| {
+                text.parse()
+                    .map_err(|_| DeserializationError::Invalid(name.to_owned()))
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Element;
+
+    #[test]
+    fn parse_document() {
+        let root = Element::parse(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Name>bucket</Name>
+  <IsTruncated>false</IsTruncated>
+  <Contents><Key>a &amp; b</Key><Size>3</Size></Contents>
+  <Contents><Key>c</Key><Size>4</Size></Contents>
+  <Delimiter/>
+</ListBucketResult>"#,
+        )
+        .unwrap();
+
+        assert_eq!(root.name, "ListBucketResult");
+        assert_eq!(root.child_text("Name"), Some("bucket"));
+        assert_eq!(
+            root.parse_child::<bool>("IsTruncated").unwrap(),
+            Some(false)
+        );
+        assert_eq!(root.child_text("Delimiter"), Some(""));
+
+        let keys: Vec<_> = root
+            .children("Contents")
+            .map(|contents| contents.child_text("Key").unwrap())
+            .collect();
+        assert_eq!(keys, ["a & b", "c"]);
+        assert!(root.parse_child::<u64>("Name").is_err());
+    }
+
+    #[test]
+    fn write_document() {
+        let element = Element::new("Delete")
+            .with_child(
+                Element::new("Object")
+                    .with_child(Element::with_text("Key", "a & <b>")),
+            )
+            .with_child(Element::with_text("Quiet", "true"));
+
+        let xml = element.to_xml();
+        assert_eq!(
+            xml,
+            "<Delete><Object><Key>a &amp; &lt;b&gt;</Key></Object>\
+             <Quiet>true</Quiet></Delete>"
+        );
+        assert_eq!(Element::parse(xml.as_bytes()).unwrap(), element);
+    }
+}
@@ -0,0 +1,326 @@
+//! Addressing buckets in the hostname rather than in the path.
+
+use std::net::IpAddr;
+
+use http::{
+    uri::{Authority, PathAndQuery, Scheme},
+    Uri,
+};
+
+/// How the bucket of a request is addressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AddressingStyle {
+    /// The bucket is the first segment of the path, like
+    /// `https://s3.amazonaws.com/bucket/key`.
+    Path,
+
+    /// The bucket is a subdomain of the endpoint, like
+    /// `https://bucket.s3.amazonaws.com/key`.
+    ///
+    /// Buckets whose name can't be a DNS label, or whose dots would break
+    /// the certificate check over HTTPS, are still addressed in the path.
+    VirtualHosted,
+
+    /// Virtual-hosted-style for the endpoints of AWS, which deprecated
+    /// path-style requests, and path-style for other servers, which often
+    /// don't have DNS records for their buckets.
+    #[default]
+    Auto,
+}
+
+impl AddressingStyle {
+    /// Whether requests to the given endpoint are addressed in the
+    /// hostname.
+    fn is_virtual_hosted(self, endpoint: &Uri) -> bool {
+        match self {
+            Self::Path => false,
+            Self::VirtualHosted => true,
+            Self::Auto => endpoint.host().is_some_and(|host| {
+                host == "amazonaws.com" || host.ends_with(".amazonaws.com")
+            }),
+        }
+    }
+}
+
+/// Whether a bucket can be addressed in the hostname of a request with the
+/// given scheme.
+///
+/// Its name must be a valid DNS name with lowercase labels, and not look
+/// like an IP address. Over HTTPS, it can't contain dots either, since
+/// wildcard certificates only match one label.
+pub fn is_virtual_hostable(bucket: &str, scheme: Option<&Scheme>) -> bool {
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.bytes().all(|b| {
+                b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-'
+            })
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+    };
+
+    (3..=63).contains(&bucket.len())
+        && bucket.split('.').all(valid_label)
+        && bucket.parse::<IpAddr>().is_err()
+        && !(scheme == Some(&Scheme::HTTPS) && bucket.contains('.'))
+}
+
+/// Moves the bucket of a path-style URI to its hostname, if the addressing
+/// style asks for it and the bucket allows it.
+///
+/// `endpoint` is the URL of the S3 service the URI was built with, which may
+/// have a path of its own.
+pub(crate) fn virtual_host(
+    uri: &Uri,
+    endpoint: &Uri,
+    style: AddressingStyle,
+) -> Option<Uri> {
+    if !style.is_virtual_hosted(endpoint) {
+        return None;
+    }
+
+    let (bucket, path) = split_bucket(uri, endpoint)?;
+    if !is_virtual_hostable(bucket, uri.scheme()) {
+        return None;
+    }
+
+    let authority = uri.authority()?;
+    let authority = match authority.port() {
+        Some(port) => format!("{}.{}:{}", bucket, authority.host(), port),
+        None => format!("{}.{}", bucket, authority.host()),
+    };
+    let prefix = endpoint.path().trim_end_matches('/');
+    with_authority(uri, &authority, &format!("{}{}", prefix, path))
+}
+
+/// Sends a path-style URI to the given transfer acceleration endpoint, if it
+/// is a request to a bucket.
+///
+/// Accelerated buckets are always addressed in the hostname, so their name
+/// can't contain dots.
+pub(crate) fn accelerate(
+    uri: &Uri,
+    endpoint: &Uri,
+    accelerate_endpoint: &Uri,
+) -> Result<Option<Uri>, String> {
+    let (bucket, path) = match split_bucket(uri, endpoint) {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+    if !is_virtual_hostable(bucket, Some(&Scheme::HTTPS)) {
+        return Err(format!(
+            "the bucket `{}` can't use transfer acceleration",
+            bucket
+        ));
+    }
+
+    let host = accelerate_endpoint.host().ok_or_else(|| {
+        format!("invalid acceleration endpoint `{}`", accelerate_endpoint)
+    })?;
+    let mut parts = uri.clone().into_parts();
+    parts.scheme = accelerate_endpoint.scheme().cloned();
+    let uri = Uri::from_parts(parts).map_err(|err| err.to_string())?;
+    Ok(with_authority(&uri, &format!("{}.{}", bucket, host), &path))
+}
+
+/// Returns the bucket of a path-style URI to the given endpoint.
+pub(crate) fn bucket<'a>(uri: &'a Uri, endpoint: &Uri) -> Option<&'a str> {
+    split_bucket(uri, endpoint).map(|(bucket, _)| bucket)
+}
+
+/// Moves a URI to the given endpoint to another one, like the endpoint of
+/// another region.
+pub(crate) fn rebase(uri: &Uri, endpoint: &Uri, target: &Uri) -> Option<Uri> {
+    let prefix = endpoint.path().trim_end_matches('/');
+    let rest = uri.path_and_query()?.as_str().strip_prefix(prefix)?;
+    let path = format!("{}{}", target.path().trim_end_matches('/'), rest);
+
+    let mut parts = uri.clone().into_parts();
+    parts.scheme = target.scheme().cloned();
+    let uri = Uri::from_parts(parts).ok()?;
+    with_authority(&uri, target.authority()?.as_str(), &path)
+}
+
+/// Splits the path of a path-style URI to the given endpoint into its
+/// bucket and the rest of its path and query.
+fn split_bucket<'a>(uri: &'a Uri, endpoint: &Uri) -> Option<(&'a str, String)> {
+    let prefix = endpoint.path().trim_end_matches('/');
+    let path = uri.path().strip_prefix(prefix)?.strip_prefix('/')?;
+    let (bucket, rest) = match path.find('/') {
+        Some(end) => path.split_at(end),
+        None => (path, "/"),
+    };
+    if bucket.is_empty() {
+        return None;
+    }
+
+    let rest = match uri.query() {
+        Some(query) => format!("{}?{}", rest, query),
+        None => rest.to_owned(),
+    };
+    Some((bucket, rest))
+}
+
+/// Replaces the authority and the path of a URI.
+fn with_authority(uri: &Uri, authority: &str, path: &str) -> Option<Uri> {
+    let mut parts = uri.clone().into_parts();
+    parts.authority = Some(authority.parse::<Authority>().ok()?);
+    parts.path_and_query = Some(path.parse::<PathAndQuery>().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+/// Returns the bucket of a virtual-hosted-style request to the given
+/// endpoint.
+#[cfg(feature = "sigv2")]
+pub(crate) fn virtual_host_bucket<'a>(
+    uri: &'a Uri,
+    endpoint: &Uri,
+) -> Option<&'a str> {
+    uri.host()?
+        .strip_suffix(endpoint.host()?)?
+        .strip_suffix('.')
+        .filter(|bucket| !bucket.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{uri::Scheme, Uri};
+
+    use super::{
+        accelerate, is_virtual_hostable, rebase, virtual_host, AddressingStyle,
+    };
+
+    fn rewrite(uri: &str, endpoint: &str, style: AddressingStyle) -> String {
+        let uri: Uri = uri.parse().unwrap();
+        match virtual_host(&uri, &endpoint.parse().unwrap(), style) {
+            Some(uri) => uri.to_string(),
+            None => uri.to_string(),
+        }
+    }
+
+    #[test]
+    fn hostable_buckets() {
+        let https = Some(&Scheme::HTTPS);
+        assert!(is_virtual_hostable("my-bucket", https));
+        assert!(is_virtual_hostable("my.bucket", Some(&Scheme::HTTP)));
+        assert!(!is_virtual_hostable("my.bucket", https));
+        assert!(!is_virtual_hostable("My-Bucket", https));
+        assert!(!is_virtual_hostable("my_bucket", https));
+        assert!(!is_virtual_hostable("-bucket", https));
+        assert!(!is_virtual_hostable("ab", https));
+        assert!(!is_virtual_hostable("my..bucket", None));
+        assert!(!is_virtual_hostable("192.168.1.1", None));
+    }
+
+    #[test]
+    fn move_bucket_to_host() {
+        let aws = "https://s3.eu-west-1.amazonaws.com";
+        assert_eq!(
+            rewrite(
+                "https://s3.eu-west-1.amazonaws.com/bucket/a/b?versionId=1",
+                aws,
+                AddressingStyle::Auto
+            ),
+            "https://bucket.s3.eu-west-1.amazonaws.com/a/b?versionId=1"
+        );
+        assert_eq!(
+            rewrite(
+                "https://s3.eu-west-1.amazonaws.com/bucket?uploads",
+                aws,
+                AddressingStyle::Auto
+            ),
+            "https://bucket.s3.eu-west-1.amazonaws.com/?uploads"
+        );
+        assert_eq!(
+            rewrite(
+                "https://s3.eu-west-1.amazonaws.com/my.bucket/key",
+                aws,
+                AddressingStyle::Auto
+            ),
+            "https://s3.eu-west-1.amazonaws.com/my.bucket/key"
+        );
+        assert_eq!(
+            rewrite(
+                "https://s3.eu-west-1.amazonaws.com/bucket/key",
+                aws,
+                AddressingStyle::Path
+            ),
+            "https://s3.eu-west-1.amazonaws.com/bucket/key"
+        );
+    }
+
+    #[test]
+    fn custom_endpoints() {
+        let minio = "http://localhost:9000/s3";
+        assert_eq!(
+            rewrite(
+                "http://localhost:9000/s3/bucket/key",
+                minio,
+                AddressingStyle::Auto
+            ),
+            "http://localhost:9000/s3/bucket/key"
+        );
+        assert_eq!(
+            rewrite(
+                "http://localhost:9000/s3/bucket/key",
+                minio,
+                AddressingStyle::VirtualHosted
+            ),
+            "http://bucket.localhost:9000/s3/key"
+        );
+    }
+
+    #[test]
+    fn accelerate_buckets() {
+        let endpoint = "https://s3.eu-west-1.amazonaws.com".parse().unwrap();
+        let accelerate_endpoint =
+            "https://s3-accelerate.amazonaws.com".parse().unwrap();
+        let accelerated = |uri: &str| {
+            accelerate(&uri.parse().unwrap(), &endpoint, &accelerate_endpoint)
+                .map(|uri| uri.map(|uri| uri.to_string()))
+        };
+
+        assert_eq!(
+            accelerated("https://s3.eu-west-1.amazonaws.com/bucket/key?acl"),
+            Ok(Some(
+                "https://bucket.s3-accelerate.amazonaws.com/key?acl".to_owned()
+            ))
+        );
+        assert!(accelerated(
+            "https://s3.eu-west-1.amazonaws.com/my.bucket/key"
+        )
+        .is_err());
+        assert_eq!(accelerated("https://s3.eu-west-1.amazonaws.com"), Ok(None));
+    }
+
+    #[test]
+    fn rebase_uris() {
+        let rebased = |uri: &str, endpoint: &str, target: &str| {
+            rebase(
+                &uri.parse().unwrap(),
+                &endpoint.parse().unwrap(),
+                &target.parse().unwrap(),
+            )
+            .map(|uri| uri.to_string())
+        };
+
+        assert_eq!(
+            rebased(
+                "https://s3.eu-west-1.amazonaws.com/bucket/key?acl",
+                "https://s3.eu-west-1.amazonaws.com",
+                "https://s3.us-west-2.amazonaws.com"
+            )
+            .as_deref(),
+            Some("https://s3.us-west-2.amazonaws.com/bucket/key?acl")
+        );
+        assert_eq!(
+            rebased(
+                "http://localhost:9000/s3/bucket",
+                "http://localhost:9000/s3",
+                "https://storage.example.com/"
+            )
+            .as_deref(),
+            Some("https://storage.example.com/bucket")
+        );
+    }
+}
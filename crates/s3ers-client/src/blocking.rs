@@ -0,0 +1,206 @@
+//! A blocking client, for programs that don't use async, like command line
+//! tools or build scripts.
+//!
+//! It runs the requests of an async [`Client`](crate::Client) on a runtime
+//! of its own, so it must not be used from within an async runtime:
+//!
+//! ```no_run
+//! # use s3ers_client::{blocking, http_client::HyperRustls, Client};
+//! let client = Client::builder()
+//!     .region("eu-west-1")
+//!     .build::<HyperRustls>();
+//! let client = blocking::Client::new(client).unwrap();
+//! ```
+
+use std::{fmt, io, sync::Arc};
+
+use bytes::{Buf, Bytes};
+use futures_util::StreamExt;
+use s3ers_api::{IncomingStreamingResponse, OutgoingRequest};
+use tokio::runtime::Runtime;
+
+use crate::{
+    ByteStream, Error, HttpClient, ReplayableBody, RequestConfig,
+    ResponseResult, StreamingBody,
+};
+
+/// A client for the S3 API whose requests block until they complete.
+///
+/// Cloning the client is cheap, clones share their runtime.
+pub struct Client<C> {
+    client: crate::Client<C>,
+    runtime: Arc<Runtime>,
+}
+
+impl<C: HttpClient> Client<C> {
+    /// Creates a blocking client sending the requests of `client`, on a
+    /// single-threaded runtime.
+    pub fn new(client: crate::Client<C>) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            client,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Returns the async client sending the requests.
+    pub fn async_client(&self) -> &crate::Client<C> {
+        &self.client
+    }
+
+    /// Returns the region requests are signed for.
+    pub fn region(&self) -> &str {
+        self.client.region()
+    }
+
+    /// Makes a request to an S3 API endpoint, like
+    /// [`Client::send_request`](crate::Client::send_request).
+    pub fn send_request<R: OutgoingRequest>(
+        &self,
+        request: R,
+    ) -> ResponseResult<C, R> {
+        self.runtime.block_on(self.client.send_request(request))
+    }
+
+    /// Makes a request to an S3 API endpoint with settings overriding those
+    /// of the client, like
+    /// [`Client::send_request_with`](crate::Client::send_request_with).
+    pub fn send_request_with<R: OutgoingRequest>(
+        &self,
+        request: R,
+        config: RequestConfig,
+    ) -> ResponseResult<C, R> {
+        self.runtime
+            .block_on(self.client.send_request_with(request, config))
+    }
+
+    /// Makes a request to an S3 API endpoint while allowing to customize
+    /// the http request, like
+    /// [`Client::send_customized_request`](crate::Client::send_customized_request).
+    pub fn send_customized_request<R, F>(
+        &self,
+        request: R,
+        customize: F,
+    ) -> ResponseResult<C, R>
+    where
+        R: OutgoingRequest,
+        F: FnOnce(&mut http::Request<C::RequestBody>),
+    {
+        self.runtime
+            .block_on(self.client.send_customized_request(request, customize))
+    }
+
+    /// Makes a request to an S3 API endpoint, returning the body of the
+    /// response as a reader, like
+    /// [`Client::send_request_streaming_response`](crate::Client::send_request_streaming_response).
+    pub fn send_request_streaming_response<R>(
+        &self,
+        request: R,
+    ) -> Result<(R::IncomingResponse, BodyReader), Error<C::Error>>
+    where
+        R: OutgoingRequest,
+        R::IncomingResponse: IncomingStreamingResponse,
+    {
+        let (response, body) = self
+            .runtime
+            .block_on(self.client.send_request_streaming_response(request))?;
+        let reader = BodyReader {
+            body,
+            chunk: Bytes::new(),
+            runtime: self.runtime.clone(),
+        };
+        Ok((response, reader))
+    }
+
+    /// Makes a request to an S3 API endpoint with a body that is read as it
+    /// is sent, like
+    /// [`Client::send_streaming_request`](crate::Client::send_streaming_request).
+    pub fn send_streaming_request<R: OutgoingRequest>(
+        &self,
+        request: R,
+        body: StreamingBody,
+    ) -> ResponseResult<C, R> {
+        self.runtime
+            .block_on(self.client.send_streaming_request(request, body))
+    }
+
+    /// Makes a request to an S3 API endpoint with a body that is read as it
+    /// is sent, and again to retry the request, like
+    /// [`Client::send_replayable_request`](crate::Client::send_replayable_request).
+    pub fn send_replayable_request<R: OutgoingRequest>(
+        &self,
+        request: R,
+        body: ReplayableBody,
+    ) -> ResponseResult<C, R> {
+        self.runtime
+            .block_on(self.client.send_replayable_request(request, body))
+    }
+}
+
+impl<C> Clone for Client<C> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+impl<C: fmt::Debug> fmt::Debug for Client<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("client", &self.client)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The body of a response, read as it is received.
+pub struct BodyReader {
+    body: ByteStream,
+    chunk: Bytes,
+    runtime: Arc<Runtime>,
+}
+
+impl io::Read for BodyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while !self.chunk.has_remaining() {
+            match self.runtime.block_on(self.body.next()) {
+                Some(chunk) => self.chunk = chunk?,
+                None => return Ok(0),
+            }
+        }
+
+        let n = buf.len().min(self.chunk.len());
+        self.chunk.copy_to_slice(&mut buf[..n]);
+        Ok(n)
+    }
+}
+
+impl fmt::Debug for BodyReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyReader").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::Client;
+    use crate::tests::{client, Request};
+
+    #[test]
+    fn read_body() {
+        let client =
+            Client::new(client(vec![http::Response::new(b"content".to_vec())]))
+                .unwrap();
+
+        let (_, mut body) =
+            client.send_request_streaming_response(Request).unwrap();
+        let mut content = String::new();
+        body.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "content");
+    }
+}
@@ -0,0 +1,382 @@
+//! Streaming request and response bodies.
+
+use std::{
+    fmt, io,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::{Bytes, BytesMut};
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+use s3ers_signature::chunked::ChunkSigner;
+use tokio::{fs::File, io::AsyncReadExt};
+
+/// The size of the chunks of `aws-chunked` bodies, but the last one.
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+/// How much of a file is read at once.
+const FILE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// A request body that is read as it is sent, rather than held in memory.
+///
+/// S3 needs the length of bodies before they are sent, so it is given with
+/// the stream of their bytes, and reading the body fails if the stream
+/// doesn't yield exactly that many bytes.
+pub struct StreamingBody {
+    stream: BoxStream<'static, io::Result<Bytes>>,
+    content_length: u64,
+    read: u64,
+    done: bool,
+}
+
+impl StreamingBody {
+    /// Creates a body of `content_length` bytes read from a stream.
+    pub fn new<S>(stream: S, content_length: u64) -> Self
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + 'static,
+    {
+        Self {
+            stream: stream.boxed(),
+            content_length,
+            read: 0,
+            done: false,
+        }
+    }
+
+    /// Returns the length of the body.
+    pub fn content_length(&self) -> u64 {
+        self.content_length
+    }
+}
+
+impl fmt::Debug for StreamingBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingBody")
+            .field("content_length", &self.content_length)
+            .field("read", &self.read)
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<Bytes> for StreamingBody {
+    fn from(bytes: Bytes) -> Self {
+        let content_length = bytes.len() as u64;
+        Self::new(stream::once(async { Ok(bytes) }), content_length)
+    }
+}
+
+impl From<Vec<u8>> for StreamingBody {
+    fn from(bytes: Vec<u8>) -> Self {
+        Bytes::from(bytes).into()
+    }
+}
+
+impl Stream for StreamingBody {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let item = match self.stream.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(bytes))) => {
+                self.read += bytes.len() as u64;
+                if self.read > self.content_length {
+                    Err(length_mismatch(self.content_length))
+                } else {
+                    Ok(bytes)
+                }
+            }
+            Poll::Ready(Some(Err(err))) => Err(err),
+            Poll::Ready(None) => {
+                self.done = true;
+                if self.read == self.content_length {
+                    return Poll::Ready(None);
+                }
+                Err(length_mismatch(self.content_length))
+            }
+            Poll::Pending => return Poll::Pending,
+        };
+        if item.is_err() {
+            self.done = true;
+        }
+        Poll::Ready(Some(item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+fn length_mismatch(content_length: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("the body isn't {} bytes long", content_length),
+    )
+}
+
+/// A request body that is read as it is sent, and can be read again from
+/// the start to retry the request, set with
+/// [`Client::send_replayable_request`](crate::Client::send_replayable_request).
+///
+/// It is held in memory, read from a file again for every attempt, or
+/// created by a function for every attempt, like one reading from a
+/// database. Clones share their content.
+#[derive(Clone)]
+pub struct ReplayableBody {
+    source: Source,
+    content_length: u64,
+}
+
+#[derive(Clone)]
+enum Source {
+    Bytes(Bytes),
+    File(PathBuf),
+    Factory(Arc<dyn Fn() -> StreamingBody + Send + Sync>),
+}
+
+impl ReplayableBody {
+    /// Creates a body read from a file, with the length it has now.
+    pub async fn from_file(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let content_length = tokio::fs::metadata(&path).await?.len();
+        Ok(Self {
+            source: Source::File(path),
+            content_length,
+        })
+    }
+
+    /// Creates a body of `content_length` bytes read from the streams
+    /// returned by `factory`, one for every attempt.
+    pub fn from_fn<F, S>(content_length: u64, factory: F) -> Self
+    where
+        F: Fn() -> S + Send + Sync + 'static,
+        S: Stream<Item = io::Result<Bytes>> + Send + 'static,
+    {
+        Self {
+            source: Source::Factory(Arc::new(move || {
+                StreamingBody::new(factory(), content_length)
+            })),
+            content_length,
+        }
+    }
+
+    /// Returns the length of the body.
+    pub fn content_length(&self) -> u64 {
+        self.content_length
+    }
+
+    /// Returns the body read from the start, for an attempt.
+    pub(crate) async fn open(&self) -> io::Result<StreamingBody> {
+        Ok(match &self.source {
+            Source::Bytes(bytes) => bytes.clone().into(),
+            Source::File(path) => {
+                let file = File::open(path).await?;
+                StreamingBody::new(
+                    ByteStream::from_file(file),
+                    self.content_length,
+                )
+            }
+            Source::Factory(factory) => factory(),
+        })
+    }
+}
+
+impl fmt::Debug for ReplayableBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match &self.source {
+            Source::Bytes(_) => "Bytes",
+            Source::File(_) => "File",
+            Source::Factory(_) => "Factory",
+        };
+        f.debug_struct("ReplayableBody")
+            .field("source", &source)
+            .field("content_length", &self.content_length)
+            .finish()
+    }
+}
+
+impl From<Bytes> for ReplayableBody {
+    fn from(bytes: Bytes) -> Self {
+        Self {
+            content_length: bytes.len() as u64,
+            source: Source::Bytes(bytes),
+        }
+    }
+}
+
+impl From<Vec<u8>> for ReplayableBody {
+    fn from(bytes: Vec<u8>) -> Self {
+        Bytes::from(bytes).into()
+    }
+}
+
+/// A stream of bytes of unknown length, like the body of a response read as
+/// it is received, or the content of an upload.
+pub struct ByteStream(BoxStream<'static, io::Result<Bytes>>);
+
+impl ByteStream {
+    /// Creates a byte stream from a stream of chunks.
+    pub fn new<S>(stream: S) -> Self
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + 'static,
+    {
+        Self(stream.boxed())
+    }
+
+    /// Creates a byte stream reading a file.
+    pub fn from_file(file: File) -> Self {
+        Self::new(stream::unfold(Some(file), |file| async move {
+            let mut file = file?;
+            let mut buffer = BytesMut::with_capacity(FILE_BUFFER_SIZE);
+            match file.read_buf(&mut buffer).await {
+                Ok(0) => None,
+                Ok(_) => Some((Ok(buffer.freeze()), Some(file))),
+                Err(err) => Some((Err(err), None)),
+            }
+        }))
+    }
+
+    /// Reads the whole body.
+    pub async fn collect(mut self) -> io::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        while let Some(bytes) = self.0.next().await {
+            body.extend_from_slice(&bytes?);
+        }
+        Ok(body)
+    }
+}
+
+impl fmt::Debug for ByteStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByteStream").finish_non_exhaustive()
+    }
+}
+
+impl From<Bytes> for ByteStream {
+    fn from(bytes: Bytes) -> Self {
+        Self::new(stream::once(async { Ok(bytes) }))
+    }
+}
+
+impl From<Vec<u8>> for ByteStream {
+    fn from(bytes: Vec<u8>) -> Self {
+        Bytes::from(bytes).into()
+    }
+}
+
+impl Stream for ByteStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// Encodes a body with the `aws-chunked` encoding, signing its chunks.
+///
+/// Every chunk but the last one is [`CHUNK_SIZE`] bytes long, as the
+/// `Content-Length` of the request was computed with.
+pub(crate) struct AwsChunked {
+    body: StreamingBody,
+    signer: ChunkSigner,
+    buffer: BytesMut,
+    done: bool,
+}
+
+impl AwsChunked {
+    pub(crate) fn new(body: StreamingBody, signer: ChunkSigner) -> Self {
+        Self {
+            body,
+            signer,
+            buffer: BytesMut::new(),
+            done: false,
+        }
+    }
+}
+
+impl Stream for AwsChunked {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+            if this.buffer.len() >= CHUNK_SIZE {
+                let chunk = this.buffer.split_to(CHUNK_SIZE);
+                let encoded = this.signer.encode_chunk(&chunk);
+                return Poll::Ready(Some(Ok(encoded.into())));
+            }
+
+            match this.body.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(bytes))) => {
+                    this.buffer.extend_from_slice(&bytes)
+                }
+                Poll::Ready(Some(Err(err))) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                Poll::Ready(None) => {
+                    this.done = true;
+                    let mut encoded = Vec::new();
+                    if !this.buffer.is_empty() {
+                        encoded = this.signer.encode_chunk(&this.buffer);
+                        this.buffer.clear();
+                    }
+                    encoded.extend(this.signer.encode_final_chunk());
+                    return Poll::Ready(Some(Ok(encoded.into())));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures_executor::block_on;
+    use futures_util::{stream, StreamExt, TryStreamExt};
+
+    use super::StreamingBody;
+
+    fn read(body: StreamingBody) -> std::io::Result<Vec<Bytes>> {
+        block_on(body.try_collect())
+    }
+
+    #[test]
+    fn check_length() {
+        let chunks = || {
+            stream::iter(vec![Ok(Bytes::from("abc")), Ok(Bytes::from("de"))])
+        };
+
+        assert_eq!(read(StreamingBody::new(chunks(), 5)).unwrap().len(), 2);
+        assert!(read(StreamingBody::new(chunks(), 4)).is_err());
+        assert!(read(StreamingBody::new(chunks(), 6)).is_err());
+
+        let mut body = StreamingBody::new(chunks(), 6);
+        assert!(block_on(body.by_ref().collect::<Vec<_>>())
+            .last()
+            .unwrap()
+            .is_err());
+        assert!(block_on(body.next()).is_none());
+    }
+}
@@ -0,0 +1,607 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use http::HeaderValue;
+use s3ers_api::header::SseKms;
+use s3ers_credentials::{ChainProvider, CredentialsProvider};
+use s3ers_signature::{
+    chunked::ChecksumAlgorithm, clock::SkewCorrectedClock, Clock, SystemClock,
+};
+
+use crate::{
+    hedge::Hedging,
+    http_client::{HttpClientConfig, Proxy, Resolve},
+    limit::Limiter,
+    middleware::Middleware,
+    AddressingStyle, AwsEndpointResolver, Client, ClientData, Compatibility,
+    DefaultConstructibleHttpClient, EndpointResolver, HedgingPolicy,
+    HttpClient, MetricsSink, RetryPolicy, RetryQuota, Timeouts,
+};
+
+/// The region used when none is configured.
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// A [`Client`] builder.
+///
+/// This type can be used to construct a `Client` through a few method calls.
+pub struct ClientBuilder {
+    endpoint_url: Option<String>,
+    endpoint_resolver: Option<Arc<dyn EndpointResolver>>,
+    use_dual_stack: bool,
+    use_fips: bool,
+    region: Option<String>,
+    addressing_style: AddressingStyle,
+    follow_region_redirects: bool,
+    accelerate: bool,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+    validate_checksums: bool,
+    decompress_responses: bool,
+    credentials: Option<Arc<dyn CredentialsProvider>>,
+    anonymous: bool,
+    requester_pays: bool,
+    sse_kms: Option<SseKms>,
+    clock: Option<Arc<dyn Clock>>,
+    timeouts: Timeouts,
+    http_client_config: HttpClientConfig,
+    retry_policy: RetryPolicy,
+    retry_quota: Option<RetryQuota>,
+    hedging_policy: Option<HedgingPolicy>,
+    max_concurrent_requests: Option<usize>,
+    max_requests_per_second: Option<u32>,
+    user_agent: Option<HeaderValue>,
+    metrics_sink: Option<Arc<dyn MetricsSink>>,
+}
+
+impl ClientBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            endpoint_url: None,
+            endpoint_resolver: None,
+            use_dual_stack: false,
+            use_fips: false,
+            region: None,
+            addressing_style: AddressingStyle::default(),
+            follow_region_redirects: false,
+            accelerate: false,
+            checksum_algorithm: None,
+            validate_checksums: false,
+            decompress_responses: false,
+            credentials: None,
+            anonymous: false,
+            requester_pays: false,
+            sse_kms: None,
+            clock: None,
+            timeouts: Timeouts::default(),
+            http_client_config: HttpClientConfig::default(),
+            retry_policy: RetryPolicy::default(),
+            retry_quota: None,
+            hedging_policy: None,
+            max_concurrent_requests: None,
+            max_requests_per_second: None,
+            user_agent: None,
+            metrics_sink: None,
+        }
+    }
+
+    /// Set the URL of the S3 service, like
+    /// `https://s3.eu-west-1.amazonaws.com` or the URL of an S3-compatible
+    /// server.
+    ///
+    /// Defaults to the endpoint of the region given by the endpoint
+    /// resolver. An [`EndpointUrl`](crate::EndpointUrl) can be given to
+    /// validate the URL beforehand.
+    pub fn endpoint_url(self, endpoint_url: impl Into<String>) -> Self {
+        Self {
+            endpoint_url: Some(endpoint_url.into()),
+            ..self
+        }
+    }
+
+    /// Set how the URL of the S3 service is resolved when none is given,
+    /// for S3-compatible services with an endpoint per region.
+    ///
+    /// Defaults to [`AwsEndpointResolver`].
+    pub fn endpoint_resolver(
+        self,
+        endpoint_resolver: impl EndpointResolver + 'static,
+    ) -> Self {
+        Self {
+            endpoint_resolver: Some(Arc::new(endpoint_resolver)),
+            ..self
+        }
+    }
+
+    /// Set whether the resolved endpoint is reachable over both IPv4 and
+    /// IPv6, like `s3.dualstack.eu-west-1.amazonaws.com`.
+    ///
+    /// Disabled by default.
+    pub fn use_dual_stack(self, use_dual_stack: bool) -> Self {
+        Self {
+            use_dual_stack,
+            ..self
+        }
+    }
+
+    /// Set whether the resolved endpoint uses FIPS 140-2 validated
+    /// cryptography, like `s3-fips.us-gov-west-1.amazonaws.com`.
+    ///
+    /// Disabled by default.
+    pub fn use_fips(self, use_fips: bool) -> Self {
+        Self { use_fips, ..self }
+    }
+
+    /// Set the region requests are signed for.
+    ///
+    /// Defaults to the region configured in the environment or the shared
+    /// config file, and to `us-east-1` otherwise.
+    pub fn region(self, region: impl Into<String>) -> Self {
+        Self {
+            region: Some(region.into()),
+            ..self
+        }
+    }
+
+    /// Set how the buckets of requests are addressed.
+    ///
+    /// Defaults to [`AddressingStyle::Auto`].
+    pub fn addressing_style(self, addressing_style: AddressingStyle) -> Self {
+        Self {
+            addressing_style,
+            ..self
+        }
+    }
+
+    /// Set whether requests redirected to the region of their bucket are
+    /// sent there again.
+    ///
+    /// S3 redirects requests to buckets of other regions with a
+    /// `301 Moved Permanently` naming the region of the bucket. When this is
+    /// enabled, the endpoint of that region is resolved, the request is
+    /// signed for it and sent again, and the region of the bucket is
+    /// remembered for the next requests. Disabled by default.
+    pub fn follow_region_redirects(
+        self,
+        follow_region_redirects: bool,
+    ) -> Self {
+        Self {
+            follow_region_redirects,
+            ..self
+        }
+    }
+
+    /// Set whether requests to buckets go through their transfer
+    /// acceleration endpoint, like `bucket.s3-accelerate.amazonaws.com`.
+    ///
+    /// Acceleration must be enabled on the buckets, and their names can't
+    /// contain dots. Requests to other buckets fail with
+    /// [`Error::Endpoint`](crate::Error::Endpoint). Disabled by default.
+    pub fn accelerate(self, accelerate: bool) -> Self {
+        Self { accelerate, ..self }
+    }
+
+    /// Set the algorithm of the checksums sent with uploads, in an
+    /// `x-amz-checksum-*` header, or in a trailer of streaming bodies.
+    ///
+    /// Only endpoints supporting flexible checksums, like `PutObject`, get
+    /// them, unless the request has one already. None are sent by default.
+    pub fn checksum_algorithm(self, algorithm: ChecksumAlgorithm) -> Self {
+        Self {
+            checksum_algorithm: Some(algorithm),
+            ..self
+        }
+    }
+
+    /// Set whether the content of downloads is checked against the checksum
+    /// the server sends with it, failing with
+    /// [`Error::Checksum`](crate::Error::Checksum) if they differ.
+    ///
+    /// Only complete contents whose checksum isn't computed from the
+    /// checksums of their parts can be checked. Disabled by default.
+    pub fn validate_checksums(self, validate_checksums: bool) -> Self {
+        Self {
+            validate_checksums,
+            ..self
+        }
+    }
+
+    /// Set whether the bodies of responses encoded with gzip or deflate are
+    /// decompressed, according to their `Content-Encoding`, which is then
+    /// removed.
+    ///
+    /// S3 returns objects uploaded with a `Content-Encoding` as they are
+    /// stored, so this changes their content, which then no longer matches
+    /// their entity tag. Bodies read as a stream, like the content of
+    /// objects downloaded with [`Client::send_request_streaming_response`],
+    /// and partial content, like the parts of a
+    /// [`Download`](crate::transfer::Download), are never decompressed, and
+    /// checksums are checked before decompressing. Bodies decompressing
+    /// into more than 1 GiB are rejected. Disabled by default.
+    pub fn decompress_responses(self, decompress_responses: bool) -> Self {
+        Self {
+            decompress_responses,
+            ..self
+        }
+    }
+
+    /// Accommodate the known quirks of an S3-compatible server.
+    ///
+    /// This sets other settings of the builder, which can still be changed
+    /// afterwards:
+    ///
+    /// - [`Compatibility::MinIo`] addresses buckets in the path, and signs
+    ///   requests for `us-east-1` unless a region was set.
+    /// - [`Compatibility::Ceph`] addresses buckets in the path, and neither
+    ///   sends nor validates flexible checksums.
+    /// - [`Compatibility::Gcs`] sends requests to
+    ///   `https://storage.googleapis.com` and signs them for the `auto`
+    ///   region, unless an endpoint and a region were set, and neither sends
+    ///   nor validates flexible checksums.
+    pub fn compatibility(self, compatibility: Compatibility) -> Self {
+        match compatibility {
+            Compatibility::Aws => self,
+            Compatibility::MinIo => Self {
+                addressing_style: AddressingStyle::Path,
+                region: self.region.or_else(|| Some("us-east-1".to_owned())),
+                ..self
+            },
+            Compatibility::Ceph => Self {
+                addressing_style: AddressingStyle::Path,
+                checksum_algorithm: None,
+                validate_checksums: false,
+                ..self
+            },
+            Compatibility::Gcs => Self {
+                endpoint_url: self.endpoint_url.or_else(|| {
+                    Some("https://storage.googleapis.com".to_owned())
+                }),
+                region: self.region.or_else(|| Some("auto".to_owned())),
+                checksum_algorithm: None,
+                validate_checksums: false,
+                ..self
+            },
+        }
+    }
+
+    /// Set the proxies requests are sent through, like
+    /// [`Proxy::from_env`](crate::http_client::Proxy::from_env).
+    ///
+    /// This configures the default HTTP client, built by
+    /// [`build`](Self::build). Requests are sent directly by default.
+    pub fn proxy(self, proxy: Proxy) -> Self {
+        Self {
+            http_client_config: HttpClientConfig {
+                proxy: Some(proxy),
+                ..self.http_client_config
+            },
+            ..self
+        }
+    }
+
+    /// Send requests without signing them, like the `--no-sign-request`
+    /// option of the AWS CLI, to access public buckets without credentials.
+    ///
+    /// Single requests can be signed or not regardless by inserting an
+    /// [`Anonymous`](crate::Anonymous) in their extensions. Requests are signed by default.
+    pub fn anonymous(self, anonymous: bool) -> Self {
+        Self { anonymous, ..self }
+    }
+
+    /// Agree to pay for every request, with the `x-amz-request-payer`
+    /// header, to access buckets with requester pays enabled.
+    ///
+    /// Single requests can agree or not regardless by inserting a
+    /// [`RequesterPays`](crate::RequesterPays) in their extensions.
+    pub fn requester_pays(self, requester_pays: bool) -> Self {
+        Self {
+            requester_pays,
+            ..self
+        }
+    }
+
+    /// Encrypt the objects written by `PutObject`, `CreateMultipartUpload`
+    /// and `CopyObject` requests with a KMS key, unless they choose another
+    /// encryption.
+    ///
+    /// Single requests can be encrypted otherwise by inserting another
+    /// [`SseKms`] in their extensions.
+    pub fn sse_kms(self, sse_kms: SseKms) -> Self {
+        Self {
+            sse_kms: Some(sse_kms),
+            ..self
+        }
+    }
+
+    /// Set the source of the time requests are signed at, like a clock
+    /// reading the time from JavaScript where the system time isn't
+    /// available.
+    ///
+    /// The time is still corrected when the server reports a skew. Defaults
+    /// to [`SystemClock`].
+    pub fn clock(self, clock: impl Clock + 'static) -> Self {
+        Self {
+            clock: Some(Arc::new(clock)),
+            ..self
+        }
+    }
+
+    /// Set the source of the credentials requests are signed with.
+    ///
+    /// Defaults to [`ChainProvider::default`].
+    pub fn credentials_provider(
+        self,
+        credentials: impl CredentialsProvider + 'static,
+    ) -> Self {
+        Self {
+            credentials: Some(Arc::new(credentials)),
+            ..self
+        }
+    }
+
+    /// Set how long a request may take, including its retries.
+    ///
+    /// Timeouts rely on the timer of the Tokio runtime. There is none by
+    /// default.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeouts: self.timeouts.with_total(timeout),
+            ..self
+        }
+    }
+
+    /// Set how long an attempt may wait for the response, and then for each
+    /// chunk of a body read as a stream. Attempts timing out are retried.
+    ///
+    /// There is none by default.
+    pub fn read_timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeouts: self.timeouts.with_read(timeout),
+            ..self
+        }
+    }
+
+    /// Set how long connecting to the server may take.
+    ///
+    /// This configures the default HTTP client, built by
+    /// [`build`](Self::build). There is none by default.
+    pub fn connect_timeout(self, timeout: Duration) -> Self {
+        Self {
+            http_client_config: HttpClientConfig {
+                connect_timeout: Some(timeout),
+                ..self.http_client_config
+            },
+            ..self
+        }
+    }
+
+    /// Set how many idle connections are kept per host, to be reused by
+    /// later requests.
+    ///
+    /// This configures the default HTTP client, built by
+    /// [`build`](Self::build), which keeps as many as were opened by
+    /// default.
+    pub fn max_idle_connections_per_host(self, max: usize) -> Self {
+        Self {
+            http_client_config: HttpClientConfig {
+                max_idle_connections_per_host: Some(max),
+                ..self.http_client_config
+            },
+            ..self
+        }
+    }
+
+    /// Set how long idle connections are kept.
+    ///
+    /// This configures the default HTTP client, built by
+    /// [`build`](Self::build), which keeps them for 90 seconds by default.
+    pub fn idle_timeout(self, timeout: Duration) -> Self {
+        Self {
+            http_client_config: HttpClientConfig {
+                idle_timeout: Some(timeout),
+                ..self.http_client_config
+            },
+            ..self
+        }
+    }
+
+    /// Set how long connections are idle before the operating system sends
+    /// TCP keepalive probes on them, so that connections dropped by NATs or
+    /// load balancers are noticed.
+    ///
+    /// This sets the keepalive idle time of the sockets, the interval
+    /// between probes is left to the system. This configures the default
+    /// HTTP client, built by [`build`](Self::build), which doesn't send
+    /// keepalive probes by default.
+    pub fn tcp_keepalive(self, idle: Duration) -> Self {
+        Self {
+            http_client_config: HttpClientConfig {
+                tcp_keepalive: Some(idle),
+                ..self.http_client_config
+            },
+            ..self
+        }
+    }
+
+    /// Set how the addresses of servers are resolved, in place of the
+    /// resolver of the system, like a
+    /// [`CachingResolver`](crate::http_client::CachingResolver) controlling
+    /// how long they are cached or pinning the addresses of VPC endpoints.
+    ///
+    /// This configures the default HTTP client, built by
+    /// [`build`](Self::build).
+    pub fn dns_resolver(self, resolver: impl Resolve + 'static) -> Self {
+        Self {
+            http_client_config: HttpClientConfig {
+                resolver: Some(Arc::new(resolver)),
+                ..self.http_client_config
+            },
+            ..self
+        }
+    }
+
+    /// Set how failed requests are retried.
+    pub fn retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy,
+            ..self
+        }
+    }
+
+    /// Set the retry tokens shared by the requests of the client.
+    ///
+    /// Defaults to [`RetryQuota::default`].
+    pub fn retry_quota(self, retry_quota: RetryQuota) -> Self {
+        Self {
+            retry_quota: Some(retry_quota),
+            ..self
+        }
+    }
+
+    /// Set how slow `GET` and `HEAD` requests are hedged.
+    ///
+    /// Requests aren't hedged by default.
+    pub fn hedging_policy(self, hedging_policy: HedgingPolicy) -> Self {
+        Self {
+            hedging_policy: Some(hedging_policy),
+            ..self
+        }
+    }
+
+    /// Set how many requests may be in flight at once.
+    ///
+    /// Requests wait for another to complete before they are handed to the
+    /// HTTP client, and requests whose response body is read as a stream
+    /// count until the head of their response is received. Unlimited by
+    /// default.
+    pub fn max_concurrent_requests(self, max: usize) -> Self {
+        Self {
+            max_concurrent_requests: Some(max),
+            ..self
+        }
+    }
+
+    /// Set how many requests may be sent every second, including retries.
+    ///
+    /// Requests are spaced evenly, waiting on the timer of the Tokio runtime
+    /// before they are handed to the HTTP client, which protects servers
+    /// rate limiting their clients. Unlimited by default.
+    pub fn max_requests_per_second(self, max: u32) -> Self {
+        Self {
+            max_requests_per_second: Some(max),
+            ..self
+        }
+    }
+
+    /// Set the `User-Agent` header of requests.
+    ///
+    /// Defaults to `s3ers/` followed by the version of this crate.
+    pub fn user_agent(self, user_agent: HeaderValue) -> Self {
+        Self {
+            user_agent: Some(user_agent),
+            ..self
+        }
+    }
+
+    /// Set the receiver of the metrics of requests.
+    ///
+    /// Metrics aren't recorded by default.
+    pub fn metrics_sink(self, metrics_sink: impl MetricsSink) -> Self {
+        Self {
+            metrics_sink: Some(Arc::new(metrics_sink)),
+            ..self
+        }
+    }
+
+    /// Finish building the [`Client`], with the default HTTP client.
+    pub fn build<C: DefaultConstructibleHttpClient>(self) -> Client<C> {
+        let http_client = C::with_config(&self.http_client_config);
+        self.http_client(http_client)
+    }
+
+    /// Finish building the [`Client`], with the given HTTP client.
+    pub fn http_client<C: HttpClient>(self, http_client: C) -> Client<C> {
+        let region = self
+            .region
+            .or_else(s3ers_credentials::default_region)
+            .unwrap_or_else(|| DEFAULT_REGION.to_owned());
+        let endpoint_resolver = self
+            .endpoint_resolver
+            .unwrap_or_else(|| Arc::new(AwsEndpointResolver));
+        let credentials = self
+            .credentials
+            .unwrap_or_else(|| Arc::new(ChainProvider::default()));
+        let user_agent = self.user_agent.unwrap_or_else(|| {
+            HeaderValue::from_static(concat!(
+                "s3ers/",
+                env!("CARGO_PKG_VERSION")
+            ))
+        });
+
+        Client(
+            Arc::new(ClientData {
+                endpoint_url: self.endpoint_url,
+                endpoint_resolver,
+                use_dual_stack: self.use_dual_stack,
+                use_fips: self.use_fips,
+                region,
+                addressing_style: self.addressing_style,
+                follow_region_redirects: self.follow_region_redirects,
+                bucket_regions: Mutex::default(),
+                accelerate: self.accelerate,
+                checksum_algorithm: self.checksum_algorithm,
+                validate_checksums: self.validate_checksums,
+                decompress_responses: self.decompress_responses,
+                http_client,
+                credentials,
+                anonymous: self.anonymous,
+                requester_pays: self.requester_pays,
+                sse_kms: self.sse_kms,
+                clock: SkewCorrectedClock::new(
+                    self.clock.unwrap_or_else(|| Arc::new(SystemClock)),
+                ),
+                timeouts: self.timeouts,
+                retry_policy: self.retry_policy,
+                retry_quota: self.retry_quota.unwrap_or_default(),
+                hedging: self.hedging_policy.map(Hedging::new),
+                limiter: Limiter::new(
+                    self.max_concurrent_requests,
+                    self.max_requests_per_second,
+                ),
+                user_agent,
+                metrics_sink: self.metrics_sink,
+            }),
+            Middleware::default(),
+        )
+    }
+}
+
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("endpoint_url", &self.endpoint_url)
+            .field("use_dual_stack", &self.use_dual_stack)
+            .field("use_fips", &self.use_fips)
+            .field("region", &self.region)
+            .field("addressing_style", &self.addressing_style)
+            .field("follow_region_redirects", &self.follow_region_redirects)
+            .field("accelerate", &self.accelerate)
+            .field("checksum_algorithm", &self.checksum_algorithm)
+            .field("validate_checksums", &self.validate_checksums)
+            .field("decompress_responses", &self.decompress_responses)
+            .field("anonymous", &self.anonymous)
+            .field("requester_pays", &self.requester_pays)
+            .field("sse_kms", &self.sse_kms)
+            .field("clock", &self.clock)
+            .field("timeouts", &self.timeouts)
+            .field("http_client_config", &self.http_client_config)
+            .field("retry_policy", &self.retry_policy)
+            .field("retry_quota", &self.retry_quota)
+            .field("hedging_policy", &self.hedging_policy)
+            .field("max_concurrent_requests", &self.max_concurrent_requests)
+            .field("max_requests_per_second", &self.max_requests_per_second)
+            .field("user_agent", &self.user_agent)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,105 @@
+//! Cancellation of requests and transfers.
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use futures_util::future::{self, Either};
+use tokio::sync::Notify;
+
+use crate::Error;
+
+/// A token cancelling the requests and transfers it is given to.
+///
+/// A request is given a token by inserting it in the extensions of the
+/// request, in the closure of
+/// [`Client::send_customized_request`](crate::Client::send_customized_request),
+/// and a transfer with its `cancellation_token` method. Cancelling the
+/// token makes them fail with [`Error::Cancelled`] right away, dropping
+/// their requests in flight, and aborting their multipart uploads.
+///
+/// Clones of a token cancel the same requests.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    /// Creates a token that isn't cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the requests and transfers given the token.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        self.0.notify.notify_waiters();
+    }
+
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Waits until the token is cancelled.
+    pub async fn cancelled(&self) {
+        let notified = self.0.notify.notified();
+        let mut notified = Box::pin(notified);
+        // Registered before checking the flag, not to miss a cancellation
+        // in between.
+        notified.as_mut().enable();
+        if !self.is_cancelled() {
+            notified.await;
+        }
+    }
+}
+
+/// Fails with a cancellation error if `token` is cancelled before `future`
+/// completes.
+pub(crate) async fn cancellable<T, E>(
+    token: Option<&CancellationToken>,
+    future: impl Future<Output = Result<T, Error<E>>>,
+) -> Result<T, Error<E>> {
+    let token = match token {
+        Some(token) => token,
+        None => return future.await,
+    };
+
+    let future = Box::pin(future);
+    let cancelled = Box::pin(token.cancelled());
+    match future::select(future, cancelled).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(Error::Cancelled),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_executor::block_on;
+    use futures_util::future;
+
+    use super::{cancellable, CancellationToken};
+    use crate::Error;
+
+    #[test]
+    fn cancel() {
+        let token = CancellationToken::new();
+        let result =
+            block_on(cancellable::<(), ()>(Some(&token), async { Ok(()) }));
+        assert!(result.is_ok());
+
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+        let result =
+            block_on(cancellable::<(), ()>(Some(&token), future::pending()));
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+}
@@ -0,0 +1,223 @@
+//! Flexible checksums of request and response bodies.
+
+use std::{
+    error::Error as StdError,
+    fmt, io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::stream::{Stream, StreamExt};
+use http::{header::HeaderName, HeaderMap, HeaderValue};
+use s3ers_signature::chunked::{Checksum, ChecksumAlgorithm};
+
+use crate::ByteStream;
+
+/// The header asking the server to return the checksum of the content.
+pub(crate) const X_AMZ_CHECKSUM_MODE: &str = "x-amz-checksum-mode";
+
+/// The prefix of the headers holding checksums.
+const CHECKSUM_PREFIX: &str = "x-amz-checksum-";
+
+/// The checksum of a body differs from the one the server sent with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ChecksumMismatch {
+    /// The algorithm of the checksums.
+    pub algorithm: ChecksumAlgorithm,
+
+    /// The base64-encoded checksum sent by the server.
+    pub expected: String,
+
+    /// The base64-encoded checksum of the body received.
+    pub actual: String,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the body doesn't match its checksum: expected {} `{}`, got `{}`",
+            self.algorithm.header_name(),
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+impl StdError for ChecksumMismatch {}
+
+/// Whether a checksum header was set already.
+pub(crate) fn has_checksum(headers: &HeaderMap) -> bool {
+    headers
+        .keys()
+        .any(|name| name.as_str().starts_with(CHECKSUM_PREFIX))
+}
+
+/// Sets the checksum header of a body.
+pub(crate) fn insert_checksum(
+    headers: &mut HeaderMap,
+    algorithm: ChecksumAlgorithm,
+    body: &[u8],
+) {
+    headers.insert(
+        HeaderName::from_static(algorithm.header_name()),
+        HeaderValue::from_str(&Checksum::compute(algorithm, body)).unwrap(),
+    );
+}
+
+/// Returns the checksum of the content of a response, if it has one that
+/// can be checked.
+///
+/// The checksums of objects uploaded in parts are checksums of the checksums
+/// of their parts, which are followed by the number of parts and can't be
+/// computed from the content.
+fn expected_checksum(headers: &HeaderMap) -> Option<(ChecksumAlgorithm, &str)> {
+    headers.iter().find_map(|(name, value)| {
+        let algorithm = ChecksumAlgorithm::from_header_name(name.as_str())?;
+        let value = value.to_str().ok()?;
+        (!value.contains('-')).then_some((algorithm, value))
+    })
+}
+
+/// Checks the body of a response against its checksum, if it has one.
+pub(crate) fn verify<B: AsRef<[u8]>>(
+    response: &http::Response<B>,
+) -> Result<(), ChecksumMismatch> {
+    let (algorithm, expected) = match expected_checksum(response.headers()) {
+        Some(expected) => expected,
+        None => return Ok(()),
+    };
+
+    let actual = Checksum::compute(algorithm, response.body().as_ref());
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(ChecksumMismatch {
+            algorithm,
+            expected: expected.to_owned(),
+            actual,
+        })
+    }
+}
+
+/// Checks the body of a response read as a stream against its checksum, if
+/// it has one.
+///
+/// A mismatch is reported as an [`io::ErrorKind::InvalidData`] error
+/// wrapping a [`ChecksumMismatch`] at the end of the stream.
+pub(crate) fn verify_stream(
+    headers: &HeaderMap,
+    body: ByteStream,
+) -> ByteStream {
+    match expected_checksum(headers) {
+        Some((algorithm, expected)) => ByteStream::new(VerifiedStream {
+            body,
+            checksum: Some(Checksum::new(algorithm)),
+            expected: expected.to_owned(),
+        }),
+        None => body,
+    }
+}
+
+/// A stream checking its bytes against a checksum once they are all read.
+struct VerifiedStream {
+    body: ByteStream,
+    checksum: Option<Checksum>,
+    expected: String,
+}
+
+impl Stream for VerifiedStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let checksum = match &mut this.checksum {
+            Some(checksum) => checksum,
+            None => return Poll::Ready(None),
+        };
+
+        match this.body.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(bytes))) => {
+                checksum.update(&bytes);
+                Poll::Ready(Some(Ok(bytes)))
+            }
+            Poll::Ready(Some(Err(err))) => {
+                this.checksum = None;
+                Poll::Ready(Some(Err(err)))
+            }
+            Poll::Ready(None) => {
+                let checksum = this.checksum.take().unwrap();
+                let algorithm = checksum.algorithm();
+                let actual = checksum.finalize();
+                if actual == this.expected {
+                    return Poll::Ready(None);
+                }
+                let mismatch = ChecksumMismatch {
+                    algorithm,
+                    expected: this.expected.clone(),
+                    actual,
+                };
+                Poll::Ready(Some(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    mismatch,
+                ))))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_executor::block_on;
+    use http::HeaderMap;
+    use s3ers_signature::chunked::ChecksumAlgorithm;
+
+    use super::{verify, verify_stream, ChecksumMismatch};
+    use crate::ByteStream;
+
+    fn response(checksum: &str) -> http::Response<Vec<u8>> {
+        http::Response::builder()
+            .header("x-amz-checksum-crc32c", checksum)
+            .body(b"content".to_vec())
+            .unwrap()
+    }
+
+    #[test]
+    fn verify_bodies() {
+        let crc32c = "Ya91Mw==";
+        assert_eq!(verify(&response(crc32c)), Ok(()));
+        assert_eq!(verify(&response("AAAAAA==-3")), Ok(()));
+        assert_eq!(
+            verify(&response("AAAAAA==")),
+            Err(ChecksumMismatch {
+                algorithm: ChecksumAlgorithm::Crc32c,
+                expected: "AAAAAA==".to_owned(),
+                actual: crc32c.to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn verify_streams() {
+        let read = |checksum: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-amz-checksum-crc32c", checksum.parse().unwrap());
+            let body = ByteStream::from(b"content".to_vec());
+            block_on(verify_stream(&headers, body).collect())
+        };
+
+        assert_eq!(read("Ya91Mw==").unwrap(), b"content");
+        let err = read("AAAAAA==").unwrap_err();
+        assert!(err
+            .into_inner()
+            .unwrap()
+            .downcast::<ChecksumMismatch>()
+            .is_ok());
+    }
+}
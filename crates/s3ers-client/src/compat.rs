@@ -0,0 +1,25 @@
+//! Presets for the quirks of S3-compatible servers.
+
+/// An S3-compatible server whose known differences from S3 the client
+/// accommodates, set with
+/// [`ClientBuilder::compatibility`](crate::ClientBuilder::compatibility).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Compatibility {
+    /// Amazon S3 itself, which needs no adjustment.
+    Aws,
+
+    /// MinIO, which is usually deployed without DNS records for buckets,
+    /// and whose default region is `us-east-1`.
+    MinIo,
+
+    /// The Ceph Object Gateway, which is usually deployed without DNS
+    /// records for buckets, and whose older releases reject flexible
+    /// checksums and the trailers of `aws-chunked` bodies.
+    Ceph,
+
+    /// The XML API of Google Cloud Storage, reached with HMAC keys, which
+    /// signs requests for the `auto` region and doesn't support flexible
+    /// checksums.
+    Gcs,
+}
@@ -0,0 +1,96 @@
+//! Configuration of single requests.
+
+use std::{fmt, sync::Arc};
+
+use s3ers_credentials::CredentialsProvider;
+
+use crate::{RetryPolicy, Timeouts};
+
+/// Settings of a single request overriding those of the client, given to
+/// [`Client::send_request_with`](crate::Client::send_request_with).
+///
+/// The settings left unset are the ones of the client.
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub struct RequestConfig {
+    /// How long the request may take.
+    pub timeouts: Option<Timeouts>,
+
+    /// How the request is retried.
+    pub retry_policy: Option<RetryPolicy>,
+
+    /// The source of the credentials the request is signed with.
+    pub credentials: Option<Arc<dyn CredentialsProvider>>,
+
+    /// The region the request is sent to and signed for.
+    pub region: Option<String>,
+}
+
+impl RequestConfig {
+    /// No overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how long the request may take.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = Some(timeouts);
+        self
+    }
+
+    /// Sets how the request is retried.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Sets the source of the credentials the request is signed with.
+    pub fn with_credentials(
+        mut self,
+        credentials: impl CredentialsProvider + 'static,
+    ) -> Self {
+        self.credentials = Some(Arc::new(credentials));
+        self
+    }
+
+    /// Sets the region the request is sent to and signed for.
+    pub fn with_region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Inserts the overrides in the extensions of a request.
+    pub(crate) fn apply<B>(self, http_request: &mut http::Request<B>) {
+        let extensions = http_request.extensions_mut();
+        if let Some(timeouts) = self.timeouts {
+            extensions.insert(timeouts);
+        }
+        if let Some(retry_policy) = self.retry_policy {
+            extensions.insert(retry_policy);
+        }
+        if let Some(credentials) = self.credentials {
+            extensions.insert(RequestCredentials(credentials));
+        }
+        if let Some(region) = self.region {
+            extensions.insert(RequestRegion(region));
+        }
+    }
+}
+
+impl fmt::Debug for RequestConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestConfig")
+            .field("timeouts", &self.timeouts)
+            .field("retry_policy", &self.retry_policy)
+            .field("region", &self.region)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The credentials of a request, in its extensions.
+#[derive(Clone)]
+pub(crate) struct RequestCredentials(pub(crate) Arc<dyn CredentialsProvider>);
+
+/// The region of a request, in its extensions.
+#[derive(Clone, Debug)]
+pub(crate) struct RequestRegion(pub(crate) String);
@@ -0,0 +1,565 @@
+//! Decompression of response bodies, according to their `Content-Encoding`.
+//!
+//! Bodies are inflated whole, with a decoder of the DEFLATE format
+//! (RFC 1951) and of its gzip (RFC 1952) and zlib (RFC 1950) wrappers, up
+//! to [`MAX_LENGTH`] bytes. Partial content, the range of a body encoded
+//! whole, can't be decompressed and is left as it is.
+
+use std::{convert::TryInto, io};
+
+use http::{
+    header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE},
+    StatusCode,
+};
+
+/// The longest decompressed body, so that a small compressed body can't
+/// take up all the memory.
+const MAX_LENGTH: usize = 1 << 30;
+
+/// The body of a response, decompressed or not.
+#[derive(Debug)]
+pub(crate) enum Body<B> {
+    /// The body as it was received.
+    Received(B),
+
+    /// The decompressed body.
+    Decompressed(Vec<u8>),
+}
+
+impl<B: AsRef<[u8]>> AsRef<[u8]> for Body<B> {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Received(body) => body.as_ref(),
+            Self::Decompressed(body) => body,
+        }
+    }
+}
+
+/// Decompresses the body of a response encoded with gzip or deflate,
+/// removing its `Content-Encoding`.
+pub(crate) fn decompress<B: AsRef<[u8]>>(
+    response: http::Response<B>,
+) -> io::Result<http::Response<Body<B>>> {
+    let encoding = response
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|encoding| encoding.to_str().ok())
+        .map(|encoding| encoding.trim().to_ascii_lowercase());
+    let partial = response.status() == StatusCode::PARTIAL_CONTENT
+        || response.headers().contains_key(CONTENT_RANGE);
+    let decompress: fn(&[u8], usize) -> io::Result<Vec<u8>> =
+        match encoding.as_deref() {
+            _ if partial || response.body().as_ref().is_empty() => {
+                return Ok(response.map(Body::Received))
+            }
+            Some("gzip" | "x-gzip") => gunzip,
+            Some("deflate") => zlib_inflate,
+            _ => return Ok(response.map(Body::Received)),
+        };
+
+    let (mut parts, body) = response.into_parts();
+    let body = decompress(body.as_ref(), MAX_LENGTH)?;
+    parts.headers.remove(CONTENT_ENCODING);
+    parts.headers.insert(CONTENT_LENGTH, body.len().into());
+    Ok(http::Response::from_parts(parts, Body::Decompressed(body)))
+}
+
+/// Returns an error about invalid compressed data.
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Returns the error of data decompressing into too long a body.
+fn too_long() -> io::Error {
+    invalid("decompressed body too long")
+}
+
+/// Decompresses gzip members, into at most `limit` bytes.
+fn gunzip(mut data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    const FHCRC: u8 = 2;
+    const FEXTRA: u8 = 4;
+    const FNAME: u8 = 8;
+    const FCOMMENT: u8 = 16;
+
+    let mut output = Vec::new();
+    while !data.is_empty() {
+        if data.len() < 10 || data[..3] != [0x1f, 0x8b, 8] {
+            return Err(invalid("invalid gzip header"));
+        }
+        let flags = data[3];
+        let mut header = &data[10..];
+        if flags & FEXTRA != 0 {
+            let length = match header {
+                [low, high, ..] => u16::from_le_bytes([*low, *high]) as usize,
+                _ => return Err(invalid("truncated gzip header")),
+            };
+            header = header
+                .get(2 + length..)
+                .ok_or_else(|| invalid("truncated gzip header"))?;
+        }
+        for flag in [FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                let end = header
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .ok_or_else(|| invalid("truncated gzip header"))?;
+                header = &header[end + 1..];
+            }
+        }
+        if flags & FHCRC != 0 {
+            header = header
+                .get(2..)
+                .ok_or_else(|| invalid("truncated gzip header"))?;
+        }
+
+        let start = output.len();
+        let read = inflate(header, &mut output, limit)?;
+        let trailer = header
+            .get(read..read + 8)
+            .ok_or_else(|| invalid("truncated gzip trailer"))?;
+        let (crc, size) = trailer.split_at(4);
+        let member = &output[start..];
+        if u32::from_le_bytes(crc.try_into().unwrap()) != crc32(member)
+            || u32::from_le_bytes(size.try_into().unwrap())
+                != member.len() as u32
+        {
+            return Err(invalid("gzip data doesn't match its checksum"));
+        }
+        data = &header[read + 8..];
+    }
+    Ok(output)
+}
+
+/// Decompresses zlib data, or raw DEFLATE data, which some servers send as
+/// `deflate` instead, into at most `limit` bytes.
+fn zlib_inflate(data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let is_zlib = match data {
+        [cmf, flg, ..] => {
+            cmf & 0x0f == 8
+                && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0
+        }
+        _ => false,
+    };
+    if !is_zlib {
+        inflate(data, &mut output, limit)?;
+        return Ok(output);
+    }
+    if data[1] & 0x20 != 0 {
+        return Err(invalid("zlib data with a preset dictionary"));
+    }
+
+    let read = inflate(&data[2..], &mut output, limit)?;
+    let adler = data
+        .get(2 + read..2 + read + 4)
+        .ok_or_else(|| invalid("truncated zlib trailer"))?;
+    if u32::from_be_bytes(adler.try_into().unwrap()) != adler32(&output) {
+        return Err(invalid("zlib data doesn't match its checksum"));
+    }
+    Ok(output)
+}
+
+/// Returns the CRC-32 of gzip.
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut n = 0;
+        while n < 256 {
+            let mut c = n as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[n] = c;
+            n += 1;
+        }
+        table
+    };
+
+    !data.iter().fold(!0, |crc, &byte| {
+        TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Returns the Adler-32 checksum of zlib.
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+
+    let (mut a, mut b) = (1, 0);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    b << 16 | a
+}
+
+/// Reads the bits of DEFLATE data, from the least significant bit of each
+/// byte.
+struct Bits<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl Bits<'_> {
+    /// Reads `count` bits, at most 16.
+    fn read(&mut self, count: u32) -> io::Result<u32> {
+        while self.count < count {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| invalid("truncated DEFLATE data"))?;
+            self.position += 1;
+            self.buffer |= u32::from(byte) << self.count;
+            self.count += 8;
+        }
+        let bits = self.buffer & ((1 << count) - 1);
+        self.buffer >>= count;
+        self.count -= count;
+        Ok(bits)
+    }
+
+    /// Drops the bits left in the current byte.
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+}
+
+/// A canonical Huffman code.
+struct Huffman {
+    /// How many codes have each length, up to 15 bits.
+    counts: [u16; 16],
+
+    /// The symbols, ordered by their code.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Builds the code with the given lengths of the codes of the symbols.
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(invalid("over-subscribed Huffman code"));
+            }
+        }
+
+        let mut offsets = [0; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+
+    /// Decodes a symbol.
+    fn decode(&self, bits: &mut Bits<'_>) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for &count in &self.counts[1..] {
+            code |= bits.read(1)? as i32;
+            let count = i32::from(count);
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid Huffman code"))
+    }
+}
+
+/// The base lengths of the length symbols, from 257.
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59,
+    67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+
+/// The extra bits of the length symbols, from 257.
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5,
+    5, 5, 5, 0,
+];
+
+/// The base distances of the distance symbols.
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513,
+    769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// The extra bits of the distance symbols.
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10,
+    11, 11, 12, 12, 13, 13,
+];
+
+/// The order in which the lengths of the code length code are sent.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Inflates DEFLATE data into `output`, returning how many bytes of it were
+/// read, or an error if `output` would be longer than `limit` bytes.
+fn inflate(
+    data: &[u8],
+    output: &mut Vec<u8>,
+    limit: usize,
+) -> io::Result<usize> {
+    let mut bits = Bits {
+        data,
+        position: 0,
+        buffer: 0,
+        count: 0,
+    };
+    loop {
+        let last = bits.read(1)? == 1;
+        match bits.read(2)? {
+            0 => stored(&mut bits, output, limit)?,
+            1 => {
+                let (lengths, distances) = fixed_codes()?;
+                codes(&mut bits, output, limit, &lengths, &distances)?;
+            }
+            2 => {
+                let (lengths, distances) = dynamic_codes(&mut bits)?;
+                codes(&mut bits, output, limit, &lengths, &distances)?;
+            }
+            _ => return Err(invalid("invalid DEFLATE block type")),
+        }
+        if last {
+            return Ok(bits.position);
+        }
+    }
+}
+
+/// Copies a stored block.
+fn stored(
+    bits: &mut Bits<'_>,
+    output: &mut Vec<u8>,
+    limit: usize,
+) -> io::Result<()> {
+    bits.align();
+    let header = bits
+        .data
+        .get(bits.position..bits.position + 4)
+        .ok_or_else(|| invalid("truncated DEFLATE data"))?;
+    let length = u16::from_le_bytes([header[0], header[1]]);
+    if length != !u16::from_le_bytes([header[2], header[3]]) {
+        return Err(invalid("invalid stored block length"));
+    }
+    let start = bits.position + 4;
+    let block = bits
+        .data
+        .get(start..start + length as usize)
+        .ok_or_else(|| invalid("truncated DEFLATE data"))?;
+    if output.len() + block.len() > limit {
+        return Err(too_long());
+    }
+    output.extend_from_slice(block);
+    bits.position = start + length as usize;
+    Ok(())
+}
+
+/// Returns the codes of blocks compressed with fixed codes.
+fn fixed_codes() -> io::Result<(Huffman, Huffman)> {
+    let mut lengths = [0; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+/// Reads the codes of a block compressed with dynamic codes.
+fn dynamic_codes(bits: &mut Bits<'_>) -> io::Result<(Huffman, Huffman)> {
+    let length_count = bits.read(5)? as usize + 257;
+    let distance_count = bits.read(5)? as usize + 1;
+    let code_count = bits.read(4)? as usize + 4;
+    if length_count > 286 || distance_count > 30 {
+        return Err(invalid("invalid DEFLATE code counts"));
+    }
+
+    let mut code_lengths = [0; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_count] {
+        code_lengths[symbol] = bits.read(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0; length_count + distance_count];
+    let mut index = 0;
+    while index < lengths.len() {
+        let (length, repeat) = match code_lengths.decode(bits)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 if index > 0 => (lengths[index - 1], 3 + bits.read(2)?),
+            17 => (0, 3 + bits.read(3)?),
+            18 => (0, 11 + bits.read(7)?),
+            _ => return Err(invalid("invalid DEFLATE code lengths")),
+        };
+        let end = index + repeat as usize;
+        if end > lengths.len() {
+            return Err(invalid("invalid DEFLATE code lengths"));
+        }
+        lengths[index..end].fill(length);
+        index = end;
+    }
+    if lengths[256] == 0 {
+        return Err(invalid("DEFLATE block without an end"));
+    }
+
+    let (lengths, distances) = lengths.split_at(length_count);
+    Ok((Huffman::new(lengths)?, Huffman::new(distances)?))
+}
+
+/// Decodes a block compressed with the given codes.
+fn codes(
+    bits: &mut Bits<'_>,
+    output: &mut Vec<u8>,
+    limit: usize,
+    lengths: &Huffman,
+    distances: &Huffman,
+) -> io::Result<()> {
+    loop {
+        let symbol = lengths.decode(bits)? as usize;
+        if symbol < 256 {
+            if output.len() == limit {
+                return Err(too_long());
+            }
+            output.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+
+        let symbol = symbol - 257;
+        if symbol >= LENGTH_BASES.len() {
+            return Err(invalid("invalid DEFLATE length"));
+        }
+        let length = LENGTH_BASES[symbol] as usize
+            + bits.read(u32::from(LENGTH_EXTRA[symbol]))? as usize;
+        let symbol = distances.decode(bits)? as usize;
+        if symbol >= DISTANCE_BASES.len() {
+            return Err(invalid("invalid DEFLATE distance"));
+        }
+        let distance = DISTANCE_BASES[symbol] as usize
+            + bits.read(u32::from(DISTANCE_EXTRA[symbol]))? as usize;
+        if distance > output.len() {
+            return Err(invalid("DEFLATE distance too far back"));
+        }
+        if output.len() + length > limit {
+            return Err(too_long());
+        }
+        let start = output.len() - distance;
+        for index in start..start + length {
+            output.push(output[index]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{
+        header::{CONTENT_ENCODING, CONTENT_LENGTH},
+        StatusCode,
+    };
+
+    use super::{decompress, gunzip, zlib_inflate};
+
+    /// `hello hello hello hello\n` compressed by gzip, with a file name.
+    const GZIP: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0x61, 0x00,
+        0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0xb9, 0x00, 0x00,
+        0x88, 0x59, 0x0b, 0x18, 0x00, 0x00, 0x00,
+    ];
+
+    /// `abracadabra abracadabra abracadabra, said the magician\n` compressed
+    /// by zlib.
+    const ZLIB: &[u8] = &[
+        0x78, 0xda, 0x4b, 0x4c, 0x2a, 0x4a, 0x4c, 0x4e, 0x4c, 0x49, 0x04, 0x52,
+        0x0a, 0x89, 0xd8, 0xd9, 0x3a, 0x0a, 0xc5, 0x89, 0x99, 0x29, 0x0a, 0x25,
+        0x19, 0xa9, 0x0a, 0xb9, 0x89, 0xe9, 0x99, 0xc9, 0x99, 0x89, 0x79, 0x5c,
+        0x00, 0x3b, 0xa8, 0x13, 0xee,
+    ];
+
+    #[test]
+    fn decompress_gzip() {
+        let response = http::Response::builder()
+            .header(CONTENT_ENCODING, "gzip")
+            .header(CONTENT_LENGTH, GZIP.len())
+            .body(GZIP)
+            .unwrap();
+        let response = decompress(response).unwrap();
+        assert_eq!(response.body().as_ref(), b"hello hello hello hello\n");
+        assert!(!response.headers().contains_key(CONTENT_ENCODING));
+        assert_eq!(response.headers()[CONTENT_LENGTH], "24");
+
+        let mut corrupted = GZIP.to_vec();
+        corrupted[GZIP.len() - 5] ^= 1;
+        let response = http::Response::builder()
+            .header(CONTENT_ENCODING, "gzip")
+            .body(corrupted)
+            .unwrap();
+        assert!(decompress(response).is_err());
+    }
+
+    #[test]
+    fn decompress_deflate() {
+        let response = http::Response::builder()
+            .header(CONTENT_ENCODING, "deflate")
+            .body(ZLIB)
+            .unwrap();
+        let response = decompress(response).unwrap();
+        assert_eq!(
+            response.body().as_ref(),
+            &b"abracadabra abracadabra abracadabra, said the magician\n"[..]
+        );
+    }
+
+    #[test]
+    fn limit_decompressed_length() {
+        assert_eq!(gunzip(GZIP, 24).unwrap().len(), 24);
+        assert!(gunzip(GZIP, 23).is_err());
+        assert!(zlib_inflate(ZLIB, 10).is_err());
+    }
+
+    #[test]
+    fn keep_partial_content() {
+        let response = http::Response::builder()
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(CONTENT_ENCODING, "gzip")
+            .header("content-range", "bytes 0-7/31")
+            .body(&GZIP[..8])
+            .unwrap();
+        let response = decompress(response).unwrap();
+        assert_eq!(response.body().as_ref(), &GZIP[..8]);
+        assert_eq!(response.headers()[CONTENT_ENCODING], "gzip");
+    }
+
+    #[test]
+    fn keep_identity_bodies() {
+        let response = http::Response::new(b"content");
+        let response = decompress(response).unwrap();
+        assert_eq!(response.body().as_ref(), b"content");
+    }
+}
@@ -0,0 +1,209 @@
+//! Resolving the URL of the S3 service.
+
+use std::{error::Error as StdError, fmt, str::FromStr};
+
+use http::{uri::Scheme, Uri};
+
+/// The validated URL of an S3 service, like
+/// `https://s3.eu-west-1.amazonaws.com` or `http://localhost:9000`.
+///
+/// It is an absolute `http` or `https` URL without query or fragment, whose
+/// path, if any, prefixes the paths of requests. It can be given to
+/// [`ClientBuilder::endpoint_url`](crate::ClientBuilder::endpoint_url) to
+/// catch mistyped URLs when parsing the configuration rather than when
+/// sending the first request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EndpointUrl(String);
+
+impl EndpointUrl {
+    /// Returns the URL as a string, without trailing slash.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for EndpointUrl {
+    type Err = InvalidEndpointUrl;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason| InvalidEndpointUrl {
+            url: url.to_owned(),
+            reason,
+        };
+        let uri: Uri = url.parse().map_err(|_| invalid("not a URL"))?;
+        match uri.scheme() {
+            Some(scheme)
+                if *scheme == Scheme::HTTP || *scheme == Scheme::HTTPS => {}
+            _ => return Err(invalid("the scheme isn't `http` or `https`")),
+        }
+        if uri.host().is_none_or(str::is_empty) {
+            return Err(invalid("there is no host"));
+        }
+        if uri.query().is_some() {
+            return Err(invalid("it has a query"));
+        }
+        Ok(Self(url.trim_end_matches('/').to_owned()))
+    }
+}
+
+impl fmt::Display for EndpointUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<EndpointUrl> for String {
+    fn from(url: EndpointUrl) -> Self {
+        url.0
+    }
+}
+
+/// An error when parsing an [`EndpointUrl`].
+#[derive(Clone, Debug)]
+pub struct InvalidEndpointUrl {
+    url: String,
+    reason: &'static str,
+}
+
+impl fmt::Display for InvalidEndpointUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid endpoint `{}`: {}", self.url, self.reason)
+    }
+}
+
+impl StdError for InvalidEndpointUrl {}
+
+/// The parameters an endpoint is resolved for.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct EndpointParams<'a> {
+    /// The region of the endpoint.
+    pub region: &'a str,
+
+    /// Whether the endpoint should be reachable over both IPv4 and IPv6.
+    pub use_dual_stack: bool,
+
+    /// Whether the endpoint should use FIPS 140-2 validated cryptography.
+    pub use_fips: bool,
+
+    /// Whether this is the transfer acceleration endpoint, under which
+    /// buckets are addressed.
+    pub accelerate: bool,
+}
+
+/// Resolves the URL of the S3 service, like
+/// `https://s3.eu-west-1.amazonaws.com`, for a region.
+///
+/// This allows S3-compatible services whose URLs depend on the region to be
+/// used like AWS. Closures taking [`EndpointParams`] implement it.
+pub trait EndpointResolver: Send + Sync {
+    /// Returns the URL of the S3 service for the given parameters.
+    fn resolve_endpoint(&self, params: &EndpointParams<'_>) -> String;
+}
+
+impl<F> EndpointResolver for F
+where
+    F: Fn(&EndpointParams<'_>) -> String + Send + Sync,
+{
+    fn resolve_endpoint(&self, params: &EndpointParams<'_>) -> String {
+        self(params)
+    }
+}
+
+/// Resolves the endpoints of AWS.
+///
+/// FIPS endpoints don't support transfer acceleration, which takes
+/// precedence over them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AwsEndpointResolver;
+
+impl EndpointResolver for AwsEndpointResolver {
+    fn resolve_endpoint(&self, params: &EndpointParams<'_>) -> String {
+        let domain = if params.region.starts_with("cn-") {
+            "amazonaws.com.cn"
+        } else {
+            "amazonaws.com"
+        };
+        let dual_stack = if params.use_dual_stack {
+            ".dualstack"
+        } else {
+            ""
+        };
+
+        if params.accelerate {
+            return format!("https://s3-accelerate{}.{}", dual_stack, domain);
+        }
+        let service = if params.use_fips { "s3-fips" } else { "s3" };
+        format!(
+            "https://{}{}.{}.{}",
+            service, dual_stack, params.region, domain
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AwsEndpointResolver, EndpointParams, EndpointResolver, EndpointUrl,
+    };
+
+    fn resolve(
+        region: &str,
+        use_dual_stack: bool,
+        use_fips: bool,
+        accelerate: bool,
+    ) -> String {
+        AwsEndpointResolver.resolve_endpoint(&EndpointParams {
+            region,
+            use_dual_stack,
+            use_fips,
+            accelerate,
+        })
+    }
+
+    #[test]
+    fn aws_endpoints() {
+        assert_eq!(
+            resolve("eu-west-1", false, false, false),
+            "https://s3.eu-west-1.amazonaws.com"
+        );
+        assert_eq!(
+            resolve("eu-west-1", true, false, false),
+            "https://s3.dualstack.eu-west-1.amazonaws.com"
+        );
+        assert_eq!(
+            resolve("us-gov-west-1", false, true, false),
+            "https://s3-fips.us-gov-west-1.amazonaws.com"
+        );
+        assert_eq!(
+            resolve("us-east-1", true, true, false),
+            "https://s3-fips.dualstack.us-east-1.amazonaws.com"
+        );
+        assert_eq!(
+            resolve("cn-north-1", false, false, false),
+            "https://s3.cn-north-1.amazonaws.com.cn"
+        );
+        assert_eq!(
+            resolve("eu-west-1", true, false, true),
+            "https://s3-accelerate.dualstack.amazonaws.com"
+        );
+    }
+
+    #[test]
+    fn parse_endpoint_urls() {
+        let url: EndpointUrl = "http://localhost:9000/".parse().unwrap();
+        assert_eq!(url.as_str(), "http://localhost:9000");
+        assert!("https://minio.example.com/s3"
+            .parse::<EndpointUrl>()
+            .is_ok());
+
+        for invalid in &[
+            "s3.amazonaws.com",
+            "ftp://s3.amazonaws.com",
+            "https://s3.amazonaws.com?versioning",
+            "https://",
+        ] {
+            assert!(invalid.parse::<EndpointUrl>().is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Error conditions.
+
+use std::{error::Error as StdError, fmt, io};
+
+use s3ers_api::error::{FromHttpResponseError, IntoHttpError, S3Error};
+use s3ers_credentials::CredentialsError;
+
+use crate::{ChecksumMismatch, InterceptorError};
+
+/// An error that can occur during client operations.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error<E> {
+    /// The credentials to sign the request couldn't be resolved.
+    Credentials(CredentialsError),
+
+    /// Converting the request to an http request, or signing it, failed.
+    IntoHttp(IntoHttpError),
+
+    /// The request can't be sent to the configured endpoint, like a bucket
+    /// whose name doesn't allow transfer acceleration.
+    Endpoint(String),
+
+    /// Reading the streaming body of the request, or writing the body of the
+    /// response, failed.
+    Body(io::Error),
+
+    /// The HTTP client failed to send the request or receive the response.
+    Response(E),
+
+    /// Converting the http response to one of s3ers's types failed, or the
+    /// server returned an error.
+    FromHttpResponse(FromHttpResponseError),
+
+    /// The server computed another signature than the client.
+    SignatureMismatch(Box<SignatureMismatch>),
+
+    /// The server kept asking to slow down, after the request was retried
+    /// as much as the retry policy allows.
+    Throttling(Box<S3Error>),
+
+    /// A condition of the request failed, with a `PreconditionFailed`
+    /// error, like a conditional write of an object that was modified.
+    PreconditionFailed(Box<S3Error>),
+
+    /// The request didn't complete within the timeout of the client.
+    ///
+    /// A [waiter](crate::waiter) also fails with it once it waited too
+    /// long.
+    Timeout,
+
+    /// The body of the response doesn't match the checksum the server sent
+    /// with it.
+    Checksum(ChecksumMismatch),
+
+    /// An interceptor of the client failed the request.
+    Interceptor(InterceptorError),
+
+    /// The request was cancelled with its [`CancellationToken`].
+    ///
+    /// [`CancellationToken`]: crate::CancellationToken
+    Cancelled,
+}
+
+/// The details of a `SignatureDoesNotMatch` error.
+///
+/// Comparing the canonical requests of the client and the server usually
+/// tells which part of the request was modified in transit, or is
+/// canonicalized differently by the server.
+#[derive(Clone, Debug)]
+pub struct SignatureMismatch {
+    /// The error returned by the server, whose details may contain its
+    /// `CanonicalRequest` and `StringToSign`.
+    pub error: S3Error,
+
+    /// The canonical request signed by the client.
+    pub canonical_request: Option<String>,
+
+    /// The string signed by the client.
+    pub string_to_sign: Option<String>,
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Credentials(err) => write!(f, "{}", err),
+            Self::IntoHttp(err) => {
+                write!(f, "failed to build the request: {}", err)
+            }
+            Self::Endpoint(message) => {
+                write!(f, "failed to resolve the endpoint: {}", message)
+            }
+            Self::Body(err) => {
+                write!(f, "failed to transfer a body: {}", err)
+            }
+            Self::Response(err) => {
+                write!(f, "failed to get a response: {}", err)
+            }
+            Self::FromHttpResponse(err) => {
+                write!(f, "failed to read the response: {}", err)
+            }
+            Self::SignatureMismatch(mismatch) => {
+                write!(
+                    f,
+                    "the server rejected the signature: {}",
+                    mismatch.error
+                )
+            }
+            Self::Throttling(err) => {
+                write!(f, "the server throttled the request: {}", err)
+            }
+            Self::PreconditionFailed(err) => {
+                write!(f, "a precondition of the request failed: {}", err)
+            }
+            Self::Timeout => f.write_str("the request timed out"),
+            Self::Checksum(mismatch) => {
+                write!(f, "failed to verify the response: {}", mismatch)
+            }
+            Self::Interceptor(err) => {
+                write!(f, "an interceptor failed the request: {}", err)
+            }
+            Self::Cancelled => f.write_str("the request was cancelled"),
+        }
+    }
+}
+
+impl<E> From<CredentialsError> for Error<E> {
+    fn from(err: CredentialsError) -> Self {
+        Self::Credentials(err)
+    }
+}
+
+impl<E> From<IntoHttpError> for Error<E> {
+    fn from(err: IntoHttpError) -> Self {
+        Self::IntoHttp(err)
+    }
+}
+
+impl<E> From<s3ers_signature::Error> for Error<E> {
+    fn from(err: s3ers_signature::Error) -> Self {
+        Self::IntoHttp(err.into())
+    }
+}
+
+impl<E> From<FromHttpResponseError> for Error<E> {
+    fn from(err: FromHttpResponseError) -> Self {
+        Self::FromHttpResponse(err)
+    }
+}
+
+impl<E: StdError + 'static> StdError for Error<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Credentials(err) => Some(err),
+            Self::IntoHttp(err) => Some(err),
+            Self::Endpoint(_) => None,
+            Self::Body(err) => Some(err),
+            Self::Response(err) => Some(err),
+            Self::FromHttpResponse(err) => Some(err),
+            Self::SignatureMismatch(mismatch) => Some(&mismatch.error),
+            Self::Throttling(err) => Some(&**err),
+            Self::PreconditionFailed(err) => Some(&**err),
+            Self::Timeout => None,
+            Self::Checksum(mismatch) => Some(mismatch),
+            Self::Interceptor(err) => Some(&**err),
+            Self::Cancelled => None,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Convenience methods for the endpoints of `s3ers-s3-api`.
+
+use async_trait::async_trait;
+use s3ers_s3_api::{
+    bucket::list_objects_v2,
+    object::{get_object, put_object},
+};
+
+use crate::{Client, Error, HttpClient};
+
+/// Sends the most common requests without building them first.
+///
+/// Every method builds the request with its default options and sends it
+/// with [`Client::send_request`]. Requests needing other options are built
+/// and sent as usual.
+#[async_trait]
+pub trait S3ClientExt {
+    /// The error requests fail with.
+    type Error;
+
+    /// Lists the first page of the keys of a bucket, those starting with
+    /// `prefix` if there is one.
+    async fn list_objects_v2(
+        &self,
+        bucket: &str,
+        prefix: Option<&str>,
+    ) -> Result<list_objects_v2::Response, Self::Error>;
+
+    /// Retrieves an object, with its content.
+    async fn get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<get_object::Response, Self::Error>;
+
+    /// Adds an object, replacing any object with the same key.
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+    ) -> Result<put_object::Response, Self::Error>;
+}
+
+#[async_trait]
+impl<C> S3ClientExt for Client<C>
+where
+    C: HttpClient + Send + Sync,
+    C::RequestBody: Sync,
+    C::ResponseBody: Send,
+    C::Error: Sync,
+{
+    type Error = Error<C::Error>;
+
+    async fn list_objects_v2(
+        &self,
+        bucket: &str,
+        prefix: Option<&str>,
+    ) -> Result<list_objects_v2::Response, Self::Error> {
+        let mut request = list_objects_v2::Request::new(bucket);
+        request.prefix = prefix.map(ToOwned::to_owned);
+        self.send_request(request).await
+    }
+
+    async fn get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<get_object::Response, Self::Error> {
+        self.send_request(get_object::Request::new(bucket, key))
+            .await
+    }
+
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+    ) -> Result<put_object::Response, Self::Error> {
+        self.send_request(put_object::Request::new(bucket, key, body))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_executor::block_on;
+    use http::Method;
+
+    use super::S3ClientExt;
+    use crate::tests::{client, ok};
+
+    #[test]
+    fn send_requests() {
+        let client =
+            client(vec![http::Response::new(b"content".to_vec()), ok()]);
+        let object = block_on(client.get_object("bucket", "key")).unwrap();
+        assert_eq!(object.body, b"content");
+        block_on(client.put_object("bucket", "key", b"content".to_vec()))
+            .unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests[0].method(), Method::GET);
+        assert_eq!(
+            requests[0].uri().to_string(),
+            "https://bucket.s3.eu-west-1.amazonaws.com/key"
+        );
+        assert_eq!(requests[1].method(), Method::PUT);
+        assert_eq!(requests[1].body(), b"content");
+    }
+}
@@ -0,0 +1,119 @@
+//! Hedging of slow requests.
+
+use std::{collections::VecDeque, sync::Mutex, time::Duration};
+
+use http::Method;
+
+/// How slow requests are hedged, sending them a second time while the first
+/// attempt is still in flight, to cut the tail of their latency.
+///
+/// Only `GET` and `HEAD` requests are hedged, since they can safely be sent
+/// twice. Once an attempt took longer than the given percentile of the
+/// latencies of the recent requests of the client, another is sent, and the
+/// first successful response of both is returned, dropping the other
+/// attempt. Requests aren't hedged until the latencies of enough requests
+/// were observed. Waiting relies on the timer of the Tokio runtime.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct HedgingPolicy {
+    /// The percentile of the latencies of recent requests after which a
+    /// request is hedged, between 0 and 100.
+    pub percentile: f64,
+
+    /// The shortest delay before a request is hedged.
+    pub min_delay: Duration,
+}
+
+impl HedgingPolicy {
+    /// A policy hedging requests slower than `percentile` percent of the
+    /// recent ones, like `95.0`.
+    pub fn new(percentile: f64) -> Self {
+        Self {
+            percentile: percentile.clamp(0.0, 100.0),
+            min_delay: Duration::from_millis(10),
+        }
+    }
+
+    /// Sets the shortest delay before a request is hedged.
+    pub fn with_min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self
+    }
+}
+
+/// A hedging policy with the latencies of the recent requests of a client.
+#[derive(Debug)]
+pub(crate) struct Hedging {
+    policy: HedgingPolicy,
+    latencies: Mutex<VecDeque<Duration>>,
+}
+
+impl Hedging {
+    /// How many latencies are kept.
+    const WINDOW: usize = 256;
+
+    /// How many latencies are needed before requests are hedged.
+    pub(crate) const MIN_SAMPLES: usize = 20;
+
+    pub(crate) fn new(policy: HedgingPolicy) -> Self {
+        Self {
+            policy,
+            latencies: Mutex::default(),
+        }
+    }
+
+    /// Returns how long a request is waited for before it is hedged, if it
+    /// is hedged.
+    pub(crate) fn delay(&self, method: &Method) -> Option<Duration> {
+        if method != Method::GET && method != Method::HEAD {
+            return None;
+        }
+
+        let mut latencies: Vec<_> =
+            self.latencies.lock().unwrap().iter().copied().collect();
+        if latencies.len() < Self::MIN_SAMPLES {
+            return None;
+        }
+        latencies.sort_unstable();
+        let rank = (self.policy.percentile / 100.0
+            * (latencies.len() - 1) as f64)
+            .round() as usize;
+        Some(latencies[rank].max(self.policy.min_delay))
+    }
+
+    /// Records the latency of a successful request.
+    pub(crate) fn record(&self, latency: Duration) {
+        let mut latencies = self.latencies.lock().unwrap();
+        if latencies.len() == Self::WINDOW {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::Method;
+
+    use super::{Hedging, HedgingPolicy};
+
+    #[test]
+    fn delay() {
+        let hedging = Hedging::new(
+            HedgingPolicy::new(90.0).with_min_delay(Duration::from_millis(5)),
+        );
+        for millis in 1..Hedging::MIN_SAMPLES as u64 {
+            hedging.record(Duration::from_millis(millis));
+        }
+        assert_eq!(hedging.delay(&Method::GET), None);
+
+        hedging.record(Duration::from_millis(20));
+        assert_eq!(
+            hedging.delay(&Method::GET),
+            Some(Duration::from_millis(18))
+        );
+        assert_eq!(hedging.delay(&Method::PUT), None);
+    }
+}
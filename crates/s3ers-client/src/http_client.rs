@@ -0,0 +1,151 @@
+//! This module contains an abstraction for HTTP clients as well as
+//! friendly-named re-exports of client types that implement this trait.
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use bytes::{BufMut, Bytes};
+use futures_util::TryStreamExt;
+
+use crate::{ByteStream, Error, StreamingBody};
+
+#[cfg(feature = "hyper")]
+mod hyper;
+mod proxy;
+#[cfg(any(feature = "wire-log", feature = "replay"))]
+pub(crate) mod redact;
+mod resolve;
+#[cfg(feature = "wire-log")]
+mod wire_log;
+
+pub use self::{
+    proxy::Proxy,
+    resolve::{CachingResolver, Resolve},
+};
+
+#[cfg(feature = "hyper-rustls")]
+pub use self::hyper::HyperRustls;
+#[cfg(feature = "hyper")]
+pub use self::hyper::{DnsResolver, Hyper, ProxyConnector, ProxyStream};
+#[cfg(feature = "hyper")]
+pub use self::resolve::SystemResolver;
+#[cfg(feature = "wire-log")]
+pub use self::wire_log::WireLog;
+
+/// An HTTP client that can be used to send requests to an S3 server.
+#[async_trait]
+pub trait HttpClient: Sync {
+    /// The type to use for `try_into_http_request`.
+    ///
+    /// The body is read to sign the request, and kept to send it again if
+    /// the signature has to be redone.
+    type RequestBody: Default + BufMut + AsRef<[u8]> + Clone + Send;
+
+    /// The type to use for `try_from_http_response`.
+    type ResponseBody: AsRef<[u8]>;
+
+    /// The error type for the `send_http_request` function.
+    type Error: Send + Unpin;
+
+    /// Send an `http::Request` to get back an `http::Response`.
+    async fn send_http_request(
+        &self,
+        req: http::Request<Self::RequestBody>,
+    ) -> Result<http::Response<Self::ResponseBody>, Self::Error>;
+
+    /// Send an `http::Request` to get back an `http::Response` whose body
+    /// is read as it is received.
+    ///
+    /// The default implementation returns the body read by
+    /// `send_http_request` as a single chunk, for clients that can't
+    /// stream response bodies.
+    async fn send_http_request_streaming_response(
+        &self,
+        req: http::Request<Self::RequestBody>,
+    ) -> Result<http::Response<ByteStream>, Self::Error> {
+        let response = self.send_http_request(req).await?;
+        Ok(response.map(|body| Bytes::copy_from_slice(body.as_ref()).into()))
+    }
+
+    /// Send an `http::Request` whose body is read as it is sent.
+    ///
+    /// The default implementation reads the whole body into a
+    /// `RequestBody` and sends it with `send_http_request`, for clients
+    /// that can't stream request bodies.
+    async fn send_streaming_http_request(
+        &self,
+        req: http::Request<StreamingBody>,
+    ) -> Result<http::Response<Self::ResponseBody>, Error<Self::Error>> {
+        let (head, body) = req.into_parts();
+        let body = body
+            .try_fold(Self::RequestBody::default(), |mut buffer, bytes| {
+                buffer.put_slice(&bytes);
+                async { Ok(buffer) }
+            })
+            .await
+            .map_err(Error::Body)?;
+
+        self.send_http_request(http::Request::from_parts(head, body))
+            .await
+            .map_err(Error::Response)
+    }
+}
+
+/// An HTTP client that has a default configuration.
+pub trait DefaultConstructibleHttpClient: HttpClient {
+    /// Creates a new HTTP client with default configuration.
+    fn default() -> Self;
+
+    /// Creates a new HTTP client with the given configuration.
+    ///
+    /// The default implementation ignores the configuration, for clients
+    /// that can't be configured.
+    fn with_config(config: &HttpClientConfig) -> Self
+    where
+        Self: Sized,
+    {
+        let _ = config;
+        Self::default()
+    }
+}
+
+/// The configuration of the HTTP clients built by
+/// [`ClientBuilder::build`](crate::ClientBuilder::build).
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub struct HttpClientConfig {
+    /// How long connecting to the server may take.
+    pub connect_timeout: Option<Duration>,
+
+    /// The proxies requests are sent through.
+    pub proxy: Option<Proxy>,
+
+    /// How many idle connections are kept per host.
+    pub max_idle_connections_per_host: Option<usize>,
+
+    /// How long idle connections are kept.
+    pub idle_timeout: Option<Duration>,
+
+    /// How long connections are idle before TCP keepalive probes are sent
+    /// on them.
+    pub tcp_keepalive: Option<Duration>,
+
+    /// Resolves the addresses of servers, in place of the resolver of the
+    /// system.
+    pub resolver: Option<Arc<dyn Resolve>>,
+}
+
+impl fmt::Debug for HttpClientConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpClientConfig")
+            .field("connect_timeout", &self.connect_timeout)
+            .field("proxy", &self.proxy)
+            .field(
+                "max_idle_connections_per_host",
+                &self.max_idle_connections_per_host,
+            )
+            .field("idle_timeout", &self.idle_timeout)
+            .field("tcp_keepalive", &self.tcp_keepalive)
+            .finish_non_exhaustive()
+    }
+}
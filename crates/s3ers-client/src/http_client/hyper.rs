@@ -0,0 +1,445 @@
+use std::{
+    fmt,
+    future::Future,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    vec,
+};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use bytes::{Bytes, BytesMut};
+use futures_util::TryStreamExt;
+use http::{uri::Scheme, Uri};
+use hyper::{
+    client::{
+        connect::{dns::Name, Connect, Connected, Connection},
+        HttpConnector,
+    },
+    service::Service,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::TcpStream,
+};
+
+use super::{
+    DefaultConstructibleHttpClient, HttpClient, HttpClientConfig, Proxy,
+    Resolve, SystemResolver,
+};
+use crate::{ByteStream, Error, StreamingBody};
+
+/// The longest response to a `CONNECT` request.
+const MAX_CONNECT_RESPONSE: usize = 64 * 1024;
+
+/// A hyper HTTP client.
+///
+/// The default connector is rarely useful, since it doesn't support `https`.
+///
+/// Uploads aren't sent with `Expect: 100-continue`: hyper 0.14 discards
+/// interim responses and sends the body right after the head of the
+/// request, so it can't wait for the server to accept the upload first.
+pub type Hyper = hyper::Client<ProxyConnector>;
+
+/// A hyper HTTP client using rustls for TLS.
+#[cfg(feature = "hyper-rustls")]
+pub type HyperRustls =
+    hyper::Client<hyper_rustls::HttpsConnector<ProxyConnector>>;
+
+#[async_trait]
+impl<C> HttpClient for hyper::Client<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    type RequestBody = BytesMut;
+    type ResponseBody = Bytes;
+    type Error = hyper::Error;
+
+    async fn send_http_request(
+        &self,
+        req: http::Request<BytesMut>,
+    ) -> Result<http::Response<Bytes>, hyper::Error> {
+        let (head, body) = self
+            .request(req.map(|body| hyper::body::Body::from(body.freeze())))
+            .await?
+            .into_parts();
+
+        let body = hyper::body::to_bytes(body).await?;
+        Ok(http::Response::from_parts(head, body))
+    }
+
+    async fn send_http_request_streaming_response(
+        &self,
+        req: http::Request<BytesMut>,
+    ) -> Result<http::Response<ByteStream>, hyper::Error> {
+        let response = self
+            .request(req.map(|body| hyper::body::Body::from(body.freeze())))
+            .await?;
+
+        Ok(
+            response
+                .map(|body| ByteStream::new(body.map_err(io::Error::other))),
+        )
+    }
+
+    async fn send_streaming_http_request(
+        &self,
+        req: http::Request<StreamingBody>,
+    ) -> Result<http::Response<Bytes>, Error<hyper::Error>> {
+        let (head, body) = self
+            .request(req.map(hyper::body::Body::wrap_stream))
+            .await
+            .map_err(Error::Response)?
+            .into_parts();
+
+        let body =
+            hyper::body::to_bytes(body).await.map_err(Error::Response)?;
+        Ok(http::Response::from_parts(head, body))
+    }
+}
+
+impl DefaultConstructibleHttpClient for Hyper {
+    fn default() -> Self {
+        Self::with_config(&HttpClientConfig::default())
+    }
+
+    fn with_config(config: &HttpClientConfig) -> Self {
+        client_builder(config).build(ProxyConnector::new(
+            http_connector(config),
+            config.proxy.clone(),
+        ))
+    }
+}
+
+#[cfg(feature = "hyper-rustls")]
+impl DefaultConstructibleHttpClient for HyperRustls {
+    fn default() -> Self {
+        Self::with_config(&HttpClientConfig::default())
+    }
+
+    fn with_config(config: &HttpClientConfig) -> Self {
+        let mut http = http_connector(config);
+        http.enforce_http(false);
+        client_builder(config).build(
+            hyper_rustls::HttpsConnectorBuilder::new()
+                .with_webpki_roots()
+                .https_or_http()
+                .enable_http1()
+                .wrap_connector(ProxyConnector::new(
+                    http,
+                    config.proxy.clone(),
+                )),
+        )
+    }
+}
+
+/// Returns the builder of a client pooling connections as configured.
+fn client_builder(config: &HttpClientConfig) -> hyper::client::Builder {
+    let mut builder = hyper::Client::builder();
+    if let Some(max) = config.max_idle_connections_per_host {
+        builder.pool_max_idle_per_host(max);
+    }
+    if let Some(timeout) = config.idle_timeout {
+        builder.pool_idle_timeout(timeout);
+    }
+    builder
+}
+
+/// Returns the connector to the servers, configured as given.
+fn http_connector(config: &HttpClientConfig) -> HttpConnector<DnsResolver> {
+    let resolver = match &config.resolver {
+        Some(resolver) => DnsResolver(resolver.clone()),
+        None => DnsResolver::default(),
+    };
+    let mut connector = HttpConnector::new_with_resolver(resolver);
+    connector.set_connect_timeout(config.connect_timeout);
+    connector.set_keepalive(config.tcp_keepalive);
+    connector
+}
+
+/// A connector sending requests through a proxy, or directly if there is
+/// none for them.
+///
+/// Requests to `http` URLs are sent to the proxy, and requests to `https`
+/// URLs go through a `CONNECT` tunnel, over which TLS is negotiated with the
+/// server.
+#[derive(Clone, Debug)]
+pub struct ProxyConnector {
+    http: HttpConnector<DnsResolver>,
+    proxy: Option<Proxy>,
+}
+
+impl ProxyConnector {
+    /// Creates a connector reaching the servers, or their proxy, with
+    /// `http`.
+    pub fn new(http: HttpConnector<DnsResolver>, proxy: Option<Proxy>) -> Self {
+        Self { http, proxy }
+    }
+}
+
+impl Service<Uri> for ProxyConnector {
+    type Response = ProxyStream;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<ProxyStream>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.http.poll_ready(cx).map_err(io::Error::other)
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        let proxy = self
+            .proxy
+            .as_ref()
+            .and_then(|proxy| proxy.proxy_for(&dst))
+            .cloned();
+        let mut http = self.http.clone();
+
+        Box::pin(async move {
+            let proxy = match proxy {
+                Some(proxy) => proxy,
+                None => {
+                    let stream =
+                        http.call(dst).await.map_err(io::Error::other)?;
+                    return Ok(ProxyStream {
+                        stream,
+                        proxied: false,
+                    });
+                }
+            };
+
+            let mut stream =
+                http.call(proxy.clone()).await.map_err(io::Error::other)?;
+            if dst.scheme() == Some(&Scheme::HTTPS) {
+                tunnel(&mut stream, &dst, &proxy).await?;
+                Ok(ProxyStream {
+                    stream,
+                    proxied: false,
+                })
+            } else {
+                Ok(ProxyStream {
+                    stream,
+                    proxied: true,
+                })
+            }
+        })
+    }
+}
+
+/// Resolves the hosts of the connections of hyper with a [`Resolve`].
+#[derive(Clone)]
+pub struct DnsResolver(Arc<dyn Resolve>);
+
+impl DnsResolver {
+    /// Resolves hosts with `resolver`.
+    pub fn new(resolver: impl Resolve + 'static) -> Self {
+        Self(Arc::new(resolver))
+    }
+}
+
+impl Default for DnsResolver {
+    fn default() -> Self {
+        Self::new(SystemResolver)
+    }
+}
+
+impl fmt::Debug for DnsResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsResolver").finish_non_exhaustive()
+    }
+}
+
+impl Service<Name> for DnsResolver {
+    type Response = vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let resolver = self.0.clone();
+        Box::pin(async move {
+            Ok(resolver.resolve(name.as_str()).await?.into_iter())
+        })
+    }
+}
+
+/// A connection to a server, or to the proxy of requests to `http` URLs.
+#[derive(Debug)]
+pub struct ProxyStream {
+    stream: TcpStream,
+    proxied: bool,
+}
+
+impl Connection for ProxyStream {
+    fn connected(&self) -> Connected {
+        // Requests sent to a proxy have an absolute URI.
+        self.stream.connected().proxy(self.proxied)
+    }
+}
+
+impl AsyncRead for ProxyStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ProxyStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+/// Opens a tunnel to `dst` through the proxy a stream is connected to.
+async fn tunnel(
+    stream: &mut TcpStream,
+    dst: &Uri,
+    proxy: &Uri,
+) -> io::Result<()> {
+    let host = dst.host().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "the URI has no host")
+    })?;
+    let port = dst.port_u16().unwrap_or(443);
+
+    let mut request =
+        format!("CONNECT {0}:{1} HTTP/1.1\r\nHost: {0}:{1}\r\n", host, port);
+    let user_info = proxy
+        .authority()
+        .and_then(|authority| authority.as_str().rsplit_once('@'))
+        .map(|(user_info, _)| user_info);
+    if let Some(user_info) = user_info {
+        request.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            STANDARD.encode(user_info)
+        ));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // The proxy doesn't send anything from the server before the client
+    // starts the TLS handshake, so nothing is read past the response.
+    let mut response = Vec::new();
+    let mut buffer = [0; 1024];
+    while !response.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || response.len() > MAX_CONNECT_RESPONSE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the proxy sent an invalid response to CONNECT",
+            ));
+        }
+        response.extend_from_slice(&buffer[..read]);
+    }
+
+    let status_line = response.split(|&b| b == b'\r').next().unwrap_or(&[]);
+    let status = String::from_utf8_lossy(status_line);
+    if status.split(' ').nth(1) != Some("200") {
+        return Err(io::Error::other(format!(
+            "the proxy refused to open a tunnel: {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use hyper::{client::HttpConnector, service::Service};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::{client_builder, DnsResolver, ProxyConnector};
+    use crate::http_client::{HttpClientConfig, Proxy};
+
+    #[test]
+    fn configure_pool() {
+        let config = HttpClientConfig {
+            max_idle_connections_per_host: Some(2),
+            idle_timeout: Some(Duration::from_secs(30)),
+            ..HttpClientConfig::default()
+        };
+        // hyper only exposes the configuration of its pool in its `Debug`
+        // output.
+        let builder = format!("{:?}", client_builder(&config));
+        assert!(builder.contains("idle_timeout: Some(30s)"), "{}", builder);
+        assert!(builder.contains("max_idle_per_host: 2"), "{}", builder);
+
+        let builder = format!("{:?}", client_builder(&Default::default()));
+        assert!(builder.contains("idle_timeout: Some(90s)"), "{}", builder);
+    }
+
+    #[test]
+    fn tunnel_through_proxy() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let request = runtime.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let proxy = format!(
+                "http://user:secret@{}",
+                listener.local_addr().unwrap()
+            );
+            let proxying = async {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 1024];
+                let read = stream.read(&mut request).await.unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                    .await
+                    .unwrap();
+                String::from_utf8(request[..read].to_vec()).unwrap()
+            };
+
+            let mut connector = ProxyConnector::new(
+                HttpConnector::new_with_resolver(DnsResolver::default()),
+                Some(Proxy::all(proxy.parse().unwrap())),
+            );
+            let connecting =
+                connector.call("https://s3.amazonaws.com".parse().unwrap());
+            let (request, connection) =
+                futures_util::future::join(proxying, connecting).await;
+            connection.unwrap();
+            request
+        });
+
+        assert_eq!(
+            request,
+            "CONNECT s3.amazonaws.com:443 HTTP/1.1\r\n\
+             Host: s3.amazonaws.com:443\r\n\
+             Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n\r\n"
+        );
+    }
+}
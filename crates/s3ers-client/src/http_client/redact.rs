@@ -0,0 +1,59 @@
+//! Redacting the secrets of requests before they are logged or recorded.
+
+use http::Uri;
+
+/// The headers whose values are secrets.
+pub(crate) const SECRET_HEADERS: &[&str] = &[
+    "authorization",
+    "x-amz-security-token",
+    "x-amz-server-side-encryption-customer-key",
+    "x-amz-copy-source-server-side-encryption-customer-key",
+];
+
+/// The query parameters of presigned URLs whose values are secrets.
+pub(crate) const SECRET_PARAMS: &[&str] =
+    &["X-Amz-Signature", "X-Amz-Security-Token"];
+
+/// What secrets are replaced with.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Returns a URI with the secrets of its query redacted.
+pub(crate) fn redact_uri(uri: &Uri) -> String {
+    let query = match uri.query() {
+        Some(query) => query,
+        None => return uri.to_string(),
+    };
+    let query = query
+        .split('&')
+        .map(|param| match param.split_once('=') {
+            Some((name, _)) if SECRET_PARAMS.contains(&name) => {
+                format!("{}={}", name, REDACTED)
+            }
+            _ => param.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    let uri = uri.to_string();
+    let path = &uri[..uri.find('?').unwrap_or(uri.len())];
+    format!("{}?{}", path, query)
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Uri;
+
+    use super::redact_uri;
+
+    #[test]
+    fn redact_query() {
+        let uri: Uri = "https://s3.amazonaws.com/b/k?X-Amz-Credential=AKID\
+                        &X-Amz-Signature=secret"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            redact_uri(&uri),
+            "https://s3.amazonaws.com/b/k?X-Amz-Credential=AKID\
+             &X-Amz-Signature=<redacted>"
+        );
+    }
+}
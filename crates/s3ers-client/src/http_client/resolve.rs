@@ -0,0 +1,143 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+
+/// Resolves the addresses of the hosts requests are sent to, in place of
+/// the resolver of the system, set with
+/// [`ClientBuilder::dns_resolver`](crate::ClientBuilder::dns_resolver).
+///
+/// The port of the addresses returned is ignored, the one of the URL of the
+/// request is used instead.
+#[async_trait]
+pub trait Resolve: Send + Sync {
+    /// Returns the addresses of `host`.
+    async fn resolve(&self, host: &str) -> io::Result<Vec<SocketAddr>>;
+}
+
+/// Resolves hosts with the resolver of the system, on the blocking threads
+/// of the Tokio runtime.
+#[cfg(feature = "hyper")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemResolver;
+
+#[cfg(feature = "hyper")]
+#[async_trait]
+impl Resolve for SystemResolver {
+    async fn resolve(&self, host: &str) -> io::Result<Vec<SocketAddr>> {
+        Ok(tokio::net::lookup_host((host, 0)).await?.collect())
+    }
+}
+
+/// Caches the addresses returned by another resolver for a while, and
+/// returns pinned addresses for some hosts, like the ones of VPC endpoints.
+///
+/// Failures to resolve a host aren't cached.
+#[derive(Debug)]
+pub struct CachingResolver<R> {
+    resolver: R,
+    ttl: Duration,
+    pinned: HashMap<String, Vec<SocketAddr>>,
+    cache: Mutex<HashMap<String, (Instant, Vec<SocketAddr>)>>,
+}
+
+impl<R: Resolve> CachingResolver<R> {
+    /// Caches the addresses returned by `resolver` for a minute.
+    pub fn new(resolver: R) -> Self {
+        Self {
+            resolver,
+            ttl: Duration::from_secs(60),
+            pinned: HashMap::new(),
+            cache: Mutex::default(),
+        }
+    }
+
+    /// Sets how long addresses are cached.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Resolves `host` to `addresses`, without asking the resolver.
+    pub fn pin(
+        mut self,
+        host: impl Into<String>,
+        addresses: impl IntoIterator<Item = SocketAddr>,
+    ) -> Self {
+        self.pinned
+            .insert(host.into(), addresses.into_iter().collect());
+        self
+    }
+}
+
+#[async_trait]
+impl<R: Resolve> Resolve for CachingResolver<R> {
+    async fn resolve(&self, host: &str) -> io::Result<Vec<SocketAddr>> {
+        if let Some(addresses) = self.pinned.get(host) {
+            return Ok(addresses.clone());
+        }
+
+        let now = Instant::now();
+        if let Some((expiration, addresses)) =
+            self.cache.lock().unwrap().get(host)
+        {
+            if *expiration > now {
+                return Ok(addresses.clone());
+            }
+        }
+
+        let addresses = self.resolver.resolve(host).await?;
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (expiration, _)| *expiration > now);
+        cache.insert(host.to_owned(), (now + self.ttl, addresses.clone()));
+        Ok(addresses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        net::SocketAddr,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use futures_executor::block_on;
+
+    use super::{CachingResolver, Resolve};
+
+    #[derive(Debug, Default)]
+    struct Counting(AtomicUsize);
+
+    #[async_trait]
+    impl Resolve for Counting {
+        async fn resolve(&self, _: &str) -> io::Result<Vec<SocketAddr>> {
+            let count = self.0.fetch_add(1, Ordering::SeqCst) as u8;
+            Ok(vec![SocketAddr::from(([10, 0, 0, count], 0))])
+        }
+    }
+
+    #[test]
+    fn cache_addresses() {
+        let vpc_endpoint = SocketAddr::from(([10, 1, 2, 3], 0));
+        let resolver = CachingResolver::new(Counting::default())
+            .pin("s3.eu-west-1.amazonaws.com", vec![vpc_endpoint]);
+        let resolve = |host| block_on(resolver.resolve(host)).unwrap();
+
+        assert_eq!(resolve("s3.eu-west-1.amazonaws.com"), [vpc_endpoint]);
+        assert_eq!(resolve("example.com"), resolve("example.com"));
+        assert_eq!(resolver.resolver.0.load(Ordering::SeqCst), 1);
+
+        let resolver =
+            CachingResolver::new(Counting::default()).with_ttl(Duration::ZERO);
+        let resolve = |host| block_on(resolver.resolve(host)).unwrap();
+        assert_ne!(resolve("example.com"), resolve("example.com"));
+    }
+}
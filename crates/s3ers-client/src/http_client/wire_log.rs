@@ -0,0 +1,198 @@
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use http::HeaderMap;
+
+use super::{
+    redact::{redact_uri, REDACTED, SECRET_HEADERS},
+    DefaultConstructibleHttpClient, HttpClient, HttpClientConfig,
+};
+use crate::{ByteStream, Error, StreamingBody};
+
+/// The target of the events of [`WireLog`].
+const TARGET: &str = "s3ers::wire";
+
+/// An HTTP client logging the requests it sends and the responses it
+/// receives, with the `wire-log` feature, to debug S3-compatible servers.
+///
+/// Their lines, headers and the beginning of their bodies are logged as
+/// `DEBUG` events of the `s3ers::wire` target of `tracing`, with the
+/// credentials, signatures and customer-provided keys redacted. Bodies read
+/// as they are sent or received aren't logged.
+#[derive(Clone, Debug)]
+pub struct WireLog<C> {
+    client: C,
+    max_body: usize,
+}
+
+impl<C> WireLog<C> {
+    /// Logs the requests of `client`, with their first kibibyte of body.
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            max_body: 1024,
+        }
+    }
+
+    /// Sets how many bytes of bodies are logged.
+    pub fn with_max_body(mut self, max_body: usize) -> Self {
+        self.max_body = max_body;
+        self
+    }
+
+    /// Returns the client whose requests are logged.
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+
+    /// Logs a request, with its body if it is known.
+    fn request<B>(&self, request: &http::Request<B>, body: Option<&[u8]>) {
+        let mut message = format!(
+            "> {} {} {:?}\n",
+            request.method(),
+            redact_uri(request.uri()),
+            request.version()
+        );
+        self.head_and_body(&mut message, '>', request.headers(), body);
+        tracing::debug!(target: TARGET, "{}", message);
+    }
+
+    /// Logs a response, with its body if it is known.
+    fn response<B>(&self, response: &http::Response<B>, body: Option<&[u8]>) {
+        let mut message =
+            format!("< {:?} {}\n", response.version(), response.status());
+        self.head_and_body(&mut message, '<', response.headers(), body);
+        tracing::debug!(target: TARGET, "{}", message);
+    }
+
+    /// Formats the headers of a message and the beginning of its body.
+    fn head_and_body(
+        &self,
+        message: &mut String,
+        prefix: char,
+        headers: &HeaderMap,
+        body: Option<&[u8]>,
+    ) {
+        for (name, value) in headers {
+            let value = if SECRET_HEADERS.contains(&name.as_str()) {
+                REDACTED.into()
+            } else {
+                String::from_utf8_lossy(value.as_bytes())
+            };
+            let _ = writeln!(message, "{} {}: {}", prefix, name, value);
+        }
+        match body {
+            Some([]) => {}
+            Some(body) => {
+                let logged = &body[..body.len().min(self.max_body)];
+                let _ = write!(
+                    message,
+                    "{}\n{}",
+                    prefix,
+                    String::from_utf8_lossy(logged)
+                );
+                if logged.len() < body.len() {
+                    let _ = write!(
+                        message,
+                        "... ({} more bytes)",
+                        body.len() - logged.len()
+                    );
+                }
+            }
+            None => {
+                let _ = write!(message, "{}\n<streaming body>", prefix);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<C> HttpClient for WireLog<C>
+where
+    C: HttpClient + Send,
+    C::RequestBody: Sync,
+    C::ResponseBody: Send,
+{
+    type RequestBody = C::RequestBody;
+    type ResponseBody = C::ResponseBody;
+    type Error = C::Error;
+
+    async fn send_http_request(
+        &self,
+        req: http::Request<Self::RequestBody>,
+    ) -> Result<http::Response<Self::ResponseBody>, Self::Error> {
+        self.request(&req, Some(req.body().as_ref()));
+        let response = self.client.send_http_request(req).await?;
+        self.response(&response, Some(response.body().as_ref()));
+        Ok(response)
+    }
+
+    async fn send_http_request_streaming_response(
+        &self,
+        req: http::Request<Self::RequestBody>,
+    ) -> Result<http::Response<ByteStream>, Self::Error> {
+        self.request(&req, Some(req.body().as_ref()));
+        let response = self
+            .client
+            .send_http_request_streaming_response(req)
+            .await?;
+        self.response(&response, None);
+        Ok(response)
+    }
+
+    async fn send_streaming_http_request(
+        &self,
+        req: http::Request<StreamingBody>,
+    ) -> Result<http::Response<Self::ResponseBody>, Error<Self::Error>> {
+        self.request(&req, None);
+        let response = self.client.send_streaming_http_request(req).await?;
+        self.response(&response, Some(response.body().as_ref()));
+        Ok(response)
+    }
+}
+
+impl<C> DefaultConstructibleHttpClient for WireLog<C>
+where
+    C: DefaultConstructibleHttpClient + Send,
+    C::RequestBody: Sync,
+    C::ResponseBody: Send,
+{
+    fn default() -> Self {
+        Self::new(C::default())
+    }
+
+    fn with_config(config: &HttpClientConfig) -> Self {
+        Self::new(C::with_config(config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WireLog;
+
+    #[test]
+    fn redact_secrets() {
+        let log = WireLog::new(()).with_max_body(4);
+        let request = http::Request::put("https://s3.amazonaws.com/b/k")
+            .header("authorization", "AWS4-HMAC-SHA256 Signature=secret")
+            .header("x-amz-server-side-encryption-customer-key", "secret")
+            .header("content-length", "7")
+            .body(())
+            .unwrap();
+        let mut message = String::new();
+        log.head_and_body(
+            &mut message,
+            '>',
+            request.headers(),
+            Some(b"content"),
+        );
+        assert_eq!(
+            message,
+            "> authorization: <redacted>\n\
+             > x-amz-server-side-encryption-customer-key: <redacted>\n\
+             > content-length: 7\n\
+             >\n\
+             cont... (3 more bytes)"
+        );
+    }
+}
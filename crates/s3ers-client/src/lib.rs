@@ -0,0 +1,2239 @@
+//! A client library for the S3 API.
+//!
+//! The [`Client`] sends requests described by the endpoint types of
+//! `s3ers-api`, signing them with the credentials of a
+//! [`CredentialsProvider`] as their endpoint requires. It is configured with
+//! a [`ClientBuilder`]:
+//!
+//! ```no_run
+//! # use s3ers_client::{http_client::HyperRustls, Client};
+//! let client = Client::builder()
+//!     .region("eu-west-1")
+//!     .build::<HyperRustls>();
+//! ```
+
+#![warn(missing_docs)]
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bytes::{BufMut, Bytes};
+use futures_util::future::{self, Either};
+use http::{
+    header::{HeaderName, CONTENT_LENGTH, USER_AGENT},
+    HeaderValue, Method, StatusCode, Uri,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError, S3Error},
+    header::{SseCustomerKey, SseKms},
+    AuthScheme, IncomingResponse, IncomingStreamingResponse, OutgoingRequest,
+    Scope,
+};
+use s3ers_credentials::CredentialsProvider;
+use s3ers_signature::{
+    chunked::{self, ChecksumAlgorithm},
+    clock::SkewCorrectedClock,
+    Clock, SigningOutput, SigningParams,
+};
+
+mod addressing;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod body;
+mod builder;
+mod cancel;
+mod checksum;
+mod compat;
+mod config;
+mod decompress;
+mod endpoint;
+mod error;
+#[cfg(feature = "s3-api")]
+mod ext;
+mod hedge;
+pub mod http_client;
+mod limit;
+mod metrics;
+mod middleware;
+mod pagination;
+mod retry;
+#[cfg(feature = "tower")]
+mod service;
+pub mod test;
+mod timeout;
+mod trace;
+#[cfg(feature = "s3-api")]
+pub mod transfer;
+#[cfg(feature = "s3-api")]
+pub mod waiter;
+
+use config::{RequestCredentials, RequestRegion};
+use hedge::Hedging;
+use limit::Limiter;
+use metrics::Recorder;
+use middleware::Middleware;
+use retry::Failure;
+
+#[cfg(feature = "s3-api")]
+pub use self::ext::S3ClientExt;
+pub use self::{
+    addressing::{is_virtual_hostable, AddressingStyle},
+    body::{ByteStream, ReplayableBody, StreamingBody},
+    builder::ClientBuilder,
+    cancel::CancellationToken,
+    checksum::ChecksumMismatch,
+    compat::Compatibility,
+    config::RequestConfig,
+    endpoint::{
+        AwsEndpointResolver, EndpointParams, EndpointResolver, EndpointUrl,
+        InvalidEndpointUrl,
+    },
+    error::{Error, SignatureMismatch},
+    hedge::HedgingPolicy,
+    http_client::{DefaultConstructibleHttpClient, HttpClient},
+    metrics::{ErrorClass, MetricsSink, RequestMetrics},
+    middleware::{Interceptor, InterceptorError},
+    retry::{RetryPolicy, RetryQuota},
+    timeout::Timeouts,
+};
+
+/// The signing name of S3.
+const SERVICE: &str = "s3";
+
+/// How long the signature of requests authenticated in the query string is
+/// valid. They are sent right away, so this only has to allow for the clock
+/// skew servers tolerate.
+const QUERY_SIGNATURE_EXPIRATION: Duration = Duration::from_secs(15 * 60);
+
+/// The header with the MD5 digest of the body, which the `http` crate
+/// doesn't define.
+const CONTENT_MD5: &str = "content-md5";
+
+/// The name of requests to presigned URLs in traces and metrics.
+const PRESIGNED: &str = "Presigned";
+
+/// The header of requests agreeing to pay for themselves.
+const REQUEST_PAYER: &str = "x-amz-request-payer";
+
+/// The endpoints writing objects, to which the SSE-KMS settings of the
+/// client apply.
+const WRITE_ENDPOINTS: &[&str] =
+    &["PutObject", "CreateMultipartUpload", "CopyObject"];
+
+/// The headers choosing how an object is encrypted.
+const ENCRYPTION_HEADERS: &[&str] = &[
+    "x-amz-server-side-encryption",
+    "x-amz-server-side-encryption-customer-algorithm",
+];
+
+/// The error codes of servers rejecting a signature because of the time it
+/// was made at.
+const SKEW_ERRORS: &[&str] = &[
+    "RequestTimeTooSkewed",
+    "RequestExpired",
+    "RequestInTheFuture",
+];
+
+/// The result of sending the request `R` with the http client `C`.
+pub type ResponseResult<C, R> = Result<
+    <R as OutgoingRequest>::IncomingResponse,
+    Error<<C as HttpClient>::Error>,
+>;
+
+/// A client for the S3 API.
+///
+/// Cloning a client is cheap, clones share their configuration and
+/// connections.
+#[derive(Debug)]
+pub struct Client<C>(Arc<ClientData<C>>, Middleware);
+
+impl<C> Clone for Client<C> {
+    fn clone(&self) -> Self {
+        Client(self.0.clone(), self.1.clone())
+    }
+}
+
+struct ClientData<C> {
+    /// The URL of the S3 service, like
+    /// `https://s3.eu-west-1.amazonaws.com`, if it isn't resolved.
+    endpoint_url: Option<String>,
+
+    /// Resolves the URL of the S3 service when none is given.
+    endpoint_resolver: Arc<dyn EndpointResolver>,
+
+    /// Whether resolved endpoints are reachable over IPv6.
+    use_dual_stack: bool,
+
+    /// Whether resolved endpoints use FIPS validated cryptography.
+    use_fips: bool,
+
+    /// The region requests are signed for.
+    region: String,
+
+    /// How the buckets of requests are addressed.
+    addressing_style: AddressingStyle,
+
+    /// Whether requests redirected to the region of their bucket are sent
+    /// there again.
+    follow_region_redirects: bool,
+
+    /// The regions of the buckets requests were redirected to.
+    bucket_regions: Mutex<HashMap<String, String>>,
+
+    /// Whether requests to buckets go through their transfer acceleration
+    /// endpoint.
+    accelerate: bool,
+
+    /// The algorithm of the checksums sent with uploads, if they are sent.
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+
+    /// Whether the content of downloads is checked against its checksum.
+    validate_checksums: bool,
+
+    /// Whether the bodies of responses are decompressed.
+    decompress_responses: bool,
+
+    /// The underlying HTTP client.
+    http_client: C,
+
+    /// The source of the credentials requests are signed with.
+    credentials: Arc<dyn CredentialsProvider>,
+
+    /// Whether requests are sent without signing them.
+    anonymous: bool,
+
+    /// Whether requests agree to pay for themselves.
+    requester_pays: bool,
+
+    /// How written objects are encrypted with KMS keys, if they are.
+    sse_kms: Option<SseKms>,
+
+    /// The clock requests are signed with, corrected when the server
+    /// reports a skew.
+    clock: SkewCorrectedClock<Arc<dyn Clock>>,
+
+    /// How long requests may take.
+    timeouts: Timeouts,
+
+    /// How failed requests are retried.
+    retry_policy: RetryPolicy,
+
+    /// The retry tokens shared by the requests of the client.
+    retry_quota: RetryQuota,
+
+    /// How slow requests are hedged, with the latencies of recent ones, if
+    /// they are.
+    hedging: Option<Hedging>,
+
+    /// The limits on the requests in flight and their rate.
+    limiter: Limiter,
+
+    /// The `User-Agent` header of requests.
+    user_agent: HeaderValue,
+
+    /// The receiver of the metrics of requests, if they are recorded.
+    metrics_sink: Option<Arc<dyn MetricsSink>>,
+}
+
+impl<C: fmt::Debug> fmt::Debug for ClientData<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientData")
+            .field("endpoint_url", &self.endpoint_url)
+            .field("use_dual_stack", &self.use_dual_stack)
+            .field("use_fips", &self.use_fips)
+            .field("region", &self.region)
+            .field("addressing_style", &self.addressing_style)
+            .field("follow_region_redirects", &self.follow_region_redirects)
+            .field("accelerate", &self.accelerate)
+            .field("checksum_algorithm", &self.checksum_algorithm)
+            .field("validate_checksums", &self.validate_checksums)
+            .field("decompress_responses", &self.decompress_responses)
+            .field("http_client", &self.http_client)
+            .field("anonymous", &self.anonymous)
+            .field("requester_pays", &self.requester_pays)
+            .field("sse_kms", &self.sse_kms)
+            .field("clock", &self.clock)
+            .field("timeouts", &self.timeouts)
+            .field("retry_policy", &self.retry_policy)
+            .field("retry_quota", &self.retry_quota)
+            .field("hedging", &self.hedging)
+            .field("limiter", &self.limiter)
+            .field("user_agent", &self.user_agent)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Whether a request is sent without signing it, overriding the
+/// [`anonymous`](ClientBuilder::anonymous) setting of the client when it is
+/// inserted in the extensions of the request, in the closure of
+/// [`Client::send_customized_request`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Anonymous(pub bool);
+
+/// Whether a request agrees to pay for itself, overriding the
+/// [`requester_pays`](ClientBuilder::requester_pays) setting of the client
+/// when it is inserted in the extensions of the request, in the closure of
+/// [`Client::send_customized_request`].
+///
+/// Endpoints setting the `x-amz-request-payer` header themselves agree
+/// regardless.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RequesterPays(pub bool);
+
+/// Where a request to a bucket is sent, kept in its extensions to address it
+/// again in another region.
+#[derive(Clone, Debug)]
+struct Routing {
+    /// The path-style URI of the request to the endpoint of the client.
+    path_uri: Uri,
+
+    /// The bucket of the request.
+    bucket: String,
+
+    /// The region the request is sent to.
+    region: String,
+}
+
+impl Client<()> {
+    /// Creates a new client builder.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+}
+
+impl<C: HttpClient> Client<C> {
+    /// Returns the region requests are signed for.
+    pub fn region(&self) -> &str {
+        &self.0.region
+    }
+
+    /// Returns the retry tokens shared by the requests of the client.
+    pub fn retry_quota(&self) -> &RetryQuota {
+        &self.0.retry_quota
+    }
+
+    /// Returns a client sending its requests through another interceptor,
+    /// after the ones it already has.
+    ///
+    /// Requests go through the interceptors in the order they were added,
+    /// and the heads of responses in reverse order. The client returned
+    /// shares its configuration and connections with this one.
+    pub fn with_middleware(&self, interceptor: impl Interceptor) -> Self {
+        Client(self.0.clone(), self.1.with(Arc::new(interceptor)))
+    }
+
+    /// Makes a request to an S3 API endpoint.
+    pub async fn send_request<R: OutgoingRequest>(
+        &self,
+        request: R,
+    ) -> ResponseResult<C, R> {
+        self.send_customized_request(request, |_| {}).await
+    }
+
+    /// Makes a request to an S3 API endpoint with settings overriding those
+    /// of the client, like its timeouts, credentials or region.
+    pub async fn send_request_with<R: OutgoingRequest>(
+        &self,
+        request: R,
+        config: RequestConfig,
+    ) -> ResponseResult<C, R> {
+        self.send_customized_request(request, |http_request| {
+            config.apply(http_request)
+        })
+        .await
+    }
+
+    /// Makes a request to an S3 API endpoint while allowing to customize
+    /// the http request before it is signed and sent.
+    ///
+    /// If the server rejects the signature because of the clock skew
+    /// between it and the client, the clock is corrected with the date of
+    /// the server and the request is signed and sent again, once. Requests
+    /// failing with a transient error are retried according to the
+    /// [`RetryPolicy`] of the client, as long as its [`RetryQuota`] isn't
+    /// exhausted. Both the retry policy and the [`Timeouts`] of the client
+    /// can be overridden by inserting others in the extensions of the http
+    /// request, where a [`CancellationToken`] cancels the request, and an
+    /// [`SseCustomerKey`] or an [`SseKms`] encrypt its object.
+    ///
+    /// The interceptors of the client modify the request once it is
+    /// customized, and the checksum of the body is computed after them, if
+    /// the client sends checksums and the endpoint supports them.
+    pub async fn send_customized_request<R, F>(
+        &self,
+        request: R,
+        customize: F,
+    ) -> ResponseResult<C, R>
+    where
+        R: OutgoingRequest,
+        F: FnOnce(&mut http::Request<C::RequestBody>),
+    {
+        let mut http_request = self.http_request(request)?;
+        customize(&mut http_request);
+        self.request_region(&mut http_request)?;
+        self.request_payer(&mut http_request);
+        sse_customer_key(&mut http_request);
+        self.sse_kms::<R, _>(&mut http_request)?;
+        http_request = self.1.modify_request(http_request)?;
+        if let Some(algorithm) = self.checksum_algorithm::<R, _>(&http_request)
+        {
+            let (mut parts, body) = http_request.into_parts();
+            checksum::insert_checksum(
+                &mut parts.headers,
+                algorithm,
+                body.as_ref(),
+            );
+            http_request = http::Request::from_parts(parts, body);
+        }
+
+        let timeouts = self.timeouts(&http_request);
+        let token = cancellation_token(&http_request);
+        let verify = self.verifies_checksums::<R>();
+        let span = request_span::<R, _>(&http_request);
+        let authentication = self.authentication::<R, _>(&http_request);
+        let recorder =
+            &Recorder::new(http_request.body().as_ref().len() as u64);
+        let sending = self.send_with_retries(
+            http_request,
+            authentication,
+            recorder,
+            |attempt| async move {
+                let _permit = self.0.limiter.acquire().await;
+                let response = self
+                    .0
+                    .http_client
+                    .send_http_request(attempt)
+                    .await
+                    .map_err(Error::Response)?;
+                trace::record_status(response.status());
+                recorder.received(response.body().as_ref().len() as u64);
+                let response = self.1.read_response(response)?;
+                if verify && response.status() == StatusCode::OK {
+                    checksum::verify(&response).map_err(Error::Checksum)?;
+                }
+                let response = if self.0.decompress_responses {
+                    decompress::decompress(response).map_err(Error::Body)?
+                } else {
+                    response.map(decompress::Body::Received)
+                };
+                Ok(R::IncomingResponse::try_from_http_response(response))
+            },
+        );
+        let result = trace::instrument(
+            span,
+            cancel::cancellable(
+                token.as_ref(),
+                timeout::timeout(timeouts.total, sending),
+            ),
+        )
+        .await;
+        recorder.finish(
+            self.0.metrics_sink.as_deref(),
+            R::METADATA.name,
+            &result,
+        );
+        result
+    }
+
+    /// Makes a request to an S3 API endpoint, returning the body of the
+    /// response as a stream, like the content of an object.
+    ///
+    /// The response is returned once its head is received and read, and
+    /// the total timeout of the client doesn't apply to reading its body,
+    /// unlike its read timeout, which applies to every chunk. Error
+    /// responses are read whole, and retried like with
+    /// [`send_request`](Self::send_request).
+    ///
+    /// When the client validates checksums, reading the body fails with a
+    /// [`ChecksumMismatch`] wrapped in an [`std::io::Error`] if its content
+    /// doesn't match the checksum sent by the server.
+    pub async fn send_request_streaming_response<R>(
+        &self,
+        request: R,
+    ) -> Result<(R::IncomingResponse, ByteStream), Error<C::Error>>
+    where
+        R: OutgoingRequest,
+        R::IncomingResponse: IncomingStreamingResponse,
+    {
+        let mut http_request = self.http_request(request)?;
+        self.request_payer(&mut http_request);
+        self.sse_kms::<R, _>(&mut http_request)?;
+        let http_request = self.1.modify_request(http_request)?;
+
+        let timeouts = self.timeouts(&http_request);
+        let token = cancellation_token(&http_request);
+        let verify = self.verifies_checksums::<R>();
+        let span = request_span::<R, _>(&http_request);
+        let authentication = self.authentication::<R, _>(&http_request);
+        let recorder =
+            &Recorder::new(http_request.body().as_ref().len() as u64);
+        let sending = self.send_with_retries(
+            http_request,
+            authentication,
+            recorder,
+            |attempt| async move {
+                let permit = self.0.limiter.acquire().await;
+                let response = self
+                    .0
+                    .http_client
+                    .send_http_request_streaming_response(attempt)
+                    .await
+                    .map_err(Error::Response)?;
+                drop(permit);
+                trace::record_status(response.status());
+                recorder.received(content_length(response.headers()));
+                let response = self.1.read_response(response)?;
+                let response = streaming_response(response, verify).await;
+                Ok(response.map(|(response, body)| {
+                    (response, timeout::read_timeout(body, timeouts.read))
+                }))
+            },
+        );
+        let result = trace::instrument(
+            span,
+            cancel::cancellable(
+                token.as_ref(),
+                timeout::timeout(timeouts.total, sending),
+            ),
+        )
+        .await;
+        recorder.finish(
+            self.0.metrics_sink.as_deref(),
+            R::METADATA.name,
+            &result,
+        );
+        result
+    }
+
+    /// Makes a request to an S3 API endpoint with a body that is read as it
+    /// is sent, like the content of a large object, instead of the body of
+    /// the request.
+    ///
+    /// The body is signed chunk by chunk with the `aws-chunked` encoding
+    /// when the endpoint requires a signature in the headers, followed by
+    /// the checksum of the body if the client sends checksums and the
+    /// endpoint supports them. Since it can only be read once, the request
+    /// isn't retried, and only the total timeout of the client applies:
+    /// [`send_replayable_request`](Self::send_replayable_request) retries
+    /// requests with bodies that can be read again.
+    pub async fn send_streaming_request<R: OutgoingRequest>(
+        &self,
+        request: R,
+        body: StreamingBody,
+    ) -> ResponseResult<C, R> {
+        let mut http_request = self.http_request(request)?;
+        self.request_payer(&mut http_request);
+        self.sse_kms::<R, _>(&mut http_request)?;
+        let http_request = self.1.modify_request(http_request)?;
+        let timeouts = self.timeouts(&http_request);
+        let token = cancellation_token(&http_request);
+        let span = request_span::<R, _>(&http_request);
+        let recorder = Recorder::new(body.content_length());
+        recorder.attempt();
+        let sending = trace::instrument(
+            trace::attempt_span(1),
+            self.send_streaming::<R>(&http_request, body, &recorder),
+        );
+        let result = trace::instrument(
+            span,
+            cancel::cancellable(
+                token.as_ref(),
+                timeout::timeout(timeouts.total, sending),
+            ),
+        )
+        .await;
+        recorder.finish(
+            self.0.metrics_sink.as_deref(),
+            R::METADATA.name,
+            &result,
+        );
+        result
+    }
+
+    /// Makes a request to an S3 API endpoint with a body that is read as it
+    /// is sent, like
+    /// [`send_streaming_request`](Self::send_streaming_request), but read
+    /// again from the start to retry the request when it fails with a
+    /// transient error, as the retry policy of the client allows.
+    pub async fn send_replayable_request<R: OutgoingRequest>(
+        &self,
+        request: R,
+        body: ReplayableBody,
+    ) -> ResponseResult<C, R> {
+        let mut http_request = self.http_request(request)?;
+        self.request_payer(&mut http_request);
+        self.sse_kms::<R, _>(&mut http_request)?;
+        let http_request = self.1.modify_request(http_request)?;
+        let timeouts = self.timeouts(&http_request);
+        let token = cancellation_token(&http_request);
+        let span = request_span::<R, _>(&http_request);
+        let recorder = Recorder::new(body.content_length());
+        let sending =
+            self.send_replayable::<R>(&http_request, &body, &recorder);
+        let result = trace::instrument(
+            span,
+            cancel::cancellable(
+                token.as_ref(),
+                timeout::timeout(timeouts.total, sending),
+            ),
+        )
+        .await;
+        recorder.finish(
+            self.0.metrics_sink.as_deref(),
+            R::METADATA.name,
+            &result,
+        );
+        result
+    }
+
+    /// Sends a request to a presigned URL, like one received from another
+    /// service, returning the response if it is successful.
+    ///
+    /// The URL is sent as is, without signing it again, but with the
+    /// retries, timeouts, limits and interceptors of the client.
+    pub async fn send_presigned(
+        &self,
+        method: Method,
+        url: &str,
+    ) -> Result<http::Response<Bytes>, Error<C::Error>> {
+        self.send_presigned_with_body(method, url, &[]).await
+    }
+
+    /// Sends a request with a body to a presigned URL, like an upload,
+    /// like [`send_presigned`](Self::send_presigned).
+    ///
+    /// The URL doesn't carry the headers it was signed with, like
+    /// `Content-Type`, which an interceptor of the client can add.
+    pub async fn send_presigned_with_body(
+        &self,
+        method: Method,
+        url: &str,
+        body: &[u8],
+    ) -> Result<http::Response<Bytes>, Error<C::Error>> {
+        let mut request_body = C::RequestBody::default();
+        request_body.put_slice(body);
+        let http_request = http::Request::builder()
+            .method(method)
+            .uri(url)
+            .body(request_body)
+            .map_err(IntoHttpError::from)?;
+        let http_request = self.1.modify_request(http_request)?;
+
+        let timeouts = self.timeouts(&http_request);
+        let token = cancellation_token(&http_request);
+        let span = trace::request_span(PRESIGNED, None, None);
+        let recorder = &Recorder::new(body.len() as u64);
+        let sending = self.send_with_retries(
+            http_request,
+            AuthScheme::None,
+            recorder,
+            |attempt| async move {
+                let _permit = self.0.limiter.acquire().await;
+                let response = self
+                    .0
+                    .http_client
+                    .send_http_request(attempt)
+                    .await
+                    .map_err(Error::Response)?;
+                trace::record_status(response.status());
+                recorder.received(response.body().as_ref().len() as u64);
+                let response = self.1.read_response(response)?;
+                if !response.status().is_success() {
+                    let error = S3Error::from_http_response(&response);
+                    return Ok(Err(error.into()));
+                }
+                Ok(Ok(
+                    response.map(|body| Bytes::copy_from_slice(body.as_ref()))
+                ))
+            },
+        );
+        let result = trace::instrument(
+            span,
+            cancel::cancellable(
+                token.as_ref(),
+                timeout::timeout(timeouts.total, sending),
+            ),
+        )
+        .await;
+        recorder.finish(self.0.metrics_sink.as_deref(), PRESIGNED, &result);
+        result
+    }
+
+    /// Converts a request to an http request.
+    fn http_request<R: OutgoingRequest>(
+        &self,
+        request: R,
+    ) -> Result<http::Request<C::RequestBody>, Error<C::Error>> {
+        let endpoint_url = self.endpoint_url(&self.0.region, false);
+        let mut http_request =
+            request.try_into_http_request::<C::RequestBody>(&endpoint_url)?;
+        if R::METADATA.scope != Scope::Service {
+            if let Some(routing) = self.routing(http_request.uri()) {
+                *http_request.uri_mut() = self.route(&routing)?;
+                http_request.extensions_mut().insert(routing);
+            }
+        }
+        if R::METADATA.requires_content_md5
+            && !http_request.headers().contains_key(CONTENT_MD5)
+        {
+            let content_md5 =
+                s3ers_signature::md5::content_md5(http_request.body().as_ref());
+            http_request.headers_mut().insert(
+                HeaderName::from_static(CONTENT_MD5),
+                HeaderValue::from_str(&content_md5).unwrap(),
+            );
+        }
+        if !http_request.headers().contains_key(USER_AGENT) {
+            http_request
+                .headers_mut()
+                .insert(USER_AGENT, self.0.user_agent.clone());
+        }
+        if self.verifies_checksums::<R>() && R::METADATA.method == Method::GET {
+            http_request.headers_mut().insert(
+                HeaderName::from_static(checksum::X_AMZ_CHECKSUM_MODE),
+                HeaderValue::from_static("ENABLED"),
+            );
+        }
+        Ok(http_request)
+    }
+
+    /// Returns where a path-style request to the endpoint of the client is
+    /// sent, if it is a request to a bucket.
+    fn routing(&self, path_uri: &Uri) -> Option<Routing> {
+        let endpoint = self.endpoint_url(&self.0.region, false).parse().ok()?;
+        let bucket = addressing::bucket(path_uri, &endpoint)?.to_owned();
+        let region = self
+            .0
+            .follow_region_redirects
+            .then(|| {
+                self.0.bucket_regions.lock().unwrap().get(&bucket).cloned()
+            })
+            .flatten()
+            .unwrap_or_else(|| self.0.region.clone());
+
+        Some(Routing {
+            path_uri: path_uri.clone(),
+            bucket,
+            region,
+        })
+    }
+
+    /// Sends a request to the region in its extensions, if it has one,
+    /// instead of the region of the client.
+    fn request_region<B>(
+        &self,
+        http_request: &mut http::Request<B>,
+    ) -> Result<(), Error<C::Error>> {
+        let region = match http_request.extensions().get::<RequestRegion>() {
+            Some(region) => region.0.clone(),
+            None => return Ok(()),
+        };
+        let uri = match http_request.extensions_mut().remove::<Routing>() {
+            Some(mut routing) => {
+                routing.region = region;
+                let uri = self.route(&routing)?;
+                http_request.extensions_mut().insert(routing);
+                uri
+            }
+            None => {
+                let endpoint =
+                    parse_endpoint(self.endpoint_url(&self.0.region, false))?;
+                let target = parse_endpoint(self.endpoint_url(&region, false))?;
+                addressing::rebase(http_request.uri(), &endpoint, &target)
+                    .ok_or_else(|| {
+                        Error::Endpoint(format!(
+                            "`{}` can't be sent to `{}`",
+                            http_request.uri(),
+                            target
+                        ))
+                    })?
+            }
+        };
+        *http_request.uri_mut() = uri;
+        Ok(())
+    }
+
+    /// Returns the URI of a request to a bucket, sent to the endpoint of its
+    /// region and addressed as configured.
+    fn route(&self, routing: &Routing) -> Result<Uri, Error<C::Error>> {
+        let endpoint =
+            parse_endpoint(self.endpoint_url(&self.0.region, false))?;
+        let target = parse_endpoint(self.endpoint_url(&routing.region, false))?;
+        let uri = addressing::rebase(&routing.path_uri, &endpoint, &target)
+            .ok_or_else(|| {
+                Error::Endpoint(format!(
+                    "`{}` can't be sent to `{}`",
+                    routing.path_uri, target
+                ))
+            })?;
+
+        let addressed = if self.0.accelerate {
+            let accelerate_endpoint =
+                parse_endpoint(self.endpoint_url(&routing.region, true))?;
+            addressing::accelerate(&uri, &target, &accelerate_endpoint)
+                .map_err(Error::Endpoint)?
+        } else {
+            addressing::virtual_host(&uri, &target, self.0.addressing_style)
+        };
+        Ok(addressed.unwrap_or(uri))
+    }
+
+    /// Returns the algorithm of the checksum to send with a request, if the
+    /// client sends checksums, the request uploads a body to an endpoint
+    /// supporting them, and it has no checksum yet.
+    fn checksum_algorithm<R: OutgoingRequest, B>(
+        &self,
+        http_request: &http::Request<B>,
+    ) -> Option<ChecksumAlgorithm> {
+        let uploads = R::METADATA.method == Method::PUT
+            || R::METADATA.method == Method::POST;
+        if !R::METADATA.flexible_checksums
+            || !uploads
+            || checksum::has_checksum(http_request.headers())
+        {
+            return None;
+        }
+        self.0.checksum_algorithm
+    }
+
+    /// Agrees to pay for a request, if the client or the request do.
+    fn request_payer<B>(&self, http_request: &mut http::Request<B>) {
+        let requester_pays = http_request
+            .extensions()
+            .get::<RequesterPays>()
+            .map_or(self.0.requester_pays, |requester_pays| requester_pays.0);
+        if requester_pays {
+            http_request
+                .headers_mut()
+                .entry(REQUEST_PAYER)
+                .or_insert(HeaderValue::from_static("requester"));
+        }
+    }
+
+    /// Encrypts the object of a request with a KMS key, if the request has
+    /// an [`SseKms`] in its extensions or the client encrypts the objects
+    /// written by the endpoint of `R`, and the request doesn't choose
+    /// another encryption.
+    fn sse_kms<R: OutgoingRequest, B>(
+        &self,
+        http_request: &mut http::Request<B>,
+    ) -> Result<(), Error<C::Error>> {
+        let sse_kms = match http_request.extensions().get::<SseKms>() {
+            Some(sse_kms) => sse_kms,
+            None if WRITE_ENDPOINTS.contains(&R::METADATA.name) => {
+                match &self.0.sse_kms {
+                    Some(sse_kms) => sse_kms,
+                    None => return Ok(()),
+                }
+            }
+            None => return Ok(()),
+        };
+        let headers = sse_kms.headers().map_err(IntoHttpError::from)?;
+        let encrypted = ENCRYPTION_HEADERS
+            .iter()
+            .any(|name| http_request.headers().contains_key(*name));
+        if !encrypted {
+            http_request.headers_mut().extend(headers);
+        }
+        Ok(())
+    }
+
+    /// Returns how a request to the endpoint of `R` is authenticated, not at
+    /// all if it is anonymous.
+    fn authentication<R: OutgoingRequest, B>(
+        &self,
+        http_request: &http::Request<B>,
+    ) -> AuthScheme {
+        let anonymous = http_request
+            .extensions()
+            .get::<Anonymous>()
+            .map_or(self.0.anonymous, |anonymous| anonymous.0);
+        if anonymous {
+            AuthScheme::None
+        } else {
+            R::METADATA.authentication
+        }
+    }
+
+    /// Whether the content returned by the endpoint of `R` is checked
+    /// against its checksum.
+    fn verifies_checksums<R: OutgoingRequest>(&self) -> bool {
+        R::METADATA.flexible_checksums && self.0.validate_checksums
+    }
+
+    /// Returns the URL of the S3 service in a region, or of its transfer
+    /// acceleration endpoint.
+    fn endpoint_url(&self, region: &str, accelerate: bool) -> String {
+        match &self.0.endpoint_url {
+            Some(endpoint_url) if !accelerate => endpoint_url.clone(),
+            _ => self.0.endpoint_resolver.resolve_endpoint(&EndpointParams {
+                region,
+                use_dual_stack: self.0.use_dual_stack,
+                use_fips: self.0.use_fips,
+                accelerate,
+            }),
+        }
+    }
+
+    /// Returns the region of the bucket of a request, if the server
+    /// redirected it there and redirects are followed.
+    fn redirect_region(&self, error: &S3Error, region: &str) -> Option<String> {
+        let redirected = error.status == StatusCode::MOVED_PERMANENTLY
+            || error.code == "AuthorizationHeaderMalformed";
+        if !self.0.follow_region_redirects || !redirected {
+            return None;
+        }
+
+        let bucket_region = error
+            .bucket_region
+            .as_ref()
+            .or_else(|| error.details.get("Region"))?;
+        (bucket_region != region).then(|| bucket_region.clone())
+    }
+
+    /// Returns the source of the credentials a request is signed with, the
+    /// one of the client unless another was inserted in its extensions.
+    fn credentials<B>(
+        &self,
+        http_request: &http::Request<B>,
+    ) -> Arc<dyn CredentialsProvider> {
+        http_request
+            .extensions()
+            .get::<RequestCredentials>()
+            .map_or_else(
+                || self.0.credentials.clone(),
+                |provider| provider.0.clone(),
+            )
+    }
+
+    /// Returns the region a request is signed for, when it isn't sent to a
+    /// bucket.
+    fn signing_region<'a, B>(
+        &'a self,
+        http_request: &'a http::Request<B>,
+    ) -> &'a str {
+        http_request
+            .extensions()
+            .get::<RequestRegion>()
+            .map_or(self.0.region.as_str(), |region| &region.0)
+    }
+
+    /// Returns the timeouts of a request, the ones of the client unless
+    /// others were inserted in its extensions.
+    fn timeouts<B>(&self, http_request: &http::Request<B>) -> Timeouts {
+        http_request
+            .extensions()
+            .get::<Timeouts>()
+            .copied()
+            .unwrap_or(self.0.timeouts)
+    }
+
+    /// Signs and sends a request with a streaming body.
+    async fn send_streaming<R: OutgoingRequest>(
+        &self,
+        http_request: &http::Request<C::RequestBody>,
+        body: StreamingBody,
+        recorder: &Recorder,
+    ) -> ResponseResult<C, R> {
+        let routing = http_request.extensions().get::<Routing>();
+        let region =
+            routing.map_or(self.0.region.as_str(), |routing| &routing.region);
+        let credentials = self.credentials(http_request);
+        let content_length = body.content_length();
+        let checksum_algorithm = self.checksum_algorithm::<R, _>(http_request);
+        let authentication = self.authentication::<R, _>(http_request);
+        // The body isn't read to sign the request, it is replaced.
+        let mut http_request = clone_request(http_request).map(|_| &[][..]);
+
+        let body = match authentication {
+            AuthScheme::AwsSignatureV4 => {
+                let credentials = credentials.provide_credentials().await?;
+                let params = self.signing_params(region);
+                let signer = match checksum_algorithm {
+                    Some(algorithm) => {
+                        chunked::sign_streaming_request_with_trailer(
+                            &mut http_request,
+                            content_length,
+                            body::CHUNK_SIZE as u64,
+                            algorithm,
+                            &credentials,
+                            &params,
+                        )?
+                    }
+                    None => chunked::sign_streaming_request(
+                        &mut http_request,
+                        content_length,
+                        body::CHUNK_SIZE as u64,
+                        &credentials,
+                        &params,
+                    )?,
+                };
+                let length = self::content_length(http_request.headers());
+                StreamingBody::new(body::AwsChunked::new(body, signer), length)
+            }
+            authentication => {
+                http_request
+                    .headers_mut()
+                    .insert(CONTENT_LENGTH, content_length.into());
+                self.sign(
+                    &mut http_request,
+                    authentication,
+                    &*credentials,
+                    region,
+                )
+                .await?;
+                body
+            }
+        };
+
+        let _permit = self.0.limiter.acquire().await;
+        let response = self
+            .0
+            .http_client
+            .send_streaming_http_request(http_request.map(|_| body))
+            .await?;
+        trace::record_status(response.status());
+        recorder.received(response.body().as_ref().len() as u64);
+        let response = self.1.read_response(response)?;
+        match R::IncomingResponse::try_from_http_response(response) {
+            Ok(response) => Ok(response),
+            Err(FromHttpResponseError::Server(error)) => {
+                let failure = retry::classify(&error);
+                Err(rejection(error, None, failure))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Signs and sends a request with a body read again for every attempt,
+    /// again if it fails with a transient error.
+    async fn send_replayable<R: OutgoingRequest>(
+        &self,
+        http_request: &http::Request<C::RequestBody>,
+        body: &ReplayableBody,
+        recorder: &Recorder,
+    ) -> ResponseResult<C, R> {
+        let retry_policy = http_request
+            .extensions()
+            .get::<RetryPolicy>()
+            .copied()
+            .unwrap_or(self.0.retry_policy);
+        let quota = &self.0.retry_quota;
+        let mut attempts = 0;
+        let mut acquired = 0;
+        loop {
+            attempts += 1;
+            recorder.attempt();
+            let can_retry =
+                retry_policy.allows_retry(http_request.method(), attempts);
+
+            let attempt = body.open().await.map_err(Error::Body)?;
+            let result = trace::instrument(
+                trace::attempt_span(attempts),
+                self.send_streaming::<R>(http_request, attempt, recorder),
+            )
+            .await;
+            let (cost, retry_after) = match &result {
+                Err(Error::Response(_)) => (retry::TIMEOUT_RETRY_COST, None),
+                Err(Error::Throttling(error)) => {
+                    (retry::RETRY_COST, error.retry_after)
+                }
+                Err(Error::FromHttpResponse(
+                    FromHttpResponseError::Server(error),
+                )) if retry::classify(error).is_some() => {
+                    (retry::RETRY_COST, error.retry_after)
+                }
+                Ok(_) => {
+                    quota.release(acquired);
+                    return result;
+                }
+                Err(_) => return result,
+            };
+            if !can_retry || !quota.acquire(cost) {
+                return result;
+            }
+            acquired += cost;
+            wait(retry_policy.delay(attempts, retry_after)).await;
+        }
+    }
+
+    /// Signs and sends a request with `send`, again if it fails with a
+    /// transient error.
+    async fn send_with_retries<T, F, Fut>(
+        &self,
+        mut http_request: http::Request<C::RequestBody>,
+        authentication: AuthScheme,
+        recorder: &Recorder,
+        send: F,
+    ) -> Result<T, Error<C::Error>>
+    where
+        F: Fn(http::Request<C::RequestBody>) -> Fut,
+        Fut: Future<
+            Output = Result<Result<T, FromHttpResponseError>, Error<C::Error>>,
+        >,
+    {
+        let mut routing = http_request.extensions_mut().remove::<Routing>();
+        let retry_policy = http_request
+            .extensions()
+            .get::<RetryPolicy>()
+            .copied()
+            .unwrap_or(self.0.retry_policy);
+        let read_timeout = self.timeouts(&http_request).read;
+        let credentials = self.credentials(&http_request);
+        let signing_region = self.signing_region(&http_request).to_owned();
+        let quota = &self.0.retry_quota;
+        let mut attempts = 0;
+        let mut acquired = 0;
+        let mut skew_corrected = false;
+        let mut redirected = false;
+        loop {
+            attempts += 1;
+            recorder.attempt();
+            let can_retry =
+                retry_policy.allows_retry(http_request.method(), attempts);
+
+            let region = routing
+                .as_ref()
+                .map_or(signing_region.as_str(), |routing| &routing.region);
+            let mut attempt = clone_request(&http_request);
+            let signing = self
+                .sign(&mut attempt, authentication, &*credentials, region)
+                .await?;
+
+            // Hedging is boxed, not to grow the futures of requests that
+            // aren't hedged.
+            let sending = match &self.0.hedging {
+                Some(hedging) => {
+                    Either::Left(Box::pin(hedge(hedging, attempt, &send)))
+                }
+                None => Either::Right(send(attempt)),
+            };
+            let sending = timeout::timeout(read_timeout, sending);
+            let response =
+                match trace::instrument(trace::attempt_span(attempts), sending)
+                    .await
+                {
+                    Ok(response) => response,
+                    Err(Error::Response(_) | Error::Timeout)
+                        if can_retry
+                            && quota.acquire(retry::TIMEOUT_RETRY_COST) =>
+                    {
+                        acquired += retry::TIMEOUT_RETRY_COST;
+                        wait(retry_policy.delay(attempts, None)).await;
+                        continue;
+                    }
+                    Err(err) => return Err(err),
+                };
+
+            let error = match response {
+                Ok(response) => {
+                    quota.release(acquired);
+                    return Ok(response);
+                }
+                Err(FromHttpResponseError::Server(error)) => error,
+                Err(err) => return Err(err.into()),
+            };
+
+            if authentication != AuthScheme::None
+                && !skew_corrected
+                && SKEW_ERRORS.contains(&error.code.as_str())
+            {
+                if let Some(date) = error.date {
+                    self.0.clock.correct(date.to_system_time());
+                    skew_corrected = true;
+                    continue;
+                }
+            }
+
+            if let Some(routing) = routing.as_mut().filter(|_| !redirected) {
+                if let Some(region) =
+                    self.redirect_region(&error, &routing.region)
+                {
+                    routing.region = region;
+                    *http_request.uri_mut() = self.route(routing)?;
+                    self.0
+                        .bucket_regions
+                        .lock()
+                        .unwrap()
+                        .insert(routing.bucket.clone(), routing.region.clone());
+                    redirected = true;
+                    continue;
+                }
+            }
+
+            let failure = retry::classify(&error);
+            if can_retry
+                && failure.is_some()
+                && quota.acquire(retry::RETRY_COST)
+            {
+                acquired += retry::RETRY_COST;
+                wait(retry_policy.delay(attempts, error.retry_after)).await;
+                continue;
+            }
+
+            return Err(rejection(error, signing, failure));
+        }
+    }
+
+    /// Returns the parameters to sign a request for a region with now.
+    fn signing_params<'a>(&self, region: &'a str) -> SigningParams<'a> {
+        let mut params =
+            SigningParams::new(region, SERVICE, self.0.clock.now());
+        // The canonical request is computed anyway, keeping it allows
+        // reporting it if the server rejects the signature.
+        params.settings.debug = true;
+        params
+    }
+
+    /// Signs a request according to the authentication scheme of its
+    /// endpoint.
+    async fn sign<B: AsRef<[u8]>>(
+        &self,
+        request: &mut http::Request<B>,
+        authentication: AuthScheme,
+        credentials: &dyn CredentialsProvider,
+        region: &str,
+    ) -> Result<Option<SigningOutput>, Error<C::Error>> {
+        if authentication == AuthScheme::None {
+            return Ok(None);
+        }
+
+        let credentials = credentials.provide_credentials().await?;
+        let params = self.signing_params(region);
+
+        let output = match authentication {
+            AuthScheme::None => unreachable!(),
+            AuthScheme::AwsSignatureV4 => Some(s3ers_signature::sign_request(
+                request,
+                &credentials,
+                &params,
+            )?),
+            AuthScheme::AwsSignatureV4QueryParams => {
+                Some(s3ers_signature::presign_request(
+                    request,
+                    &credentials,
+                    &params,
+                    QUERY_SIGNATURE_EXPIRATION,
+                )?)
+            }
+            #[cfg(feature = "sigv2")]
+            AuthScheme::AwsSignatureV2 => {
+                let endpoint = self.endpoint_url(region, false).parse().ok();
+                let uri = request.uri().clone();
+                let params = s3ers_signature::v2::SigningParams {
+                    time: params.time,
+                    virtual_host_bucket: endpoint.as_ref().and_then(
+                        |endpoint| {
+                            addressing::virtual_host_bucket(&uri, endpoint)
+                        },
+                    ),
+                };
+                s3ers_signature::v2::sign_request(
+                    request,
+                    &credentials,
+                    &params,
+                )?;
+                None
+            }
+            #[cfg(not(feature = "sigv2"))]
+            AuthScheme::AwsSignatureV2 => {
+                return Err(s3ers_signature::Error::Unsupported("sigv2").into())
+            }
+        };
+        Ok(output)
+    }
+}
+
+/// Sends an attempt of a request with `send`, and again if it is slow,
+/// returning the first successful response.
+async fn hedge<B: Clone, T, E, F, Fut>(
+    hedging: &Hedging,
+    attempt: http::Request<B>,
+    send: &F,
+) -> Fut::Output
+where
+    F: Fn(http::Request<B>) -> Fut,
+    Fut: Future<Output = Result<Result<T, FromHttpResponseError>, Error<E>>>,
+{
+    let succeeded = |output: &Fut::Output| matches!(output, Ok(Ok(_)));
+
+    let start = Instant::now();
+    let output = match hedging.delay(attempt.method()) {
+        Some(delay) => {
+            let hedge = clone_request(&attempt);
+            let first = Box::pin(send(attempt));
+            let sleep = Box::pin(tokio::time::sleep(delay));
+            match future::select(first, sleep).await {
+                Either::Left((output, _)) => output,
+                Either::Right((_, first)) => {
+                    let second = Box::pin(send(hedge));
+                    // The other attempt is dropped once one succeeds.
+                    match future::select(first, second).await {
+                        Either::Left((output, other))
+                        | Either::Right((output, other))
+                            if !succeeded(&output) =>
+                        {
+                            other.await
+                        }
+                        Either::Left((output, _))
+                        | Either::Right((output, _)) => output,
+                    }
+                }
+            }
+        }
+        None => send(attempt).await,
+    };
+    if succeeded(&output) {
+        hedging.record(start.elapsed());
+    }
+    output
+}
+
+/// Waits before retrying a request.
+async fn wait(delay: Duration) {
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Reads the head of a response whose body is read as a stream, or the whole
+/// response if it is an error.
+///
+/// With `verify`, the body of a complete content is checked against its
+/// checksum as it is read.
+async fn streaming_response<T: IncomingStreamingResponse>(
+    response: http::Response<ByteStream>,
+    verify: bool,
+) -> Result<(T, ByteStream), FromHttpResponseError> {
+    let (head, mut body) = response.into_parts();
+    if head.status.is_success() {
+        if verify && head.status == StatusCode::OK {
+            body = checksum::verify_stream(&head.headers, body);
+        }
+        let response = T::try_from_http_response_head(
+            http::Response::from_parts(head, ()),
+        )?;
+        return Ok((response, body));
+    }
+
+    // The error is still read from the head if its body can't be.
+    let body = body.collect().await.unwrap_or_default();
+    Err(
+        S3Error::from_http_response(&http::Response::from_parts(head, body))
+            .into(),
+    )
+}
+
+/// Returns the span of a request to the endpoint of `R`, before its routing
+/// is removed from its extensions.
+fn request_span<R: OutgoingRequest, B>(
+    http_request: &http::Request<B>,
+) -> trace::Span {
+    let routing = http_request.extensions().get::<Routing>();
+    let bucket = routing.map(|routing| routing.bucket.as_str());
+    let key = routing
+        .and_then(|routing| routing.path_uri.path()[1..].split_once('/'))
+        .map(|(_, key)| key)
+        .filter(|key| !key.is_empty());
+    trace::request_span(R::METADATA.name, bucket, key)
+}
+
+/// Adds the headers of the customer-provided key inserted in the extensions
+/// of a request, unless the request has them already.
+fn sse_customer_key<B>(http_request: &mut http::Request<B>) {
+    let headers = match http_request.extensions().get::<SseCustomerKey>() {
+        Some(key) => key.headers(),
+        None => return,
+    };
+    for (name, value) in headers {
+        http_request.headers_mut().entry(name).or_insert(value);
+    }
+}
+
+/// Returns the cancellation token inserted in the extensions of a request.
+fn cancellation_token<B>(
+    http_request: &http::Request<B>,
+) -> Option<CancellationToken> {
+    http_request
+        .extensions()
+        .get::<CancellationToken>()
+        .cloned()
+}
+
+/// Returns the length of a body announced by its `Content-Length` header, or
+/// zero.
+fn content_length(headers: &http::HeaderMap) -> u64 {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Parses the URL of an endpoint.
+fn parse_endpoint<E>(url: String) -> Result<Uri, Error<E>> {
+    url.parse()
+        .map_err(|_| Error::Endpoint(format!("invalid endpoint `{}`", url)))
+}
+
+/// Converts an error returned by the server to the error of the client.
+fn rejection<E>(
+    error: Box<S3Error>,
+    signing: Option<SigningOutput>,
+    failure: Option<Failure>,
+) -> Error<E> {
+    if failure == Some(Failure::Throttling) {
+        return Error::Throttling(error);
+    }
+
+    match signing {
+        Some(signing) if error.code == "SignatureDoesNotMatch" => {
+            Error::SignatureMismatch(Box::new(SignatureMismatch {
+                error: *error,
+                canonical_request: signing.canonical_request,
+                string_to_sign: signing.string_to_sign,
+            }))
+        }
+        _ if error.code == "PreconditionFailed" => {
+            Error::PreconditionFailed(error)
+        }
+        _ => Error::FromHttpResponse(FromHttpResponseError::Server(error)),
+    }
+}
+
+/// Copies a request, to sign and send it again.
+fn clone_request<B: Clone>(request: &http::Request<B>) -> http::Request<B> {
+    let mut clone = http::Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+    clone
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        convert::Infallible,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use futures_executor::block_on;
+    use futures_util::{future, stream, StreamExt};
+    use http::{Method, StatusCode};
+    use s3ers_api::{
+        error::{FromHttpResponseError, IntoHttpError, S3Error},
+        AuthScheme, IncomingResponse, IncomingStreamingResponse, Metadata,
+        OutgoingRequest, Paginated, Scope,
+    };
+    use s3ers_signature::{clock::FixedClock, Credentials};
+
+    use super::{
+        AddressingStyle, Anonymous, ChecksumAlgorithm, Client, Compatibility,
+        Error, ErrorClass, Hedging, HedgingPolicy, HttpClient, Interceptor,
+        InterceptorError, MetricsSink, ReplayableBody, RequestConfig,
+        RequestMetrics, RequesterPays, RetryPolicy, SseCustomerKey, SseKms,
+        StreamingBody,
+    };
+
+    /// Replies to requests with canned responses and records them.
+    #[derive(Debug, Default)]
+    pub(crate) struct MockHttpClient {
+        responses: Mutex<VecDeque<http::Response<Vec<u8>>>>,
+        pub(crate) requests: Mutex<Vec<http::Request<Vec<u8>>>>,
+    }
+
+    impl MockHttpClient {
+        fn new(responses: Vec<http::Response<Vec<u8>>>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into()),
+                requests: Mutex::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for MockHttpClient {
+        type RequestBody = Vec<u8>;
+        type ResponseBody = Vec<u8>;
+        type Error = Infallible;
+
+        async fn send_http_request(
+            &self,
+            req: http::Request<Vec<u8>>,
+        ) -> Result<http::Response<Vec<u8>>, Infallible> {
+            self.requests.lock().unwrap().push(req);
+            Ok(self.responses.lock().unwrap().pop_front().unwrap())
+        }
+    }
+
+    pub(crate) struct Request;
+
+    #[derive(Debug)]
+    pub(crate) struct Response;
+
+    impl OutgoingRequest for Request {
+        const METADATA: Metadata = Metadata {
+            description: "Test endpoint",
+            method: Method::GET,
+            name: "Test",
+            path: "/bucket/key",
+            scope: Scope::Service,
+            authentication: AuthScheme::AwsSignatureV4,
+            requires_content_md5: false,
+            flexible_checksums: false,
+        };
+
+        type IncomingResponse = Response;
+
+        fn try_into_http_request<T: Default + bytes::BufMut>(
+            self,
+            base_url: &str,
+        ) -> Result<http::Request<T>, IntoHttpError> {
+            Ok(http::Request::get(format!("{}/bucket/key", base_url))
+                .body(T::default())?)
+        }
+    }
+
+    /// The test endpoint, addressing its bucket as configured and requiring
+    /// a `Content-MD5` header.
+    struct BucketRequest;
+
+    impl OutgoingRequest for BucketRequest {
+        const METADATA: Metadata = Metadata {
+            description: "Test endpoint",
+            method: Method::GET,
+            name: "Test",
+            path: "/:bucket/*key",
+            scope: Scope::Object,
+            authentication: AuthScheme::AwsSignatureV4,
+            requires_content_md5: true,
+            flexible_checksums: false,
+        };
+
+        type IncomingResponse = Response;
+
+        fn try_into_http_request<T: Default + bytes::BufMut>(
+            self,
+            base_url: &str,
+        ) -> Result<http::Request<T>, IntoHttpError> {
+            Request.try_into_http_request(base_url)
+        }
+    }
+
+    impl IncomingResponse for Response {
+        fn try_from_http_response<T: AsRef<[u8]>>(
+            response: http::Response<T>,
+        ) -> Result<Self, FromHttpResponseError> {
+            if response.status().is_success() {
+                Ok(Response)
+            } else {
+                Err(S3Error::from_http_response(&response).into())
+            }
+        }
+    }
+
+    impl IncomingStreamingResponse for Response {
+        fn try_from_http_response_head(
+            _response: http::Response<()>,
+        ) -> Result<Self, FromHttpResponseError> {
+            Ok(Response)
+        }
+    }
+
+    pub(crate) fn client(
+        responses: Vec<http::Response<Vec<u8>>>,
+    ) -> Client<MockHttpClient> {
+        Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .retry_policy(
+                RetryPolicy::default()
+                    .with_backoff(Duration::ZERO, Duration::ZERO),
+            )
+            .http_client(MockHttpClient::new(responses))
+    }
+
+    pub(crate) fn ok() -> http::Response<Vec<u8>> {
+        http::Response::new(Vec::new())
+    }
+
+    fn error(code: &str, date: &str) -> http::Response<Vec<u8>> {
+        http::Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .header("Date", date)
+            .body(format!("<Error><Code>{}</Code></Error>", code).into_bytes())
+            .unwrap()
+    }
+
+    fn header<'a>(request: &'a http::Request<Vec<u8>>, name: &str) -> &'a str {
+        request.headers()[name].to_str().unwrap()
+    }
+
+    /// A paginated endpoint whose pages are numbered up to 3.
+    #[derive(Clone)]
+    struct PagedRequest(u32);
+
+    #[derive(Debug)]
+    struct Page(u32);
+
+    impl OutgoingRequest for PagedRequest {
+        const METADATA: Metadata = Request::METADATA;
+
+        type IncomingResponse = Page;
+
+        fn try_into_http_request<T: Default + bytes::BufMut>(
+            self,
+            base_url: &str,
+        ) -> Result<http::Request<T>, IntoHttpError> {
+            Ok(http::Request::get(format!(
+                "{}/bucket?page={}",
+                base_url, self.0
+            ))
+            .body(T::default())?)
+        }
+    }
+
+    impl IncomingResponse for Page {
+        fn try_from_http_response<T: AsRef<[u8]>>(
+            response: http::Response<T>,
+        ) -> Result<Self, FromHttpResponseError> {
+            let page = std::str::from_utf8(response.body().as_ref())
+                .unwrap()
+                .parse()
+                .unwrap();
+            Ok(Page(page))
+        }
+    }
+
+    impl Paginated for PagedRequest {
+        type Item = u32;
+
+        fn next_page(self, response: &Page) -> Option<Self> {
+            (response.0 < 3).then(|| PagedRequest(response.0 + 1))
+        }
+
+        fn into_items(response: Page) -> Vec<u32> {
+            vec![response.0 * 10, response.0 * 10 + 1]
+        }
+    }
+
+    #[test]
+    fn sign_request() {
+        let client = client(vec![ok()]);
+        block_on(client.send_request(Request)).unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        let authorization = header(&requests[0], "authorization");
+        assert!(authorization
+            .starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+        assert!(authorization.contains("/eu-west-1/s3/aws4_request"));
+    }
+
+    #[test]
+    fn sign_with_signature_v2() {
+        let client = client(vec![]);
+        let credentials = Credentials::new("AKIDEXAMPLE", "secret");
+        let mut request = http::Request::get("https://s3.amazonaws.com/b/k")
+            .body(Vec::new())
+            .unwrap();
+        let signed = block_on(client.sign(
+            &mut request,
+            AuthScheme::AwsSignatureV2,
+            &credentials,
+            "us-east-1",
+        ));
+        #[cfg(feature = "sigv2")]
+        {
+            signed.unwrap();
+            assert!(header(&request, "authorization").starts_with("AWS "));
+        }
+        #[cfg(not(feature = "sigv2"))]
+        assert!(matches!(
+            signed,
+            Err(Error::IntoHttp(IntoHttpError::Signature(
+                s3ers_signature::Error::Unsupported("sigv2")
+            )))
+        ));
+    }
+
+    #[test]
+    fn stream_body() {
+        let client = client(vec![ok()]);
+        let body = StreamingBody::from(vec![b'a'; 100_000]);
+        block_on(client.send_streaming_request(Request, body)).unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        let request = &requests[0];
+        assert_eq!(header(request, "content-encoding"), "aws-chunked");
+        assert_eq!(
+            header(request, "x-amz-content-sha256"),
+            "STREAMING-AWS4-HMAC-SHA256-PAYLOAD"
+        );
+        assert_eq!(header(request, "x-amz-decoded-content-length"), "100000");
+        assert_eq!(
+            header(request, "content-length"),
+            request.body().len().to_string()
+        );
+        assert!(request.body().starts_with(b"10000;chunk-signature="));
+        assert!(request.body().ends_with(b"\r\n\r\n"));
+    }
+
+    #[test]
+    fn retry_replayable_body() {
+        let unavailable = http::Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body(b"<Error><Code>SlowDown</Code></Error>".to_vec())
+            .unwrap();
+        let client = client(vec![unavailable, ok()]);
+        let opened = Arc::new(AtomicUsize::new(0));
+        let body = ReplayableBody::from_fn(5, {
+            let opened = opened.clone();
+            move || {
+                opened.fetch_add(1, Ordering::SeqCst);
+                stream::once(async { Ok(Bytes::from("abcde")) })
+            }
+        });
+        block_on(client.send_replayable_request(Request, body)).unwrap();
+
+        assert_eq!(opened.load(Ordering::SeqCst), 2);
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(header(&requests[1], "x-amz-decoded-content-length"), "5");
+        assert!(requests[1].body().windows(5).any(|w| w == b"abcde"));
+        drop(requests);
+
+        let denied = self::client(vec![error("AccessDenied", ""), ok()]);
+        let body = ReplayableBody::from(b"abcde".to_vec());
+        block_on(denied.send_replayable_request(Request, body)).unwrap_err();
+        assert_eq!(denied.0.http_client.requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn stream_response() {
+        let unavailable = http::Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body(b"<Error><Code>InternalError</Code></Error>".to_vec())
+            .unwrap();
+        let content = http::Response::new(b"content".to_vec());
+        let client = client(vec![unavailable, content]);
+
+        let (_, body) =
+            block_on(client.send_request_streaming_response(Request)).unwrap();
+        assert_eq!(block_on(body.collect()).unwrap(), b"content");
+        assert_eq!(client.0.http_client.requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn paginate() {
+        let page = |n: &str| http::Response::new(n.as_bytes().to_vec());
+        let client = client(vec![page("1"), page("2"), page("3")]);
+
+        let items: Vec<u32> = block_on(
+            client
+                .paginate_items(PagedRequest(1))
+                .map(Result::unwrap)
+                .collect(),
+        );
+        assert_eq!(items, [10, 11, 20, 21, 30, 31]);
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests[2].uri().query(), Some("page=3"));
+    }
+
+    #[test]
+    fn retry_transient_errors() {
+        let unavailable = http::Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body(Vec::new())
+            .unwrap();
+        let client = client(vec![unavailable, ok()]);
+        block_on(client.send_request(Request)).unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(header(&requests[1], "user-agent").starts_with("s3ers/"));
+        assert_eq!(client.retry_quota().tokens_consumed(), 5);
+        assert_eq!(client.retry_quota().available(), 500);
+    }
+
+    #[test]
+    fn report_throttling() {
+        let slow_down = || {
+            http::Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header("Retry-After", "0")
+                .body(b"<Error><Code>SlowDown</Code></Error>".to_vec())
+                .unwrap()
+        };
+        let client = client(vec![slow_down(), slow_down(), slow_down()]);
+
+        match block_on(client.send_request(Request)).unwrap_err() {
+            Error::Throttling(error) => assert_eq!(error.code, "SlowDown"),
+            err => panic!("unexpected error: {:?}", err),
+        }
+        assert_eq!(client.0.http_client.requests.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn report_failed_preconditions() {
+        let failed = http::Response::builder()
+            .status(StatusCode::PRECONDITION_FAILED)
+            .body(b"<Error><Code>PreconditionFailed</Code></Error>".to_vec())
+            .unwrap();
+        let client = client(vec![failed]);
+
+        let error = block_on(client.send_request(Request)).unwrap_err();
+        assert!(matches!(error, Error::PreconditionFailed(_)));
+        assert_eq!(ErrorClass::of(&error), ErrorClass::Client);
+    }
+
+    #[test]
+    fn decompress_responses() {
+        // `<Error><Code>PreconditionFailed</Code><Message>Failed</Message>
+        // </Error>` compressed by gzip.
+        let body = vec![
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xb3,
+            0x71, 0x2d, 0x2a, 0xca, 0x2f, 0xb2, 0xb3, 0x71, 0xce, 0x4f, 0x49,
+            0xb5, 0x0b, 0x28, 0x4a, 0x4d, 0xce, 0xcf, 0x4b, 0xc9, 0x2c, 0xc9,
+            0xcc, 0xcf, 0x73, 0x4b, 0xcc, 0xcc, 0x49, 0x4d, 0xb1, 0xd1, 0x07,
+            0x4b, 0xd8, 0xf8, 0xa6, 0x16, 0x17, 0x27, 0xa6, 0xa7, 0xda, 0xc1,
+            0x44, 0x61, 0x7c, 0x1b, 0x7d, 0x88, 0x7e, 0x00, 0x32, 0x0d, 0xc0,
+            0x2e, 0x47, 0x00, 0x00, 0x00,
+        ];
+        let failed = || {
+            http::Response::builder()
+                .status(StatusCode::PRECONDITION_FAILED)
+                .header("content-encoding", "gzip")
+                .body(body.clone())
+                .unwrap()
+        };
+
+        let message = |client: Client<MockHttpClient>| match block_on(
+            client.send_request(Request),
+        )
+        .unwrap_err()
+        {
+            Error::PreconditionFailed(error) => error.message,
+            error => panic!("unexpected error: {:?}", error),
+        };
+        assert_eq!(message(client(vec![failed()])), None);
+
+        let client = Client::builder()
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .decompress_responses(true)
+            .http_client(MockHttpClient::new(vec![failed()]));
+        assert_eq!(message(client).unwrap(), "Failed");
+    }
+
+    #[test]
+    fn send_presigned_requests() {
+        let unavailable = http::Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body(Vec::new())
+            .unwrap();
+        let client =
+            client(vec![unavailable, http::Response::new(b"content".to_vec())]);
+        let url = "https://bucket.s3.amazonaws.com/key?X-Amz-Signature=abc";
+
+        let response =
+            block_on(client.send_presigned(Method::GET, url)).unwrap();
+        assert_eq!(response.body().as_ref(), b"content");
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].uri(), url);
+        assert!(!requests[1].headers().contains_key("authorization"));
+    }
+
+    #[test]
+    fn correct_skew() {
+        let client = client(vec![
+            error("RequestTimeTooSkewed", "Tue, 01 Jan 2030 12:00:00 GMT"),
+            ok(),
+        ]);
+        block_on(client.send_request(Request)).unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(header(&requests[1], "x-amz-date").starts_with("20300101T1"));
+    }
+
+    #[test]
+    fn report_signature_mismatch() {
+        let client = client(vec![error(
+            "SignatureDoesNotMatch",
+            "Tue, 01 Jan 2030 00:00:00 GMT",
+        )]);
+        let err = block_on(client.send_request(Request)).unwrap_err();
+
+        match err {
+            Error::SignatureMismatch(mismatch) => {
+                assert_eq!(mismatch.error.code, "SignatureDoesNotMatch");
+                assert!(mismatch
+                    .canonical_request
+                    .unwrap()
+                    .starts_with("GET\n/bucket/key\n"));
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn follow_region_redirects() {
+        let redirect = http::Response::builder()
+            .status(StatusCode::MOVED_PERMANENTLY)
+            .header("x-amz-bucket-region", "us-west-2")
+            .body(b"<Error><Code>PermanentRedirect</Code></Error>".to_vec())
+            .unwrap();
+        let client = Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .follow_region_redirects(true)
+            .http_client(MockHttpClient::new(vec![redirect, ok(), ok()]));
+        block_on(client.send_request(BucketRequest)).unwrap();
+        block_on(client.send_request(BucketRequest)).unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(
+            requests[0].uri(),
+            "https://bucket.s3.eu-west-1.amazonaws.com/key"
+        );
+        for request in &requests[1..] {
+            assert_eq!(
+                request.uri(),
+                "https://bucket.s3.us-west-2.amazonaws.com/key"
+            );
+            assert!(header(request, "authorization")
+                .contains("/us-west-2/s3/aws4_request"));
+        }
+    }
+
+    #[test]
+    fn override_client_settings() {
+        let client = client(vec![ok()]);
+        let config = RequestConfig::new()
+            .with_region("us-west-2")
+            .with_credentials(Credentials::new("AKIDOVERRIDE", "secret"))
+            .with_retry_policy(RetryPolicy::disabled());
+        block_on(client.send_request_with(BucketRequest, config)).unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(
+            requests[0].uri(),
+            "https://bucket.s3.us-west-2.amazonaws.com/key"
+        );
+        let authorization = header(&requests[0], "authorization");
+        assert!(authorization.contains("Credential=AKIDOVERRIDE/"));
+        assert!(authorization.contains("/us-west-2/s3/aws4_request"));
+    }
+
+    #[test]
+    fn hedge_slow_requests() {
+        /// Never answers one of the requests, and answers the others.
+        struct Stalling {
+            stalled: usize,
+            requests: AtomicUsize,
+        }
+
+        #[async_trait]
+        impl HttpClient for Stalling {
+            type RequestBody = Vec<u8>;
+            type ResponseBody = Vec<u8>;
+            type Error = Infallible;
+
+            async fn send_http_request(
+                &self,
+                _: http::Request<Vec<u8>>,
+            ) -> Result<http::Response<Vec<u8>>, Infallible> {
+                if self.requests.fetch_add(1, Ordering::SeqCst) == self.stalled
+                {
+                    future::pending().await
+                }
+                Ok(ok())
+            }
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let samples = Hedging::MIN_SAMPLES;
+        let client = Client::builder()
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .hedging_policy(HedgingPolicy::new(99.0))
+            .http_client(Stalling {
+                stalled: samples,
+                requests: AtomicUsize::new(0),
+            });
+        runtime.block_on(async {
+            for _ in 0..=samples {
+                client.send_request(Request).await.unwrap();
+            }
+        });
+        assert_eq!(
+            client.0.http_client.requests.load(Ordering::SeqCst),
+            samples + 2
+        );
+    }
+
+    #[test]
+    fn intercept_requests() {
+        struct Tag(&'static str);
+
+        impl Interceptor for Tag {
+            fn modify_request(
+                &self,
+                request: &mut http::request::Parts,
+            ) -> Result<(), InterceptorError> {
+                let tags = match request.headers.get("x-tags") {
+                    Some(tags) => format!("{},{}", tags.to_str()?, self.0),
+                    None => self.0.to_owned(),
+                };
+                request.headers.insert("x-tags", tags.parse()?);
+                Ok(())
+            }
+
+            fn read_response(
+                &self,
+                response: &http::response::Parts,
+            ) -> Result<(), InterceptorError> {
+                if response.headers.contains_key("x-reject") {
+                    return Err(self.0.into());
+                }
+                Ok(())
+            }
+        }
+
+        let rejected = http::Response::builder()
+            .header("x-reject", "")
+            .body(Vec::new())
+            .unwrap();
+        let client = client(vec![ok(), rejected])
+            .with_middleware(Tag("first"))
+            .with_middleware(Tag("second"));
+        block_on(client.send_request(Request)).unwrap();
+        match block_on(client.send_request(Request)).unwrap_err() {
+            Error::Interceptor(err) => assert_eq!(err.to_string(), "second"),
+            err => panic!("unexpected error: {:?}", err),
+        }
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(header(&requests[0], "x-tags"), "first,second");
+    }
+
+    #[test]
+    fn record_metrics() {
+        #[derive(Clone, Default)]
+        struct Sink(Arc<Mutex<Vec<RequestMetrics>>>);
+
+        impl MetricsSink for Sink {
+            fn record(&self, metrics: &RequestMetrics) {
+                self.0.lock().unwrap().push(metrics.clone());
+            }
+        }
+
+        let unavailable = || {
+            http::Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .body(Vec::new())
+                .unwrap()
+        };
+        let forbidden = error("AccessDenied", "Mon, 09 Sep 2024 12:00:00 GMT");
+        let sink = Sink::default();
+        let client = Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .retry_policy(
+                RetryPolicy::default()
+                    .with_backoff(Duration::ZERO, Duration::ZERO),
+            )
+            .metrics_sink(sink.clone())
+            .http_client(MockHttpClient::new(vec![
+                unavailable(),
+                http::Response::new(b"content".to_vec()),
+                forbidden,
+            ]));
+        block_on(client.send_request(Request)).unwrap();
+        block_on(client.send_request(Request)).unwrap_err();
+
+        let metrics = sink.0.lock().unwrap();
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].endpoint, "Test");
+        assert_eq!(metrics[0].retries(), 1);
+        assert_eq!(metrics[0].bytes_received, 7);
+        assert_eq!(metrics[0].error, None);
+        assert_eq!(metrics[1].attempts, 1);
+        assert_eq!(metrics[1].error, Some(ErrorClass::Client));
+    }
+
+    #[test]
+    fn sign_with_clock() {
+        let client = Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .clock(FixedClock(UNIX_EPOCH + Duration::from_secs(1_440_938_160)))
+            .http_client(MockHttpClient::new(vec![ok()]));
+        block_on(client.send_request(Request)).unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(header(&requests[0], "x-amz-date"), "20150830T123600Z");
+    }
+
+    #[test]
+    fn send_anonymous_requests() {
+        let client = Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .anonymous(true)
+            .http_client(MockHttpClient::new(vec![ok(), ok()]));
+        block_on(client.send_request(Request)).unwrap();
+        block_on(client.send_customized_request(Request, |request| {
+            request.extensions_mut().insert(Anonymous(false));
+        }))
+        .unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert!(!requests[0].headers().contains_key("authorization"));
+        assert!(requests[1].headers().contains_key("authorization"));
+    }
+
+    #[test]
+    fn agree_to_pay_for_requests() {
+        let client = Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .requester_pays(true)
+            .http_client(MockHttpClient::new(vec![ok(), ok()]));
+        block_on(client.send_request(Request)).unwrap();
+        block_on(client.send_customized_request(Request, |request| {
+            request.extensions_mut().insert(RequesterPays(false));
+        }))
+        .unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests[0].headers()["x-amz-request-payer"], "requester");
+        assert!(!requests[1].headers().contains_key("x-amz-request-payer"));
+    }
+
+    #[test]
+    fn insert_customer_keys() {
+        let client = client(vec![ok()]);
+        block_on(client.send_customized_request(Request, |request| {
+            request
+                .extensions_mut()
+                .insert(SseCustomerKey::new([0; 32]));
+        }))
+        .unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        let headers = requests[0].headers();
+        assert_eq!(
+            headers["x-amz-server-side-encryption-customer-algorithm"],
+            "AES256"
+        );
+        assert!(headers
+            .contains_key("x-amz-server-side-encryption-customer-key-md5"));
+    }
+
+    #[test]
+    fn encrypt_with_kms() {
+        /// The test endpoint, named like an endpoint writing objects.
+        struct PutRequest;
+
+        impl OutgoingRequest for PutRequest {
+            const METADATA: Metadata = Metadata {
+                description: "Test endpoint",
+                method: Method::PUT,
+                name: "PutObject",
+                path: "/bucket/key",
+                scope: Scope::Service,
+                authentication: AuthScheme::AwsSignatureV4,
+                requires_content_md5: false,
+                flexible_checksums: false,
+            };
+
+            type IncomingResponse = Response;
+
+            fn try_into_http_request<T: Default + bytes::BufMut>(
+                self,
+                base_url: &str,
+            ) -> Result<http::Request<T>, IntoHttpError> {
+                Request.try_into_http_request(base_url)
+            }
+        }
+
+        let client = Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .sse_kms(SseKms::new().with_key_id("alias/key"))
+            .http_client(MockHttpClient::new(vec![ok(), ok(), ok()]));
+        block_on(client.send_request(PutRequest)).unwrap();
+        block_on(client.send_request(Request)).unwrap();
+        block_on(client.send_customized_request(PutRequest, |request| {
+            request
+                .extensions_mut()
+                .insert(SseCustomerKey::new([0; 32]));
+        }))
+        .unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(
+            requests[0].headers()
+                ["x-amz-server-side-encryption-aws-kms-key-id"],
+            "alias/key"
+        );
+        assert!(!requests[1]
+            .headers()
+            .contains_key("x-amz-server-side-encryption"));
+        assert!(!requests[2]
+            .headers()
+            .contains_key("x-amz-server-side-encryption"));
+    }
+
+    #[test]
+    fn apply_compatibility() {
+        let client = Client::builder()
+            .checksum_algorithm(ChecksumAlgorithm::Crc32c)
+            .compatibility(Compatibility::Gcs)
+            .http_client(MockHttpClient::default());
+        assert_eq!(
+            client.0.endpoint_url.as_deref(),
+            Some("https://storage.googleapis.com")
+        );
+        assert_eq!(client.region(), "auto");
+        assert_eq!(client.0.checksum_algorithm, None);
+
+        let client = Client::builder()
+            .compatibility(Compatibility::MinIo)
+            .region("eu-west-1")
+            .http_client(MockHttpClient::default());
+        assert_eq!(client.0.addressing_style, AddressingStyle::Path);
+        assert_eq!(client.region(), "eu-west-1");
+    }
+
+    #[test]
+    fn compute_content_md5() {
+        let client = client(vec![ok()]);
+        block_on(client.send_request(BucketRequest)).unwrap();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(
+            header(&requests[0], "content-md5"),
+            "1B2M2Y8AsgTpgAmY7PhCfg=="
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Limits on the requests a client sends.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Limits how many requests are in flight and how often they are sent.
+///
+/// Every attempt of a request waits for the limits before it is handed to
+/// the HTTP client, and is counted as in flight until its response, or the
+/// head of its response when its body is read as a stream, is received.
+#[derive(Debug, Default)]
+pub(crate) struct Limiter {
+    /// The permits of the requests in flight.
+    concurrency: Option<Semaphore>,
+
+    /// The interval between requests, and when the next one can be sent.
+    rate: Option<(Duration, Mutex<Instant>)>,
+}
+
+impl Limiter {
+    /// Creates a limiter allowing at most `max_in_flight` requests at once,
+    /// and `max_per_second` requests every second.
+    pub(crate) fn new(
+        max_in_flight: Option<usize>,
+        max_per_second: Option<u32>,
+    ) -> Self {
+        Self {
+            concurrency: max_in_flight.map(|max| Semaphore::new(max.max(1))),
+            rate: max_per_second.map(|max| {
+                let interval = Duration::from_secs(1) / max.max(1);
+                (interval, Mutex::new(Instant::now()))
+            }),
+        }
+    }
+
+    /// Waits until a request can be sent, returning the permit it holds
+    /// while in flight, if their number is limited.
+    pub(crate) async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let permit = match &self.concurrency {
+            Some(semaphore) => {
+                Some(semaphore.acquire().await.expect("semaphore closed"))
+            }
+            None => None,
+        };
+
+        if let Some((interval, next)) = &self.rate {
+            // Requests are spaced evenly, in the order they reserve a slot.
+            let slot = {
+                let mut next = next.lock().unwrap();
+                let slot = (*next).max(Instant::now());
+                *next = slot + *interval;
+                slot
+            };
+            if slot > Instant::now() {
+                tokio::time::sleep_until(slot.into()).await;
+            }
+        }
+        permit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use futures_executor::block_on;
+
+    use super::Limiter;
+
+    #[test]
+    fn limit_concurrency() {
+        let limiter = Limiter::new(Some(2), None);
+        let first = block_on(limiter.acquire());
+        let _second = block_on(limiter.acquire());
+        assert!(limiter.concurrency.as_ref().unwrap().try_acquire().is_err());
+
+        drop(first);
+        assert!(limiter.concurrency.as_ref().unwrap().try_acquire().is_ok());
+    }
+
+    #[test]
+    fn limit_rate() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let limiter = Limiter::new(None, Some(100));
+
+        let start = Instant::now();
+        runtime.block_on(async {
+            for _ in 0..3 {
+                limiter.acquire().await;
+            }
+        });
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,164 @@
+//! Metrics of the requests of a client.
+
+use std::{
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use s3ers_api::error::FromHttpResponseError;
+
+use crate::Error;
+
+/// A receiver of the metrics of requests, like an exporter to Prometheus or
+/// statsd, set with
+/// [`ClientBuilder::metrics_sink`](crate::ClientBuilder::metrics_sink).
+///
+/// It is called once every request completes, so it should only update
+/// counters and histograms, and not block.
+pub trait MetricsSink: Send + Sync + 'static {
+    /// Records the metrics of a request.
+    fn record(&self, metrics: &RequestMetrics);
+}
+
+/// The metrics of a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RequestMetrics {
+    /// The name of the endpoint of the request, like `GetObject`.
+    pub endpoint: &'static str,
+
+    /// How long the request took, including its retries.
+    ///
+    /// When the body of the response is read as a stream, this is how long
+    /// receiving its head took.
+    pub latency: Duration,
+
+    /// How many times the request was sent.
+    pub attempts: u32,
+
+    /// The length of the body of the request.
+    pub bytes_sent: u64,
+
+    /// The length of the body of the last response, as announced by its
+    /// `Content-Length` header when it is read as a stream.
+    pub bytes_received: u64,
+
+    /// Why the request failed, if it did.
+    pub error: Option<ErrorClass>,
+}
+
+impl RequestMetrics {
+    /// How many times the request was retried.
+    pub fn retries(&self) -> u32 {
+        self.attempts.saturating_sub(1)
+    }
+}
+
+/// The classes of errors requests fail with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorClass {
+    /// The request couldn't be built or signed, including when its
+    /// credentials couldn't be resolved or an interceptor failed it.
+    Request,
+
+    /// The HTTP client failed to send the request or receive the response,
+    /// or to transfer a body.
+    Transport,
+
+    /// The request timed out.
+    Timeout,
+
+    /// The request was cancelled.
+    Cancelled,
+
+    /// The server kept throttling the request.
+    Throttling,
+
+    /// The server rejected the request, with a `4xx` status.
+    Client,
+
+    /// The server failed to handle the request, with a `5xx` status.
+    Server,
+
+    /// The response couldn't be read, or didn't match its checksum.
+    Response,
+}
+
+impl ErrorClass {
+    /// Returns the class of an error.
+    pub fn of<E>(error: &Error<E>) -> Self {
+        match error {
+            Error::Credentials(_)
+            | Error::IntoHttp(_)
+            | Error::Endpoint(_)
+            | Error::Interceptor(_) => Self::Request,
+            Error::Body(_) | Error::Response(_) => Self::Transport,
+            Error::Timeout => Self::Timeout,
+            Error::Cancelled => Self::Cancelled,
+            Error::Throttling(_) => Self::Throttling,
+            Error::SignatureMismatch(_) | Error::PreconditionFailed(_) => {
+                Self::Client
+            }
+            Error::FromHttpResponse(FromHttpResponseError::Server(error))
+                if error.status.is_server_error() =>
+            {
+                Self::Server
+            }
+            Error::FromHttpResponse(FromHttpResponseError::Server(_)) => {
+                Self::Client
+            }
+            Error::FromHttpResponse(_) | Error::Checksum(_) => Self::Response,
+        }
+    }
+}
+
+/// Measures a request, from its start.
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    start: Instant,
+    bytes_sent: u64,
+    attempts: AtomicU32,
+    bytes_received: AtomicU64,
+}
+
+impl Recorder {
+    /// Starts measuring a request with a body of `bytes_sent` bytes.
+    pub(crate) fn new(bytes_sent: u64) -> Self {
+        Self {
+            start: Instant::now(),
+            bytes_sent,
+            attempts: AtomicU32::new(0),
+            bytes_received: AtomicU64::new(0),
+        }
+    }
+
+    /// Counts an attempt.
+    pub(crate) fn attempt(&self) {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the length of the body of a response.
+    pub(crate) fn received(&self, bytes: u64) {
+        self.bytes_received.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Reports the metrics of the request to `sink`, if there is one.
+    pub(crate) fn finish<T, E>(
+        &self,
+        sink: Option<&dyn MetricsSink>,
+        endpoint: &'static str,
+        result: &Result<T, Error<E>>,
+    ) {
+        if let Some(sink) = sink {
+            sink.record(&RequestMetrics {
+                endpoint,
+                latency: self.start.elapsed(),
+                attempts: self.attempts.load(Ordering::Relaxed),
+                bytes_sent: self.bytes_sent,
+                bytes_received: self.bytes_received.load(Ordering::Relaxed),
+                error: result.as_ref().err().map(ErrorClass::of),
+            });
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Interceptors of the requests of a client.
+
+use std::{error::Error as StdError, fmt, sync::Arc};
+
+use http::{request, response};
+
+use crate::Error;
+
+/// The error of an interceptor failing a request.
+pub type InterceptorError = Box<dyn StdError + Send + Sync>;
+
+/// An interceptor of the requests of a client, added with
+/// [`Client::with_middleware`](crate::Client::with_middleware).
+///
+/// Unlike the closure of
+/// [`Client::send_customized_request`](crate::Client::send_customized_request),
+/// interceptors apply to every request of the client, and see the head of
+/// the response to every attempt. Failing a request short-circuits it with
+/// an [`Error::Interceptor`], which isn't retried.
+pub trait Interceptor: Send + Sync + 'static {
+    /// Modifies a request before it is signed, once for all its attempts.
+    fn modify_request(
+        &self,
+        request: &mut request::Parts,
+    ) -> Result<(), InterceptorError> {
+        let _ = request;
+        Ok(())
+    }
+
+    /// Reads the head of the response to an attempt, before it is parsed.
+    fn read_response(
+        &self,
+        response: &response::Parts,
+    ) -> Result<(), InterceptorError> {
+        let _ = response;
+        Ok(())
+    }
+}
+
+/// The interceptors of a client.
+///
+/// Requests go through them in order, and responses in reverse order.
+#[derive(Clone, Default)]
+pub(crate) struct Middleware(Arc<[Arc<dyn Interceptor>]>);
+
+impl Middleware {
+    /// Returns the middleware with another interceptor, after the others.
+    pub(crate) fn with(&self, interceptor: Arc<dyn Interceptor>) -> Self {
+        let mut interceptors = self.0.to_vec();
+        interceptors.push(interceptor);
+        Self(interceptors.into())
+    }
+
+    /// Passes a request through the interceptors.
+    pub(crate) fn modify_request<B, E>(
+        &self,
+        request: http::Request<B>,
+    ) -> Result<http::Request<B>, Error<E>> {
+        if self.0.is_empty() {
+            return Ok(request);
+        }
+
+        let (mut parts, body) = request.into_parts();
+        for interceptor in self.0.iter() {
+            interceptor
+                .modify_request(&mut parts)
+                .map_err(Error::Interceptor)?;
+        }
+        Ok(http::Request::from_parts(parts, body))
+    }
+
+    /// Passes the head of a response through the interceptors.
+    pub(crate) fn read_response<B, E>(
+        &self,
+        response: http::Response<B>,
+    ) -> Result<http::Response<B>, Error<E>> {
+        if self.0.is_empty() {
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        for interceptor in self.0.iter().rev() {
+            interceptor
+                .read_response(&parts)
+                .map_err(Error::Interceptor)?;
+        }
+        Ok(http::Response::from_parts(parts, body))
+    }
+}
+
+impl fmt::Debug for Middleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Middleware")
+            .field("interceptors", &self.0.len())
+            .finish()
+    }
+}
@@ -0,0 +1,51 @@
+use futures_util::stream::{self, Stream, StreamExt};
+use s3ers_api::Paginated;
+
+use crate::{Client, Error, HttpClient, ResponseResult};
+
+impl<C: HttpClient> Client<C> {
+    /// Sends a request to a paginated endpoint, and the requests for the
+    /// following pages, returning a stream of the pages.
+    ///
+    /// The next page is only requested when the stream is polled after the
+    /// previous one, and the stream ends after the last page or the first
+    /// error.
+    pub fn paginate<'a, R: Paginated + 'a>(
+        &'a self,
+        request: R,
+    ) -> impl Stream<Item = ResponseResult<C, R>> + 'a {
+        stream::unfold(Some(request), move |request| async move {
+            let request = request?;
+            let next = request.clone();
+            match self.send_request(request).await {
+                Ok(response) => {
+                    let next = next.next_page(&response);
+                    Some((Ok(response), next))
+                }
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+
+    /// Like [`paginate`](Self::paginate), but returns a stream of the
+    /// results of the pages, like the objects of `ListObjectsV2`.
+    pub fn paginate_items<'a, R>(
+        &'a self,
+        request: R,
+    ) -> impl Stream<Item = Result<R::Item, Error<C::Error>>> + 'a
+    where
+        R: Paginated + 'a,
+        R::Item: 'a,
+    {
+        self.paginate(request).flat_map(|page| {
+            let items = match page {
+                Ok(response) => R::into_items(response)
+                    .into_iter()
+                    .map(Ok)
+                    .collect::<Vec<_>>(),
+                Err(err) => vec![Err(err)],
+            };
+            stream::iter(items)
+        })
+    }
+}
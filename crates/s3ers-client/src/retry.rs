@@ -0,0 +1,323 @@
+//! Retries of failed requests.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use http::{Method, StatusCode};
+use s3ers_api::error::S3Error;
+
+/// How requests that failed because of a transient error are retried.
+///
+/// Only idempotent requests are retried, that is every request but `POST`
+/// ones, which may have had an effect even if their response was lost.
+///
+/// Retries are delayed with an exponential backoff and full jitter: the
+/// delay before the `n`th retry is picked at random between zero and
+/// `initial_backoff * 2^(n - 1)`, capped at `max_backoff`, so that clients
+/// failing at the same time don't retry at the same time. When a server
+/// asking to slow down sends a `Retry-After` header, its delay is used
+/// instead. Waiting relies on the timer of the Tokio runtime.
+///
+/// The policy of the client can be overridden for a single request by
+/// inserting another policy in the extensions of the request, in the
+/// closure of [`Client::send_customized_request`].
+///
+/// [`Client::send_customized_request`]: crate::Client::send_customized_request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RetryPolicy {
+    /// How many times a request is sent at most, including the first
+    /// attempt.
+    pub max_attempts: u32,
+
+    /// The longest delay before the first retry.
+    pub initial_backoff: Duration,
+
+    /// The longest delay before any retry.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy sending requests up to `max_attempts` times, with the
+    /// default backoff.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(20),
+        }
+    }
+
+    /// A policy that never retries requests.
+    pub fn disabled() -> Self {
+        Self::new(1)
+    }
+
+    /// Sets the longest delays before the first retry and before any retry.
+    pub fn with_backoff(
+        mut self,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Whether a request can be sent again after `attempts` attempts.
+    pub(crate) fn allows_retry(&self, method: &Method, attempts: u32) -> bool {
+        attempts < self.max_attempts && method != Method::POST
+    }
+
+    /// Returns how long to wait before retrying after `attempts` attempts.
+    ///
+    /// A delay asked by the server is honored, up to `max_backoff`.
+    pub(crate) fn delay(
+        &self,
+        attempts: u32,
+        retry_after: Option<Duration>,
+    ) -> Duration {
+        match retry_after {
+            Some(retry_after) => retry_after.min(self.max_backoff),
+            None => self.backoff(attempts),
+        }
+    }
+
+    /// Returns the jittered exponential backoff after `attempts` attempts.
+    fn backoff(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(31);
+        let ceiling = self
+            .initial_backoff
+            .checked_mul(1 << exponent)
+            .map_or(self.max_backoff, |ceiling| ceiling.min(self.max_backoff));
+        ceiling.mul_f64(random_fraction())
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Requests are sent up to three times, like the AWS SDKs do.
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+/// The tokens a retry after a transient error response costs.
+pub(crate) const RETRY_COST: u32 = 5;
+
+/// The tokens a retry after a connection error or a timeout costs, higher as
+/// they are more likely to mean that the server is overwhelmed.
+pub(crate) const TIMEOUT_RETRY_COST: u32 = 10;
+
+/// The tokens a request succeeding at its first attempt gives back.
+const SUCCESS_INCREMENT: u32 = 1;
+
+/// A bucket of retry tokens shared by the requests of a client.
+///
+/// Each retry takes tokens from the bucket, and requests are not retried
+/// anymore once it is empty, so that a failing server isn't sent several
+/// times the usual traffic. Requests that succeed after being retried give
+/// their tokens back, and the others refill the bucket by one token, as in
+/// the standard retry mode of the AWS SDKs.
+#[derive(Debug)]
+pub struct RetryQuota {
+    capacity: u32,
+    available: Mutex<u32>,
+    consumed: AtomicU64,
+    refused: AtomicU64,
+}
+
+impl RetryQuota {
+    /// Creates a full bucket of `capacity` tokens.
+    pub fn new(capacity: u32) -> Self {
+        Self {
+            capacity,
+            available: Mutex::new(capacity),
+            consumed: AtomicU64::new(0),
+            refused: AtomicU64::new(0),
+        }
+    }
+
+    /// The number of tokens the bucket holds when it is full.
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// The number of tokens currently in the bucket.
+    pub fn available(&self) -> u32 {
+        *self.lock()
+    }
+
+    /// The total number of tokens taken by retries.
+    pub fn tokens_consumed(&self) -> u64 {
+        self.consumed.load(Ordering::Relaxed)
+    }
+
+    /// The number of retries that were given up because the bucket was
+    /// empty.
+    pub fn retries_refused(&self) -> u64 {
+        self.refused.load(Ordering::Relaxed)
+    }
+
+    /// Takes `cost` tokens for a retry, if there are enough.
+    pub(crate) fn acquire(&self, cost: u32) -> bool {
+        let mut available = self.lock();
+        if *available < cost {
+            self.refused.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        *available -= cost;
+        self.consumed.fetch_add(u64::from(cost), Ordering::Relaxed);
+        true
+    }
+
+    /// Gives back the tokens taken by the retries of a request that
+    /// succeeded, or rewards a request that succeeded at once.
+    pub(crate) fn release(&self, acquired: u32) {
+        let refill = if acquired == 0 {
+            SUCCESS_INCREMENT
+        } else {
+            acquired
+        };
+        let mut available = self.lock();
+        *available = available.saturating_add(refill).min(self.capacity);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, u32> {
+        // The count is always consistent, even if a thread panicked.
+        self.available.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Default for RetryQuota {
+    /// A bucket of 500 tokens, allowing 100 retries of error responses in a
+    /// row.
+    fn default() -> Self {
+        Self::new(500)
+    }
+}
+
+/// Why a request failed, for the errors that are worth retrying.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Failure {
+    /// The server failed with an error that may not happen again.
+    Transient,
+
+    /// The server asked to slow down.
+    Throttling,
+}
+
+/// The error codes servers use to ask to slow down.
+const THROTTLING_ERRORS: &[&str] = &[
+    "SlowDown",
+    "Throttling",
+    "ThrottlingException",
+    "ThrottledException",
+    "RequestThrottled",
+    "RequestLimitExceeded",
+    "TooManyRequestsException",
+    "BandwidthLimitExceeded",
+];
+
+/// Classifies an error returned by the server, if it is worth retrying.
+pub(crate) fn classify(error: &S3Error) -> Option<Failure> {
+    if THROTTLING_ERRORS.contains(&error.code.as_str())
+        || error.status == StatusCode::TOO_MANY_REQUESTS
+        || error.status == StatusCode::SERVICE_UNAVAILABLE
+    {
+        Some(Failure::Throttling)
+    } else if matches!(
+        error.status,
+        StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::GATEWAY_TIMEOUT
+    ) || error.code == "RequestTimeout"
+    {
+        Some(Failure::Transient)
+    } else {
+        None
+    }
+}
+
+/// Returns a random number in `[0, 1)`.
+///
+/// The hasher of a new `RandomState` is randomly keyed, which is enough to
+/// spread retries.
+fn random_fraction() -> f64 {
+    // 53 bits fit exactly in the mantissa of an `f64`.
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::StatusCode;
+    use s3ers_api::error::S3Error;
+
+    use super::{classify, Failure, RetryPolicy, RetryQuota};
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::new(10)
+            .with_backoff(Duration::from_millis(100), Duration::from_secs(1));
+
+        for attempts in 1..10 {
+            let backoff = policy.backoff(attempts);
+            let ceiling = Duration::from_millis(100 << (attempts - 1));
+            assert!(backoff < ceiling.min(Duration::from_secs(1)));
+        }
+    }
+
+    #[test]
+    fn classify_errors() {
+        let error = |status: StatusCode, body: &str| {
+            let response = http::Response::builder()
+                .status(status)
+                .body(body.to_owned())
+                .unwrap();
+            S3Error::from_http_response(&response)
+        };
+
+        assert_eq!(
+            classify(&error(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "<Error><Code>SlowDown</Code></Error>"
+            )),
+            Some(Failure::Throttling)
+        );
+        assert_eq!(
+            classify(&error(StatusCode::INTERNAL_SERVER_ERROR, "")),
+            Some(Failure::Transient)
+        );
+        assert_eq!(
+            classify(&error(
+                StatusCode::BAD_REQUEST,
+                "<Error><Code>RequestTimeout</Code></Error>"
+            )),
+            Some(Failure::Transient)
+        );
+        assert_eq!(classify(&error(StatusCode::NOT_FOUND, "")), None);
+    }
+
+    #[test]
+    fn quota() {
+        let quota = RetryQuota::new(12);
+        assert!(quota.acquire(10));
+        assert!(!quota.acquire(5));
+        assert_eq!(quota.retries_refused(), 1);
+
+        quota.release(10);
+        assert_eq!(quota.available(), 12);
+        quota.release(0);
+        assert_eq!(quota.available(), 12);
+        assert_eq!(quota.tokens_consumed(), 10);
+    }
+}
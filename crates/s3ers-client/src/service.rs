@@ -0,0 +1,57 @@
+//! The client as a `tower::Service`.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use s3ers_api::OutgoingRequest;
+use tower_service::Service;
+
+use crate::{Client, Error, HttpClient, ResponseResult};
+
+/// Sends requests with [`Client::send_request`], so that the client can be
+/// wrapped in `tower` layers, like concurrency limits or load shedding.
+///
+/// The client is always ready, its HTTP client handles back pressure.
+impl<C, R> Service<R> for Client<C>
+where
+    C: HttpClient + Send + Sync + 'static,
+    C::RequestBody: Sync,
+    C::ResponseBody: Send,
+    C::Error: Sync,
+    R: OutgoingRequest + Send + 'static,
+    R::IncomingResponse: Send,
+{
+    type Response = R::IncomingResponse;
+    type Error = Error<C::Error>;
+    type Future = Pin<Box<dyn Future<Output = ResponseResult<C, R>> + Send>>;
+
+    fn poll_ready(
+        &mut self,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let client = self.clone();
+        Box::pin(async move { client.send_request(request).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_executor::block_on;
+    use tower_service::Service;
+
+    use crate::tests::{client, ok, Request};
+
+    #[test]
+    fn call_service() {
+        let mut client = client(vec![ok()]);
+        block_on(client.call(Request)).unwrap();
+        assert_eq!(client.0.http_client.requests.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,169 @@
+//! Utilities for testing applications using the client without a network.
+//!
+//! ```
+//! # use s3ers_client::{test::MockClient, Client};
+//! let mock = MockClient::new();
+//! mock.push_error(http::StatusCode::NOT_FOUND, "NoSuchKey", "Not found");
+//! let client = Client::builder()
+//!     .region("eu-west-1")
+//!     .http_client(mock.clone());
+//! // Send requests with `client`, then check `mock.take_requests()`.
+//! ```
+
+use std::{
+    collections::VecDeque,
+    error::Error as StdError,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use http::StatusCode;
+
+use crate::HttpClient;
+
+mod fault;
+#[cfg(feature = "replay")]
+mod replay;
+#[cfg(feature = "memory-server")]
+mod router;
+
+pub use self::fault::{Fault, FaultError, FaultInjector};
+#[cfg(feature = "replay")]
+pub use self::replay::{Recorder, Replay, ReplayError};
+#[cfg(feature = "memory-server")]
+pub use self::router::RouterClient;
+
+/// An HTTP client replying to requests with canned responses, and recording
+/// the requests it received.
+///
+/// Clones share their responses and requests, so a clone can be given to the
+/// [`Client`](crate::Client) while the original is used to queue responses
+/// and check requests. Responses are returned in the order they were queued,
+/// and requests fail with [`NoResponse`] once there are none left.
+#[derive(Clone, Debug, Default)]
+pub struct MockClient(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    responses: Mutex<VecDeque<http::Response<Vec<u8>>>>,
+    requests: Mutex<Vec<http::Request<Vec<u8>>>>,
+}
+
+impl MockClient {
+    /// A client with no responses queued.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a response.
+    pub fn push_response(&self, response: http::Response<Vec<u8>>) {
+        self.0.responses.lock().unwrap().push_back(response);
+    }
+
+    /// Queues a `200 OK` response with the given body, like the XML
+    /// document of an S3 response.
+    pub fn push_ok(&self, body: impl Into<Vec<u8>>) {
+        self.push_response(http::Response::new(body.into()));
+    }
+
+    /// Queues an S3 error response with the given status, code and message.
+    pub fn push_error(&self, status: StatusCode, code: &str, message: &str) {
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <Error><Code>{}</Code><Message>{}</Message></Error>",
+            code, message
+        );
+        let mut response = http::Response::new(body.into_bytes());
+        *response.status_mut() = status;
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/xml"),
+        );
+        self.push_response(response);
+    }
+
+    /// Returns the requests received so far, in order, and forgets them.
+    pub fn take_requests(&self) -> Vec<http::Request<Vec<u8>>> {
+        std::mem::take(&mut *self.0.requests.lock().unwrap())
+    }
+
+    /// Returns how many responses are still queued.
+    pub fn remaining_responses(&self) -> usize {
+        self.0.responses.lock().unwrap().len()
+    }
+}
+
+#[async_trait]
+impl HttpClient for MockClient {
+    type RequestBody = Vec<u8>;
+    type ResponseBody = Vec<u8>;
+    type Error = NoResponse;
+
+    async fn send_http_request(
+        &self,
+        req: http::Request<Vec<u8>>,
+    ) -> Result<http::Response<Vec<u8>>, NoResponse> {
+        self.0.requests.lock().unwrap().push(req);
+        self.0
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or(NoResponse(()))
+    }
+}
+
+/// The error of a [`MockClient`] receiving a request with no response
+/// queued.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NoResponse(());
+
+impl fmt::Display for NoResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no response queued for the request")
+    }
+}
+
+impl StdError for NoResponse {}
+
+#[cfg(test)]
+mod tests {
+    use futures_executor::block_on;
+    use http::StatusCode;
+    use s3ers_api::error::FromHttpResponseError;
+    use s3ers_signature::Credentials;
+
+    use super::MockClient;
+    use crate::{tests::Request, Client, Error, RetryPolicy};
+
+    #[test]
+    fn mock_responses() {
+        let mock = MockClient::new();
+        mock.push_ok("");
+        mock.push_error(StatusCode::NOT_FOUND, "NoSuchKey", "Not found");
+        let client = Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .retry_policy(RetryPolicy::disabled())
+            .http_client(mock.clone());
+
+        block_on(client.send_request(Request)).unwrap();
+        match block_on(client.send_request(Request)) {
+            Err(Error::FromHttpResponse(FromHttpResponseError::Server(
+                error,
+            ))) => assert_eq!(error.code, "NoSuchKey"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(matches!(
+            block_on(client.send_request(Request)),
+            Err(Error::Response(_))
+        ));
+
+        let requests = mock.take_requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].uri().path(), "/bucket/key");
+        assert!(mock.take_requests().is_empty());
+        assert_eq!(mock.remaining_responses(), 0);
+    }
+}
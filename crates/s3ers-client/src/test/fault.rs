@@ -0,0 +1,285 @@
+use std::{
+    collections::VecDeque, error::Error as StdError, fmt, sync::Mutex,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use http::{header::CONTENT_TYPE, HeaderValue, StatusCode};
+
+use crate::HttpClient;
+
+/// A failure injected by a [`FaultInjector`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Fault {
+    /// The request is sent after a delay, to exceed the timeouts of the
+    /// client. Delays rely on the timer of the Tokio runtime.
+    Delay(Duration),
+
+    /// The request isn't sent, and the server replies with a
+    /// `500 Internal Server Error` `InternalError` error.
+    InternalError,
+
+    /// The request isn't sent, and the server replies with a
+    /// `503 Service Unavailable` `SlowDown` error.
+    SlowDown,
+
+    /// The request is sent, and the body of the response is cut in half
+    /// while its headers are kept.
+    TruncatedBody,
+
+    /// The request isn't sent, and the connection is reset with a
+    /// [`FaultError::ConnectionReset`] error.
+    ConnectionReset,
+}
+
+/// An HTTP client injecting failures in the requests sent by another, to
+/// test how they are retried and handled.
+///
+/// Faults are injected in the order of a sequence first, then at random
+/// with the rates they were given, from a seeded generator so that tests
+/// are reproducible. Bodies sent or received as they are read are buffered,
+/// so that faults apply to them too.
+#[derive(Debug)]
+pub struct FaultInjector<C> {
+    client: C,
+    sequence: Mutex<VecDeque<Option<Fault>>>,
+    rates: Vec<(Fault, f64)>,
+    state: Mutex<u64>,
+}
+
+impl<C> FaultInjector<C> {
+    /// Injects no faults in the requests of `client` until told to.
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            sequence: Mutex::default(),
+            rates: Vec::new(),
+            state: Mutex::new(0x853c_49e6_748f_ea9b),
+        }
+    }
+
+    /// Injects the given faults in the next requests, one per request, with
+    /// `None` letting a request through.
+    pub fn with_sequence(
+        self,
+        faults: impl IntoIterator<Item = Option<Fault>>,
+    ) -> Self {
+        self.sequence.lock().unwrap().extend(faults);
+        self
+    }
+
+    /// Injects `fault` in a fraction of the requests, between `0.0` and
+    /// `1.0`.
+    ///
+    /// With several rates, the faults are drawn in the order they were
+    /// given.
+    pub fn with_rate(mut self, fault: Fault, rate: f64) -> Self {
+        self.rates.push((fault, rate.clamp(0.0, 1.0)));
+        self
+    }
+
+    /// Sets the seed of the faults injected at random.
+    pub fn with_seed(self, seed: u64) -> Self {
+        *self.state.lock().unwrap() = seed.max(1);
+        self
+    }
+
+    /// Returns the client faults are injected in.
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+
+    /// Returns the fault to inject in the next request, if any.
+    fn next_fault(&self) -> Option<Fault> {
+        if let Some(fault) = self.sequence.lock().unwrap().pop_front() {
+            return fault;
+        }
+        self.rates
+            .iter()
+            .find(|(_, rate)| self.random() < *rate)
+            .map(|(fault, _)| *fault)
+    }
+
+    /// Returns a random number between `0.0` and `1.0`, with xorshift.
+    fn random(&self) -> f64 {
+        let mut state = self.state.lock().unwrap();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        (*state >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[async_trait]
+impl<C> HttpClient for FaultInjector<C>
+where
+    C: HttpClient + Send,
+    C::RequestBody: Sync,
+    C::ResponseBody: From<Vec<u8>> + Send,
+{
+    type RequestBody = C::RequestBody;
+    type ResponseBody = C::ResponseBody;
+    type Error = FaultError<C::Error>;
+
+    async fn send_http_request(
+        &self,
+        req: http::Request<Self::RequestBody>,
+    ) -> Result<http::Response<Self::ResponseBody>, Self::Error> {
+        let fault = self.next_fault();
+        let (status, code) = match fault {
+            Some(Fault::Delay(delay)) => {
+                tokio::time::sleep(delay).await;
+                return self
+                    .client
+                    .send_http_request(req)
+                    .await
+                    .map_err(FaultError::Client);
+            }
+            Some(Fault::InternalError) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "InternalError")
+            }
+            Some(Fault::SlowDown) => {
+                (StatusCode::SERVICE_UNAVAILABLE, "SlowDown")
+            }
+            Some(Fault::ConnectionReset) => {
+                return Err(FaultError::ConnectionReset)
+            }
+            Some(Fault::TruncatedBody) => {
+                let response = self
+                    .client
+                    .send_http_request(req)
+                    .await
+                    .map_err(FaultError::Client)?;
+                return Ok(response.map(|body| {
+                    let body = body.as_ref();
+                    body[..body.len() / 2].to_vec().into()
+                }));
+            }
+            None => {
+                return self
+                    .client
+                    .send_http_request(req)
+                    .await
+                    .map_err(FaultError::Client)
+            }
+        };
+
+        let body = format!(
+            "<Error><Code>{}</Code><Message>Injected fault</Message></Error>",
+            code
+        );
+        let mut response = http::Response::new(body.into_bytes().into());
+        *response.status_mut() = status;
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/xml"));
+        Ok(response)
+    }
+}
+
+/// The error of a [`FaultInjector`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FaultError<E> {
+    /// The connection was reset by an injected fault.
+    ConnectionReset,
+
+    /// The client faults are injected in failed.
+    Client(E),
+}
+
+impl<E: fmt::Display> fmt::Display for FaultError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConnectionReset => {
+                f.write_str("the connection was reset by an injected fault")
+            }
+            Self::Client(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E: StdError + 'static> StdError for FaultError<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::ConnectionReset => None,
+            Self::Client(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures_executor::block_on;
+    use s3ers_api::error::FromHttpResponseError;
+    use s3ers_signature::Credentials;
+
+    use super::{Fault, FaultError, FaultInjector};
+    use crate::{
+        test::MockClient, tests::Request, Client, Error, HttpClient,
+        RetryPolicy,
+    };
+
+    fn client(
+        injector: FaultInjector<MockClient>,
+    ) -> Client<FaultInjector<MockClient>> {
+        Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .retry_policy(
+                RetryPolicy::default()
+                    .with_backoff(Duration::ZERO, Duration::ZERO),
+            )
+            .http_client(injector)
+    }
+
+    #[test]
+    fn inject_faults() {
+        let mock = MockClient::new();
+        mock.push_ok("");
+        let injector = FaultInjector::new(mock.clone()).with_sequence(vec![
+            Some(Fault::ConnectionReset),
+            Some(Fault::SlowDown),
+            None,
+        ]);
+        block_on(client(injector).send_request(Request)).unwrap();
+        assert_eq!(mock.take_requests().len(), 1);
+
+        let injector = FaultInjector::new(mock.clone())
+            .with_rate(Fault::InternalError, 1.0);
+        match block_on(client(injector).send_request(Request)).unwrap_err() {
+            Error::FromHttpResponse(FromHttpResponseError::Server(error)) => {
+                assert_eq!(error.code, "InternalError")
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        assert!(mock.take_requests().is_empty());
+
+        mock.push_ok("0123456789");
+        let injector = FaultInjector::new(mock.clone())
+            .with_sequence(vec![Some(Fault::TruncatedBody)]);
+        let response = block_on(
+            injector.send_http_request(http::Request::new(Vec::new())),
+        );
+        assert_eq!(response.unwrap().body(), b"01234");
+
+        let injector = FaultInjector::new(mock)
+            .with_rate(Fault::ConnectionReset, 0.5)
+            .with_seed(42);
+        let resets = (0..1000)
+            .filter(|_| {
+                matches!(
+                    block_on(
+                        injector
+                            .send_http_request(http::Request::new(Vec::new()))
+                    ),
+                    Err(FaultError::ConnectionReset)
+                )
+            })
+            .count();
+        assert!((400..600).contains(&resets), "{} resets", resets);
+    }
+}
@@ -0,0 +1,328 @@
+use std::{
+    convert::TryInto, error::Error as StdError, fmt, fs, io, path::Path,
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use serde_json::{json, Value as JsonValue};
+
+use crate::{
+    http_client::redact::{redact_uri, REDACTED, SECRET_HEADERS},
+    HttpClient,
+};
+
+/// An HTTP client recording the exchanges of another with a server, to save
+/// them to a file replayed by [`Replay`], with the `replay` feature.
+///
+/// Exchanges are saved as a JSON array, with the credentials, signatures and
+/// customer-provided keys redacted. Bodies sent or received as they are read
+/// are buffered, so that they are recorded too.
+#[derive(Debug)]
+pub struct Recorder<C> {
+    client: C,
+    exchanges: Mutex<Vec<JsonValue>>,
+}
+
+impl<C> Recorder<C> {
+    /// Records the exchanges of `client`.
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            exchanges: Mutex::default(),
+        }
+    }
+
+    /// Saves the exchanges recorded so far to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let exchanges = self.exchanges.lock().unwrap();
+        let json = serde_json::to_vec_pretty(&*exchanges)?;
+        fs::write(path, json)
+    }
+
+    /// Returns the client whose exchanges are recorded.
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+}
+
+#[async_trait]
+impl<C> HttpClient for Recorder<C>
+where
+    C: HttpClient + Send,
+    C::RequestBody: Sync,
+    C::ResponseBody: Send,
+{
+    type RequestBody = C::RequestBody;
+    type ResponseBody = C::ResponseBody;
+    type Error = C::Error;
+
+    async fn send_http_request(
+        &self,
+        req: http::Request<Self::RequestBody>,
+    ) -> Result<http::Response<Self::ResponseBody>, Self::Error> {
+        let mut request = json!({
+            "method": req.method().as_str(),
+            "uri": redact_uri(req.uri()),
+            "headers": headers_to_json(req.headers()),
+        });
+        insert_body(&mut request, req.body().as_ref());
+
+        let response = self.client.send_http_request(req).await?;
+        let mut recorded = json!({
+            "status": response.status().as_u16(),
+            "headers": headers_to_json(response.headers()),
+        });
+        insert_body(&mut recorded, response.body().as_ref());
+
+        self.exchanges
+            .lock()
+            .unwrap()
+            .push(json!({ "request": request, "response": recorded }));
+        Ok(response)
+    }
+}
+
+/// An HTTP client replying to requests with the responses recorded by a
+/// [`Recorder`], with the `replay` feature.
+///
+/// A request gets the response of the first exchange not replayed yet whose
+/// request has the same method, path and query, ignoring the `X-Amz-*`
+/// parameters of presigned URLs since they change with time. Requests that
+/// match no exchange fail with [`ReplayError`].
+#[derive(Debug)]
+pub struct Replay {
+    exchanges: Mutex<Vec<Option<Exchange>>>,
+}
+
+#[derive(Debug)]
+struct Exchange {
+    method: String,
+    target: String,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Replay {
+    /// Replays the exchanges saved to a file by [`Recorder::save`].
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_slice(&fs::read(path)?)
+    }
+
+    /// Replays the exchanges of a JSON array saved by [`Recorder::save`].
+    pub fn from_slice(json: &[u8]) -> io::Result<Self> {
+        let invalid =
+            || io::Error::new(io::ErrorKind::InvalidData, "invalid recording");
+        let json: JsonValue = serde_json::from_slice(json)?;
+        let exchanges = json
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|exchange| Exchange::from_json(exchange).ok_or_else(invalid))
+            .map(|exchange| exchange.map(Some))
+            .collect::<io::Result<_>>()?;
+        Ok(Self {
+            exchanges: Mutex::new(exchanges),
+        })
+    }
+
+    /// Returns how many exchanges weren't replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.exchanges.lock().unwrap().iter().flatten().count()
+    }
+}
+
+impl Exchange {
+    fn from_json(json: &JsonValue) -> Option<Self> {
+        let request = json.get("request")?;
+        let response = json.get("response")?;
+        let uri: Uri = request.get("uri")?.as_str()?.parse().ok()?;
+        Some(Self {
+            method: request.get("method")?.as_str()?.to_owned(),
+            target: target(&uri),
+            status: StatusCode::from_u16(
+                response.get("status")?.as_u64()?.try_into().ok()?,
+            )
+            .ok()?,
+            headers: headers_from_json(response.get("headers")?)?,
+            body: body_from_json(response)?,
+        })
+    }
+}
+
+#[async_trait]
+impl HttpClient for Replay {
+    type RequestBody = Vec<u8>;
+    type ResponseBody = Vec<u8>;
+    type Error = ReplayError;
+
+    async fn send_http_request(
+        &self,
+        req: http::Request<Vec<u8>>,
+    ) -> Result<http::Response<Vec<u8>>, ReplayError> {
+        let target = target(req.uri());
+        let exchange = self
+            .exchanges
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|exchange| {
+                exchange.as_ref().is_some_and(|exchange| {
+                    exchange.method == req.method().as_str()
+                        && exchange.target == target
+                })
+            })
+            .and_then(Option::take)
+            .ok_or_else(|| ReplayError {
+                method: req.method().clone(),
+                target,
+            })?;
+
+        let mut response = http::Response::new(exchange.body);
+        *response.status_mut() = exchange.status;
+        *response.headers_mut() = exchange.headers;
+        Ok(response)
+    }
+}
+
+/// The error of a request to [`Replay`] that matches no recorded exchange.
+#[derive(Clone, Debug)]
+pub struct ReplayError {
+    method: Method,
+    target: String,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no recorded exchange for `{} {}`",
+            self.method, self.target
+        )
+    }
+}
+
+impl StdError for ReplayError {}
+
+/// Returns the path and the query of a URI, without the parameters of
+/// presigned URLs.
+fn target(uri: &Uri) -> String {
+    let query = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("X-Amz-"))
+        .collect::<Vec<_>>();
+    if query.is_empty() {
+        uri.path().to_owned()
+    } else {
+        format!("{}?{}", uri.path(), query.join("&"))
+    }
+}
+
+/// Returns headers as an array of names and values, with secrets redacted.
+fn headers_to_json(headers: &HeaderMap) -> JsonValue {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SECRET_HEADERS.contains(&name.as_str()) {
+                REDACTED.into()
+            } else {
+                String::from_utf8_lossy(value.as_bytes())
+            };
+            json!([name.as_str(), value])
+        })
+        .collect()
+}
+
+fn headers_from_json(json: &JsonValue) -> Option<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for header in json.as_array()? {
+        let name = header.get(0)?.as_str()?;
+        let value = header.get(1)?.as_str()?;
+        headers.append(
+            http::header::HeaderName::from_bytes(name.as_bytes()).ok()?,
+            HeaderValue::from_str(value).ok()?,
+        );
+    }
+    Some(headers)
+}
+
+/// Inserts a body as text if it is UTF-8, or as base64 otherwise.
+fn insert_body(message: &mut JsonValue, body: &[u8]) {
+    let (key, body) = match std::str::from_utf8(body) {
+        Ok(body) => ("body", body.to_owned()),
+        Err(_) => ("body_base64", STANDARD.encode(body)),
+    };
+    message[key] = body.into();
+}
+
+fn body_from_json(message: &JsonValue) -> Option<Vec<u8>> {
+    match message.get("body_base64") {
+        Some(body) => STANDARD.decode(body.as_str()?).ok(),
+        None => Some(message.get("body")?.as_str()?.as_bytes().to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_executor::block_on;
+    use s3ers_signature::Credentials;
+
+    use super::{Recorder, Replay};
+    use crate::{
+        test::MockClient, tests::Request, Client, Error, HttpClient,
+        RetryPolicy,
+    };
+
+    #[test]
+    fn record_and_replay() {
+        let mock = MockClient::new();
+        mock.push_ok(&b"\xffbinary"[..]);
+        mock.push_ok("<Text/>");
+        let recorder = Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .http_client(Recorder::new(mock));
+        block_on(recorder.send_request(Request)).unwrap();
+        block_on(recorder.send_request(Request)).unwrap();
+
+        let path = std::env::temp_dir()
+            .join(format!("s3ers-replay-{}.json", std::process::id()));
+        recorder.0.http_client.save(&path).unwrap();
+        let recording = std::fs::read(&path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_slice(&recording).unwrap();
+        let headers = json[0]["request"]["headers"].as_array().unwrap();
+        assert!(headers
+            .iter()
+            .any(|header| header[0] == "authorization"
+                && header[1] == "<redacted>"));
+        assert!(!String::from_utf8_lossy(&recording).contains("Signature="));
+
+        let replay = Replay::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let request = || http::Request::get("/bucket/key").body(Vec::new());
+        let response =
+            block_on(replay.send_http_request(request().unwrap())).unwrap();
+        assert_eq!(response.body(), b"\xffbinary");
+        let response =
+            block_on(replay.send_http_request(request().unwrap())).unwrap();
+        assert_eq!(response.body(), b"<Text/>");
+        assert_eq!(replay.remaining(), 0);
+
+        let client = Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .retry_policy(RetryPolicy::disabled())
+            .http_client(Replay::from_slice(&recording).unwrap());
+        block_on(client.send_request(Request)).unwrap();
+        block_on(client.send_request(Request)).unwrap();
+        assert!(matches!(
+            block_on(client.send_request(Request)),
+            Err(Error::Response(_))
+        ));
+    }
+}
@@ -0,0 +1,101 @@
+use std::{convert::Infallible, sync::Arc};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use s3ers_server::{memory::MemoryServer, Router};
+
+use crate::HttpClient;
+
+/// An HTTP client handing requests to a server [`Router`] in the same
+/// process, like the one of a [`MemoryServer`], to test applications end to
+/// end without a network.
+///
+/// The client must send path-style requests, unless the router accepts
+/// virtual-hosted-style requests to the endpoint of the client.
+///
+/// ```
+/// # use s3ers_client::{test::RouterClient, AddressingStyle, Client};
+/// # use s3ers_server::memory::MemoryServer;
+/// let server = MemoryServer::new().with_bucket("bucket");
+/// let client = Client::builder()
+///     .endpoint_url("http://localhost")
+///     .addressing_style(AddressingStyle::Path)
+///     .http_client(RouterClient::new(server.router()));
+/// // Send requests with `client`, then check `server.object("bucket", _)`.
+/// ```
+#[derive(Clone, Debug)]
+pub struct RouterClient {
+    router: Arc<Router>,
+}
+
+impl RouterClient {
+    /// A client handing requests to `router`.
+    pub fn new(router: Router) -> Self {
+        Self {
+            router: Arc::new(router),
+        }
+    }
+
+    /// A client handing requests to a new [`MemoryServer`] with the given
+    /// unversioned buckets, returned along with it.
+    pub fn memory(buckets: &[&str]) -> (Self, MemoryServer) {
+        let server =
+            buckets.iter().fold(MemoryServer::new(), |server, bucket| {
+                server.with_bucket(*bucket)
+            });
+        (Self::new(server.router()), server)
+    }
+}
+
+#[async_trait]
+impl HttpClient for RouterClient {
+    type RequestBody = Vec<u8>;
+    type ResponseBody = Bytes;
+    type Error = Infallible;
+
+    async fn send_http_request(
+        &self,
+        req: http::Request<Vec<u8>>,
+    ) -> Result<http::Response<Bytes>, Infallible> {
+        Ok(self.router.handle(req.map(Bytes::from)).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_executor::block_on;
+    use s3ers_s3_api::{
+        multipart::create_multipart_upload, object::head_object,
+    };
+    use s3ers_signature::Credentials;
+
+    use super::RouterClient;
+    use crate::{AddressingStyle, Client, S3ClientExt};
+
+    #[test]
+    fn send_requests_to_router() {
+        let (router, server) = RouterClient::memory(&["bucket"]);
+        let client = Client::builder()
+            .region("eu-west-1")
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret"))
+            .endpoint_url("http://localhost:9000")
+            .addressing_style(AddressingStyle::Path)
+            .http_client(router);
+
+        block_on(client.put_object("bucket", "key", b"content".to_vec()))
+            .unwrap();
+        assert_eq!(server.object("bucket", "key").unwrap(), "content");
+        let object = block_on(client.get_object("bucket", "key")).unwrap();
+        assert_eq!(object.body, b"content");
+        let list = block_on(client.list_objects_v2("bucket", None)).unwrap();
+        assert_eq!(list.contents[0].key, "key");
+
+        let request = head_object::Request::new("bucket", "key");
+        let head = block_on(client.send_request(request)).unwrap();
+        assert_eq!(head.content_length, Some(7));
+        let request = create_multipart_upload::Request::new("bucket", "key");
+        block_on(client.send_request(request)).unwrap();
+        assert_eq!(server.upload_count("bucket"), 1);
+        assert!(block_on(client.get_object("other", "key")).is_err());
+    }
+}
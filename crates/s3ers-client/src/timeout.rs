@@ -0,0 +1,155 @@
+//! Timeouts of requests.
+
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_util::stream::{Stream, StreamExt};
+use tokio::time::{Instant, Sleep};
+
+use crate::{ByteStream, Error};
+
+/// How long requests may take.
+///
+/// Timeouts rely on the timer of the Tokio runtime. How long connecting to
+/// the server may take is a setting of the HTTP client instead, since
+/// connections are shared by requests.
+///
+/// The timeouts of the client can be overridden for a single request by
+/// inserting others in the extensions of the request, in the closure of
+/// [`Client::send_customized_request`].
+///
+/// [`Client::send_customized_request`]: crate::Client::send_customized_request
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Timeouts {
+    /// How long a request may take, including its retries.
+    pub total: Option<Duration>,
+
+    /// How long an attempt may wait for the response, and then for each
+    /// chunk of a body read as a stream. Attempts timing out are retried.
+    pub read: Option<Duration>,
+}
+
+impl Timeouts {
+    /// No timeouts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how long a request may take, including its retries.
+    pub fn with_total(mut self, total: Duration) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// Sets how long an attempt may wait for the response, and then for
+    /// each chunk of a body read as a stream.
+    pub fn with_read(mut self, read: Duration) -> Self {
+        self.read = Some(read);
+        self
+    }
+}
+
+/// Fails with a timeout error if `future` takes longer than `timeout`.
+pub(crate) async fn timeout<T, E>(
+    timeout: Option<Duration>,
+    future: impl Future<Output = Result<T, Error<E>>>,
+) -> Result<T, Error<E>> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .unwrap_or(Err(Error::Timeout)),
+        None => future.await,
+    }
+}
+
+/// Fails reading a body with an [`io::ErrorKind::TimedOut`] error if a
+/// chunk takes longer than `timeout` to arrive.
+pub(crate) fn read_timeout(
+    body: ByteStream,
+    timeout: Option<Duration>,
+) -> ByteStream {
+    match timeout {
+        Some(timeout) => ByteStream::new(ReadTimeout {
+            body,
+            timeout,
+            sleep: Box::pin(tokio::time::sleep(timeout)),
+            done: false,
+        }),
+        None => body,
+    }
+}
+
+struct ReadTimeout {
+    body: ByteStream,
+    timeout: Duration,
+    sleep: Pin<Box<Sleep>>,
+    done: bool,
+}
+
+impl Stream for ReadTimeout {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        match this.body.poll_next_unpin(cx) {
+            Poll::Ready(item) => {
+                let deadline = Instant::now() + this.timeout;
+                this.sleep.as_mut().reset(deadline);
+                Poll::Ready(item)
+            }
+            Poll::Pending => match this.sleep.as_mut().poll(cx) {
+                Poll::Ready(()) => {
+                    this.done = true;
+                    Poll::Ready(Some(Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "reading the body timed out",
+                    ))))
+                }
+                Poll::Pending => Poll::Pending,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, time::Duration};
+
+    use futures_util::stream;
+
+    use super::read_timeout;
+    use crate::ByteStream;
+
+    #[test]
+    fn time_out_reading() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let timeout = Some(Duration::from_millis(10));
+
+        let read = |body| {
+            runtime
+                .block_on(async { read_timeout(body, timeout).collect().await })
+        };
+
+        let content = read(ByteStream::from(b"content".to_vec()));
+        assert_eq!(content.unwrap(), b"content");
+        let err = read(ByteStream::new(stream::pending())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,91 @@
+//! Tracing of requests, with the `tracing` feature.
+//!
+//! Requests are traced in an `s3ers.request` span with the name of their
+//! endpoint, their bucket and key and how long they took, and each of their
+//! attempts in an `s3ers.attempt` span with its number, the status of its
+//! response and how long it took. Without the feature, the functions of this
+//! module do nothing.
+
+use std::future::Future;
+
+use http::StatusCode;
+
+/// A span, which is nothing without the `tracing` feature.
+#[cfg(feature = "tracing")]
+pub(crate) type Span = tracing::Span;
+
+/// A span, which is nothing without the `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+#[derive(Clone, Debug)]
+pub(crate) struct Span;
+
+/// Returns the span of a request.
+pub(crate) fn request_span(
+    endpoint: &'static str,
+    bucket: Option<&str>,
+    key: Option<&str>,
+) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        tracing::info_span!(
+            "s3ers.request",
+            endpoint,
+            bucket,
+            key,
+            duration_ms = tracing::field::Empty,
+        )
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (endpoint, bucket, key);
+        Span
+    }
+}
+
+/// Returns the span of an attempt of a request, numbered from 1.
+pub(crate) fn attempt_span(attempt: u32) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        tracing::info_span!(
+            "s3ers.attempt",
+            attempt,
+            status = tracing::field::Empty,
+            duration_ms = tracing::field::Empty,
+        )
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = attempt;
+        Span
+    }
+}
+
+/// Runs a future in a span, recording how long it took.
+pub(crate) async fn instrument<F: Future>(span: Span, future: F) -> F::Output {
+    #[cfg(feature = "tracing")]
+    {
+        use tracing::Instrument;
+
+        let start = std::time::Instant::now();
+        let output = future.instrument(span.clone()).await;
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        output
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = span;
+        future.await
+    }
+}
+
+/// Records the status of the response to the current attempt.
+pub(crate) fn record_status(status: StatusCode) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("status", status.as_u16());
+
+    #[cfg(not(feature = "tracing"))]
+    let _ = status;
+}
@@ -0,0 +1,1303 @@
+//! Transfers of objects too large for a single request, split into parts
+//! that are sent concurrently.
+
+use std::{
+    future::Future,
+    io::{self, SeekFrom},
+    path::Path,
+    sync::Arc,
+};
+
+use bytes::{Bytes, BytesMut};
+use futures_util::{
+    future::{FutureExt, TryFutureExt},
+    stream::{self, BoxStream, Stream, StreamExt, TryStreamExt},
+};
+use s3ers_api::{
+    error::{DeserializationError, FromHttpResponseError},
+    header::HttpDate,
+};
+use s3ers_s3_api::{
+    multipart::{
+        abort_multipart_upload,
+        complete_multipart_upload::{self, CompletedPart},
+        create_multipart_upload, upload_part, upload_part_copy,
+    },
+    object::{copy_object, get_object, head_object, put_object},
+    WriteCondition,
+};
+use tokio::{
+    fs::File,
+    io::{AsyncSeekExt, AsyncWriteExt},
+};
+
+use self::{
+    progress::{Listener, Tracker},
+    throttle::Throttle,
+};
+use crate::{
+    cancel::cancellable, ByteStream, CancellationToken, Client, Error,
+    HttpClient,
+};
+
+mod progress;
+mod throttle;
+
+pub use self::{
+    progress::{Progress, ProgressListener},
+    throttle::BandwidthLimit,
+};
+
+/// The size S3 requires of every part of a multipart upload but the last
+/// one, at least.
+pub const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+const DEFAULT_PART_SIZE: usize = 8 * 1024 * 1024;
+
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// The most parts S3 accepts in a multipart upload.
+const MAX_PARTS: u64 = 10_000;
+
+/// The largest object S3 copies with a single request, and the largest part
+/// of a multipart upload.
+pub const MAX_COPY_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+const DEFAULT_COPY_PART_SIZE: u64 = 128 * 1024 * 1024;
+
+impl<C: HttpClient> Client<C> {
+    /// Prepares the upload of an object, which is sent with
+    /// [`Upload::send`].
+    pub fn upload(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        body: impl Into<ByteStream>,
+    ) -> Upload<'_, C> {
+        Upload {
+            client: self,
+            bucket: bucket.into(),
+            key: key.into(),
+            body: body.into(),
+            content_type: None,
+            content_length: None,
+            condition: None,
+            part_size: DEFAULT_PART_SIZE,
+            concurrency: DEFAULT_CONCURRENCY,
+            listener: None,
+            throttle: Throttle::default(),
+            token: None,
+        }
+    }
+
+    /// Prepares the download of an object, which is received with
+    /// [`Download::send`] or [`Download::to_file`].
+    pub fn download(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+    ) -> Download<'_, C> {
+        Download {
+            client: self,
+            request: get_object::Request::new(bucket, key),
+            part_size: DEFAULT_PART_SIZE as u64,
+            concurrency: DEFAULT_CONCURRENCY,
+            listener: None,
+            throttle: Throttle::default(),
+            token: None,
+        }
+    }
+
+    /// Prepares the copy of an object, which is made with
+    /// [`ObjectCopy::send`].
+    pub fn copy_object(
+        &self,
+        source_bucket: impl Into<String>,
+        source_key: impl Into<String>,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+    ) -> ObjectCopy<'_, C> {
+        ObjectCopy {
+            client: self,
+            source: head_object::Request::new(source_bucket, source_key),
+            bucket: bucket.into(),
+            key: key.into(),
+            part_size: DEFAULT_COPY_PART_SIZE,
+            concurrency: DEFAULT_CONCURRENCY,
+            token: None,
+        }
+    }
+
+    /// Uploads a file, with a single request if it fits in a part and with
+    /// a multipart upload otherwise.
+    ///
+    /// The parts are large enough for the file to fit in the 10,000 parts
+    /// S3 accepts.
+    pub async fn upload_file(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        path: impl AsRef<Path>,
+    ) -> Result<UploadOutput, Error<C::Error>> {
+        let file = File::open(path).await.map_err(Error::Body)?;
+        let length = file.metadata().await.map_err(Error::Body)?.len();
+        let part_size =
+            (DEFAULT_PART_SIZE as u64).max(length.div_ceil(MAX_PARTS));
+
+        self.upload(bucket, key, ByteStream::from_file(file))
+            .content_length(length)
+            .part_size(part_size as usize)
+            .send()
+            .await
+    }
+
+    /// Downloads an object to a file, created or truncated, with concurrent
+    /// ranged requests if it is larger than a part.
+    ///
+    /// The file is removed if the download fails.
+    pub async fn download_file(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        path: impl AsRef<Path>,
+    ) -> Result<DownloadOutput, Error<C::Error>> {
+        let path = path.as_ref();
+        let mut file = File::create(path).await.map_err(Error::Body)?;
+        let output = self.download(bucket, key).to_file(&mut file).await;
+        if output.is_err() {
+            drop(file);
+            let _ = tokio::fs::remove_file(path).await;
+        }
+        output
+    }
+}
+
+/// The upload of an object, with a multipart upload if it is larger than a
+/// part.
+///
+/// The body is read one part at a time, and at most `concurrency` parts
+/// are sent at once, so that much of the body is held in memory. Each part
+/// is retried according to the retry policy of the client, and if the
+/// upload fails anyway, it is aborted so its parts don't linger.
+#[derive(Debug)]
+pub struct Upload<'a, C> {
+    client: &'a Client<C>,
+    bucket: String,
+    key: String,
+    body: ByteStream,
+    content_type: Option<String>,
+    content_length: Option<u64>,
+    condition: Option<WriteCondition>,
+    part_size: usize,
+    concurrency: usize,
+    listener: Option<Listener>,
+    throttle: Throttle,
+    token: Option<CancellationToken>,
+}
+
+impl<'a, C: HttpClient> Upload<'a, C> {
+    /// Sets the media type of the object.
+    pub fn content_type(self, content_type: impl Into<String>) -> Self {
+        Self {
+            content_type: Some(content_type.into()),
+            ..self
+        }
+    }
+
+    /// Sets the condition the object must meet to be written, checked when
+    /// the upload completes.
+    pub fn condition(self, condition: WriteCondition) -> Self {
+        Self {
+            condition: Some(condition),
+            ..self
+        }
+    }
+
+    /// Sets the length of the body, reported to the progress listener.
+    pub fn content_length(self, content_length: u64) -> Self {
+        Self {
+            content_length: Some(content_length),
+            ..self
+        }
+    }
+
+    /// Sets the size of the parts, 8 MiB by default.
+    ///
+    /// S3 rejects parts smaller than [`MIN_PART_SIZE`] but the last one,
+    /// and uploads of more than 10,000 parts.
+    pub fn part_size(self, part_size: usize) -> Self {
+        Self {
+            part_size: part_size.max(1),
+            ..self
+        }
+    }
+
+    /// Sets how many parts are sent at once, 4 by default.
+    pub fn concurrency(self, concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            ..self
+        }
+    }
+
+    /// Sets a listener called every time a part is sent.
+    pub fn progress_listener(self, listener: impl ProgressListener) -> Self {
+        Self {
+            listener: Some(Listener::new(listener)),
+            ..self
+        }
+    }
+
+    /// Limits the bandwidth of the upload, along with the other limits it
+    /// was given.
+    pub fn bandwidth_limit(mut self, limit: BandwidthLimit) -> Self {
+        self.throttle.push(limit);
+        self
+    }
+
+    /// Sets a token cancelling the upload, and aborting it.
+    pub fn cancellation_token(self, token: CancellationToken) -> Self {
+        Self {
+            token: Some(token),
+            ..self
+        }
+    }
+
+    /// Uploads the object.
+    pub async fn send(self) -> Result<UploadOutput, Error<C::Error>> {
+        let Self {
+            client,
+            bucket,
+            key,
+            body,
+            content_type,
+            content_length,
+            condition,
+            part_size,
+            concurrency,
+            listener,
+            throttle,
+            token,
+        } = self;
+        let token = token.as_ref();
+        let tracker = Tracker::new(listener, content_length, part_size as u64);
+
+        let mut parts = parts(body, part_size);
+        let first = parts.try_next().map_err(Error::Body);
+        let first = cancellable(token, first).await?;
+        let first = match first {
+            Some(first) if first.len() == part_size => first,
+            first => {
+                let body = Vec::from(first.unwrap_or_default());
+                let length = body.len() as u64;
+                let mut request = put_object::Request::new(&bucket, &key, body);
+                request.content_type = content_type;
+                request.condition = condition;
+
+                let sending = throttle
+                    .acquire(length)
+                    .then(|()| client.send_request(request));
+                let response = cancellable(token, sending).await?;
+                tracker.part(length);
+                return Ok(UploadOutput {
+                    etag: response.etag,
+                    version_id: response.version_id,
+                    upload_id: None,
+                });
+            }
+        };
+
+        let mut request = create_multipart_upload::Request::new(&bucket, &key);
+        request.content_type = content_type;
+        let response = cancellable(token, client.send_request(request)).await?;
+        let upload_id = response.upload_id;
+
+        // Parts wait for the bandwidth they take before they are sent.
+        let parts =
+            stream::once(async { Ok(first) })
+                .chain(parts)
+                .and_then(|part| {
+                    throttle.acquire(part.len() as u64).map(|()| Ok(part))
+                });
+        let completed = upload_parts(
+            client,
+            &bucket,
+            &key,
+            &upload_id,
+            parts,
+            concurrency,
+            &tracker,
+        );
+        let completed = cancellable(token, completed).await;
+        let completed = match completed {
+            Ok(parts) => {
+                let mut request = complete_multipart_upload::Request::new(
+                    &bucket, &key, &upload_id, parts,
+                );
+                request.condition = condition;
+                client.send_request(request).await
+            }
+            Err(err) => Err(err),
+        };
+
+        match completed {
+            Ok(response) => Ok(UploadOutput {
+                etag: response.etag,
+                version_id: response.version_id,
+                upload_id: Some(upload_id),
+            }),
+            Err(err) => {
+                // The error of the upload matters more than the one of
+                // aborting it.
+                let request = abort_multipart_upload::Request::new(
+                    &bucket, &key, &upload_id,
+                );
+                let _ = client.send_request(request).await;
+                Err(err)
+            }
+        }
+    }
+}
+
+/// The result of an upload.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct UploadOutput {
+    /// The entity tag of the object, with its quotes.
+    pub etag: Option<String>,
+
+    /// The ID of the version of the object, if the bucket is versioned.
+    pub version_id: Option<String>,
+
+    /// The ID of the multipart upload, if the object was sent in parts.
+    pub upload_id: Option<String>,
+}
+
+/// The download of an object, with concurrent ranged requests if it is
+/// larger than a part.
+///
+/// The first part also tells the length and the entity tag of the object.
+/// The other parts are only returned if the object still has that entity
+/// tag, so an object replaced during the download isn't mixed with the new
+/// one, and each part must have the length of its range. At most
+/// `concurrency` parts are received at once.
+#[derive(Debug)]
+pub struct Download<'a, C> {
+    client: &'a Client<C>,
+    request: get_object::Request,
+    part_size: u64,
+    concurrency: usize,
+    listener: Option<Listener>,
+    throttle: Throttle,
+    token: Option<CancellationToken>,
+}
+
+impl<'a, C: HttpClient> Download<'a, C> {
+    /// Sets the version of the object to download.
+    pub fn version_id(mut self, version_id: impl Into<String>) -> Self {
+        self.request.version_id = Some(version_id.into());
+        self
+    }
+
+    /// Sets the size of the parts, 8 MiB by default.
+    pub fn part_size(self, part_size: u64) -> Self {
+        Self {
+            part_size: part_size.max(1),
+            ..self
+        }
+    }
+
+    /// Sets how many parts are received at once, 4 by default.
+    pub fn concurrency(self, concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            ..self
+        }
+    }
+
+    /// Sets a listener called every time a part is received.
+    pub fn progress_listener(self, listener: impl ProgressListener) -> Self {
+        Self {
+            listener: Some(Listener::new(listener)),
+            ..self
+        }
+    }
+
+    /// Limits the bandwidth of the download, along with the other limits it
+    /// was given.
+    pub fn bandwidth_limit(mut self, limit: BandwidthLimit) -> Self {
+        self.throttle.push(limit);
+        self
+    }
+
+    /// Sets a token cancelling the download.
+    pub fn cancellation_token(self, token: CancellationToken) -> Self {
+        Self {
+            token: Some(token),
+            ..self
+        }
+    }
+
+    /// Starts the download, and returns the details of the object along
+    /// with a stream of its content.
+    ///
+    /// The content is streamed in order, while the following parts are
+    /// already being received.
+    pub async fn send(
+        self,
+    ) -> Result<
+        (
+            DownloadOutput,
+            impl Stream<Item = Result<Bytes, Error<C::Error>>> + 'a,
+        ),
+        Error<C::Error>,
+    > {
+        let first_part = self.first_part();
+        let (output, first, tracker) =
+            cancellable(self.token.as_ref(), first_part).await?;
+        let parts = self
+            .remaining_parts(&output, first.len() as u64, tracker)
+            .buffered(self.concurrency)
+            .map_ok(|(_, part)| part);
+
+        let content = stream::once(async { Ok(first) }).chain(parts);
+        Ok((output, content))
+    }
+
+    /// Downloads the object to a file, writing the parts at their offset as
+    /// they are received.
+    pub async fn to_file(
+        self,
+        file: &mut File,
+    ) -> Result<DownloadOutput, Error<C::Error>> {
+        let first_part = self.first_part();
+        let (output, first, tracker) =
+            cancellable(self.token.as_ref(), first_part).await?;
+        write_at(file, 0, &first).await.map_err(Error::Body)?;
+
+        let mut parts = self
+            .remaining_parts(&output, first.len() as u64, tracker)
+            .buffer_unordered(self.concurrency);
+        while let Some((offset, part)) = parts.try_next().await? {
+            write_at(file, offset, &part).await.map_err(Error::Body)?;
+        }
+
+        file.flush().await.map_err(Error::Body)?;
+        Ok(output)
+    }
+
+    /// Receives the first part of the object, or the whole object if the
+    /// server doesn't support ranges, along with the tracker of the
+    /// download.
+    async fn first_part(
+        &self,
+    ) -> Result<(DownloadOutput, Bytes, Arc<Tracker>), Error<C::Error>> {
+        let mut request = self.request.clone();
+        request.range = Some(range(0, self.part_size));
+        self.throttle.acquire(self.part_size).await;
+        let response = match self.client.send_request(request).await {
+            // Empty objects don't have a first byte.
+            Err(Error::FromHttpResponse(FromHttpResponseError::Server(
+                error,
+            ))) if error.code == "InvalidRange" => {
+                self.client.send_request(self.request.clone()).await?
+            }
+            response => response?,
+        };
+
+        // Servers ignoring the range send the whole object in one part.
+        let (content_length, part_size) = match &response.content_range {
+            Some(content_range) => {
+                (complete_length(content_range)?, self.part_size)
+            }
+            None => {
+                let content_length = response.body.len() as u64;
+                (content_length, content_length.max(1))
+            }
+        };
+        let output = DownloadOutput {
+            content_length,
+            content_type: response.content_type,
+            etag: response.etag,
+            last_modified: response.last_modified,
+            version_id: response.version_id,
+        };
+        let tracker = Tracker::new(
+            self.listener.clone(),
+            Some(content_length),
+            part_size,
+        );
+        tracker.part(response.body.len() as u64);
+        Ok((output, response.body.into(), Arc::new(tracker)))
+    }
+
+    /// Returns the requests of the parts after the first one, of `received`
+    /// bytes, which resolve to the offset and the content of their part.
+    fn remaining_parts(
+        &self,
+        output: &DownloadOutput,
+        received: u64,
+        tracker: Arc<Tracker>,
+    ) -> impl Stream<
+        Item = impl Future<Output = Result<(u64, Bytes), Error<C::Error>>> + 'a,
+    > + 'a {
+        let client = self.client;
+        let part_size = self.part_size;
+        let content_length = output.content_length;
+        let token = self.token.clone();
+        let throttle = self.throttle.clone();
+
+        let mut request = self.request.clone();
+        request.if_match = output.etag.clone();
+        // Later versions of the object would fail the entity tag check.
+        request.version_id =
+            request.version_id.or_else(|| output.version_id.clone());
+
+        let offsets = (0..).map(move |part| received + part * part_size);
+        stream::iter(offsets.take_while(move |&offset| offset < content_length))
+            .map(move |offset| {
+                let mut request = request.clone();
+                let length = part_size.min(content_length - offset);
+                request.range = Some(range(offset, length));
+                let tracker = tracker.clone();
+                let token = token.clone();
+                let throttle = throttle.clone();
+                async move {
+                    let part = throttle
+                        .acquire(length)
+                        .then(|()| client.send_request(request));
+                    let part = cancellable(token.as_ref(), part).await?.body;
+                    if part.len() as u64 != length {
+                        return Err(FromHttpResponseError::from(
+                            DeserializationError::Invalid(
+                                "Content-Length".to_owned(),
+                            ),
+                        )
+                        .into());
+                    }
+                    tracker.part(length);
+                    Ok((offset, part.into()))
+                }
+            })
+    }
+}
+
+/// The copy of an object, with a multipart upload of parts copied from the
+/// source if it is larger than [`MAX_COPY_SIZE`].
+///
+/// The parts are copied from the version of the source that was current
+/// when the copy started, or, if the bucket of the source isn't versioned,
+/// only as long as the source keeps its entity tag. At most `concurrency`
+/// parts are copied at once, and if the copy fails, its multipart upload is
+/// aborted.
+#[derive(Debug)]
+pub struct ObjectCopy<'a, C> {
+    client: &'a Client<C>,
+    source: head_object::Request,
+    bucket: String,
+    key: String,
+    part_size: u64,
+    concurrency: usize,
+    token: Option<CancellationToken>,
+}
+
+impl<'a, C: HttpClient> ObjectCopy<'a, C> {
+    /// Sets the version of the object to copy.
+    pub fn version_id(mut self, version_id: impl Into<String>) -> Self {
+        self.source.version_id = Some(version_id.into());
+        self
+    }
+
+    /// Sets the size of the parts of a multipart copy, 128 MiB by default.
+    ///
+    /// The parts are made larger if the source wouldn't fit in the 10,000
+    /// parts S3 accepts.
+    pub fn part_size(self, part_size: u64) -> Self {
+        Self {
+            part_size: part_size.clamp(1, MAX_COPY_SIZE),
+            ..self
+        }
+    }
+
+    /// Sets how many parts are copied at once, 4 by default.
+    pub fn concurrency(self, concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            ..self
+        }
+    }
+
+    /// Sets a token cancelling the copy, and aborting its multipart upload.
+    pub fn cancellation_token(self, token: CancellationToken) -> Self {
+        Self {
+            token: Some(token),
+            ..self
+        }
+    }
+
+    /// Copies the object.
+    pub async fn send(self) -> Result<UploadOutput, Error<C::Error>> {
+        let client = self.client;
+        let token = self.token.as_ref();
+        let head = client.send_request(self.source.clone());
+        let head = cancellable(token, head).await?;
+        let content_length = head.content_length.ok_or_else(|| {
+            FromHttpResponseError::from(DeserializationError::Missing(
+                "Content-Length".to_owned(),
+            ))
+        })?;
+        let version_id = self
+            .source
+            .version_id
+            .clone()
+            .or_else(|| head.version_id.clone());
+
+        if content_length <= MAX_COPY_SIZE {
+            let mut request = copy_object::Request::new(
+                &self.source.bucket,
+                &self.source.key,
+                &self.bucket,
+                &self.key,
+            );
+            request.source_version_id = version_id;
+            let response =
+                cancellable(token, client.send_request(request)).await?;
+            return Ok(UploadOutput {
+                etag: response.etag,
+                version_id: response.version_id,
+                upload_id: None,
+            });
+        }
+
+        let request =
+            create_multipart_upload::Request::new(&self.bucket, &self.key);
+        let response = cancellable(token, client.send_request(request)).await?;
+        let upload_id = response.upload_id;
+
+        let part_size = self.part_size.max(content_length.div_ceil(MAX_PARTS));
+        let completed = self.copy_parts(
+            &upload_id,
+            content_length,
+            part_size,
+            version_id,
+            head.etag,
+        );
+        let completed = cancellable(token, completed).await;
+        let completed = match completed {
+            Ok(parts) => {
+                let request = complete_multipart_upload::Request::new(
+                    &self.bucket,
+                    &self.key,
+                    &upload_id,
+                    parts,
+                );
+                client.send_request(request).await
+            }
+            Err(err) => Err(err),
+        };
+
+        match completed {
+            Ok(response) => Ok(UploadOutput {
+                etag: response.etag,
+                version_id: response.version_id,
+                upload_id: Some(upload_id),
+            }),
+            Err(err) => {
+                let request = abort_multipart_upload::Request::new(
+                    &self.bucket,
+                    &self.key,
+                    &upload_id,
+                );
+                let _ = client.send_request(request).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Copies the parts of the source, and returns them in order.
+    async fn copy_parts(
+        &self,
+        upload_id: &str,
+        content_length: u64,
+        part_size: u64,
+        version_id: Option<String>,
+        etag: Option<String>,
+    ) -> Result<Vec<CompletedPart>, Error<C::Error>> {
+        let client = self.client;
+        let part_count = content_length.div_ceil(part_size) as u32;
+
+        let mut completed: Vec<CompletedPart> = stream::iter(1..=part_count)
+            .map(|part_number| {
+                let offset = u64::from(part_number - 1) * part_size;
+                let length = part_size.min(content_length - offset);
+                let mut request = upload_part_copy::Request::new(
+                    &self.source.bucket,
+                    &self.source.key,
+                    &self.bucket,
+                    &self.key,
+                    upload_id,
+                    part_number,
+                );
+                request.source_version_id = version_id.clone();
+                request.source_range = Some(range(offset, length));
+                // Versions can't change, unlike unversioned objects.
+                if version_id.is_none() {
+                    request.source_if_match = etag.clone();
+                }
+
+                async move {
+                    let etag = client.send_request(request).await?.etag;
+                    let etag = etag.ok_or_else(|| {
+                        FromHttpResponseError::from(
+                            DeserializationError::Missing("ETag".to_owned()),
+                        )
+                    })?;
+                    Ok::<_, Error<C::Error>>(CompletedPart::new(
+                        part_number,
+                        etag,
+                    ))
+                }
+            })
+            .buffer_unordered(self.concurrency)
+            .try_collect()
+            .await?;
+
+        completed.sort_by_key(|part| part.part_number);
+        Ok(completed)
+    }
+}
+
+/// The details of a downloaded object.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct DownloadOutput {
+    /// The length of the object.
+    pub content_length: u64,
+
+    /// The media type of the object.
+    pub content_type: Option<String>,
+
+    /// The entity tag of the object, with its quotes.
+    pub etag: Option<String>,
+
+    /// When the object was last modified.
+    pub last_modified: Option<HttpDate>,
+
+    /// The ID of the version of the object, if the bucket is versioned.
+    pub version_id: Option<String>,
+}
+
+/// Returns the value of the `Range` header of a part.
+fn range(offset: u64, length: u64) -> String {
+    format!("bytes={}-{}", offset, offset + length - 1)
+}
+
+/// Returns the length of the object of a `Content-Range` header, like
+/// `bytes 0-1023/146515`.
+fn complete_length<E>(content_range: &str) -> Result<u64, Error<E>> {
+    content_range
+        .rsplit('/')
+        .next()
+        .and_then(|length| length.parse().ok())
+        .ok_or_else(|| {
+            FromHttpResponseError::from(DeserializationError::Invalid(
+                "Content-Range".to_owned(),
+            ))
+            .into()
+        })
+}
+
+/// Writes a part of a download to a file.
+async fn write_at(file: &mut File, offset: u64, part: &[u8]) -> io::Result<()> {
+    file.seek(SeekFrom::Start(offset)).await?;
+    file.write_all(part).await
+}
+
+/// Splits a byte stream into parts of `part_size` bytes, but the last one.
+fn parts(
+    body: ByteStream,
+    part_size: usize,
+) -> BoxStream<'static, io::Result<Bytes>> {
+    let state = (body, BytesMut::new(), false);
+    stream::try_unfold(state, move |(mut body, mut buffer, done)| async move {
+        if done {
+            return Ok(None);
+        }
+        while buffer.len() < part_size {
+            match body.try_next().await? {
+                Some(bytes) => buffer.extend_from_slice(&bytes),
+                None if buffer.is_empty() => return Ok(None),
+                None => {
+                    let part = buffer.split().freeze();
+                    return Ok(Some((part, (body, buffer, true))));
+                }
+            }
+        }
+        let part = buffer.split_to(part_size).freeze();
+        Ok(Some((part, (body, buffer, false))))
+    })
+    .boxed()
+}
+
+/// Uploads the parts of a multipart upload, and returns them in order.
+async fn upload_parts<C: HttpClient>(
+    client: &Client<C>,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    parts: impl Stream<Item = io::Result<Bytes>>,
+    concurrency: usize,
+    tracker: &Tracker,
+) -> Result<Vec<CompletedPart>, Error<C::Error>> {
+    let mut completed: Vec<CompletedPart> = parts
+        .zip(stream::iter(1..))
+        .map(|(part, part_number)| async move {
+            let part = part.map_err(Error::<C::Error>::Body)?;
+            let length = part.len() as u64;
+            let request = upload_part::Request::new(
+                bucket,
+                key,
+                upload_id,
+                part_number,
+                part,
+            );
+            let etag =
+                client.send_request(request).await?.etag.ok_or_else(|| {
+                    FromHttpResponseError::from(DeserializationError::Missing(
+                        "ETag".to_owned(),
+                    ))
+                })?;
+            tracker.part(length);
+            Ok::<_, Error<C::Error>>(CompletedPart::new(part_number, etag))
+        })
+        .buffer_unordered(concurrency)
+        .try_collect()
+        .await?;
+
+    completed.sort_by_key(|part| part.part_number);
+    Ok(completed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        task::Poll,
+        time::{Duration, Instant},
+    };
+
+    use bytes::Bytes;
+    use futures_executor::block_on;
+    use futures_util::{stream, StreamExt, TryStreamExt};
+    use http::{Method, StatusCode};
+    use tokio::io::AsyncReadExt;
+
+    use super::{BandwidthLimit, Progress};
+    use crate::{
+        tests::{client, ok},
+        ByteStream, CancellationToken, Error,
+    };
+
+    fn part(etag: &str) -> http::Response<Vec<u8>> {
+        http::Response::builder()
+            .header("ETag", etag)
+            .body(Vec::new())
+            .unwrap()
+    }
+
+    fn xml(body: &str) -> http::Response<Vec<u8>> {
+        http::Response::new(body.as_bytes().to_vec())
+    }
+
+    fn range(range: &str, body: &str) -> http::Response<Vec<u8>> {
+        http::Response::builder()
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header("Content-Range", range)
+            .header("ETag", "\"etag\"")
+            .body(body.as_bytes().to_vec())
+            .unwrap()
+    }
+
+    #[test]
+    fn upload_small_object() {
+        let client = client(vec![part("\"etag\"")]);
+        let output = block_on(
+            client
+                .upload("bucket", "key", b"abc".to_vec())
+                .part_size(4)
+                .send(),
+        )
+        .unwrap();
+        assert_eq!(output.etag.as_deref(), Some("\"etag\""));
+        assert_eq!(output.upload_id, None);
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method(), Method::PUT);
+        assert_eq!(requests[0].body(), b"abc");
+    }
+
+    #[test]
+    fn upload_parts() {
+        let client = client(vec![
+            xml("<InitiateMultipartUploadResult><UploadId>id</UploadId>\
+                 </InitiateMultipartUploadResult>"),
+            part("\"1\""),
+            part("\"2\""),
+            part("\"3\""),
+            xml("<CompleteMultipartUploadResult><ETag>\"e-3\"</ETag>\
+                 </CompleteMultipartUploadResult>"),
+        ]);
+        let output = block_on(
+            client
+                .upload("bucket", "key", b"0123456789".to_vec())
+                .part_size(4)
+                .concurrency(1)
+                .send(),
+        )
+        .unwrap();
+        assert_eq!(output.etag.as_deref(), Some("\"e-3\""));
+        assert_eq!(output.upload_id.as_deref(), Some("id"));
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests[0].uri().query(), Some("uploads"));
+        assert_eq!(requests[1].uri().query(), Some("partNumber=1&uploadId=id"));
+        assert_eq!(requests[1].body(), b"0123");
+        assert_eq!(requests[3].body(), b"89");
+        assert_eq!(requests[4].method(), Method::POST);
+        let complete = std::str::from_utf8(requests[4].body()).unwrap();
+        assert!(complete.contains(
+            "<Part><PartNumber>3</PartNumber><ETag>&quot;3&quot;</ETag></Part>"
+        ));
+    }
+
+    #[test]
+    fn abort_failed_upload() {
+        let invalid = http::Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(b"<Error><Code>InvalidRequest</Code></Error>".to_vec())
+            .unwrap();
+        let client = client(vec![
+            xml("<InitiateMultipartUploadResult><UploadId>id</UploadId>\
+                 </InitiateMultipartUploadResult>"),
+            part("\"1\""),
+            invalid,
+            ok(),
+        ]);
+        block_on(
+            client
+                .upload("bucket", "key", b"0123456789".to_vec())
+                .part_size(4)
+                .concurrency(1)
+                .send(),
+        )
+        .unwrap_err();
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[3].method(), Method::DELETE);
+        assert_eq!(requests[3].uri().query(), Some("uploadId=id"));
+    }
+
+    #[test]
+    fn cancel_upload() {
+        let client = client(vec![
+            xml("<InitiateMultipartUploadResult><UploadId>id</UploadId>\
+                 </InitiateMultipartUploadResult>"),
+            part("\"1\""),
+            ok(),
+        ]);
+        let token = CancellationToken::new();
+
+        // The body is cancelled once its first part is read.
+        let cancel = token.clone();
+        let body = stream::iter(vec![Ok(Bytes::from_static(b"0123"))]).chain(
+            stream::poll_fn(move |_| {
+                cancel.cancel();
+                Poll::Pending
+            }),
+        );
+        let result = block_on(
+            client
+                .upload("bucket", "key", ByteStream::new(body))
+                .part_size(4)
+                .cancellation_token(token)
+                .send(),
+        );
+        assert!(matches!(result, Err(Error::Cancelled)));
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2].method(), Method::DELETE);
+        assert_eq!(requests[2].uri().query(), Some("uploadId=id"));
+    }
+
+    #[test]
+    fn download_parts() {
+        let client = client(vec![
+            range("bytes 0-3/10", "0123"),
+            range("bytes 4-7/10", "4567"),
+            range("bytes 8-9/10", "89"),
+        ]);
+
+        let (output, content) = block_on(
+            client
+                .download("bucket", "key")
+                .part_size(4)
+                .concurrency(1)
+                .send(),
+        )
+        .unwrap();
+        assert_eq!(output.content_length, 10);
+        let content: Vec<_> = block_on(content.try_collect()).unwrap();
+        assert_eq!(content.concat(), b"0123456789");
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests[0].headers()["range"], "bytes=0-3");
+        assert_eq!(requests[2].headers()["range"], "bytes=8-9");
+        assert_eq!(requests[2].headers()["if-match"], "\"etag\"");
+    }
+
+    #[test]
+    fn download_without_ranges() {
+        // The server ignores the range of the first part.
+        let whole = || {
+            http::Response::builder()
+                .header("ETag", "\"etag\"")
+                .body(b"0123456789".to_vec())
+                .unwrap()
+        };
+        let client = client(vec![whole(), whole(), whole()]);
+
+        let (output, content) = block_on(
+            client
+                .download("bucket", "key")
+                .part_size(4)
+                .concurrency(1)
+                .send(),
+        )
+        .unwrap();
+        assert_eq!(output.content_length, 10);
+        let content: Vec<_> = block_on(content.try_collect()).unwrap();
+        assert_eq!(content.concat(), b"0123456789");
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].headers()["range"], "bytes=0-3");
+    }
+
+    #[test]
+    fn report_progress() {
+        let client = client(vec![
+            range("bytes 0-3/10", "0123"),
+            range("bytes 4-7/10", "4567"),
+            range("bytes 8-9/10", "89"),
+        ]);
+        let reports = Arc::new(Mutex::new(Vec::new()));
+
+        let listener = reports.clone();
+        let (_, content) = block_on(
+            client
+                .download("bucket", "key")
+                .part_size(4)
+                .concurrency(1)
+                .progress_listener(move |progress: &Progress| {
+                    listener.lock().unwrap().push(*progress)
+                })
+                .send(),
+        )
+        .unwrap();
+        let _: Vec<_> = block_on(content.try_collect()).unwrap();
+
+        let reports = reports.lock().unwrap();
+        let transferred: Vec<_> =
+            reports.iter().map(|p| p.bytes_transferred).collect();
+        assert_eq!(transferred, [4, 8, 10]);
+        assert_eq!(reports[2].parts_completed, 3);
+        assert_eq!(reports[2].total_parts, Some(3));
+        assert_eq!(reports[2].total_bytes, Some(10));
+    }
+
+    #[test]
+    fn limit_bandwidth() {
+        let client = client(vec![
+            range("bytes 0-3/10", "0123"),
+            range("bytes 4-7/10", "4567"),
+            range("bytes 8-9/10", "89"),
+        ]);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let shared = BandwidthLimit::new(1000);
+
+        let start = Instant::now();
+        let content: Vec<_> = runtime.block_on(async {
+            let (_, content) = client
+                .download("bucket", "key")
+                .part_size(4)
+                .bandwidth_limit(shared)
+                .bandwidth_limit(BandwidthLimit::new(40))
+                .send()
+                .await
+                .unwrap();
+            content.try_collect().await.unwrap()
+        });
+        assert_eq!(content.concat(), b"0123456789");
+        // The last part waits for the 8 bytes before it, at 40 bytes per
+        // second.
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn check_part_length() {
+        let client = client(vec![
+            range("bytes 0-3/10", "0123"),
+            range("bytes 4-7/10", "45"),
+        ]);
+
+        let (_, content) = block_on(
+            client
+                .download("bucket", "key")
+                .part_size(4)
+                .concurrency(1)
+                .send(),
+        )
+        .unwrap();
+        block_on(content.try_collect::<Vec<_>>()).unwrap_err();
+    }
+
+    #[test]
+    fn download_empty_object() {
+        let invalid_range = http::Response::builder()
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .body(b"<Error><Code>InvalidRange</Code></Error>".to_vec())
+            .unwrap();
+        let client = client(vec![invalid_range, ok()]);
+
+        let (output, content) =
+            block_on(client.download("bucket", "key").send()).unwrap();
+        assert_eq!(output.content_length, 0);
+        let content: Vec<_> = block_on(content.try_collect()).unwrap();
+        assert_eq!(content.concat(), b"");
+    }
+
+    #[test]
+    fn download_to_file() {
+        let client = client(vec![
+            range("bytes 0-3/10", "0123"),
+            range("bytes 4-7/10", "4567"),
+            range("bytes 8-9/10", "89"),
+        ]);
+        let path = std::env::temp_dir()
+            .join(format!("s3ers-download-{}", std::process::id()));
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let content = runtime.block_on(async {
+            let mut file = tokio::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)
+                .await
+                .unwrap();
+            client
+                .download("bucket", "key")
+                .part_size(4)
+                .to_file(&mut file)
+                .await
+                .unwrap();
+
+            let mut content = String::new();
+            let mut file = tokio::fs::File::open(&path).await.unwrap();
+            file.read_to_string(&mut content).await.unwrap();
+            content
+        });
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(content, "0123456789");
+    }
+
+    #[test]
+    fn copy_objects() {
+        let head = |length: u64| {
+            http::Response::builder()
+                .header("Content-Length", length)
+                .header("ETag", "\"source\"")
+                .body(Vec::new())
+                .unwrap()
+        };
+        let copied =
+            "<CopyObjectResult><ETag>\"copy\"</ETag></CopyObjectResult>";
+        let copied_part = |etag: &str| {
+            xml(&format!(
+                "<CopyPartResult><ETag>{}</ETag></CopyPartResult>",
+                etag
+            ))
+        };
+
+        let single = client(vec![head(10), xml(copied)]);
+        let output = block_on(
+            single.copy_object("source", "key", "bucket", "copy").send(),
+        )
+        .unwrap();
+        assert_eq!(output.etag.as_deref(), Some("\"copy\""));
+        assert_eq!(output.upload_id, None);
+
+        let length = super::MAX_COPY_SIZE + 1;
+        let client = client(vec![
+            head(length),
+            xml("<InitiateMultipartUploadResult><UploadId>id</UploadId>\
+                 </InitiateMultipartUploadResult>"),
+            copied_part("\"1\""),
+            copied_part("\"2\""),
+            xml("<CompleteMultipartUploadResult><ETag>\"copy-2\"</ETag>\
+                 </CompleteMultipartUploadResult>"),
+        ]);
+        let output = block_on(
+            client
+                .copy_object("source", "key", "bucket", "copy")
+                .part_size(super::MAX_COPY_SIZE)
+                .concurrency(1)
+                .send(),
+        )
+        .unwrap();
+        assert_eq!(output.upload_id.as_deref(), Some("id"));
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(
+            requests[3].headers()["x-amz-copy-source-range"],
+            format!("bytes={}-{}", super::MAX_COPY_SIZE, length - 1)
+        );
+        assert_eq!(
+            requests[3].headers()["x-amz-copy-source-if-match"],
+            "\"source\""
+        );
+    }
+
+    #[test]
+    fn transfer_files() {
+        let client = client(vec![ok(), xml("content")]);
+        let path = std::env::temp_dir()
+            .join(format!("s3ers-transfer-{}", std::process::id()));
+        std::fs::write(&path, "content").unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let content = runtime.block_on(async {
+            client.upload_file("bucket", "key", &path).await.unwrap();
+            client.download_file("bucket", "key", &path).await.unwrap();
+            tokio::fs::read_to_string(&path).await.unwrap()
+        });
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(content, "content");
+
+        let requests = client.0.http_client.requests.lock().unwrap();
+        assert_eq!(requests[0].method(), Method::PUT);
+        assert_eq!(requests[0].body(), b"content");
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// A listener of the progress of a transfer, like a progress bar, set with
+/// [`Upload::progress_listener`](super::Upload::progress_listener) or
+/// [`Download::progress_listener`](super::Download::progress_listener).
+///
+/// It is called every time a part completes, possibly from concurrent
+/// tasks, so it should not block.
+pub trait ProgressListener: Send + Sync + 'static {
+    /// Reports the progress of the transfer.
+    fn on_progress(&self, progress: &Progress);
+}
+
+impl<F> ProgressListener for F
+where
+    F: Fn(&Progress) + Send + Sync + 'static,
+{
+    fn on_progress(&self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+/// The progress of a transfer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Progress {
+    /// How many bytes of the object were transferred.
+    pub bytes_transferred: u64,
+
+    /// The length of the object, if it is known.
+    pub total_bytes: Option<u64>,
+
+    /// How many parts were transferred.
+    pub parts_completed: u32,
+
+    /// How many parts the object is transferred in, if it is known.
+    pub total_parts: Option<u32>,
+}
+
+/// The listener of a transfer.
+#[derive(Clone)]
+pub(super) struct Listener(Arc<dyn ProgressListener>);
+
+impl Listener {
+    pub(super) fn new(listener: impl ProgressListener) -> Self {
+        Self(Arc::new(listener))
+    }
+}
+
+impl fmt::Debug for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Listener").finish_non_exhaustive()
+    }
+}
+
+/// Counts the parts of a transfer, and reports them to its listener.
+#[derive(Debug)]
+pub(super) struct Tracker {
+    listener: Option<Listener>,
+    total_bytes: Option<u64>,
+    total_parts: Option<u32>,
+    bytes_transferred: AtomicU64,
+    parts_completed: AtomicU32,
+}
+
+impl Tracker {
+    /// Creates a tracker of a transfer of `total_bytes` bytes, in parts of
+    /// `part_size` bytes.
+    pub(super) fn new(
+        listener: Option<Listener>,
+        total_bytes: Option<u64>,
+        part_size: u64,
+    ) -> Self {
+        let total_parts =
+            total_bytes.map(|total| total.div_ceil(part_size).max(1) as u32);
+        Self {
+            listener,
+            total_bytes,
+            total_parts,
+            bytes_transferred: AtomicU64::new(0),
+            parts_completed: AtomicU32::new(0),
+        }
+    }
+
+    /// Counts a part of `length` bytes.
+    pub(super) fn part(&self, length: u64) {
+        let bytes_transferred =
+            self.bytes_transferred.fetch_add(length, Ordering::Relaxed)
+                + length;
+        let parts_completed =
+            self.parts_completed.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if let Some(Listener(listener)) = &self.listener {
+            listener.on_progress(&Progress {
+                bytes_transferred,
+                total_bytes: self.total_bytes,
+                parts_completed,
+                total_parts: self.total_parts,
+            });
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A limit on the bandwidth of transfers, set with
+/// [`Upload::bandwidth_limit`](super::Upload::bandwidth_limit) or
+/// [`Download::bandwidth_limit`](super::Download::bandwidth_limit).
+///
+/// Clones of a limit are shared by the transfers they are given to, to
+/// limit them all at once, while a limit given to a single transfer limits
+/// only that one. Parts are sent whole, so the bandwidth is spread over
+/// the duration of a part: a part waits until the parts before it would
+/// have been transferred at the limit. Waiting relies on the timer of the
+/// Tokio runtime.
+#[derive(Clone, Debug)]
+pub struct BandwidthLimit(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    bytes_per_second: u64,
+    next: Mutex<Instant>,
+}
+
+impl BandwidthLimit {
+    /// Limits transfers to `bytes_per_second` bytes per second.
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self(Arc::new(Inner {
+            bytes_per_second: bytes_per_second.max(1),
+            next: Mutex::new(Instant::now()),
+        }))
+    }
+
+    /// Returns the limit, in bytes per second.
+    pub fn bytes_per_second(&self) -> u64 {
+        self.0.bytes_per_second
+    }
+
+    /// Waits until `bytes` more bytes can be transferred.
+    async fn acquire(&self, bytes: u64) {
+        let duration = Duration::from_secs_f64(
+            bytes as f64 / self.0.bytes_per_second as f64,
+        );
+        let slot = {
+            let mut next = self.0.next.lock().unwrap();
+            let slot = (*next).max(Instant::now());
+            *next = slot + duration;
+            slot
+        };
+        if slot > Instant::now() {
+            tokio::time::sleep_until(slot.into()).await;
+        }
+    }
+}
+
+/// The bandwidth limits of a transfer.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Throttle(Vec<BandwidthLimit>);
+
+impl Throttle {
+    /// Adds a limit to the transfer.
+    pub(super) fn push(&mut self, limit: BandwidthLimit) {
+        self.0.push(limit);
+    }
+
+    /// Waits until a part of `bytes` bytes can be transferred under every
+    /// limit.
+    pub(super) async fn acquire(&self, bytes: u64) {
+        for limit in &self.0 {
+            limit.acquire(bytes).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::BandwidthLimit;
+
+    #[test]
+    fn limit_bandwidth() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let limit = BandwidthLimit::new(1000);
+
+        let start = Instant::now();
+        runtime.block_on(limit.acquire(100));
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        // The first part took the bandwidth of a tenth of a second.
+        runtime.block_on(limit.clone().acquire(100));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,193 @@
+//! Waiting for buckets and objects to exist, or not to, like after creating
+//! a bucket or while an object is replicated to another region.
+//!
+//! Waiters poll the bucket or the object with `HeadBucket` or `HeadObject`
+//! until it reaches the state they wait for, with a delay doubling between
+//! attempts, and fail with [`Error::Timeout`] once they waited too long.
+
+use std::time::{Duration, Instant};
+
+use http::StatusCode;
+use s3ers_api::{error::FromHttpResponseError, OutgoingRequest};
+use s3ers_s3_api::{bucket::head_bucket, object::head_object};
+
+use crate::{Client, Error, HttpClient};
+
+/// How long a waiter polls, and how often.
+///
+/// Waiting relies on the timer of the Tokio runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Waiter {
+    /// How long the waiter waits at most.
+    pub max_wait: Duration,
+
+    /// The delay before the second attempt, doubled after every attempt.
+    pub min_delay: Duration,
+
+    /// The longest delay between attempts.
+    pub max_delay: Duration,
+}
+
+impl Waiter {
+    /// A waiter waiting up to `max_wait`, with the default delays.
+    pub fn new(max_wait: Duration) -> Self {
+        Self {
+            max_wait,
+            min_delay: Duration::from_secs(5),
+            max_delay: Duration::from_secs(120),
+        }
+    }
+
+    /// Sets the delay before the second attempt and the longest delay
+    /// between attempts.
+    pub fn with_delays(
+        mut self,
+        min_delay: Duration,
+        max_delay: Duration,
+    ) -> Self {
+        self.min_delay = min_delay;
+        self.max_delay = max_delay.max(min_delay);
+        self
+    }
+}
+
+impl Default for Waiter {
+    /// Waits up to 5 minutes, polling every 5 seconds at first and every 2
+    /// minutes at most.
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5 * 60))
+    }
+}
+
+impl<C: HttpClient> Client<C> {
+    /// Waits until an object exists, returning its metadata.
+    pub async fn wait_until_object_exists(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        waiter: Waiter,
+    ) -> Result<head_object::Response, Error<C::Error>> {
+        let request = head_object::Request::new(bucket, key);
+        let response = self.wait_until(request, true, waiter).await?;
+        Ok(response.unwrap_or_default())
+    }
+
+    /// Waits until an object doesn't exist.
+    pub async fn wait_until_object_not_exists(
+        &self,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        waiter: Waiter,
+    ) -> Result<(), Error<C::Error>> {
+        let request = head_object::Request::new(bucket, key);
+        self.wait_until(request, false, waiter).await?;
+        Ok(())
+    }
+
+    /// Waits until a bucket exists, returning its details.
+    pub async fn wait_until_bucket_exists(
+        &self,
+        bucket: impl Into<String>,
+        waiter: Waiter,
+    ) -> Result<head_bucket::Response, Error<C::Error>> {
+        let request = head_bucket::Request::new(bucket);
+        let response = self.wait_until(request, true, waiter).await?;
+        Ok(response.unwrap_or_default())
+    }
+
+    /// Waits until a bucket doesn't exist.
+    pub async fn wait_until_bucket_not_exists(
+        &self,
+        bucket: impl Into<String>,
+        waiter: Waiter,
+    ) -> Result<(), Error<C::Error>> {
+        let request = head_bucket::Request::new(bucket);
+        self.wait_until(request, false, waiter).await?;
+        Ok(())
+    }
+
+    /// Sends `request` until it succeeds if `exists`, or until it fails
+    /// with a `404 Not Found` error otherwise, returning the response it
+    /// succeeded with.
+    async fn wait_until<R: OutgoingRequest + Clone>(
+        &self,
+        request: R,
+        exists: bool,
+        waiter: Waiter,
+    ) -> Result<Option<R::IncomingResponse>, Error<C::Error>> {
+        let deadline = Instant::now() + waiter.max_wait;
+        let mut delay = waiter.min_delay;
+        loop {
+            match self.send_request(request.clone()).await {
+                Ok(response) if exists => return Ok(Some(response)),
+                Err(Error::FromHttpResponse(
+                    FromHttpResponseError::Server(error),
+                )) if error.status == StatusCode::NOT_FOUND => {
+                    if !exists {
+                        return Ok(None);
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => return Err(err),
+            }
+
+            if Instant::now() + delay > deadline {
+                return Err(Error::Timeout);
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(waiter.max_delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::StatusCode;
+
+    use super::Waiter;
+    use crate::{
+        tests::{client, ok},
+        Error,
+    };
+
+    fn not_found() -> http::Response<Vec<u8>> {
+        http::Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Vec::new())
+            .unwrap()
+    }
+
+    #[test]
+    fn wait_for_objects() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let waiter = Waiter::new(Duration::from_millis(100))
+            .with_delays(Duration::from_millis(1), Duration::from_millis(2));
+
+        let objects = client(vec![not_found(), not_found(), ok()]);
+        runtime
+            .block_on(objects.wait_until_object_exists("bucket", "key", waiter))
+            .unwrap();
+        assert_eq!(objects.0.http_client.requests.lock().unwrap().len(), 3);
+
+        let buckets = client(vec![ok(), not_found()]);
+        runtime
+            .block_on(buckets.wait_until_bucket_not_exists("bucket", waiter))
+            .unwrap();
+
+        let waiter = Waiter::new(Duration::from_millis(5))
+            .with_delays(Duration::from_millis(4), Duration::from_millis(4));
+        let objects = client(vec![ok(), ok()]);
+        let error = runtime
+            .block_on(
+                objects.wait_until_object_not_exists("bucket", "key", waiter),
+            )
+            .unwrap_err();
+        assert!(matches!(error, Error::Timeout));
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    future::Future,
+    time::{Duration, SystemTime},
+};
+
+use tokio::sync::Mutex;
+
+use crate::{Credentials, CredentialsError};
+
+/// How long before their expiry cached credentials are refreshed.
+pub(crate) const DEFAULT_REFRESH_BUFFER: Duration = Duration::from_secs(300);
+
+/// A cache for expiring credentials.
+///
+/// Credentials are reloaded when they are about to expire. Concurrent callers
+/// wait for the same reload instead of starting their own.
+#[derive(Debug)]
+pub(crate) struct ExpiringCache {
+    credentials: Mutex<Option<Credentials>>,
+    buffer: Duration,
+}
+
+impl ExpiringCache {
+    pub(crate) fn new(buffer: Duration) -> Self {
+        Self {
+            credentials: Mutex::new(None),
+            buffer,
+        }
+    }
+
+    /// Returns the cached credentials, or loads new ones if they are about
+    /// to expire.
+    ///
+    /// If loading fails while the cached credentials are still valid, they
+    /// are returned instead of the error.
+    pub(crate) async fn get_or_load<F, Fut>(
+        &self,
+        load: F,
+    ) -> Result<Credentials, CredentialsError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Credentials, CredentialsError>>,
+    {
+        let mut cached = self.credentials.lock().await;
+        let now = SystemTime::now();
+
+        if let Some(credentials) = &*cached {
+            if !credentials.is_expired_at(now + self.buffer) {
+                return Ok(credentials.clone());
+            }
+        }
+
+        match load().await {
+            Ok(credentials) => {
+                *cached = Some(credentials.clone());
+                Ok(credentials)
+            }
+            Err(err) => match &*cached {
+                Some(credentials) if !credentials.is_expired_at(now) => {
+                    Ok(credentials.clone())
+                }
+                _ => Err(err),
+            },
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+
+use async_trait::async_trait;
+
+use crate::{
+    cache::{ExpiringCache, DEFAULT_REFRESH_BUFFER},
+    ContainerProvider, Credentials, CredentialsError, CredentialsProvider,
+    EnvironmentProvider, ImdsProvider, ProfileProvider, WebIdentityProvider,
+};
+
+/// Tries a list of providers in order, and caches the first credentials
+/// found.
+///
+/// Providers returning [`CredentialsError::NotLoaded`] are skipped, while
+/// other errors are returned immediately, so that a broken configuration
+/// isn't silently replaced by another one.
+///
+/// The credentials are cached, and concurrent callers wait for the same
+/// lookup. Temporary credentials are looked up again shortly before they
+/// expire.
+pub struct ChainProvider {
+    providers: Vec<(&'static str, Box<dyn CredentialsProvider>)>,
+    cache: ExpiringCache,
+}
+
+impl ChainProvider {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self {
+            providers: Vec::new(),
+            cache: ExpiringCache::new(DEFAULT_REFRESH_BUFFER),
+        }
+    }
+
+    /// Adds a provider at the end of the chain.
+    ///
+    /// The name is used in the error returned when no provider has
+    /// credentials.
+    pub fn or_else(
+        mut self,
+        name: &'static str,
+        provider: impl CredentialsProvider + 'static,
+    ) -> Self {
+        self.providers.push((name, Box::new(provider)));
+        self
+    }
+
+    async fn load(&self) -> Result<Credentials, CredentialsError> {
+        let mut reasons = Vec::new();
+        for (name, provider) in &self.providers {
+            match provider.provide_credentials().await {
+                Ok(credentials) => return Ok(credentials),
+                Err(CredentialsError::NotLoaded(reason)) => {
+                    reasons.push(format!("{}: {}", name, reason));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(CredentialsError::not_loaded(if reasons.is_empty() {
+            "the credentials chain is empty".to_owned()
+        } else {
+            reasons.join("; ")
+        }))
+    }
+}
+
+impl Default for ChainProvider {
+    /// The default chain looks for credentials in the same places as the AWS
+    /// CLI: the environment variables, the shared configuration files, a web
+    /// identity token, the container credentials endpoint and finally the
+    /// instance metadata service.
+    fn default() -> Self {
+        Self::new()
+            .or_else("environment", EnvironmentProvider::new())
+            .or_else("profile", ProfileProvider::new())
+            .or_else("web identity", WebIdentityProvider::new())
+            .or_else("container", ContainerProvider::new())
+            .or_else("instance metadata", ImdsProvider::new())
+    }
+}
+
+impl fmt::Debug for ChainProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainProvider")
+            .field(
+                "providers",
+                &self
+                    .providers
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for ChainProvider {
+    async fn provide_credentials(
+        &self,
+    ) -> Result<Credentials, CredentialsError> {
+        self.cache.get_or_load(|| self.load()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use futures_executor::block_on;
+
+    use super::ChainProvider;
+    use crate::{Credentials, CredentialsError, CredentialsProvider};
+
+    struct NotLoaded;
+
+    #[async_trait]
+    impl CredentialsProvider for NotLoaded {
+        async fn provide_credentials(
+            &self,
+        ) -> Result<Credentials, CredentialsError> {
+            Err(CredentialsError::not_loaded("not configured"))
+        }
+    }
+
+    struct Invalid;
+
+    #[async_trait]
+    impl CredentialsProvider for Invalid {
+        async fn provide_credentials(
+            &self,
+        ) -> Result<Credentials, CredentialsError> {
+            Err(CredentialsError::invalid_configuration("invalid"))
+        }
+    }
+
+    #[test]
+    fn skip_providers_not_loaded() {
+        let chain = ChainProvider::new()
+            .or_else("first", NotLoaded)
+            .or_else("second", Credentials::new("key", "secret"))
+            .or_else("third", Invalid);
+
+        let credentials = block_on(chain.provide_credentials()).unwrap();
+        assert_eq!(credentials.access_key_id, "key");
+    }
+
+    #[test]
+    fn stop_at_invalid_configuration() {
+        let chain = ChainProvider::new()
+            .or_else("first", Invalid)
+            .or_else("second", Credentials::new("key", "secret"));
+
+        assert!(matches!(
+            block_on(chain.provide_credentials()),
+            Err(CredentialsError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn report_every_provider() {
+        let chain = ChainProvider::new()
+            .or_else("first", NotLoaded)
+            .or_else("second", NotLoaded);
+
+        match block_on(chain.provide_credentials()) {
+            Err(CredentialsError::NotLoaded(reason)) => assert_eq!(
+                reason,
+                "first: not configured; second: not configured"
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::{fs, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use hyper::{header::AUTHORIZATION, Body, Request, StatusCode, Uri};
+
+use crate::{
+    cache::{ExpiringCache, DEFAULT_REFRESH_BUFFER},
+    environment::var,
+    http::{parse_credentials, HttpClient},
+    Credentials, CredentialsError, CredentialsProvider,
+};
+
+const ECS_ENDPOINT: &str = "http://169.254.170.2";
+
+/// The link-local addresses of the ECS and EKS credentials endpoints, which
+/// may be used without TLS.
+const CONTAINER_HOSTS: &[&str] =
+    &["169.254.170.2", "169.254.170.23", "fd00:ec2::23"];
+
+/// Loads credentials from the endpoint of a container orchestrator, like
+/// ECS, Fargate or EKS Pod Identity.
+///
+/// The endpoint is read from `AWS_CONTAINER_CREDENTIALS_RELATIVE_URI`,
+/// relative to the ECS endpoint, or `AWS_CONTAINER_CREDENTIALS_FULL_URI`.
+/// Full URIs must use HTTPS, unless they point to a loopback address or to
+/// one of the container endpoints. The `Authorization` header is read from
+/// the file at `AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE`, or from
+/// `AWS_CONTAINER_AUTHORIZATION_TOKEN`.
+///
+/// Credentials are cached, and fetched again shortly before they expire.
+#[derive(Debug)]
+pub struct ContainerProvider {
+    client: HttpClient,
+    cache: ExpiringCache,
+}
+
+impl ContainerProvider {
+    /// Creates a new `ContainerProvider`.
+    pub fn new() -> Self {
+        Self {
+            client: HttpClient::new(Duration::from_secs(2)),
+            cache: ExpiringCache::new(DEFAULT_REFRESH_BUFFER),
+        }
+    }
+
+    async fn load(&self) -> Result<Credentials, CredentialsError> {
+        let uri = endpoint()?;
+
+        let mut request = Request::get(uri);
+        if let Some(token) = authorization_token()? {
+            request = request.header(AUTHORIZATION, token);
+        }
+        let request = request
+            .body(Body::empty())
+            .map_err(|err| CredentialsError::ProviderError(Box::new(err)))?;
+
+        let (status, body) = self.client.send(request).await?;
+        if status != StatusCode::OK {
+            return Err(CredentialsError::ProviderError(
+                format!(
+                    "the container credentials endpoint responded with {}",
+                    status
+                )
+                .into(),
+            ));
+        }
+        parse_credentials(&body)
+    }
+}
+
+impl Default for ContainerProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for ContainerProvider {
+    async fn provide_credentials(
+        &self,
+    ) -> Result<Credentials, CredentialsError> {
+        self.cache.get_or_load(|| self.load()).await
+    }
+}
+
+fn endpoint() -> Result<Uri, CredentialsError> {
+    if let Some(relative) = var(&["AWS_CONTAINER_CREDENTIALS_RELATIVE_URI"]) {
+        return format!("{}{}", ECS_ENDPOINT, relative)
+            .parse()
+            .map_err(|_| {
+                CredentialsError::invalid_configuration(
+                    "AWS_CONTAINER_CREDENTIALS_RELATIVE_URI is not a valid \
+                     path",
+                )
+            });
+    }
+
+    let full =
+        var(&["AWS_CONTAINER_CREDENTIALS_FULL_URI"]).ok_or_else(|| {
+            CredentialsError::not_loaded(
+                "AWS_CONTAINER_CREDENTIALS_RELATIVE_URI and \
+                 AWS_CONTAINER_CREDENTIALS_FULL_URI are not set",
+            )
+        })?;
+    let uri: Uri = full.parse().map_err(|_| {
+        CredentialsError::invalid_configuration(
+            "AWS_CONTAINER_CREDENTIALS_FULL_URI is not a valid URI",
+        )
+    })?;
+
+    if uri.scheme_str() != Some("https") && !is_allowed_http_host(&uri) {
+        return Err(CredentialsError::invalid_configuration(format!(
+            "the container credentials endpoint `{}` must use HTTPS",
+            uri
+        )));
+    }
+    Ok(uri)
+}
+
+fn is_allowed_http_host(uri: &Uri) -> bool {
+    let host = match uri.host() {
+        Some(host) => host.trim_start_matches('[').trim_end_matches(']'),
+        None => return false,
+    };
+
+    if host.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    match host.parse::<IpAddr>() {
+        Ok(ip) => {
+            ip.is_loopback()
+                || CONTAINER_HOSTS
+                    .iter()
+                    .any(|allowed| allowed.parse() == Ok(ip))
+        }
+        Err(_) => false,
+    }
+}
+
+fn authorization_token() -> Result<Option<String>, CredentialsError> {
+    if let Some(path) = var(&["AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE"]) {
+        // The token file is rotated by the orchestrator, so it is read again
+        // for every request.
+        let token = fs::read_to_string(&path)
+            .map_err(|err| CredentialsError::ProviderError(Box::new(err)))?;
+        return Ok(Some(token.trim().to_owned()));
+    }
+    Ok(var(&["AWS_CONTAINER_AUTHORIZATION_TOKEN"]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_allowed_http_host;
+
+    #[test]
+    fn allowed_http_hosts() {
+        let allowed = |uri: &str| is_allowed_http_host(&uri.parse().unwrap());
+
+        assert!(allowed("http://127.0.0.1:8080/credentials"));
+        assert!(allowed("http://localhost/credentials"));
+        assert!(allowed("http://[::1]/credentials"));
+        assert!(allowed("http://169.254.170.23/v1/credentials"));
+        assert!(allowed("http://[fd00:ec2::23]/v1/credentials"));
+        assert!(!allowed("http://example.com/credentials"));
+        assert!(!allowed("http://10.0.0.1/credentials"));
+    }
+}
@@ -0,0 +1,71 @@
+use std::env;
+
+use async_trait::async_trait;
+
+use crate::{Credentials, CredentialsError, CredentialsProvider};
+
+/// Loads credentials from the environment variables used by the AWS CLI and
+/// SDKs.
+///
+/// The access key ID is read from `AWS_ACCESS_KEY_ID` (or `AWS_ACCESS_KEY`),
+/// the secret access key from `AWS_SECRET_ACCESS_KEY` (or `AWS_SECRET_KEY`)
+/// and the optional session token from `AWS_SESSION_TOKEN`.
+#[derive(Clone, Debug, Default)]
+pub struct EnvironmentProvider {
+    _private: (),
+}
+
+impl EnvironmentProvider {
+    /// Creates a new `EnvironmentProvider`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn load(&self) -> Result<Credentials, CredentialsError> {
+        let access_key_id = var(&["AWS_ACCESS_KEY_ID", "AWS_ACCESS_KEY"])
+            .ok_or_else(|| {
+                CredentialsError::not_loaded("AWS_ACCESS_KEY_ID is not set")
+            })?;
+        let secret_access_key =
+            var(&["AWS_SECRET_ACCESS_KEY", "AWS_SECRET_KEY"]).ok_or_else(
+                || {
+                    CredentialsError::invalid_configuration(
+                        "AWS_ACCESS_KEY_ID is set but AWS_SECRET_ACCESS_KEY \
+                         is not",
+                    )
+                },
+            )?;
+
+        let credentials = Credentials::new(access_key_id, secret_access_key);
+        Ok(match var(&["AWS_SESSION_TOKEN"]) {
+            Some(session_token) => {
+                credentials.with_session_token(session_token)
+            }
+            None => credentials,
+        })
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for EnvironmentProvider {
+    async fn provide_credentials(
+        &self,
+    ) -> Result<Credentials, CredentialsError> {
+        self.load()
+    }
+}
+
+/// Returns the value of the first of the given variables that is set and
+/// not empty.
+pub(crate) fn var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Returns the region configured in the environment, with `AWS_REGION` or
+/// `AWS_DEFAULT_REGION`.
+pub(crate) fn region() -> Option<String> {
+    var(&["AWS_REGION", "AWS_DEFAULT_REGION"])
+}
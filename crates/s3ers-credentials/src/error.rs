@@ -0,0 +1,51 @@
+use std::{error::Error as StdError, fmt};
+
+/// An error that occurred while resolving credentials.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CredentialsError {
+    /// The provider isn't configured in this environment, like when the
+    /// environment variables it reads aren't set.
+    NotLoaded(String),
+
+    /// The provider is configured, but its configuration is invalid.
+    InvalidConfiguration(String),
+
+    /// The provider failed to fetch the credentials.
+    ProviderError(Box<dyn StdError + Send + Sync>),
+}
+
+impl CredentialsError {
+    pub(crate) fn not_loaded(message: impl Into<String>) -> Self {
+        Self::NotLoaded(message.into())
+    }
+
+    pub(crate) fn invalid_configuration(message: impl Into<String>) -> Self {
+        Self::InvalidConfiguration(message.into())
+    }
+}
+
+impl fmt::Display for CredentialsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotLoaded(message) => {
+                write!(f, "no credentials loaded: {}", message)
+            }
+            Self::InvalidConfiguration(message) => {
+                write!(f, "invalid credentials configuration: {}", message)
+            }
+            Self::ProviderError(err) => {
+                write!(f, "failed to fetch credentials: {}", err)
+            }
+        }
+    }
+}
+
+impl StdError for CredentialsError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::ProviderError(err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use hyper::{Body, Method, Request, StatusCode};
+use tokio::sync::Mutex;
+
+use crate::{
+    cache::{ExpiringCache, DEFAULT_REFRESH_BUFFER},
+    environment::var,
+    http::{parse_credentials, HttpClient},
+    Credentials, CredentialsError, CredentialsProvider,
+};
+
+const DEFAULT_ENDPOINT: &str = "http://169.254.169.254";
+const TOKEN_PATH: &str = "/latest/api/token";
+const CREDENTIALS_PATH: &str = "/latest/meta-data/iam/security-credentials/";
+
+const TOKEN_HEADER: &str = "x-aws-ec2-metadata-token";
+const TOKEN_TTL_HEADER: &str = "x-aws-ec2-metadata-token-ttl-seconds";
+const TOKEN_TTL: Duration = Duration::from_secs(21600);
+
+/// Loads the credentials of the IAM role attached to an EC2 instance from
+/// the instance metadata service.
+///
+/// The token-based IMDSv2 flow is used. The endpoint can be overridden with
+/// `AWS_EC2_METADATA_SERVICE_ENDPOINT`, and the provider is disabled by
+/// setting `AWS_EC2_METADATA_DISABLED` to `true`.
+///
+/// Credentials are cached, and fetched again shortly before they expire.
+#[derive(Debug)]
+pub struct ImdsProvider {
+    client: HttpClient,
+    endpoint: String,
+    token: Mutex<Option<(String, Instant)>>,
+    cache: ExpiringCache,
+}
+
+impl ImdsProvider {
+    /// Creates a new `ImdsProvider`.
+    pub fn new() -> Self {
+        let endpoint = var(&["AWS_EC2_METADATA_SERVICE_ENDPOINT"])
+            .unwrap_or_else(|| DEFAULT_ENDPOINT.to_owned());
+        Self::with_endpoint(endpoint)
+    }
+
+    /// Creates an `ImdsProvider` querying the given endpoint, like
+    /// `http://[fd00:ec2::254]`.
+    pub fn with_endpoint(endpoint: impl Into<String>) -> Self {
+        Self {
+            client: HttpClient::new(Duration::from_secs(1)),
+            endpoint: endpoint.into().trim_end_matches('/').to_owned(),
+            token: Mutex::new(None),
+            cache: ExpiringCache::new(DEFAULT_REFRESH_BUFFER),
+        }
+    }
+
+    async fn load(&self) -> Result<Credentials, CredentialsError> {
+        if var(&["AWS_EC2_METADATA_DISABLED"])
+            .is_some_and(|disabled| disabled.eq_ignore_ascii_case("true"))
+        {
+            return Err(CredentialsError::not_loaded(
+                "the instance metadata service is disabled",
+            ));
+        }
+
+        let token = self.token().await?;
+
+        let role = self.get(CREDENTIALS_PATH, &token).await?;
+        let role = role.lines().next().unwrap_or_default().trim();
+        if role.is_empty() {
+            return Err(CredentialsError::not_loaded(
+                "no IAM role is attached to the instance",
+            ));
+        }
+
+        let credentials = self
+            .get(&format!("{}{}", CREDENTIALS_PATH, role), &token)
+            .await?;
+        parse_credentials(credentials.as_bytes())
+    }
+
+    /// Returns the session token, requesting a new one if it expired.
+    async fn token(&self) -> Result<String, CredentialsError> {
+        let mut token = self.token.lock().await;
+        if let Some((value, expiry)) = &*token {
+            if Instant::now() < *expiry {
+                return Ok(value.clone());
+            }
+        }
+
+        let request = Request::builder()
+            .method(Method::PUT)
+            .uri(format!("{}{}", self.endpoint, TOKEN_PATH))
+            .header(TOKEN_TTL_HEADER, TOKEN_TTL.as_secs())
+            .body(Body::empty())
+            .map_err(|err| CredentialsError::ProviderError(Box::new(err)))?;
+        let value = self.body(request).await?;
+
+        // Renew the token a minute early, to account for the time spent on
+        // the request.
+        let expiry = Instant::now() + TOKEN_TTL - Duration::from_secs(60);
+        *token = Some((value.clone(), expiry));
+        Ok(value)
+    }
+
+    async fn get(
+        &self,
+        path: &str,
+        token: &str,
+    ) -> Result<String, CredentialsError> {
+        let request = Request::get(format!("{}{}", self.endpoint, path))
+            .header(TOKEN_HEADER, token)
+            .body(Body::empty())
+            .map_err(|err| CredentialsError::ProviderError(Box::new(err)))?;
+        self.body(request).await
+    }
+
+    async fn body(
+        &self,
+        request: Request<Body>,
+    ) -> Result<String, CredentialsError> {
+        let (status, body) = self.client.send(request).await?;
+        match status {
+            StatusCode::OK => String::from_utf8(body.to_vec())
+                .map_err(|err| CredentialsError::ProviderError(Box::new(err))),
+            StatusCode::NOT_FOUND => Err(CredentialsError::not_loaded(
+                "no IAM role is attached to the instance",
+            )),
+            status => Err(CredentialsError::ProviderError(
+                format!(
+                    "the instance metadata service responded with {}",
+                    status
+                )
+                .into(),
+            )),
+        }
+    }
+}
+
+impl Default for ImdsProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for ImdsProvider {
+    async fn provide_credentials(
+        &self,
+    ) -> Result<Credentials, CredentialsError> {
+        self.cache.get_or_load(|| self.load()).await
+    }
+}
@@ -0,0 +1,78 @@
+//! AWS credentials providers for s3ers.
+//!
+//! A [`CredentialsProvider`] resolves the [`Credentials`] used to sign
+//! requests, possibly asynchronously and repeatedly, as temporary
+//! credentials have to be renewed.
+
+#![warn(missing_docs)]
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+pub use s3ers_signature::Credentials;
+
+mod cache;
+mod chain;
+mod container;
+mod environment;
+mod error;
+mod http;
+mod imds;
+pub mod profile;
+mod sts;
+
+pub use chain::ChainProvider;
+pub use container::ContainerProvider;
+pub use environment::EnvironmentProvider;
+pub use error::CredentialsError;
+pub use imds::ImdsProvider;
+pub use profile::ProfileProvider;
+pub use sts::{AssumeRoleProvider, WebIdentityProvider};
+
+/// Returns the region configured in the environment, with `AWS_REGION` or
+/// `AWS_DEFAULT_REGION`, or in the selected profile of the shared config
+/// file.
+pub fn default_region() -> Option<String> {
+    environment::region().or_else(|| ProfileProvider::new().region())
+}
+
+/// A source of credentials.
+#[async_trait]
+pub trait CredentialsProvider: Send + Sync {
+    /// Returns the current credentials.
+    ///
+    /// Providers that don't find their configuration return
+    /// [`CredentialsError::NotLoaded`], so that other providers can be tried.
+    async fn provide_credentials(
+        &self,
+    ) -> Result<Credentials, CredentialsError>;
+}
+
+/// Static credentials provide themselves.
+#[async_trait]
+impl CredentialsProvider for Credentials {
+    async fn provide_credentials(
+        &self,
+    ) -> Result<Credentials, CredentialsError> {
+        Ok(self.clone())
+    }
+}
+
+#[async_trait]
+impl<T: CredentialsProvider + ?Sized> CredentialsProvider for Arc<T> {
+    async fn provide_credentials(
+        &self,
+    ) -> Result<Credentials, CredentialsError> {
+        (**self).provide_credentials().await
+    }
+}
+
+#[async_trait]
+impl<T: CredentialsProvider + ?Sized> CredentialsProvider for Box<T> {
+    async fn provide_credentials(
+        &self,
+    ) -> Result<Credentials, CredentialsError> {
+        (**self).provide_credentials().await
+    }
+}
@@ -0,0 +1,5 @@
+//! Endpoints operating on buckets.
+
+pub mod head_bucket;
+pub mod list_object_versions;
+pub mod list_objects_v2;
@@ -0,0 +1,158 @@
+//! [HEAD /{bucket}](https://docs.aws.amazon.com/AmazonS3/latest/API/API_HeadBucket.html)
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError},
+    uri::bucket_url,
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Scope,
+};
+
+const METADATA: Metadata = Metadata {
+    description: "Checks that a bucket exists and can be accessed.",
+    method: Method::HEAD,
+    name: "HeadBucket",
+    path: "/:bucket",
+    scope: Scope::Bucket,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `HeadBucket` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket to check.
+    pub bucket: String,
+}
+
+impl Request {
+    /// Creates a new `Request` checking the given bucket.
+    pub fn new(bucket: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+        }
+    }
+}
+
+/// Response type for the `HeadBucket` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The region of the bucket.
+    pub bucket_region: Option<String>,
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        Ok(http::Request::builder()
+            .method(METADATA.method)
+            .uri(bucket_url(base_url, &self.bucket))
+            .body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        crate::check_status(&response)?;
+
+        Ok(Self {
+            bucket_region: crate::header_string(
+                &response,
+                "x-amz-bucket-region",
+            )?,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        _request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self::new(crate::path_arg(path_args, 0, "bucket")?))
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let response = crate::header_opt(
+            http::Response::builder(),
+            "x-amz-bucket-region",
+            self.bucket_region,
+        );
+        Ok(response.body(T::default())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, OutgoingResponse};
+    use s3ers_api::{IncomingResponse, OutgoingRequest};
+
+    use super::{Request, Response};
+
+    #[test]
+    fn request_uri() {
+        let http_request = Request::new("bucket")
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(http_request.method(), "HEAD");
+        assert_eq!(http_request.uri(), "https://s3.amazonaws.com/bucket");
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::builder()
+            .header("x-amz-bucket-region", "eu-west-1")
+            .body(Vec::new())
+            .unwrap();
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(response.bucket_region.as_deref(), Some("eu-west-1"));
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        let http_request = Request::new("bucket")
+            .try_into_http_request::<Vec<u8>>("")
+            .unwrap();
+        let request =
+            Request::try_from_http_request(http_request, &["bucket".into()])
+                .unwrap();
+        assert_eq!(request.bucket, "bucket");
+
+        let response = Response {
+            bucket_region: Some("eu-west-1".to_owned()),
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.bucket_region.as_deref(), Some("eu-west-1"));
+    }
+}
@@ -0,0 +1,422 @@
+//! [GET /{bucket}?versions](https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListObjectVersions.html)
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{DeserializationError, FromHttpResponseError, IntoHttpError},
+    header::HttpDate,
+    uri::{bucket_url, Query},
+    xml::Element,
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Paginated, Scope,
+};
+
+use crate::Owner;
+
+const METADATA: Metadata = Metadata {
+    description: "Lists the versions of the objects of a bucket.",
+    method: Method::GET,
+    name: "ListObjectVersions",
+    path: "/:bucket?versions",
+    scope: Scope::Bucket,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `ListObjectVersions` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket to list.
+    pub bucket: String,
+
+    /// Only list the keys starting with this prefix.
+    pub prefix: Option<String>,
+
+    /// Group the keys containing this delimiter after the prefix into
+    /// common prefixes.
+    pub delimiter: Option<String>,
+
+    /// Start listing after this key, from the previous response.
+    pub key_marker: Option<String>,
+
+    /// Start listing after this version of the key marker, from the
+    /// previous response.
+    pub version_id_marker: Option<String>,
+
+    /// The maximum number of versions to return, at most 1000.
+    pub max_keys: Option<u32>,
+}
+
+impl Request {
+    /// Creates a new `Request` listing the given bucket.
+    pub fn new(bucket: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            prefix: None,
+            delimiter: None,
+            key_marker: None,
+            version_id_marker: None,
+            max_keys: None,
+        }
+    }
+}
+
+/// Response type for the `ListObjectVersions` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The versions and delete markers of this page, in the order of the
+    /// listing.
+    pub versions: Vec<ObjectVersion>,
+
+    /// The common prefixes of the keys, if a delimiter was given.
+    pub common_prefixes: Vec<String>,
+
+    /// Whether there are more versions to list.
+    pub is_truncated: bool,
+
+    /// The key marker of the next page, if there is one.
+    pub next_key_marker: Option<String>,
+
+    /// The version ID marker of the next page, if there is one.
+    pub next_version_id_marker: Option<String>,
+}
+
+/// A version of an object, or a delete marker.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ObjectVersion {
+    /// The key of the object.
+    pub key: String,
+
+    /// The ID of the version.
+    pub version_id: Option<String>,
+
+    /// Whether this is the current version of the object.
+    pub is_latest: bool,
+
+    /// Whether this version is a delete marker rather than an object.
+    pub is_delete_marker: bool,
+
+    /// When the version was created.
+    pub last_modified: Option<HttpDate>,
+
+    /// The entity tag of the version, with its quotes. Delete markers don't
+    /// have one.
+    pub etag: Option<String>,
+
+    /// The size of the version, in bytes. Delete markers don't have one.
+    pub size: Option<u64>,
+
+    /// The storage class of the version.
+    pub storage_class: Option<String>,
+
+    /// The owner of the version.
+    pub owner: Option<Owner>,
+}
+
+impl ObjectVersion {
+    /// Creates a new `ObjectVersion` of the given key, which isn't a delete
+    /// marker.
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            version_id: None,
+            is_latest: false,
+            is_delete_marker: false,
+            last_modified: None,
+            etag: None,
+            size: None,
+            storage_class: None,
+            owner: None,
+        }
+    }
+
+    fn from_xml(element: &Element) -> Result<Self, DeserializationError> {
+        Ok(Self {
+            key: element.required_text("Key")?.to_owned(),
+            version_id: element.child_string("VersionId"),
+            is_latest: element.parse_child("IsLatest")?.unwrap_or_default(),
+            is_delete_marker: element.name == "DeleteMarker",
+            last_modified: element.parse_child("LastModified")?,
+            etag: element.child_string("ETag"),
+            size: element.parse_child("Size")?,
+            storage_class: element.child_string("StorageClass"),
+            owner: element.child("Owner").map(Owner::from_xml),
+        })
+    }
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let query = Query::for_endpoint(&METADATA)
+            .param_opt("prefix", self.prefix)
+            .param_opt("delimiter", self.delimiter)
+            .param_opt("key-marker", self.key_marker)
+            .param_opt("version-id-marker", self.version_id_marker)
+            .param_opt("max-keys", self.max_keys);
+
+        Ok(http::Request::builder()
+            .method(METADATA.method)
+            .uri(query.append_to(bucket_url(base_url, &self.bucket)))
+            .body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        let body = crate::xml_body(&response)?;
+
+        Ok(Self {
+            versions: body
+                .children
+                .iter()
+                .filter(|child| {
+                    child.name == "Version" || child.name == "DeleteMarker"
+                })
+                .map(ObjectVersion::from_xml)
+                .collect::<Result<_, _>>()?,
+            common_prefixes: body
+                .children("CommonPrefixes")
+                .filter_map(|prefix| prefix.child_string("Prefix"))
+                .collect(),
+            is_truncated: body.parse_child("IsTruncated")?.unwrap_or_default(),
+            next_key_marker: body.child_string("NextKeyMarker"),
+            next_version_id_marker: body.child_string("NextVersionIdMarker"),
+        })
+    }
+}
+
+impl Paginated for Request {
+    type Item = ObjectVersion;
+
+    fn next_page(mut self, response: &Response) -> Option<Self> {
+        if !response.is_truncated {
+            return None;
+        }
+        self.key_marker = Some(response.next_key_marker.clone()?);
+        self.version_id_marker = response.next_version_id_marker.clone();
+        Some(self)
+    }
+
+    fn into_items(response: Response) -> Vec<ObjectVersion> {
+        response.versions
+    }
+}
+
+#[cfg(feature = "server")]
+impl ObjectVersion {
+    fn to_xml(&self) -> Element {
+        let name = match self.is_delete_marker {
+            true => "DeleteMarker",
+            false => "Version",
+        };
+        let mut element = Element::new(name)
+            .with_child(Element::with_text("Key", self.key.as_str()));
+        element = crate::with_text_opt(
+            element,
+            "VersionId",
+            self.version_id.as_ref(),
+        )
+        .with_child(Element::with_text("IsLatest", self.is_latest.to_string()));
+        element = crate::with_text_opt(
+            element,
+            "LastModified",
+            self.last_modified.map(|date| date.to_iso8601()),
+        );
+        element = crate::with_text_opt(element, "ETag", self.etag.as_ref());
+        element = crate::with_text_opt(element, "Size", self.size);
+        element = crate::with_text_opt(
+            element,
+            "StorageClass",
+            self.storage_class.as_ref(),
+        );
+        match &self.owner {
+            Some(owner) => element.with_child(owner.to_xml()),
+            None => element,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            prefix: crate::query_param(&request, "prefix"),
+            delimiter: crate::query_param(&request, "delimiter"),
+            key_marker: crate::query_param(&request, "key-marker"),
+            version_id_marker: crate::query_param(
+                &request,
+                "version-id-marker",
+            ),
+            max_keys: crate::parse_query_param(&request, "max-keys")?,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let mut document = Element::new("ListVersionsResult");
+        for version in &self.versions {
+            document = document.with_child(version.to_xml());
+        }
+        for prefix in self.common_prefixes {
+            document = document.with_child(
+                Element::new("CommonPrefixes")
+                    .with_child(Element::with_text("Prefix", prefix)),
+            );
+        }
+        document = document.with_child(Element::with_text(
+            "IsTruncated",
+            self.is_truncated.to_string(),
+        ));
+        document = crate::with_text_opt(
+            document,
+            "NextKeyMarker",
+            self.next_key_marker,
+        );
+        document = crate::with_text_opt(
+            document,
+            "NextVersionIdMarker",
+            self.next_version_id_marker,
+        );
+        crate::xml_response(http::Response::builder(), document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use s3ers_api::{IncomingResponse, Paginated};
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, OutgoingRequest, OutgoingResponse};
+
+    use super::{Request, Response};
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::new(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListVersionsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01">
+  <Name>bucket</Name>
+  <KeyMarker>my</KeyMarker>
+  <VersionIdMarker/>
+  <MaxKeys>2</MaxKeys>
+  <IsTruncated>true</IsTruncated>
+  <NextKeyMarker>my-second-image.jpg</NextKeyMarker>
+  <NextVersionIdMarker>03jpff543dhffds434rfdsFDN943fdsFkdmqnh892</NextVersionIdMarker>
+  <DeleteMarker>
+    <Key>my-second-image.jpg</Key>
+    <VersionId>03jpff543dhffds434rfdsFDN943fdsFkdmqnh892</VersionId>
+    <IsLatest>true</IsLatest>
+    <LastModified>2009-11-12T17:50:30.000Z</LastModified>
+  </DeleteMarker>
+  <Version>
+    <Key>my-image.jpg</Key>
+    <VersionId>3/L4kqtJl40Nr8X8gdRQBpUMLUo</VersionId>
+    <IsLatest>true</IsLatest>
+    <LastModified>2009-10-12T17:50:30.000Z</LastModified>
+    <ETag>"fba9dede5f27731c9771645a39863328"</ETag>
+    <Size>434234</Size>
+    <StorageClass>STANDARD</StorageClass>
+  </Version>
+</ListVersionsResult>"#,
+        );
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(response.versions.len(), 2);
+        assert!(response.versions[0].is_delete_marker);
+        assert_eq!(response.versions[0].size, None);
+        assert!(!response.versions[1].is_delete_marker);
+        assert_eq!(response.versions[1].size, Some(434_234));
+
+        let next = Request::new("bucket").next_page(&response).unwrap();
+        assert_eq!(next.key_marker.as_deref(), Some("my-second-image.jpg"));
+        assert_eq!(
+            next.version_id_marker.as_deref(),
+            Some("03jpff543dhffds434rfdsFDN943fdsFkdmqnh892")
+        );
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        use super::ObjectVersion;
+
+        let mut request = Request::new("bucket");
+        request.key_marker = Some("key".to_owned());
+        request.version_id_marker = Some("id".to_owned());
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let request = Request::try_from_http_request(
+            http_request,
+            &["bucket".to_owned()],
+        )
+        .unwrap();
+        assert_eq!(request.key_marker.as_deref(), Some("key"));
+        assert_eq!(request.version_id_marker.as_deref(), Some("id"));
+
+        let version = ObjectVersion {
+            key: "key".to_owned(),
+            version_id: Some("2".to_owned()),
+            is_latest: false,
+            is_delete_marker: false,
+            last_modified: None,
+            etag: Some("\"etag\"".to_owned()),
+            size: Some(42),
+            storage_class: Some("STANDARD".to_owned()),
+            owner: None,
+        };
+        let delete_marker = ObjectVersion {
+            key: "key".to_owned(),
+            version_id: Some("3".to_owned()),
+            is_latest: true,
+            is_delete_marker: true,
+            last_modified: None,
+            etag: None,
+            size: None,
+            storage_class: None,
+            owner: None,
+        };
+        let response = Response {
+            versions: vec![version.clone(), delete_marker.clone()],
+            common_prefixes: Vec::new(),
+            is_truncated: true,
+            next_key_marker: Some("key".to_owned()),
+            next_version_id_marker: Some("1".to_owned()),
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.versions, [version, delete_marker]);
+        assert!(response.is_truncated);
+        assert_eq!(response.next_key_marker.as_deref(), Some("key"));
+        assert_eq!(response.next_version_id_marker.as_deref(), Some("1"));
+    }
+}
@@ -0,0 +1,401 @@
+//! [GET /{bucket}?list-type=2](https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListObjectsV2.html)
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{DeserializationError, FromHttpResponseError, IntoHttpError},
+    header::HttpDate,
+    uri::{bucket_url, Query},
+    xml::Element,
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Paginated, Scope,
+};
+
+use crate::Owner;
+
+const METADATA: Metadata = Metadata {
+    description: "Lists the objects of a bucket.",
+    method: Method::GET,
+    name: "ListObjectsV2",
+    path: "/:bucket?list-type=2",
+    scope: Scope::Bucket,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `ListObjectsV2` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket to list.
+    pub bucket: String,
+
+    /// Only list the keys starting with this prefix.
+    pub prefix: Option<String>,
+
+    /// Group the keys containing this delimiter after the prefix into
+    /// common prefixes.
+    pub delimiter: Option<String>,
+
+    /// The token of the page to list, from the previous response.
+    pub continuation_token: Option<String>,
+
+    /// Only list the keys after this one.
+    pub start_after: Option<String>,
+
+    /// The maximum number of keys to return, at most 1000.
+    pub max_keys: Option<u32>,
+
+    /// Whether to return the owner of each object.
+    pub fetch_owner: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` listing the given bucket.
+    pub fn new(bucket: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            prefix: None,
+            delimiter: None,
+            continuation_token: None,
+            start_after: None,
+            max_keys: None,
+            fetch_owner: false,
+        }
+    }
+}
+
+/// Response type for the `ListObjectsV2` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The objects of this page.
+    pub contents: Vec<Object>,
+
+    /// The common prefixes of the keys, if a delimiter was given.
+    pub common_prefixes: Vec<String>,
+
+    /// Whether there are more keys to list.
+    pub is_truncated: bool,
+
+    /// The token of the next page, if there is one.
+    pub next_continuation_token: Option<String>,
+
+    /// The number of keys and common prefixes of this page.
+    pub key_count: u32,
+}
+
+/// An object in a bucket listing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Object {
+    /// The key of the object.
+    pub key: String,
+
+    /// When the object was last modified.
+    pub last_modified: Option<HttpDate>,
+
+    /// The entity tag of the object, with its quotes.
+    pub etag: Option<String>,
+
+    /// The size of the object, in bytes.
+    pub size: u64,
+
+    /// The storage class of the object.
+    pub storage_class: Option<String>,
+
+    /// The owner of the object, if it was requested.
+    pub owner: Option<Owner>,
+}
+
+impl Object {
+    /// Creates a new `Object` with the given key and size.
+    pub fn new(key: impl Into<String>, size: u64) -> Self {
+        Self {
+            key: key.into(),
+            last_modified: None,
+            etag: None,
+            size,
+            storage_class: None,
+            owner: None,
+        }
+    }
+
+    fn from_xml(element: &Element) -> Result<Self, DeserializationError> {
+        Ok(Self {
+            key: element.required_text("Key")?.to_owned(),
+            last_modified: element.parse_child("LastModified")?,
+            etag: element.child_string("ETag"),
+            size: element.parse_child("Size")?.unwrap_or_default(),
+            storage_class: element.child_string("StorageClass"),
+            owner: element.child("Owner").map(Owner::from_xml),
+        })
+    }
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let query = Query::for_endpoint(&METADATA)
+            .param_opt("prefix", self.prefix)
+            .param_opt("delimiter", self.delimiter)
+            .param_opt("continuation-token", self.continuation_token)
+            .param_opt("start-after", self.start_after)
+            .param_opt("max-keys", self.max_keys)
+            .param_opt("fetch-owner", self.fetch_owner.then_some(true));
+
+        Ok(http::Request::builder()
+            .method(METADATA.method)
+            .uri(query.append_to(bucket_url(base_url, &self.bucket)))
+            .body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        let body = crate::xml_body(&response)?;
+
+        Ok(Self {
+            contents: body
+                .children("Contents")
+                .map(Object::from_xml)
+                .collect::<Result<_, _>>()?,
+            common_prefixes: body
+                .children("CommonPrefixes")
+                .filter_map(|prefix| prefix.child_string("Prefix"))
+                .collect(),
+            is_truncated: body.parse_child("IsTruncated")?.unwrap_or_default(),
+            next_continuation_token: body.child_string("NextContinuationToken"),
+            key_count: body.parse_child("KeyCount")?.unwrap_or_default(),
+        })
+    }
+}
+
+impl Paginated for Request {
+    type Item = Object;
+
+    fn next_page(mut self, response: &Response) -> Option<Self> {
+        if !response.is_truncated {
+            return None;
+        }
+        self.continuation_token =
+            Some(response.next_continuation_token.clone()?);
+        Some(self)
+    }
+
+    fn into_items(response: Response) -> Vec<Object> {
+        response.contents
+    }
+}
+
+#[cfg(feature = "server")]
+impl Object {
+    fn to_xml(&self) -> Element {
+        let mut element = Element::new("Contents")
+            .with_child(Element::with_text("Key", self.key.as_str()));
+        element = crate::with_text_opt(
+            element,
+            "LastModified",
+            self.last_modified.map(|date| date.to_iso8601()),
+        );
+        element = crate::with_text_opt(element, "ETag", self.etag.as_ref())
+            .with_child(Element::with_text("Size", self.size.to_string()));
+        element = crate::with_text_opt(
+            element,
+            "StorageClass",
+            self.storage_class.as_ref(),
+        );
+        match &self.owner {
+            Some(owner) => element.with_child(owner.to_xml()),
+            None => element,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            prefix: crate::query_param(&request, "prefix"),
+            delimiter: crate::query_param(&request, "delimiter"),
+            continuation_token: crate::query_param(
+                &request,
+                "continuation-token",
+            ),
+            start_after: crate::query_param(&request, "start-after"),
+            max_keys: crate::parse_query_param(&request, "max-keys")?,
+            fetch_owner: crate::parse_query_param(&request, "fetch-owner")?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let mut document = Element::new("ListBucketResult");
+        for object in &self.contents {
+            document = document.with_child(object.to_xml());
+        }
+        for prefix in self.common_prefixes {
+            document = document.with_child(
+                Element::new("CommonPrefixes")
+                    .with_child(Element::with_text("Prefix", prefix)),
+            );
+        }
+        document = document
+            .with_child(Element::with_text(
+                "IsTruncated",
+                self.is_truncated.to_string(),
+            ))
+            .with_child(Element::with_text(
+                "KeyCount",
+                self.key_count.to_string(),
+            ));
+        document = crate::with_text_opt(
+            document,
+            "NextContinuationToken",
+            self.next_continuation_token,
+        );
+        crate::xml_response(http::Response::builder(), document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use s3ers_api::{IncomingResponse, OutgoingRequest, Paginated};
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, OutgoingResponse};
+
+    use super::{Request, Response};
+
+    #[test]
+    fn request_uri() {
+        let mut request = Request::new("examplebucket");
+        request.prefix = Some("photos/".to_owned());
+        request.delimiter = Some("/".to_owned());
+
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(
+            http_request.uri(),
+            "https://s3.amazonaws.com/examplebucket\
+             ?list-type=2&prefix=photos%2F&delimiter=%2F"
+        );
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::new(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Name>bucket</Name>
+  <Prefix/>
+  <KeyCount>205</KeyCount>
+  <MaxKeys>1000</MaxKeys>
+  <IsTruncated>true</IsTruncated>
+  <NextContinuationToken>1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=</NextContinuationToken>
+  <Contents>
+    <Key>my-image.jpg</Key>
+    <LastModified>2009-10-12T17:50:30.000Z</LastModified>
+    <ETag>"fba9dede5f27731c9771645a39863328"</ETag>
+    <Size>434234</Size>
+    <StorageClass>STANDARD</StorageClass>
+  </Contents>
+</ListBucketResult>"#,
+        );
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(response.key_count, 205);
+        assert!(response.is_truncated);
+        assert_eq!(response.contents.len(), 1);
+        assert_eq!(response.contents[0].key, "my-image.jpg");
+        assert_eq!(response.contents[0].size, 434_234);
+        assert_eq!(
+            response.contents[0].last_modified.unwrap().to_string(),
+            "Mon, 12 Oct 2009 17:50:30 GMT"
+        );
+
+        let next = Request::new("bucket").next_page(&response).unwrap();
+        assert_eq!(
+            next.continuation_token.as_deref(),
+            Some("1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=")
+        );
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        use super::Object;
+        use crate::Owner;
+
+        let mut request = Request::new("bucket");
+        request.prefix = Some("photos/".to_owned());
+        request.max_keys = Some(10);
+        request.fetch_owner = true;
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let request = Request::try_from_http_request(
+            http_request,
+            &["bucket".to_owned()],
+        )
+        .unwrap();
+        assert_eq!(request.prefix.as_deref(), Some("photos/"));
+        assert_eq!(request.max_keys, Some(10));
+        assert!(request.fetch_owner);
+
+        let object = Object {
+            key: "photos/a & b.jpg".to_owned(),
+            last_modified: Some(
+                "Mon, 12 Oct 2009 17:50:30 GMT".parse().unwrap(),
+            ),
+            etag: Some("\"etag\"".to_owned()),
+            size: 42,
+            storage_class: Some("STANDARD".to_owned()),
+            owner: Some(Owner {
+                id: Some("id".to_owned()),
+                display_name: None,
+            }),
+        };
+        let response = Response {
+            contents: vec![object.clone()],
+            common_prefixes: vec!["photos/2009/".to_owned()],
+            is_truncated: true,
+            next_continuation_token: Some("token".to_owned()),
+            key_count: 2,
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.contents, [object]);
+        assert_eq!(response.common_prefixes, ["photos/2009/"]);
+        assert!(response.is_truncated);
+        assert_eq!(response.next_continuation_token.as_deref(), Some("token"));
+        assert_eq!(response.key_count, 2);
+    }
+}
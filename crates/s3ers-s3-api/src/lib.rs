@@ -0,0 +1,250 @@
+//! Types for the endpoints of the [Amazon S3 API].
+//!
+//! Each endpoint has its own module with a `Request` type, implementing
+//! [`OutgoingRequest`](s3ers_api::OutgoingRequest), and a `Response` type,
+//! implementing [`IncomingResponse`](s3ers_api::IncomingResponse).
+//!
+//! [Amazon S3 API]: https://docs.aws.amazon.com/AmazonS3/latest/API/Welcome.html
+
+#![warn(missing_docs)]
+
+use std::str::FromStr;
+
+use http::header::{HeaderName, HeaderValue};
+use s3ers_api::{
+    error::{DeserializationError, FromHttpResponseError, S3Error},
+    uri::{object_url, Query},
+    xml::Element,
+};
+
+pub mod bucket;
+pub mod multipart;
+pub mod object;
+mod types;
+
+pub use types::{Owner, WriteCondition};
+
+/// Fails with the error returned by the server if a response isn't
+/// successful.
+pub(crate) fn check_status<T: AsRef<[u8]>>(
+    response: &http::Response<T>,
+) -> Result<(), FromHttpResponseError> {
+    if !response.status().is_success() {
+        return Err(S3Error::from_http_response(response).into());
+    }
+    Ok(())
+}
+
+/// Reads the XML body of a successful response.
+pub(crate) fn xml_body<T: AsRef<[u8]>>(
+    response: &http::Response<T>,
+) -> Result<Element, FromHttpResponseError> {
+    check_status(response)?;
+    Ok(Element::parse(response.body().as_ref())?)
+}
+
+/// Adds headers to a request, if there are any.
+pub(crate) fn with_headers(
+    mut request: http::request::Builder,
+    headers: Option<impl IntoIterator<Item = (HeaderName, HeaderValue)>>,
+) -> http::request::Builder {
+    for (name, value) in headers.into_iter().flatten() {
+        request = request.header(name, value);
+    }
+    request
+}
+
+/// Adds the `x-amz-request-payer` header to a request if its requester
+/// agrees to pay for it.
+pub(crate) fn request_payer(
+    request: http::request::Builder,
+    request_payer: bool,
+) -> http::request::Builder {
+    if request_payer {
+        request.header("x-amz-request-payer", "requester")
+    } else {
+        request
+    }
+}
+
+/// Returns the value of the `x-amz-copy-source` header of a copy, like
+/// `/bucket/key?versionId=id`.
+pub(crate) fn copy_source(
+    bucket: &str,
+    key: &str,
+    version_id: Option<String>,
+) -> String {
+    Query::new()
+        .param_opt("versionId", version_id)
+        .append_to(object_url("", bucket, key))
+}
+
+/// Returns the value of a header of a response, if it has one.
+pub(crate) fn header_string<T>(
+    response: &http::Response<T>,
+    name: &str,
+) -> Result<Option<String>, DeserializationError> {
+    response
+        .headers()
+        .get(name)
+        .map(|value| Ok(value.to_str()?.to_owned()))
+        .transpose()
+}
+
+/// Parses the value of a header of a response, if it has one.
+pub(crate) fn parse_header<T: FromStr, B>(
+    response: &http::Response<B>,
+    name: &str,
+) -> Result<Option<T>, DeserializationError> {
+    header_string(response, name)?
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| DeserializationError::Invalid(name.to_owned()))
+        })
+        .transpose()
+}
+
+/// Returns the argument of the path of a request at `index`, named `name`.
+#[cfg(feature = "server")]
+pub(crate) fn path_arg(
+    path_args: &[String],
+    index: usize,
+    name: &str,
+) -> Result<String, DeserializationError> {
+    path_args
+        .get(index)
+        .cloned()
+        .ok_or_else(|| DeserializationError::Missing(name.to_owned()))
+}
+
+/// Returns the value of a query parameter of a request, if it has one.
+#[cfg(feature = "server")]
+pub(crate) fn query_param<T>(
+    request: &http::Request<T>,
+    name: &str,
+) -> Option<String> {
+    s3ers_api::uri::parse_query(request.uri().query().unwrap_or_default())
+        .into_iter()
+        .find(|(param, _)| param == name)
+        .map(|(_, value)| value)
+}
+
+/// Parses the value of a query parameter of a request, if it has one.
+#[cfg(feature = "server")]
+pub(crate) fn parse_query_param<T: FromStr, B>(
+    request: &http::Request<B>,
+    name: &str,
+) -> Result<Option<T>, DeserializationError> {
+    query_param(request, name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| DeserializationError::Invalid(name.to_owned()))
+        })
+        .transpose()
+}
+
+/// Returns the value of a query parameter a request must have.
+#[cfg(feature = "server")]
+pub(crate) fn required_query_param<T: FromStr, B>(
+    request: &http::Request<B>,
+    name: &str,
+) -> Result<T, DeserializationError> {
+    parse_query_param(request, name)?
+        .ok_or_else(|| DeserializationError::Missing(name.to_owned()))
+}
+
+/// Returns the value of a header of a request, if it has one.
+#[cfg(feature = "server")]
+pub(crate) fn request_header<T>(
+    request: &http::Request<T>,
+    name: &str,
+) -> Result<Option<String>, DeserializationError> {
+    request
+        .headers()
+        .get(name)
+        .map(|value| Ok(value.to_str()?.to_owned()))
+        .transpose()
+}
+
+/// Parses the value of the `x-amz-copy-source` header of a copy, like
+/// `/bucket/key?versionId=id`, into the bucket, the key and the version of
+/// its source.
+#[cfg(feature = "server")]
+pub(crate) fn parse_copy_source<T>(
+    request: &http::Request<T>,
+) -> Result<(String, String, Option<String>), DeserializationError> {
+    let name = "x-amz-copy-source";
+    let copy_source = request_header(request, name)?
+        .ok_or_else(|| DeserializationError::Missing(name.to_owned()))?;
+    let (path, query) = match copy_source.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (copy_source.as_str(), None),
+    };
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let (bucket, key) = path
+        .split_once('/')
+        .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+        .ok_or_else(|| DeserializationError::Invalid(name.to_owned()))?;
+    let version_id = s3ers_api::uri::parse_query(query.unwrap_or_default())
+        .into_iter()
+        .find(|(param, _)| param == "versionId")
+        .map(|(_, value)| value);
+    Ok((
+        s3ers_api::uri::decode(bucket),
+        s3ers_api::uri::decode(key),
+        version_id,
+    ))
+}
+
+/// Whether the requester of a request agrees to pay for it.
+#[cfg(feature = "server")]
+pub(crate) fn requester_pays<T>(request: &http::Request<T>) -> bool {
+    request
+        .headers()
+        .get("x-amz-request-payer")
+        .is_some_and(|value| value == "requester")
+}
+
+/// Returns a response with an XML document as its body.
+#[cfg(feature = "server")]
+pub(crate) fn xml_response<T: Default + bytes::BufMut>(
+    response: http::response::Builder,
+    document: Element,
+) -> Result<http::Response<T>, s3ers_api::error::IntoHttpError> {
+    let mut body = T::default();
+    body.put_slice(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    body.put_slice(document.to_xml().as_bytes());
+    Ok(response
+        .header(http::header::CONTENT_TYPE, "application/xml")
+        .body(body)?)
+}
+
+/// Adds a child with the given text to an XML element if it has a value.
+#[cfg(feature = "server")]
+pub(crate) fn with_text_opt(
+    element: Element,
+    name: &str,
+    text: Option<impl ToString>,
+) -> Element {
+    match text {
+        Some(text) => {
+            element.with_child(Element::with_text(name, text.to_string()))
+        }
+        None => element,
+    }
+}
+
+/// Adds a header to a response if it has a value.
+#[cfg(feature = "server")]
+pub(crate) fn header_opt(
+    response: http::response::Builder,
+    name: &str,
+    value: Option<impl ToString>,
+) -> http::response::Builder {
+    match value {
+        Some(value) => response.header(name, value.to_string()),
+        None => response,
+    }
+}
@@ -0,0 +1,123 @@
+//! [DELETE /{bucket}/{key}?uploadId](https://docs.aws.amazon.com/AmazonS3/latest/API/API_AbortMultipartUpload.html)
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError},
+    uri::{object_url, Query},
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Scope,
+};
+
+const METADATA: Metadata = Metadata {
+    description: "Aborts a multipart upload, deleting its parts.",
+    method: Method::DELETE,
+    name: "AbortMultipartUpload",
+    path: "/:bucket/*key",
+    scope: Scope::Object,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `AbortMultipartUpload` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket of the upload.
+    pub bucket: String,
+
+    /// The key of the object being uploaded.
+    pub key: String,
+
+    /// The ID of the upload.
+    pub upload_id: String,
+
+    /// Whether the requester agrees to pay for the request, which buckets
+    /// with requester pays enabled require.
+    pub request_payer: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` aborting the given upload.
+    pub fn new(
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        upload_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            upload_id: upload_id.into(),
+            request_payer: false,
+        }
+    }
+}
+
+/// Response type for the `AbortMultipartUpload` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let url = object_url(base_url, &self.bucket, &self.key);
+        let query = Query::new().param("uploadId", self.upload_id);
+
+        let request = http::Request::builder()
+            .method(METADATA.method)
+            .uri(query.append_to(url));
+        Ok(crate::request_payer(request, self.request_payer)
+            .body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        crate::check_status(&response)?;
+        Ok(Self {})
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            key: crate::path_arg(path_args, 1, "key")?,
+            upload_id: crate::required_query_param(&request, "uploadId")?,
+            request_payer: crate::requester_pays(&request),
+        })
+    }
+}
+
+/// The response is `204 No Content`.
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        Ok(http::Response::builder()
+            .status(http::StatusCode::NO_CONTENT)
+            .body(T::default())?)
+    }
+}
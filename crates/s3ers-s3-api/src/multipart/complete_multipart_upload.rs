@@ -0,0 +1,335 @@
+//! [POST /{bucket}/{key}?uploadId](https://docs.aws.amazon.com/AmazonS3/latest/API/API_CompleteMultipartUpload.html)
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::{DeserializationError, FromHttpRequestError},
+    IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError, S3Error},
+    uri::{object_url, Query},
+    xml::Element,
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Scope,
+};
+
+use crate::WriteCondition;
+
+const METADATA: Metadata = Metadata {
+    description: "Completes a multipart upload by assembling its parts.",
+    method: Method::POST,
+    name: "CompleteMultipartUpload",
+    path: "/:bucket/*key",
+    scope: Scope::Object,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `CompleteMultipartUpload` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket of the upload.
+    pub bucket: String,
+
+    /// The key of the object being uploaded.
+    pub key: String,
+
+    /// The ID of the upload.
+    pub upload_id: String,
+
+    /// The parts making up the object, in ascending order of their
+    /// numbers.
+    pub parts: Vec<CompletedPart>,
+
+    /// The condition the object must meet to be written, if any.
+    pub condition: Option<WriteCondition>,
+
+    /// Whether the requester agrees to pay for the request, which buckets
+    /// with requester pays enabled require.
+    pub request_payer: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` completing the given upload.
+    pub fn new(
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        upload_id: impl Into<String>,
+        parts: Vec<CompletedPart>,
+    ) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            upload_id: upload_id.into(),
+            parts,
+            condition: None,
+            request_payer: false,
+        }
+    }
+}
+
+/// An uploaded part of an object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CompletedPart {
+    /// The number of the part.
+    pub part_number: u32,
+
+    /// The entity tag returned when uploading the part.
+    pub etag: String,
+}
+
+impl CompletedPart {
+    /// Creates a new `CompletedPart`.
+    pub fn new(part_number: u32, etag: impl Into<String>) -> Self {
+        Self {
+            part_number,
+            etag: etag.into(),
+        }
+    }
+}
+
+/// Response type for the `CompleteMultipartUpload` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The URL of the object.
+    pub location: Option<String>,
+
+    /// The entity tag of the object, with its quotes.
+    pub etag: Option<String>,
+
+    /// The ID of the version of the object, if the bucket is versioned.
+    pub version_id: Option<String>,
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let url = object_url(base_url, &self.bucket, &self.key);
+        let query = Query::new().param("uploadId", self.upload_id);
+
+        let document = self.parts.into_iter().fold(
+            Element::new("CompleteMultipartUpload"),
+            |document, part| {
+                document.with_child(
+                    Element::new("Part")
+                        .with_child(Element::with_text(
+                            "PartNumber",
+                            part.part_number.to_string(),
+                        ))
+                        .with_child(Element::with_text("ETag", part.etag)),
+                )
+            },
+        );
+        let mut body = T::default();
+        body.put_slice(document.to_xml().as_bytes());
+
+        let mut request = http::Request::builder()
+            .method(METADATA.method)
+            .uri(query.append_to(url));
+        if let Some(condition) = self.condition {
+            request = condition.apply(request);
+        }
+        request = crate::request_payer(request, self.request_payer);
+        Ok(request.body(body)?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        let body = crate::xml_body(&response)?;
+        // The server may fail to assemble the object after it sent the
+        // status of the response, and then returns an error document.
+        if body.name == "Error" {
+            return Err(S3Error::from_http_response(&response).into());
+        }
+
+        Ok(Self {
+            location: body.child_string("Location"),
+            etag: body.child_string("ETag"),
+            version_id: crate::header_string(&response, "x-amz-version-id")?,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl CompletedPart {
+    fn from_xml(element: &Element) -> Result<Self, DeserializationError> {
+        Ok(Self {
+            part_number: element.parse_child("PartNumber")?.ok_or_else(
+                || DeserializationError::Missing("PartNumber".to_owned()),
+            )?,
+            etag: element.required_text("ETag")?.to_owned(),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        let document = Element::parse(request.body().as_ref())?;
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            key: crate::path_arg(path_args, 1, "key")?,
+            upload_id: crate::required_query_param(&request, "uploadId")?,
+            parts: document
+                .children("Part")
+                .map(CompletedPart::from_xml)
+                .collect::<Result<_, _>>()?,
+            condition: WriteCondition::from_request(&request)?,
+            request_payer: crate::requester_pays(&request),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let response = crate::header_opt(
+            http::Response::builder(),
+            "x-amz-version-id",
+            self.version_id,
+        );
+        let document = Element::new("CompleteMultipartUploadResult");
+        let document =
+            crate::with_text_opt(document, "Location", self.location);
+        let document = crate::with_text_opt(document, "ETag", self.etag);
+        crate::xml_response(response, document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use s3ers_api::{
+        error::FromHttpResponseError, IncomingResponse, OutgoingRequest,
+    };
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, OutgoingResponse};
+
+    use super::{CompletedPart, Request, Response};
+
+    #[test]
+    fn request_body() {
+        let parts = vec![
+            CompletedPart::new(1, "\"a54357aff0632cce46d942af68356b38\""),
+            CompletedPart::new(2, "\"0c78aef83f66abc1fa1e8477f296d394\""),
+        ];
+        let http_request = Request::new("bucket", "object", "id", parts)
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+
+        assert_eq!(
+            http_request.uri(),
+            "https://s3.amazonaws.com/bucket/object?uploadId=id"
+        );
+        assert_eq!(
+            std::str::from_utf8(http_request.body()).unwrap(),
+            "<CompleteMultipartUpload>\
+             <Part><PartNumber>1</PartNumber>\
+             <ETag>&quot;a54357aff0632cce46d942af68356b38&quot;</ETag></Part>\
+             <Part><PartNumber>2</PartNumber>\
+             <ETag>&quot;0c78aef83f66abc1fa1e8477f296d394&quot;</ETag></Part>\
+             </CompleteMultipartUpload>"
+        );
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::new(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<CompleteMultipartUploadResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Location>http://Example-Bucket.s3.amazonaws.com/Example-Object</Location>
+  <Bucket>Example-Bucket</Bucket>
+  <Key>Example-Object</Key>
+  <ETag>"3858f62230ac3c915f300c664312c11f-9"</ETag>
+</CompleteMultipartUploadResult>"#,
+        );
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(
+            response.etag.as_deref(),
+            Some("\"3858f62230ac3c915f300c664312c11f-9\"")
+        );
+    }
+
+    #[test]
+    fn parse_error() {
+        let response = http::Response::new(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<Error>
+  <Code>InternalError</Code>
+  <Message>We encountered an internal error. Please try again.</Message>
+</Error>"#,
+        );
+
+        match Response::try_from_http_response(response).unwrap_err() {
+            FromHttpResponseError::Server(error) => {
+                assert_eq!(error.code, "InternalError")
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        use crate::WriteCondition;
+
+        let parts = vec![
+            CompletedPart::new(1, "\"a\""),
+            CompletedPart::new(2, "\"b\""),
+        ];
+        let mut request =
+            Request::new("bucket", "key", "upload", parts.clone());
+        request.condition = Some(WriteCondition::Matches("\"c\"".to_owned()));
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let path_args = ["bucket".to_owned(), "key".to_owned()];
+        let request =
+            Request::try_from_http_request(http_request, &path_args).unwrap();
+        assert_eq!(request.upload_id, "upload");
+        assert_eq!(request.parts, parts);
+        assert_eq!(
+            request.condition,
+            Some(WriteCondition::Matches("\"c\"".to_owned()))
+        );
+
+        let response = Response {
+            location: Some("https://bucket.s3.amazonaws.com/key".to_owned()),
+            etag: Some("\"etag-2\"".to_owned()),
+            version_id: Some("id".to_owned()),
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            response.location.as_deref(),
+            Some("https://bucket.s3.amazonaws.com/key")
+        );
+        assert_eq!(response.etag.as_deref(), Some("\"etag-2\""));
+        assert_eq!(response.version_id.as_deref(), Some("id"));
+    }
+}
@@ -0,0 +1,230 @@
+//! [POST /{bucket}/{key}?uploads](https://docs.aws.amazon.com/AmazonS3/latest/API/API_CreateMultipartUpload.html)
+
+use bytes::BufMut;
+use http::{header::CONTENT_TYPE, Method};
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, xml::Element, IncomingRequest,
+    OutgoingResponse,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError},
+    header::SseKms,
+    uri::{object_url, Query},
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Scope,
+};
+
+const METADATA: Metadata = Metadata {
+    description: "Starts a multipart upload.",
+    method: Method::POST,
+    name: "CreateMultipartUpload",
+    path: "/:bucket/*key?uploads",
+    scope: Scope::Object,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `CreateMultipartUpload` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket to add the object to.
+    pub bucket: String,
+
+    /// The key of the object.
+    pub key: String,
+
+    /// The media type of the object.
+    pub content_type: Option<String>,
+
+    /// How the object is encrypted with a KMS key, if it is.
+    pub sse_kms: Option<SseKms>,
+
+    /// Whether the requester agrees to pay for the request, which buckets
+    /// with requester pays enabled require.
+    pub request_payer: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` starting the upload of the given object.
+    pub fn new(bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            content_type: None,
+            sse_kms: None,
+            request_payer: false,
+        }
+    }
+}
+
+/// Response type for the `CreateMultipartUpload` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The ID of the upload.
+    pub upload_id: String,
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let url = object_url(base_url, &self.bucket, &self.key);
+
+        let mut request = http::Request::builder()
+            .method(METADATA.method)
+            .uri(Query::for_endpoint(&METADATA).append_to(url));
+        if let Some(content_type) = self.content_type {
+            request = request.header(CONTENT_TYPE, content_type);
+        }
+        let sse_kms = self.sse_kms.map(|sse_kms| sse_kms.headers());
+        request = crate::with_headers(request, sse_kms.transpose()?);
+        request = crate::request_payer(request, self.request_payer);
+        Ok(request.body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        let body = crate::xml_body(&response)?;
+
+        Ok(Self {
+            upload_id: body.required_text("UploadId")?.to_owned(),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            key: crate::path_arg(path_args, 1, "key")?,
+            content_type: crate::request_header(
+                &request,
+                CONTENT_TYPE.as_str(),
+            )?,
+            sse_kms: SseKms::from_headers(request.headers())?,
+            request_payer: crate::requester_pays(&request),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let document = Element::new("InitiateMultipartUploadResult")
+            .with_child(Element::with_text("UploadId", self.upload_id));
+        crate::xml_response(http::Response::builder(), document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use s3ers_api::{header::SseKms, IncomingResponse, OutgoingRequest};
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, OutgoingResponse};
+
+    use super::{Request, Response};
+
+    #[test]
+    fn request_uri() {
+        let http_request = Request::new("example-bucket", "example-object")
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(
+            http_request.uri(),
+            "https://s3.amazonaws.com/example-bucket/example-object?uploads"
+        );
+    }
+
+    #[test]
+    fn encrypt_with_kms() {
+        let mut request = Request::new("bucket", "key");
+        request.sse_kms = Some(
+            SseKms::new()
+                .with_key_id("alias/key")
+                .with_context("a", "b")
+                .with_bucket_key_enabled(true),
+        );
+
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        let headers = http_request.headers();
+        assert_eq!(headers["x-amz-server-side-encryption"], "aws:kms");
+        assert_eq!(
+            headers["x-amz-server-side-encryption-aws-kms-key-id"],
+            "alias/key"
+        );
+        assert_eq!(
+            headers["x-amz-server-side-encryption-context"],
+            "eyJhIjoiYiJ9"
+        );
+        assert_eq!(
+            headers["x-amz-server-side-encryption-bucket-key-enabled"],
+            "true"
+        );
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::new(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<InitiateMultipartUploadResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Bucket>example-bucket</Bucket>
+  <Key>example-object</Key>
+  <UploadId>VXBsb2FkIElEIGZvciA2aWWpbmcncyBteS1tb3ZpZS5tMnRzIHVwbG9hZA</UploadId>
+</InitiateMultipartUploadResult>"#,
+        );
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(
+            response.upload_id,
+            "VXBsb2FkIElEIGZvciA2aWWpbmcncyBteS1tb3ZpZS5tMnRzIHVwbG9hZA"
+        );
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        let mut request = Request::new("bucket", "key");
+        request.content_type = Some("text/plain".to_owned());
+        request.sse_kms = Some(SseKms::new().with_key_id("key-id"));
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let path_args = ["bucket".to_owned(), "key".to_owned()];
+        let request =
+            Request::try_from_http_request(http_request, &path_args).unwrap();
+        assert_eq!(request.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(request.sse_kms, Some(SseKms::new().with_key_id("key-id")));
+
+        let response = Response {
+            upload_id: "upload".to_owned(),
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.upload_id, "upload");
+    }
+}
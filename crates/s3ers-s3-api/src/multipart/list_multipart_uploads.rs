@@ -0,0 +1,362 @@
+//! [GET /{bucket}?uploads](https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListMultipartUploads.html)
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{DeserializationError, FromHttpResponseError, IntoHttpError},
+    header::HttpDate,
+    uri::{bucket_url, Query},
+    xml::Element,
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Paginated, Scope,
+};
+
+use crate::Owner;
+
+const METADATA: Metadata = Metadata {
+    description: "Lists the multipart uploads in progress in a bucket.",
+    method: Method::GET,
+    name: "ListMultipartUploads",
+    path: "/:bucket?uploads",
+    scope: Scope::Bucket,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `ListMultipartUploads` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket to list.
+    pub bucket: String,
+
+    /// Only list the uploads of keys starting with this prefix.
+    pub prefix: Option<String>,
+
+    /// Group the keys containing this delimiter after the prefix into
+    /// common prefixes.
+    pub delimiter: Option<String>,
+
+    /// Start listing after this key, from the previous response.
+    pub key_marker: Option<String>,
+
+    /// Start listing after this upload of the key marker, from the previous
+    /// response.
+    pub upload_id_marker: Option<String>,
+
+    /// The maximum number of uploads to return, at most 1000.
+    pub max_uploads: Option<u32>,
+}
+
+impl Request {
+    /// Creates a new `Request` listing the uploads of the given bucket.
+    pub fn new(bucket: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            prefix: None,
+            delimiter: None,
+            key_marker: None,
+            upload_id_marker: None,
+            max_uploads: None,
+        }
+    }
+}
+
+/// Response type for the `ListMultipartUploads` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The uploads of this page.
+    pub uploads: Vec<Upload>,
+
+    /// The common prefixes of the keys, if a delimiter was given.
+    pub common_prefixes: Vec<String>,
+
+    /// Whether there are more uploads to list.
+    pub is_truncated: bool,
+
+    /// The key marker of the next page, if there is one.
+    pub next_key_marker: Option<String>,
+
+    /// The upload ID marker of the next page, if there is one.
+    pub next_upload_id_marker: Option<String>,
+}
+
+/// A multipart upload in progress.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Upload {
+    /// The key of the object being uploaded.
+    pub key: String,
+
+    /// The ID of the upload.
+    pub upload_id: String,
+
+    /// When the upload was started.
+    pub initiated: Option<HttpDate>,
+
+    /// The storage class of the object being uploaded.
+    pub storage_class: Option<String>,
+
+    /// The owner of the object being uploaded.
+    pub owner: Option<Owner>,
+}
+
+impl Upload {
+    /// Creates a new `Upload` of the given key.
+    pub fn new(key: impl Into<String>, upload_id: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            upload_id: upload_id.into(),
+            initiated: None,
+            storage_class: None,
+            owner: None,
+        }
+    }
+
+    fn from_xml(element: &Element) -> Result<Self, DeserializationError> {
+        Ok(Self {
+            key: element.required_text("Key")?.to_owned(),
+            upload_id: element.required_text("UploadId")?.to_owned(),
+            initiated: element.parse_child("Initiated")?,
+            storage_class: element.child_string("StorageClass"),
+            owner: element.child("Owner").map(Owner::from_xml),
+        })
+    }
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let query = Query::for_endpoint(&METADATA)
+            .param_opt("prefix", self.prefix)
+            .param_opt("delimiter", self.delimiter)
+            .param_opt("key-marker", self.key_marker)
+            .param_opt("upload-id-marker", self.upload_id_marker)
+            .param_opt("max-uploads", self.max_uploads);
+
+        Ok(http::Request::builder()
+            .method(METADATA.method)
+            .uri(query.append_to(bucket_url(base_url, &self.bucket)))
+            .body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        let body = crate::xml_body(&response)?;
+
+        Ok(Self {
+            uploads: body
+                .children("Upload")
+                .map(Upload::from_xml)
+                .collect::<Result<_, _>>()?,
+            common_prefixes: body
+                .children("CommonPrefixes")
+                .filter_map(|prefix| prefix.child_string("Prefix"))
+                .collect(),
+            is_truncated: body.parse_child("IsTruncated")?.unwrap_or_default(),
+            next_key_marker: body.child_string("NextKeyMarker"),
+            next_upload_id_marker: body.child_string("NextUploadIdMarker"),
+        })
+    }
+}
+
+impl Paginated for Request {
+    type Item = Upload;
+
+    fn next_page(mut self, response: &Response) -> Option<Self> {
+        if !response.is_truncated {
+            return None;
+        }
+        self.key_marker = Some(response.next_key_marker.clone()?);
+        self.upload_id_marker = response.next_upload_id_marker.clone();
+        Some(self)
+    }
+
+    fn into_items(response: Response) -> Vec<Upload> {
+        response.uploads
+    }
+}
+
+#[cfg(feature = "server")]
+impl Upload {
+    fn to_xml(&self) -> Element {
+        let element = Element::new("Upload")
+            .with_child(Element::with_text("Key", self.key.as_str()))
+            .with_child(Element::with_text(
+                "UploadId",
+                self.upload_id.as_str(),
+            ));
+        let element = crate::with_text_opt(
+            element,
+            "Initiated",
+            self.initiated.map(|date| date.to_iso8601()),
+        );
+        let element = crate::with_text_opt(
+            element,
+            "StorageClass",
+            self.storage_class.as_ref(),
+        );
+        match &self.owner {
+            Some(owner) => element.with_child(owner.to_xml()),
+            None => element,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            prefix: crate::query_param(&request, "prefix"),
+            delimiter: crate::query_param(&request, "delimiter"),
+            key_marker: crate::query_param(&request, "key-marker"),
+            upload_id_marker: crate::query_param(&request, "upload-id-marker"),
+            max_uploads: crate::parse_query_param(&request, "max-uploads")?,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let mut document = Element::new("ListMultipartUploadsResult");
+        for upload in &self.uploads {
+            document = document.with_child(upload.to_xml());
+        }
+        for prefix in self.common_prefixes {
+            document = document.with_child(
+                Element::new("CommonPrefixes")
+                    .with_child(Element::with_text("Prefix", prefix)),
+            );
+        }
+        document = document.with_child(Element::with_text(
+            "IsTruncated",
+            self.is_truncated.to_string(),
+        ));
+        document = crate::with_text_opt(
+            document,
+            "NextKeyMarker",
+            self.next_key_marker,
+        );
+        document = crate::with_text_opt(
+            document,
+            "NextUploadIdMarker",
+            self.next_upload_id_marker,
+        );
+        crate::xml_response(http::Response::builder(), document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use s3ers_api::{IncomingResponse, Paginated};
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, OutgoingRequest, OutgoingResponse};
+
+    use super::{Request, Response};
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::new(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListMultipartUploadsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Bucket>bucket</Bucket>
+  <KeyMarker></KeyMarker>
+  <UploadIdMarker></UploadIdMarker>
+  <NextKeyMarker>my-movie.m2ts</NextKeyMarker>
+  <NextUploadIdMarker>YW55IGlkZWEgd2h5IGVsdmluZydzIHVwbG9hZCBmYWlsZWQ</NextUploadIdMarker>
+  <MaxUploads>3</MaxUploads>
+  <IsTruncated>true</IsTruncated>
+  <Upload>
+    <Key>my-divisor</Key>
+    <UploadId>XMgbGlrZSBlbHZpbmcncyBub3QgaGF2aW5nIG11Y2ggbHVjaw</UploadId>
+    <StorageClass>REDUCED_REDUNDANCY</StorageClass>
+    <Initiated>2010-11-10T20:48:33.000Z</Initiated>
+  </Upload>
+  <Upload>
+    <Key>my-movie.m2ts</Key>
+    <UploadId>YW55IGlkZWEgd2h5IGVsdmluZydzIHVwbG9hZCBmYWlsZWQ</UploadId>
+    <StorageClass>STANDARD</StorageClass>
+    <Initiated>2010-11-10T20:48:33.000Z</Initiated>
+  </Upload>
+</ListMultipartUploadsResult>"#,
+        );
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(response.uploads.len(), 2);
+        assert_eq!(response.uploads[0].key, "my-divisor");
+
+        let next = Request::new("bucket").next_page(&response).unwrap();
+        assert_eq!(next.key_marker.as_deref(), Some("my-movie.m2ts"));
+        assert_eq!(
+            next.upload_id_marker.as_deref(),
+            Some("YW55IGlkZWEgd2h5IGVsdmluZydzIHVwbG9hZCBmYWlsZWQ")
+        );
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        use super::Upload;
+
+        let mut request = Request::new("bucket");
+        request.key_marker = Some("key".to_owned());
+        request.max_uploads = Some(10);
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let request = Request::try_from_http_request(
+            http_request,
+            &["bucket".to_owned()],
+        )
+        .unwrap();
+        assert_eq!(request.key_marker.as_deref(), Some("key"));
+        assert_eq!(request.max_uploads, Some(10));
+
+        let upload = Upload {
+            key: "key".to_owned(),
+            upload_id: "upload".to_owned(),
+            initiated: Some("Mon, 12 Oct 2009 17:50:30 GMT".parse().unwrap()),
+            storage_class: None,
+            owner: None,
+        };
+        let response = Response {
+            uploads: vec![upload.clone()],
+            common_prefixes: vec!["dir/".to_owned()],
+            is_truncated: false,
+            next_key_marker: None,
+            next_upload_id_marker: None,
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.uploads, [upload]);
+        assert_eq!(response.common_prefixes, ["dir/"]);
+        assert!(!response.is_truncated);
+    }
+}
@@ -0,0 +1,353 @@
+//! [GET /{bucket}/{key}?uploadId](https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListParts.html)
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{DeserializationError, FromHttpResponseError, IntoHttpError},
+    header::HttpDate,
+    uri::{object_url, Query},
+    xml::Element,
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Paginated, Scope,
+};
+
+const METADATA: Metadata = Metadata {
+    description: "Lists the parts uploaded for a multipart upload.",
+    method: Method::GET,
+    name: "ListParts",
+    path: "/:bucket/*key",
+    scope: Scope::Object,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `ListParts` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket of the upload.
+    pub bucket: String,
+
+    /// The key of the object being uploaded.
+    pub key: String,
+
+    /// The ID of the upload.
+    pub upload_id: String,
+
+    /// Only list the parts after this part number, from the previous
+    /// response.
+    pub part_number_marker: Option<u32>,
+
+    /// The maximum number of parts to return, at most 1000.
+    pub max_parts: Option<u32>,
+
+    /// Whether the requester agrees to pay for the request, which buckets
+    /// with requester pays enabled require.
+    pub request_payer: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` listing the parts of the given upload.
+    pub fn new(
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        upload_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            upload_id: upload_id.into(),
+            part_number_marker: None,
+            max_parts: None,
+            request_payer: false,
+        }
+    }
+}
+
+/// Response type for the `ListParts` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The parts of this page.
+    pub parts: Vec<Part>,
+
+    /// Whether there are more parts to list.
+    pub is_truncated: bool,
+
+    /// The part number marker of the next page, if there is one.
+    pub next_part_number_marker: Option<u32>,
+
+    /// The storage class of the object being uploaded.
+    pub storage_class: Option<String>,
+}
+
+/// An uploaded part.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Part {
+    /// The number of the part.
+    pub part_number: u32,
+
+    /// When the part was uploaded.
+    pub last_modified: Option<HttpDate>,
+
+    /// The entity tag of the part, with its quotes.
+    pub etag: Option<String>,
+
+    /// The size of the part, in bytes.
+    pub size: u64,
+}
+
+impl Part {
+    /// Creates a new `Part` with the given number and size.
+    pub fn new(part_number: u32, size: u64) -> Self {
+        Self {
+            part_number,
+            last_modified: None,
+            etag: None,
+            size,
+        }
+    }
+
+    fn from_xml(element: &Element) -> Result<Self, DeserializationError> {
+        Ok(Self {
+            part_number: element.parse_child("PartNumber")?.ok_or_else(
+                || DeserializationError::Missing("PartNumber".to_owned()),
+            )?,
+            last_modified: element.parse_child("LastModified")?,
+            etag: element.child_string("ETag"),
+            size: element.parse_child("Size")?.unwrap_or_default(),
+        })
+    }
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let query = Query::new()
+            .param("uploadId", self.upload_id)
+            .param_opt("part-number-marker", self.part_number_marker)
+            .param_opt("max-parts", self.max_parts);
+
+        let request = http::Request::builder().method(METADATA.method).uri(
+            query.append_to(object_url(base_url, &self.bucket, &self.key)),
+        );
+        Ok(crate::request_payer(request, self.request_payer)
+            .body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        let body = crate::xml_body(&response)?;
+
+        Ok(Self {
+            parts: body
+                .children("Part")
+                .map(Part::from_xml)
+                .collect::<Result<_, _>>()?,
+            is_truncated: body.parse_child("IsTruncated")?.unwrap_or_default(),
+            next_part_number_marker: body
+                .parse_child("NextPartNumberMarker")?,
+            storage_class: body.child_string("StorageClass"),
+        })
+    }
+}
+
+impl Paginated for Request {
+    type Item = Part;
+
+    fn next_page(mut self, response: &Response) -> Option<Self> {
+        if !response.is_truncated {
+            return None;
+        }
+        self.part_number_marker = Some(response.next_part_number_marker?);
+        Some(self)
+    }
+
+    fn into_items(response: Response) -> Vec<Part> {
+        response.parts
+    }
+}
+
+#[cfg(feature = "server")]
+impl Part {
+    fn to_xml(&self) -> Element {
+        let element = Element::new("Part").with_child(Element::with_text(
+            "PartNumber",
+            self.part_number.to_string(),
+        ));
+        let element = crate::with_text_opt(
+            element,
+            "LastModified",
+            self.last_modified.map(|date| date.to_iso8601()),
+        );
+        crate::with_text_opt(element, "ETag", self.etag.as_ref())
+            .with_child(Element::with_text("Size", self.size.to_string()))
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            key: crate::path_arg(path_args, 1, "key")?,
+            upload_id: crate::required_query_param(&request, "uploadId")?,
+            part_number_marker: crate::parse_query_param(
+                &request,
+                "part-number-marker",
+            )?,
+            max_parts: crate::parse_query_param(&request, "max-parts")?,
+            request_payer: crate::requester_pays(&request),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let mut document = Element::new("ListPartsResult");
+        for part in &self.parts {
+            document = document.with_child(part.to_xml());
+        }
+        document = document.with_child(Element::with_text(
+            "IsTruncated",
+            self.is_truncated.to_string(),
+        ));
+        document = crate::with_text_opt(
+            document,
+            "NextPartNumberMarker",
+            self.next_part_number_marker,
+        );
+        document =
+            crate::with_text_opt(document, "StorageClass", self.storage_class);
+        crate::xml_response(http::Response::builder(), document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use s3ers_api::{IncomingResponse, OutgoingRequest, Paginated};
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, OutgoingResponse};
+
+    use super::{Request, Response};
+
+    #[test]
+    fn request_uri() {
+        let request = Request::new(
+            "example-bucket",
+            "example-object",
+            "XXBsb2FkIElEIGZvciBlbHZpbmcncyVcdS1tb3ZpZS5tMnRzEEEwbG9hZA",
+        );
+
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(
+            http_request.uri(),
+            "https://s3.amazonaws.com/example-bucket/example-object\
+             ?uploadId=XXBsb2FkIElEIGZvciBlbHZpbmcncyVcdS1tb3ZpZS5tMnRzEEEwbG9hZA"
+        );
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::new(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListPartsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Bucket>example-bucket</Bucket>
+  <Key>example-object</Key>
+  <UploadId>XXBsb2FkIElEIGZvciBlbHZpbmcncyVcdS1tb3ZpZS5tMnRzEEEwbG9hZA</UploadId>
+  <StorageClass>STANDARD</StorageClass>
+  <PartNumberMarker>1</PartNumberMarker>
+  <NextPartNumberMarker>3</NextPartNumberMarker>
+  <MaxParts>2</MaxParts>
+  <IsTruncated>true</IsTruncated>
+  <Part>
+    <PartNumber>2</PartNumber>
+    <LastModified>2010-11-10T20:48:34.000Z</LastModified>
+    <ETag>"7778aef83f66abc1fa1e8477f296d394"</ETag>
+    <Size>10485760</Size>
+  </Part>
+  <Part>
+    <PartNumber>3</PartNumber>
+    <LastModified>2010-11-10T20:48:33.000Z</LastModified>
+    <ETag>"aaaa18db4cc2f85cedef654fccc4a4x8"</ETag>
+    <Size>10485760</Size>
+  </Part>
+</ListPartsResult>"#,
+        );
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(response.parts.len(), 2);
+        assert_eq!(response.parts[1].part_number, 3);
+        assert_eq!(response.parts[1].size, 10_485_760);
+
+        let next = Request::new("example-bucket", "example-object", "id")
+            .next_page(&response)
+            .unwrap();
+        assert_eq!(next.part_number_marker, Some(3));
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        use super::Part;
+
+        let mut request = Request::new("bucket", "key", "upload");
+        request.part_number_marker = Some(2);
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let path_args = ["bucket".to_owned(), "key".to_owned()];
+        let request =
+            Request::try_from_http_request(http_request, &path_args).unwrap();
+        assert_eq!(request.upload_id, "upload");
+        assert_eq!(request.part_number_marker, Some(2));
+
+        let part = Part {
+            part_number: 3,
+            last_modified: Some(
+                "Mon, 12 Oct 2009 17:50:30 GMT".parse().unwrap(),
+            ),
+            etag: Some("\"etag\"".to_owned()),
+            size: 5_242_880,
+        };
+        let response = Response {
+            parts: vec![part.clone()],
+            is_truncated: true,
+            next_part_number_marker: Some(3),
+            storage_class: Some("STANDARD".to_owned()),
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.parts, [part]);
+        assert!(response.is_truncated);
+        assert_eq!(response.next_part_number_marker, Some(3));
+    }
+}
@@ -0,0 +1,190 @@
+//! [PUT /{bucket}/{key}?partNumber&uploadId](https://docs.aws.amazon.com/AmazonS3/latest/API/API_UploadPart.html)
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError},
+    uri::{object_url, Query},
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Scope,
+};
+
+const METADATA: Metadata = Metadata {
+    description: "Uploads a part of a multipart upload.",
+    method: Method::PUT,
+    name: "UploadPart",
+    path: "/:bucket/*key",
+    scope: Scope::Object,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `UploadPart` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket of the upload.
+    pub bucket: String,
+
+    /// The key of the object being uploaded.
+    pub key: String,
+
+    /// The ID of the upload.
+    pub upload_id: String,
+
+    /// The number of the part, from 1 to 10000.
+    pub part_number: u32,
+
+    /// The content of the part.
+    pub body: Vec<u8>,
+
+    /// Whether the requester agrees to pay for the request, which buckets
+    /// with requester pays enabled require.
+    pub request_payer: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` uploading a part.
+    pub fn new(
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        upload_id: impl Into<String>,
+        part_number: u32,
+        body: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            upload_id: upload_id.into(),
+            part_number,
+            body: body.into(),
+            request_payer: false,
+        }
+    }
+}
+
+/// Response type for the `UploadPart` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The entity tag of the part, with its quotes.
+    pub etag: Option<String>,
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let url = object_url(base_url, &self.bucket, &self.key);
+        let query = Query::new()
+            .param("partNumber", self.part_number)
+            .param("uploadId", self.upload_id);
+
+        let mut body = T::default();
+        body.put_slice(&self.body);
+        let request = http::Request::builder()
+            .method(METADATA.method)
+            .uri(query.append_to(url));
+        Ok(crate::request_payer(request, self.request_payer).body(body)?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        crate::check_status(&response)?;
+
+        Ok(Self {
+            etag: crate::header_string(&response, "etag")?,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            key: crate::path_arg(path_args, 1, "key")?,
+            upload_id: crate::required_query_param(&request, "uploadId")?,
+            part_number: crate::required_query_param(&request, "partNumber")?,
+            body: request.body().as_ref().to_vec(),
+            request_payer: crate::requester_pays(&request),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let response =
+            crate::header_opt(http::Response::builder(), "etag", self.etag);
+        Ok(response.body(T::default())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use s3ers_api::OutgoingRequest;
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, IncomingResponse, OutgoingResponse};
+
+    use super::Request;
+
+    #[test]
+    fn request_uri() {
+        let http_request = Request::new("bucket", "object", "id", 3, "part")
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(
+            http_request.uri(),
+            "https://s3.amazonaws.com/bucket/object?partNumber=3&uploadId=id"
+        );
+        assert_eq!(http_request.body(), b"part");
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        use super::Response;
+
+        let request = Request::new("bucket", "key", "upload", 2, "part");
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let path_args = ["bucket".to_owned(), "key".to_owned()];
+        let request =
+            Request::try_from_http_request(http_request, &path_args).unwrap();
+        assert_eq!(request.upload_id, "upload");
+        assert_eq!(request.part_number, 2);
+        assert_eq!(request.body, b"part");
+
+        let response = Response {
+            etag: Some("\"etag\"".to_owned()),
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.etag.as_deref(), Some("\"etag\""));
+    }
+}
@@ -0,0 +1,273 @@
+//! [PUT /{bucket}/{key}?partNumber&uploadId](https://docs.aws.amazon.com/AmazonS3/latest/API/API_UploadPartCopy.html)
+//! with `x-amz-copy-source`
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, xml::Element, IncomingRequest,
+    OutgoingResponse,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError, S3Error},
+    uri::{object_url, Query},
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Scope,
+};
+
+const METADATA: Metadata = Metadata {
+    description: "Uploads a part of a multipart upload copied from an \
+                  existing object.",
+    method: Method::PUT,
+    name: "UploadPartCopy",
+    path: "/:bucket/*key",
+    scope: Scope::Object,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `UploadPartCopy` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket of the upload.
+    pub bucket: String,
+
+    /// The key of the object being uploaded.
+    pub key: String,
+
+    /// The ID of the upload.
+    pub upload_id: String,
+
+    /// The number of the part, from 1 to 10000.
+    pub part_number: u32,
+
+    /// The bucket of the object to copy.
+    pub source_bucket: String,
+
+    /// The key of the object to copy.
+    pub source_key: String,
+
+    /// The version of the object to copy, the current one if there is none.
+    pub source_version_id: Option<String>,
+
+    /// The bytes of the object to copy, like `bytes=0-1023`, all of them if
+    /// there is none.
+    pub source_range: Option<String>,
+
+    /// Only copy the object if its entity tag is this one.
+    pub source_if_match: Option<String>,
+
+    /// Whether the requester agrees to pay for the request, which buckets
+    /// with requester pays enabled require.
+    pub request_payer: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` copying an object as a part.
+    pub fn new(
+        source_bucket: impl Into<String>,
+        source_key: impl Into<String>,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        upload_id: impl Into<String>,
+        part_number: u32,
+    ) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            upload_id: upload_id.into(),
+            part_number,
+            source_bucket: source_bucket.into(),
+            source_key: source_key.into(),
+            source_version_id: None,
+            source_range: None,
+            source_if_match: None,
+            request_payer: false,
+        }
+    }
+}
+
+/// Response type for the `UploadPartCopy` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The entity tag of the part, with its quotes.
+    pub etag: Option<String>,
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let url = object_url(base_url, &self.bucket, &self.key);
+        let query = Query::new()
+            .param("partNumber", self.part_number)
+            .param("uploadId", self.upload_id);
+        let copy_source = crate::copy_source(
+            &self.source_bucket,
+            &self.source_key,
+            self.source_version_id,
+        );
+
+        let mut request = http::Request::builder()
+            .method(METADATA.method)
+            .uri(query.append_to(url))
+            .header("x-amz-copy-source", copy_source);
+        if let Some(range) = self.source_range {
+            request = request.header("x-amz-copy-source-range", range);
+        }
+        if let Some(if_match) = self.source_if_match {
+            request = request.header("x-amz-copy-source-if-match", if_match);
+        }
+        request = crate::request_payer(request, self.request_payer);
+        Ok(request.body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        let body = crate::xml_body(&response)?;
+        if body.name == "Error" {
+            return Err(S3Error::from_http_response(&response).into());
+        }
+
+        Ok(Self {
+            etag: body.child_string("ETag"),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        let (source_bucket, source_key, source_version_id) =
+            crate::parse_copy_source(&request)?;
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            key: crate::path_arg(path_args, 1, "key")?,
+            upload_id: crate::required_query_param(&request, "uploadId")?,
+            part_number: crate::required_query_param(&request, "partNumber")?,
+            source_bucket,
+            source_key,
+            source_version_id,
+            source_range: crate::request_header(
+                &request,
+                "x-amz-copy-source-range",
+            )?,
+            source_if_match: crate::request_header(
+                &request,
+                "x-amz-copy-source-if-match",
+            )?,
+            request_payer: crate::requester_pays(&request),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let document = crate::with_text_opt(
+            Element::new("CopyPartResult"),
+            "ETag",
+            self.etag,
+        );
+        crate::xml_response(http::Response::builder(), document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use s3ers_api::{IncomingResponse, OutgoingRequest};
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, OutgoingResponse};
+
+    use super::{Request, Response};
+
+    #[test]
+    fn request() {
+        let mut request =
+            Request::new("source", "object", "bucket", "copy", "id", 2);
+        request.source_range = Some("bytes=10-19".to_owned());
+
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(
+            http_request.uri(),
+            "https://s3.amazonaws.com/bucket/copy?partNumber=2&uploadId=id"
+        );
+        assert_eq!(
+            http_request.headers()["x-amz-copy-source"],
+            "/source/object"
+        );
+        assert_eq!(
+            http_request.headers()["x-amz-copy-source-range"],
+            "bytes=10-19"
+        );
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::new(
+            r#"<CopyPartResult>
+  <LastModified>2009-10-28T22:32:00</LastModified>
+  <ETag>"9b2cf535f27731c974343645a3985328"</ETag>
+</CopyPartResult>"#,
+        );
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(
+            response.etag.as_deref(),
+            Some("\"9b2cf535f27731c974343645a3985328\"")
+        );
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        let mut request = Request::new(
+            "source",
+            "my image.jpg",
+            "bucket",
+            "key",
+            "upload",
+            3,
+        );
+        request.source_range = Some("bytes=0-9".to_owned());
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let path_args = ["bucket".to_owned(), "key".to_owned()];
+        let request =
+            Request::try_from_http_request(http_request, &path_args).unwrap();
+        assert_eq!(request.source_key, "my image.jpg");
+        assert_eq!(request.part_number, 3);
+        assert_eq!(request.source_range.as_deref(), Some("bytes=0-9"));
+
+        let response = Response {
+            etag: Some("\"etag\"".to_owned()),
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.etag.as_deref(), Some("\"etag\""));
+    }
+}
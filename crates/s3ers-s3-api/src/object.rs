@@ -0,0 +1,6 @@
+//! Endpoints operating on objects.
+
+pub mod copy_object;
+pub mod get_object;
+pub mod head_object;
+pub mod put_object;
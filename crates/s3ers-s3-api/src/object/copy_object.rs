@@ -0,0 +1,298 @@
+//! [PUT /{bucket}/{key}](https://docs.aws.amazon.com/AmazonS3/latest/API/API_CopyObject.html)
+//! with `x-amz-copy-source`
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, xml::Element, IncomingRequest,
+    OutgoingResponse,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError, S3Error},
+    header::{SseCustomerKey, SseKms},
+    uri::object_url,
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Scope,
+};
+
+const METADATA: Metadata = Metadata {
+    description: "Copies an object of up to 5 GB.",
+    method: Method::PUT,
+    name: "CopyObject",
+    path: "/:bucket/*key",
+    scope: Scope::Object,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `CopyObject` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket to add the copy to.
+    pub bucket: String,
+
+    /// The key of the copy.
+    pub key: String,
+
+    /// The bucket of the object to copy.
+    pub source_bucket: String,
+
+    /// The key of the object to copy.
+    pub source_key: String,
+
+    /// The version of the object to copy, the current one if there is none.
+    pub source_version_id: Option<String>,
+
+    /// How the copy is encrypted with a KMS key, if it is.
+    pub sse_kms: Option<SseKms>,
+
+    /// The key to encrypt the copy with, to encrypt it with a
+    /// customer-provided key.
+    pub sse_customer_key: Option<SseCustomerKey>,
+
+    /// The key the source is encrypted with, if it is encrypted with a
+    /// customer-provided key.
+    pub source_sse_customer_key: Option<SseCustomerKey>,
+
+    /// Whether the requester agrees to pay for the request, which buckets
+    /// with requester pays enabled require.
+    pub request_payer: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` copying an object.
+    pub fn new(
+        source_bucket: impl Into<String>,
+        source_key: impl Into<String>,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+    ) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            source_bucket: source_bucket.into(),
+            source_key: source_key.into(),
+            source_version_id: None,
+            sse_kms: None,
+            sse_customer_key: None,
+            source_sse_customer_key: None,
+            request_payer: false,
+        }
+    }
+}
+
+/// Response type for the `CopyObject` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The entity tag of the copy, with its quotes.
+    pub etag: Option<String>,
+
+    /// The ID of the version of the copy, if the bucket is versioned.
+    pub version_id: Option<String>,
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let copy_source = crate::copy_source(
+            &self.source_bucket,
+            &self.source_key,
+            self.source_version_id,
+        );
+
+        let mut request = http::Request::builder()
+            .method(METADATA.method)
+            .uri(object_url(base_url, &self.bucket, &self.key))
+            .header("x-amz-copy-source", copy_source);
+        let sse_kms = self.sse_kms.map(|sse_kms| sse_kms.headers());
+        request = crate::with_headers(request, sse_kms.transpose()?);
+        request = crate::with_headers(
+            request,
+            self.sse_customer_key.map(|key| key.headers()),
+        );
+        request = crate::with_headers(
+            request,
+            self.source_sse_customer_key
+                .map(|key| key.copy_source_headers()),
+        );
+        request = crate::request_payer(request, self.request_payer);
+        Ok(request.body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        let body = crate::xml_body(&response)?;
+        // The copy may fail after the server sent the status of the
+        // response, and then it returns an error document.
+        if body.name == "Error" {
+            return Err(S3Error::from_http_response(&response).into());
+        }
+
+        Ok(Self {
+            etag: body.child_string("ETag"),
+            version_id: crate::header_string(&response, "x-amz-version-id")?,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        let (source_bucket, source_key, source_version_id) =
+            crate::parse_copy_source(&request)?;
+        let headers = request.headers();
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            key: crate::path_arg(path_args, 1, "key")?,
+            source_bucket,
+            source_key,
+            source_version_id,
+            sse_kms: SseKms::from_headers(headers)?,
+            sse_customer_key: SseCustomerKey::from_headers(headers)?,
+            source_sse_customer_key: SseCustomerKey::from_copy_source_headers(
+                headers,
+            )?,
+            request_payer: crate::requester_pays(&request),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let response = crate::header_opt(
+            http::Response::builder(),
+            "x-amz-version-id",
+            self.version_id,
+        );
+        let document = crate::with_text_opt(
+            Element::new("CopyObjectResult"),
+            "ETag",
+            self.etag,
+        );
+        crate::xml_response(response, document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use s3ers_api::{
+        header::SseCustomerKey, IncomingResponse, OutgoingRequest,
+    };
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, OutgoingResponse};
+
+    use super::{Request, Response};
+
+    #[test]
+    fn request() {
+        let mut request =
+            Request::new("source", "my image.jpg", "bucket", "copy.jpg");
+        request.source_version_id = Some("id".to_owned());
+
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(
+            http_request.uri(),
+            "https://s3.amazonaws.com/bucket/copy.jpg"
+        );
+        assert_eq!(
+            http_request.headers()["x-amz-copy-source"],
+            "/source/my%20image.jpg?versionId=id"
+        );
+    }
+
+    #[test]
+    fn encrypt_with_customer_keys() {
+        let key: Vec<u8> = (0..32).collect();
+        let key = SseCustomerKey::new(key.try_into().unwrap());
+        let mut request = Request::new("source", "key", "bucket", "copy");
+        request.sse_customer_key = Some(key.clone());
+        request.source_sse_customer_key = Some(key);
+
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        let headers = http_request.headers();
+        assert_eq!(
+            headers["x-amz-server-side-encryption-customer-algorithm"],
+            "AES256"
+        );
+        assert_eq!(
+            headers["x-amz-server-side-encryption-customer-key"],
+            "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8="
+        );
+        assert_eq!(
+            headers
+                ["x-amz-copy-source-server-side-encryption-customer-key-md5"],
+            "tP/LI3N87DFaSk0aoqYgzg=="
+        );
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::new(
+            r#"<CopyObjectResult>
+  <LastModified>2009-10-28T22:32:00</LastModified>
+  <ETag>"9b2cf535f27731c974343645a3985328"</ETag>
+</CopyObjectResult>"#,
+        );
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(
+            response.etag.as_deref(),
+            Some("\"9b2cf535f27731c974343645a3985328\"")
+        );
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        let mut request =
+            Request::new("source", "my image.jpg", "bucket", "copy.jpg");
+        request.source_version_id = Some("id".to_owned());
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let path_args = ["bucket".to_owned(), "copy.jpg".to_owned()];
+        let request =
+            Request::try_from_http_request(http_request, &path_args).unwrap();
+        assert_eq!(request.source_bucket, "source");
+        assert_eq!(request.source_key, "my image.jpg");
+        assert_eq!(request.source_version_id.as_deref(), Some("id"));
+
+        let response = Response {
+            etag: Some("\"etag\"".to_owned()),
+            version_id: None,
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.etag.as_deref(), Some("\"etag\""));
+    }
+}
@@ -0,0 +1,460 @@
+//! [GET /{bucket}/{key}](https://docs.aws.amazon.com/AmazonS3/latest/API/API_GetObject.html)
+
+use bytes::BufMut;
+use http::{
+    header::{IF_MATCH, RANGE},
+    Method,
+};
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError},
+    header::{HttpDate, SseCustomerKey},
+    uri::{object_url, Query},
+    AuthScheme, IncomingResponse, IncomingStreamingResponse, Metadata,
+    OutgoingRequest, Scope,
+};
+
+const METADATA: Metadata = Metadata {
+    description: "Retrieves an object.",
+    method: Method::GET,
+    name: "GetObject",
+    path: "/:bucket/*key",
+    scope: Scope::Object,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: true,
+};
+
+/// Request type for the `GetObject` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket of the object.
+    pub bucket: String,
+
+    /// The key of the object.
+    pub key: String,
+
+    /// The version of the object, the current one if there is none.
+    pub version_id: Option<String>,
+
+    /// The bytes of the object to retrieve, like `bytes=0-1023`.
+    pub range: Option<String>,
+
+    /// Only retrieve the object if its entity tag is this one.
+    pub if_match: Option<String>,
+
+    /// The headers of the response to set to other values than the ones
+    /// of the object.
+    pub response_overrides: ResponseOverrides,
+
+    /// The key the object is encrypted with, if it is encrypted with a
+    /// customer-provided key.
+    pub sse_customer_key: Option<SseCustomerKey>,
+
+    /// Whether the requester agrees to pay for the request, which buckets
+    /// with requester pays enabled require.
+    pub request_payer: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` retrieving the given object.
+    pub fn new(bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            version_id: None,
+            range: None,
+            if_match: None,
+            response_overrides: ResponseOverrides::default(),
+            sse_customer_key: None,
+            request_payer: false,
+        }
+    }
+}
+
+/// The headers of a response to set to other values than the ones of the
+/// object, like to have browsers following a presigned URL save the object
+/// under another name.
+///
+/// Only requests authenticated with a signature can override headers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ResponseOverrides {
+    /// The `Cache-Control` header of the response.
+    pub cache_control: Option<String>,
+
+    /// The `Content-Disposition` header of the response, like
+    /// `attachment; filename="report.pdf"`.
+    pub content_disposition: Option<String>,
+
+    /// The `Content-Encoding` header of the response.
+    pub content_encoding: Option<String>,
+
+    /// The `Content-Language` header of the response.
+    pub content_language: Option<String>,
+
+    /// The `Content-Type` header of the response.
+    pub content_type: Option<String>,
+
+    /// The `Expires` header of the response.
+    pub expires: Option<HttpDate>,
+}
+
+impl ResponseOverrides {
+    /// Overrides no headers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `Cache-Control` header of the response.
+    pub fn with_cache_control(
+        mut self,
+        cache_control: impl Into<String>,
+    ) -> Self {
+        self.cache_control = Some(cache_control.into());
+        self
+    }
+
+    /// Sets the `Content-Disposition` header of the response.
+    pub fn with_content_disposition(
+        mut self,
+        content_disposition: impl Into<String>,
+    ) -> Self {
+        self.content_disposition = Some(content_disposition.into());
+        self
+    }
+
+    /// Sets the `Content-Encoding` header of the response.
+    pub fn with_content_encoding(
+        mut self,
+        content_encoding: impl Into<String>,
+    ) -> Self {
+        self.content_encoding = Some(content_encoding.into());
+        self
+    }
+
+    /// Sets the `Content-Language` header of the response.
+    pub fn with_content_language(
+        mut self,
+        content_language: impl Into<String>,
+    ) -> Self {
+        self.content_language = Some(content_language.into());
+        self
+    }
+
+    /// Sets the `Content-Type` header of the response.
+    pub fn with_content_type(
+        mut self,
+        content_type: impl Into<String>,
+    ) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Sets the `Expires` header of the response.
+    pub fn with_expires(mut self, expires: HttpDate) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Adds the `response-*` parameters of the overrides to a query string.
+    fn append_to(&self, query: Query) -> Query {
+        query
+            .param_opt("response-cache-control", self.cache_control.as_ref())
+            .param_opt(
+                "response-content-disposition",
+                self.content_disposition.as_ref(),
+            )
+            .param_opt(
+                "response-content-encoding",
+                self.content_encoding.as_ref(),
+            )
+            .param_opt(
+                "response-content-language",
+                self.content_language.as_ref(),
+            )
+            .param_opt("response-content-type", self.content_type.as_ref())
+            .param_opt("response-expires", self.expires)
+    }
+}
+
+/// Response type for the `GetObject` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The content of the object, empty if it is read as a stream.
+    pub body: Vec<u8>,
+
+    /// The length of the content.
+    pub content_length: Option<u64>,
+
+    /// The range of the object the content is, for ranged requests.
+    pub content_range: Option<String>,
+
+    /// The media type of the object.
+    pub content_type: Option<String>,
+
+    /// The entity tag of the object, with its quotes.
+    pub etag: Option<String>,
+
+    /// When the object was last modified.
+    pub last_modified: Option<HttpDate>,
+
+    /// The ID of the version of the object, if the bucket is versioned.
+    pub version_id: Option<String>,
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let url = object_url(base_url, &self.bucket, &self.key);
+        let query = self
+            .response_overrides
+            .append_to(Query::new())
+            .param_opt("versionId", self.version_id);
+
+        let mut request = http::Request::builder()
+            .method(METADATA.method)
+            .uri(query.append_to(url));
+        if let Some(range) = self.range {
+            request = request.header(RANGE, range);
+        }
+        if let Some(if_match) = self.if_match {
+            request = request.header(IF_MATCH, if_match);
+        }
+        request = crate::with_headers(
+            request,
+            self.sse_customer_key.map(|key| key.headers()),
+        );
+        request = crate::request_payer(request, self.request_payer);
+        Ok(request.body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        crate::check_status(&response)?;
+
+        let (head, body) = response.into_parts();
+        let mut response = Self::try_from_http_response_head(
+            http::Response::from_parts(head, ()),
+        )?;
+        response.body = body.as_ref().to_vec();
+        Ok(response)
+    }
+}
+
+impl IncomingStreamingResponse for Response {
+    fn try_from_http_response_head(
+        response: http::Response<()>,
+    ) -> Result<Self, FromHttpResponseError> {
+        Ok(Self {
+            body: Vec::new(),
+            content_length: crate::parse_header(&response, "content-length")?,
+            content_range: crate::header_string(&response, "content-range")?,
+            content_type: crate::header_string(&response, "content-type")?,
+            etag: crate::header_string(&response, "etag")?,
+            last_modified: crate::parse_header(&response, "last-modified")?,
+            version_id: crate::header_string(&response, "x-amz-version-id")?,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl ResponseOverrides {
+    /// Reads the `response-*` parameters of a request.
+    fn from_request<T>(
+        request: &http::Request<T>,
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            cache_control: crate::query_param(
+                request,
+                "response-cache-control",
+            ),
+            content_disposition: crate::query_param(
+                request,
+                "response-content-disposition",
+            ),
+            content_encoding: crate::query_param(
+                request,
+                "response-content-encoding",
+            ),
+            content_language: crate::query_param(
+                request,
+                "response-content-language",
+            ),
+            content_type: crate::query_param(request, "response-content-type"),
+            expires: crate::parse_query_param(request, "response-expires")?,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            key: crate::path_arg(path_args, 1, "key")?,
+            version_id: crate::query_param(&request, "versionId"),
+            range: crate::request_header(&request, RANGE.as_str())?,
+            if_match: crate::request_header(&request, IF_MATCH.as_str())?,
+            response_overrides: ResponseOverrides::from_request(&request)?,
+            sse_customer_key: SseCustomerKey::from_headers(request.headers())?,
+            request_payer: crate::requester_pays(&request),
+        })
+    }
+}
+
+/// The response is `206 Partial Content` if it has a content range.
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let status = match self.content_range {
+            Some(_) => http::StatusCode::PARTIAL_CONTENT,
+            None => http::StatusCode::OK,
+        };
+        let mut response = http::Response::builder().status(status);
+        response =
+            crate::header_opt(response, "content-length", self.content_length);
+        response =
+            crate::header_opt(response, "content-range", self.content_range);
+        response =
+            crate::header_opt(response, "content-type", self.content_type);
+        response = crate::header_opt(response, "etag", self.etag);
+        response =
+            crate::header_opt(response, "last-modified", self.last_modified);
+        response =
+            crate::header_opt(response, "x-amz-version-id", self.version_id);
+
+        let mut body = T::default();
+        body.put_slice(&self.body);
+        Ok(response.body(body)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use s3ers_api::{IncomingResponse, OutgoingRequest};
+    use s3ers_signature::{Credentials, SigningParams};
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{IncomingRequest, OutgoingResponse};
+
+    use super::{Request, Response, ResponseOverrides};
+
+    #[test]
+    fn request() {
+        let mut request = Request::new("bucket", "my-image.jpg");
+        request.range = Some("bytes=0-9".to_owned());
+        request.request_payer = true;
+
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(
+            http_request.uri(),
+            "https://s3.amazonaws.com/bucket/my-image.jpg"
+        );
+        assert_eq!(http_request.headers()["range"], "bytes=0-9");
+        assert_eq!(http_request.headers()["x-amz-request-payer"], "requester");
+    }
+
+    #[test]
+    fn presign_with_response_overrides() {
+        let mut request = Request::new("bucket", "report");
+        request.response_overrides = ResponseOverrides::new()
+            .with_content_type("application/pdf")
+            .with_content_disposition("attachment; filename=\"report.pdf\"");
+
+        let uri = request
+            .try_into_presigned_uri(
+                "https://s3.amazonaws.com",
+                &Credentials::new("AKIDEXAMPLE", "secret"),
+                &SigningParams::new("us-east-1", "s3", UNIX_EPOCH),
+                Duration::from_secs(3600),
+            )
+            .unwrap();
+        let query = uri.query().unwrap();
+        assert!(query.starts_with(
+            "response-content-disposition=\
+             attachment%3B%20filename%3D%22report.pdf%22\
+             &response-content-type=application%2Fpdf&"
+        ));
+        assert!(query.contains("X-Amz-Signature="));
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::builder()
+            .status(206)
+            .header("Content-Length", "10")
+            .header("Content-Range", "bytes 0-9/443")
+            .header("ETag", "\"0f343b0931126a20f133d67c2b018a3b\"")
+            .header("Last-Modified", "Wed, 28 Oct 2009 22:32:00 GMT")
+            .body(b"0123456789".to_vec())
+            .unwrap();
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(response.body, b"0123456789");
+        assert_eq!(response.content_length, Some(10));
+        assert_eq!(response.content_range.as_deref(), Some("bytes 0-9/443"));
+        assert_eq!(
+            response.last_modified.unwrap().to_string(),
+            "Wed, 28 Oct 2009 22:32:00 GMT"
+        );
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        let mut request = Request::new("bucket", "report");
+        request.range = Some("bytes=0-9".to_owned());
+        request.response_overrides =
+            ResponseOverrides::new().with_content_type("application/pdf");
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let path_args = ["bucket".to_owned(), "report".to_owned()];
+        let request =
+            Request::try_from_http_request(http_request, &path_args).unwrap();
+        assert_eq!(request.range.as_deref(), Some("bytes=0-9"));
+        assert_eq!(
+            request.response_overrides,
+            ResponseOverrides::new().with_content_type("application/pdf")
+        );
+
+        let response = Response {
+            body: b"0123456789".to_vec(),
+            content_range: Some("bytes 0-9/443".to_owned()),
+            ..Response::default()
+        };
+        let http_response =
+            response.try_into_http_response::<Vec<u8>>().unwrap();
+        assert_eq!(http_response.status(), 206);
+        let response = Response::try_from_http_response(http_response).unwrap();
+        assert_eq!(response.body, b"0123456789");
+        assert_eq!(response.content_range.as_deref(), Some("bytes 0-9/443"));
+    }
+}
@@ -0,0 +1,238 @@
+//! [HEAD /{bucket}/{key}](https://docs.aws.amazon.com/AmazonS3/latest/API/API_HeadObject.html)
+
+use bytes::BufMut;
+use http::Method;
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError},
+    header::{HttpDate, SseCustomerKey},
+    uri::{object_url, Query},
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Scope,
+};
+
+const METADATA: Metadata = Metadata {
+    description: "Retrieves the metadata of an object.",
+    method: Method::HEAD,
+    name: "HeadObject",
+    path: "/:bucket/*key",
+    scope: Scope::Object,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: false,
+};
+
+/// Request type for the `HeadObject` endpoint.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket of the object.
+    pub bucket: String,
+
+    /// The key of the object.
+    pub key: String,
+
+    /// The version of the object, the current one if there is none.
+    pub version_id: Option<String>,
+
+    /// The key the object is encrypted with, if it is encrypted with a
+    /// customer-provided key.
+    pub sse_customer_key: Option<SseCustomerKey>,
+
+    /// Whether the requester agrees to pay for the request, which buckets
+    /// with requester pays enabled require.
+    pub request_payer: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` retrieving the metadata of the given object.
+    pub fn new(bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            version_id: None,
+            sse_customer_key: None,
+            request_payer: false,
+        }
+    }
+}
+
+/// Response type for the `HeadObject` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The length of the object.
+    pub content_length: Option<u64>,
+
+    /// The media type of the object.
+    pub content_type: Option<String>,
+
+    /// The entity tag of the object, with its quotes.
+    pub etag: Option<String>,
+
+    /// When the object was last modified.
+    pub last_modified: Option<HttpDate>,
+
+    /// The ID of the version of the object, if the bucket is versioned.
+    pub version_id: Option<String>,
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let url = object_url(base_url, &self.bucket, &self.key);
+        let query = Query::new().param_opt("versionId", self.version_id);
+
+        let mut request = http::Request::builder()
+            .method(METADATA.method)
+            .uri(query.append_to(url));
+        request = crate::with_headers(
+            request,
+            self.sse_customer_key.map(|key| key.headers()),
+        );
+        request = crate::request_payer(request, self.request_payer);
+        Ok(request.body(T::default())?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        crate::check_status(&response)?;
+
+        Ok(Self {
+            content_length: crate::parse_header(&response, "content-length")?,
+            content_type: crate::header_string(&response, "content-type")?,
+            etag: crate::header_string(&response, "etag")?,
+            last_modified: crate::parse_header(&response, "last-modified")?,
+            version_id: crate::header_string(&response, "x-amz-version-id")?,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            key: crate::path_arg(path_args, 1, "key")?,
+            version_id: crate::query_param(&request, "versionId"),
+            sse_customer_key: SseCustomerKey::from_headers(request.headers())?,
+            request_payer: crate::requester_pays(&request),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let mut response = http::Response::builder();
+        response =
+            crate::header_opt(response, "content-length", self.content_length);
+        response =
+            crate::header_opt(response, "content-type", self.content_type);
+        response = crate::header_opt(response, "etag", self.etag);
+        response =
+            crate::header_opt(response, "last-modified", self.last_modified);
+        response =
+            crate::header_opt(response, "x-amz-version-id", self.version_id);
+        Ok(response.body(T::default())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "server")]
+    use s3ers_api::{
+        header::SseCustomerKey, IncomingRequest, OutgoingResponse,
+    };
+    use s3ers_api::{IncomingResponse, OutgoingRequest};
+
+    use super::{Request, Response};
+
+    #[test]
+    fn request_uri() {
+        let mut request = Request::new("bucket", "my-image.jpg");
+        request.version_id = Some("3HL4kqtJlcpXroDTDmJ".to_owned());
+
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(http_request.method(), "HEAD");
+        assert_eq!(
+            http_request.uri(),
+            "https://s3.amazonaws.com/bucket/my-image.jpg\
+             ?versionId=3HL4kqtJlcpXroDTDmJ"
+        );
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::builder()
+            .header("Content-Length", "434234")
+            .header("Content-Type", "text/plain")
+            .header("ETag", "\"fba9dede5f27731c9771645a39863328\"")
+            .body(Vec::new())
+            .unwrap();
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(response.content_length, Some(434234));
+        assert_eq!(response.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(
+            response.etag.as_deref(),
+            Some("\"fba9dede5f27731c9771645a39863328\"")
+        );
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        let mut request = Request::new("bucket", "my-image.jpg");
+        request.version_id = Some("3HL4kqtJlcpXroDTDmJ".to_owned());
+        request.sse_customer_key = Some(SseCustomerKey::new([7; 32]));
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let path_args = ["bucket".to_owned(), "my-image.jpg".to_owned()];
+        let request =
+            Request::try_from_http_request(http_request, &path_args).unwrap();
+        assert_eq!(request.key, "my-image.jpg");
+        assert_eq!(request.version_id.as_deref(), Some("3HL4kqtJlcpXroDTDmJ"));
+        assert_eq!(
+            request.sse_customer_key,
+            Some(SseCustomerKey::new([7; 32]))
+        );
+
+        let response = Response {
+            content_length: Some(434234),
+            etag: Some("\"fba9dede5f27731c9771645a39863328\"".to_owned()),
+            ..Response::default()
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.content_length, Some(434234));
+        assert_eq!(
+            response.etag.as_deref(),
+            Some("\"fba9dede5f27731c9771645a39863328\"")
+        );
+    }
+}
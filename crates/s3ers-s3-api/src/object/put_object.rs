@@ -0,0 +1,265 @@
+//! [PUT /{bucket}/{key}](https://docs.aws.amazon.com/AmazonS3/latest/API/API_PutObject.html)
+
+use bytes::BufMut;
+use http::{header::CONTENT_TYPE, Method};
+#[cfg(feature = "server")]
+use s3ers_api::{
+    error::FromHttpRequestError, IncomingRequest, OutgoingResponse,
+};
+use s3ers_api::{
+    error::{FromHttpResponseError, IntoHttpError},
+    header::{SseCustomerKey, SseKms},
+    uri::object_url,
+    AuthScheme, IncomingResponse, Metadata, OutgoingRequest, Scope,
+};
+
+use crate::WriteCondition;
+
+const METADATA: Metadata = Metadata {
+    description: "Adds an object to a bucket.",
+    method: Method::PUT,
+    name: "PutObject",
+    path: "/:bucket/*key",
+    scope: Scope::Object,
+    authentication: AuthScheme::AwsSignatureV4,
+    requires_content_md5: false,
+    flexible_checksums: true,
+};
+
+/// Request type for the `PutObject` endpoint.
+///
+/// Large objects are better sent with a streaming body, which replaces
+/// `body`.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Request {
+    /// The bucket to add the object to.
+    pub bucket: String,
+
+    /// The key of the object.
+    pub key: String,
+
+    /// The content of the object.
+    pub body: Vec<u8>,
+
+    /// The media type of the object.
+    pub content_type: Option<String>,
+
+    /// How the object is encrypted with a KMS key, if it is.
+    pub sse_kms: Option<SseKms>,
+
+    /// The key the object is encrypted with, if it is encrypted with a
+    /// customer-provided key.
+    pub sse_customer_key: Option<SseCustomerKey>,
+
+    /// The condition the object must meet to be written, if any.
+    pub condition: Option<WriteCondition>,
+
+    /// Whether the requester agrees to pay for the request, which buckets
+    /// with requester pays enabled require.
+    pub request_payer: bool,
+}
+
+impl Request {
+    /// Creates a new `Request` adding an object with the given key and
+    /// content.
+    pub fn new(
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        body: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            body: body.into(),
+            content_type: None,
+            sse_kms: None,
+            sse_customer_key: None,
+            condition: None,
+            request_payer: false,
+        }
+    }
+}
+
+/// Response type for the `PutObject` endpoint.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Response {
+    /// The entity tag of the object, with its quotes.
+    pub etag: Option<String>,
+
+    /// The ID of the version of the object, if the bucket is versioned.
+    pub version_id: Option<String>,
+}
+
+impl OutgoingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type IncomingResponse = Response;
+
+    fn try_into_http_request<T: Default + BufMut>(
+        self,
+        base_url: &str,
+    ) -> Result<http::Request<T>, IntoHttpError> {
+        let mut request = http::Request::builder()
+            .method(METADATA.method)
+            .uri(object_url(base_url, &self.bucket, &self.key));
+        if let Some(content_type) = self.content_type {
+            request = request.header(CONTENT_TYPE, content_type);
+        }
+
+        let mut body = T::default();
+        body.put_slice(&self.body);
+        let sse_kms = self.sse_kms.map(|sse_kms| sse_kms.headers());
+        request = crate::with_headers(request, sse_kms.transpose()?);
+        request = crate::with_headers(
+            request,
+            self.sse_customer_key.map(|key| key.headers()),
+        );
+        if let Some(condition) = self.condition {
+            request = condition.apply(request);
+        }
+        request = crate::request_payer(request, self.request_payer);
+        Ok(request.body(body)?)
+    }
+}
+
+impl IncomingResponse for Response {
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, FromHttpResponseError> {
+        crate::check_status(&response)?;
+
+        Ok(Self {
+            etag: crate::header_string(&response, "etag")?,
+            version_id: crate::header_string(&response, "x-amz-version-id")?,
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl IncomingRequest for Request {
+    const METADATA: Metadata = METADATA;
+
+    type OutgoingResponse = Response;
+
+    fn try_from_http_request<T: AsRef<[u8]>>(
+        request: http::Request<T>,
+        path_args: &[String],
+    ) -> Result<Self, FromHttpRequestError> {
+        Ok(Self {
+            bucket: crate::path_arg(path_args, 0, "bucket")?,
+            key: crate::path_arg(path_args, 1, "key")?,
+            body: request.body().as_ref().to_vec(),
+            content_type: crate::request_header(
+                &request,
+                CONTENT_TYPE.as_str(),
+            )?,
+            sse_kms: SseKms::from_headers(request.headers())?,
+            sse_customer_key: SseCustomerKey::from_headers(request.headers())?,
+            condition: WriteCondition::from_request(&request)?,
+            request_payer: crate::requester_pays(&request),
+        })
+    }
+}
+
+#[cfg(feature = "server")]
+impl OutgoingResponse for Response {
+    fn try_into_http_response<T: Default + BufMut>(
+        self,
+    ) -> Result<http::Response<T>, IntoHttpError> {
+        let mut response = http::Response::builder();
+        response = crate::header_opt(response, "etag", self.etag);
+        response =
+            crate::header_opt(response, "x-amz-version-id", self.version_id);
+        Ok(response.body(T::default())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use s3ers_api::{IncomingResponse, OutgoingRequest};
+
+    #[cfg(feature = "server")]
+    use s3ers_api::{header::SseKms, IncomingRequest, OutgoingResponse};
+
+    use super::{Request, Response};
+    use crate::WriteCondition;
+
+    #[test]
+    fn request() {
+        let mut request = Request::new("bucket", "my image.jpg", "content");
+        request.content_type = Some("image/jpeg".to_owned());
+
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(
+            http_request.uri(),
+            "https://s3.amazonaws.com/bucket/my%20image.jpg"
+        );
+        assert_eq!(http_request.headers()["content-type"], "image/jpeg");
+        assert_eq!(http_request.body(), b"content");
+    }
+
+    #[test]
+    fn write_conditionally() {
+        let mut request = Request::new("bucket", "key", "content");
+        request.condition = Some(WriteCondition::NotExists);
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(http_request.headers()["if-none-match"], "*");
+
+        let mut request = Request::new("bucket", "key", "content");
+        request.condition = Some(WriteCondition::Matches("\"etag\"".into()));
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>("https://s3.amazonaws.com")
+            .unwrap();
+        assert_eq!(http_request.headers()["if-match"], "\"etag\"");
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = http::Response::builder()
+            .header("ETag", "\"1b2cf535f27731c974343645a3985328\"")
+            .body(Vec::new())
+            .unwrap();
+
+        let response = Response::try_from_http_response(response).unwrap();
+        assert_eq!(
+            response.etag.as_deref(),
+            Some("\"1b2cf535f27731c974343645a3985328\"")
+        );
+        assert_eq!(response.version_id, None);
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn round_trip() {
+        let mut request = Request::new("bucket", "my image.jpg", "content");
+        request.content_type = Some("image/jpeg".to_owned());
+        request.sse_kms = Some(SseKms::new().with_context("a", "b"));
+        request.condition = Some(WriteCondition::NotExists);
+        let http_request =
+            request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let path_args = ["bucket".to_owned(), "my image.jpg".to_owned()];
+        let request =
+            Request::try_from_http_request(http_request, &path_args).unwrap();
+        assert_eq!(request.body, b"content");
+        assert_eq!(request.content_type.as_deref(), Some("image/jpeg"));
+        assert_eq!(request.sse_kms, Some(SseKms::new().with_context("a", "b")));
+        assert_eq!(request.condition, Some(WriteCondition::NotExists));
+
+        let response = Response {
+            etag: Some("\"etag\"".to_owned()),
+            version_id: Some("id".to_owned()),
+        };
+        let response = Response::try_from_http_response(
+            response.try_into_http_response::<Vec<u8>>().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.etag.as_deref(), Some("\"etag\""));
+        assert_eq!(response.version_id.as_deref(), Some("id"));
+    }
+}
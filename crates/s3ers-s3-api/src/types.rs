@@ -0,0 +1,79 @@
+use http::header::{IF_MATCH, IF_NONE_MATCH};
+#[cfg(feature = "server")]
+use s3ers_api::error::DeserializationError;
+use s3ers_api::xml::Element;
+
+/// The owner of a bucket, an object or a multipart upload.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Owner {
+    /// The canonical user ID of the owner.
+    pub id: Option<String>,
+
+    /// The display name of the owner.
+    pub display_name: Option<String>,
+}
+
+impl Owner {
+    pub(crate) fn from_xml(element: &Element) -> Self {
+        Self {
+            id: element.child_string("ID"),
+            display_name: element.child_string("DisplayName"),
+        }
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn to_xml(&self) -> Element {
+        let owner =
+            crate::with_text_opt(Element::new("Owner"), "ID", self.id.as_ref());
+        crate::with_text_opt(owner, "DisplayName", self.display_name.as_ref())
+    }
+}
+
+/// The condition an object must meet to be written, to avoid overwriting
+/// the changes of concurrent writers.
+///
+/// Servers reject writes whose condition fails with a
+/// `PreconditionFailed` error, and concurrent conditional writes with a
+/// `ConditionalRequestConflict` one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WriteCondition {
+    /// Only create the object, if it doesn't exist, with `If-None-Match: *`.
+    NotExists,
+
+    /// Only overwrite the object if it has this entity tag, with
+    /// `If-Match`.
+    Matches(String),
+}
+
+impl WriteCondition {
+    /// Adds the header of the condition to a request.
+    pub(crate) fn apply(
+        self,
+        request: http::request::Builder,
+    ) -> http::request::Builder {
+        match self {
+            Self::NotExists => request.header(IF_NONE_MATCH, "*"),
+            Self::Matches(etag) => request.header(IF_MATCH, etag),
+        }
+    }
+
+    /// Reads the condition of a request received by a server, if it has
+    /// one.
+    #[cfg(feature = "server")]
+    pub(crate) fn from_request<T>(
+        request: &http::Request<T>,
+    ) -> Result<Option<Self>, DeserializationError> {
+        if let Some(etag) = crate::request_header(request, IF_MATCH.as_str())? {
+            return Ok(Some(Self::Matches(etag)));
+        }
+        match crate::request_header(request, IF_NONE_MATCH.as_str())? {
+            Some(etag) if etag == "*" => Ok(Some(Self::NotExists)),
+            Some(_) => Err(DeserializationError::Invalid(
+                IF_NONE_MATCH.as_str().to_owned(),
+            )),
+            None => Ok(None),
+        }
+    }
+}
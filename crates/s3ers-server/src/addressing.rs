@@ -0,0 +1,185 @@
+//! Finding the bucket and the key of requests, addressed in the path or in
+//! the hostname.
+
+use http::{header::HOST, uri::PathAndQuery, Uri};
+use s3ers_api::uri;
+
+/// How the server finds the bucket of the requests it receives.
+///
+/// Path-style requests, like `https://s3.example.com/bucket/key`, are always
+/// accepted. With a base domain, virtual-hosted-style requests to its
+/// subdomains, like `https://bucket.s3.example.com/key`, are accepted too,
+/// while requests to the base domain itself, or to other hosts like IP
+/// addresses, are path-style.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Addressing {
+    base_domain: Option<String>,
+}
+
+/// The bucket and the key a request is addressed to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Resource {
+    /// The bucket, if the request isn't to the service, like `ListBuckets`.
+    pub bucket: Option<String>,
+
+    /// The key of the object, decoded, if the request is to an object.
+    pub key: Option<String>,
+}
+
+impl Addressing {
+    /// Only accepts path-style requests.
+    pub fn path_style() -> Self {
+        Self::default()
+    }
+
+    /// Accepts path-style requests, and virtual-hosted-style requests to
+    /// the subdomains of `base_domain`, like `s3.example.com`.
+    pub fn with_base_domain(base_domain: impl Into<String>) -> Self {
+        let base_domain = base_domain.into().trim_matches('.').to_lowercase();
+        Self {
+            base_domain: Some(base_domain),
+        }
+    }
+
+    /// Returns the base domain of virtual-hosted-style requests, if they
+    /// are accepted.
+    pub fn base_domain(&self) -> Option<&str> {
+        self.base_domain.as_deref()
+    }
+
+    /// Returns the bucket and the key a request is addressed to.
+    pub fn resource<T>(&self, request: &http::Request<T>) -> Resource {
+        let path = request.uri().path();
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let (bucket, key) = match self.virtual_host_bucket(request) {
+            Some(bucket) => (Some(bucket), Some(path)),
+            None => {
+                let (bucket, key) = match path.split_once('/') {
+                    Some((bucket, key)) => (bucket, Some(key)),
+                    None => (path, None),
+                };
+                (Some(uri::decode(bucket)), key)
+            }
+        };
+        Resource {
+            bucket: bucket.filter(|bucket| !bucket.is_empty()),
+            key: key.filter(|key| !key.is_empty()).map(uri::decode),
+        }
+    }
+
+    /// Returns the bucket in the hostname of a virtual-hosted-style
+    /// request.
+    fn virtual_host_bucket<T>(
+        &self,
+        request: &http::Request<T>,
+    ) -> Option<String> {
+        let base_domain = self.base_domain.as_deref()?;
+        let host = match request.headers().get(HOST) {
+            Some(host) => host.to_str().ok()?,
+            None => request.uri().authority()?.as_str(),
+        };
+        let host = match host.rsplit_once(':') {
+            Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => {
+                host
+            }
+            _ => host,
+        };
+        let host = host.trim_end_matches('.').to_lowercase();
+        let bucket = host.strip_suffix(base_domain)?.strip_suffix('.')?;
+        (!bucket.is_empty()).then(|| bucket.to_owned())
+    }
+
+    /// Rewrites the URI of a virtual-hosted-style request to the one of the
+    /// same request in path-style, so that it matches the paths of the
+    /// endpoints.
+    pub(crate) fn to_path_style<T>(&self, request: &mut http::Request<T>) {
+        let bucket = match self.virtual_host_bucket(request) {
+            Some(bucket) => bucket,
+            None => return,
+        };
+        let path = match request.uri().path() {
+            "/" | "" => String::new(),
+            path => path.to_owned(),
+        };
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("/{}{}?{}", bucket, path, query),
+            None => format!("/{}{}", bucket, path),
+        };
+        let mut parts = request.uri().clone().into_parts();
+        parts.path_and_query = match path_and_query.parse::<PathAndQuery>() {
+            Ok(path_and_query) => Some(path_and_query),
+            Err(_) => return,
+        };
+        if let Ok(uri) = Uri::from_parts(parts) {
+            *request.uri_mut() = uri;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Addressing, Resource};
+
+    fn request(host: &str, uri: &str) -> http::Request<()> {
+        http::Request::builder()
+            .uri(uri)
+            .header("host", host)
+            .body(())
+            .unwrap()
+    }
+
+    fn resource(bucket: Option<&str>, key: Option<&str>) -> Resource {
+        Resource {
+            bucket: bucket.map(str::to_owned),
+            key: key.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn find_resources() {
+        let path_style = Addressing::path_style();
+        let both = Addressing::with_base_domain("S3.example.com.");
+        assert_eq!(both.base_domain(), Some("s3.example.com"));
+
+        let req = request("s3.example.com", "/bucket/dir/my%20image.jpg");
+        let expected = resource(Some("bucket"), Some("dir/my image.jpg"));
+        assert_eq!(path_style.resource(&req), expected);
+        assert_eq!(both.resource(&req), expected);
+
+        let req = request("bucket.s3.example.com:9000", "/dir/my%20image.jpg");
+        assert_eq!(both.resource(&req), expected);
+        assert_eq!(
+            path_style.resource(&req),
+            resource(Some("dir"), Some("my image.jpg"))
+        );
+
+        let req = request("my.bucket.s3.example.com", "/");
+        assert_eq!(both.resource(&req), resource(Some("my.bucket"), None));
+        let req = request("s3.example.com", "/bucket/");
+        assert_eq!(both.resource(&req), resource(Some("bucket"), None));
+        let req = request("s3.example.com", "/");
+        assert_eq!(both.resource(&req), resource(None, None));
+        let req = request("127.0.0.1:9000", "/bucket/key");
+        assert_eq!(both.resource(&req), resource(Some("bucket"), Some("key")));
+        let req = request("bucket.other.example.com", "/key");
+        assert_eq!(both.resource(&req), resource(Some("key"), None));
+    }
+
+    #[test]
+    fn rewrite_to_path_style() {
+        let addressing = Addressing::with_base_domain("s3.example.com");
+
+        let mut req = request("bucket.s3.example.com", "/dir/key?versionId=1");
+        addressing.to_path_style(&mut req);
+        assert_eq!(req.uri(), "/bucket/dir/key?versionId=1");
+
+        let mut req = request("bucket.s3.example.com", "/?uploads");
+        addressing.to_path_style(&mut req);
+        assert_eq!(req.uri(), "/bucket?uploads");
+
+        let mut req = request("s3.example.com", "/bucket/key");
+        addressing.to_path_style(&mut req);
+        assert_eq!(req.uri(), "/bucket/key");
+    }
+}
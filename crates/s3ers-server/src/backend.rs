@@ -0,0 +1,81 @@
+//! Helpers shared by the servers of the crate.
+
+use http::StatusCode;
+use s3ers_api::error::S3Error;
+use s3ers_s3_api::multipart::complete_multipart_upload::CompletedPart;
+use sha2::{Digest, Sha256};
+
+/// Returns the entity tag of data.
+pub(crate) fn etag(data: &[u8]) -> String {
+    format!("\"{}\"", hex::encode(&Sha256::digest(data)[..16]))
+}
+
+/// Checks that a part number is between 1 and 10000.
+pub(crate) fn check_part_number(part_number: u32) -> Result<(), Box<S3Error>> {
+    if !(1..=10_000).contains(&part_number) {
+        return Err(Box::new(
+            S3Error::new(StatusCode::BAD_REQUEST, "InvalidArgument")
+                .with_message(
+                    "Part number must be an integer between 1 and 10000, \
+                     inclusive.",
+                ),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that the parts completing an upload are given in ascending order.
+pub(crate) fn check_parts(parts: &[CompletedPart]) -> Result<(), Box<S3Error>> {
+    if parts.is_empty() {
+        return Err(Box::new(
+            S3Error::new(StatusCode::BAD_REQUEST, "MalformedXML")
+                .with_message("The upload must be completed with parts."),
+        ));
+    }
+    let ascending = parts
+        .windows(2)
+        .all(|parts| parts[0].part_number < parts[1].part_number);
+    if !ascending {
+        return Err(Box::new(
+            S3Error::new(StatusCode::BAD_REQUEST, "InvalidPartOrder")
+                .with_message("The list of parts was not in ascending order."),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the entity tag of an object assembled from parts with the given
+/// entity tags, the digest of their digests followed by their count.
+pub(crate) fn multipart_etag<'a>(
+    etags: impl ExactSizeIterator<Item = &'a str>,
+) -> String {
+    let count = etags.len();
+    let mut digests = Sha256::new();
+    for etag in etags {
+        digests.update(etag.trim_matches('"'));
+    }
+    format!("\"{}-{}\"", hex::encode(&digests.finalize()[..16]), count)
+}
+
+pub(crate) fn no_such_bucket(bucket: &str) -> S3Error {
+    S3Error::new(StatusCode::NOT_FOUND, "NoSuchBucket")
+        .with_message("The specified bucket does not exist.")
+        .with_resource(bucket)
+}
+
+pub(crate) fn no_such_key(bucket: &str, key: &str) -> S3Error {
+    S3Error::new(StatusCode::NOT_FOUND, "NoSuchKey")
+        .with_message("The specified key does not exist.")
+        .with_resource(format!("/{}/{}", bucket, key))
+}
+
+pub(crate) fn no_such_upload(upload_id: &str) -> S3Error {
+    S3Error::new(StatusCode::NOT_FOUND, "NoSuchUpload")
+        .with_message("The specified upload does not exist.")
+        .with_resource(upload_id)
+}
+
+pub(crate) fn invalid_part(part_number: u32) -> S3Error {
+    S3Error::new(StatusCode::BAD_REQUEST, "InvalidPart")
+        .with_message(format!("Part {} could not be found.", part_number))
+}
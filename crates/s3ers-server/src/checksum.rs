@@ -0,0 +1,280 @@
+//! Checking the bodies of writes against their `Content-MD5` and
+//! `x-amz-checksum-*` headers.
+//!
+//! The [`Router`](crate::Router) checks the body of the requests it
+//! receives with [`check_request`] before dispatching them, and servers
+//! reading bodies as they are streamed feed them to a [`BodyChecker`].
+//!
+//! ```
+//! use s3ers_server::checksum::BodyChecker;
+//!
+//! let request = http::Request::put("/bucket/key")
+//!     .header("content-md5", "XUFAKrxLKna5cZ2REBfFkg==")
+//!     .body(())
+//!     .unwrap();
+//! let mut checker = BodyChecker::from_headers(request.headers()).unwrap();
+//! checker.update(b"hel");
+//! checker.update(b"lo");
+//! assert!(checker.finish().is_ok());
+//! ```
+
+use std::convert::TryFrom;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use bytes::Bytes;
+use http::{HeaderMap, StatusCode};
+use s3ers_api::error::S3Error;
+use s3ers_signature::{
+    chunked::{Checksum, ChecksumAlgorithm},
+    md5::Md5,
+};
+
+use crate::chunked::is_aws_chunked;
+
+/// A check of a body against the `Content-MD5` and `x-amz-checksum-*`
+/// headers of its request, fed with the body as it is received.
+#[derive(Clone, Debug)]
+pub struct BodyChecker {
+    md5: Option<(Md5, [u8; 16])>,
+    checksum: Option<(Checksum, String)>,
+}
+
+impl BodyChecker {
+    /// Starts checking the body of a request with the given headers.
+    ///
+    /// Headers which can't be digests, like a `Content-MD5` which isn't 16
+    /// bytes in base64, are rejected with the errors of S3. Checksums with
+    /// an unsupported algorithm are not checked.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, Box<S3Error>> {
+        let md5 = match headers.get("content-md5") {
+            Some(value) => {
+                let expected = value
+                    .to_str()
+                    .ok()
+                    .and_then(|value| STANDARD.decode(value).ok())
+                    .and_then(|digest| <[u8; 16]>::try_from(digest).ok())
+                    .ok_or_else(|| {
+                        error(
+                            "InvalidDigest",
+                            "The Content-MD5 you specified is not valid.",
+                        )
+                    })?;
+                Some((Md5::new(), expected))
+            }
+            None => None,
+        };
+
+        let mut checksums = headers.iter().filter(|(name, _)| {
+            let name = name.as_str();
+            name.starts_with("x-amz-checksum-")
+                && name != "x-amz-checksum-algorithm"
+                && name != "x-amz-checksum-type"
+        });
+        let checksum = match (checksums.next(), checksums.next()) {
+            (Some(_), Some(_)) => {
+                return Err(error(
+                    "InvalidRequest",
+                    "Expecting a single x-amz-checksum- header. Multiple \
+                     checksum Types are not allowed.",
+                ))
+            }
+            (Some((name, value)), None) => {
+                match ChecksumAlgorithm::from_header_name(name.as_str()) {
+                    Some(algorithm) => {
+                        let expected = value
+                            .to_str()
+                            .ok()
+                            .filter(|value| is_checksum(algorithm, value))
+                            .ok_or_else(|| {
+                                error(
+                                    "InvalidRequest",
+                                    format!(
+                                        "Value for {} header is invalid.",
+                                        name
+                                    ),
+                                )
+                            })?;
+                        Some((Checksum::new(algorithm), expected.to_owned()))
+                    }
+                    None => None,
+                }
+            }
+            (None, _) => None,
+        };
+        Ok(Self { md5, checksum })
+    }
+
+    /// Adds the next bytes of the body.
+    pub fn update(&mut self, data: &[u8]) {
+        if let Some((md5, _)) = &mut self.md5 {
+            md5.update(data);
+        }
+        if let Some((checksum, _)) = &mut self.checksum {
+            checksum.update(data);
+        }
+    }
+
+    /// Checks the body once it is all received, with a `BadDigest` error
+    /// if it doesn't match a digest.
+    pub fn finish(self) -> Result<(), Box<S3Error>> {
+        if let Some((md5, expected)) = self.md5 {
+            if md5.finalize() != expected {
+                return Err(error(
+                    "BadDigest",
+                    "The Content-MD5 you specified did not match what we \
+                     received.",
+                ));
+            }
+        }
+        if let Some((checksum, expected)) = self.checksum {
+            let algorithm = checksum.algorithm();
+            if checksum.finalize() != expected {
+                return Err(bad_checksum(algorithm));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks the body of a request against its `Content-MD5` and
+/// `x-amz-checksum-*` headers.
+///
+/// The digests of `aws-chunked` bodies are the ones of their decoded
+/// payload, so they are not checked before they are decoded with
+/// [`decode_request`](crate::chunked::decode_request).
+pub fn check_request(
+    request: &http::Request<Bytes>,
+) -> Result<(), Box<S3Error>> {
+    if is_aws_chunked(request) {
+        return Ok(());
+    }
+    let mut checker = BodyChecker::from_headers(request.headers())?;
+    checker.update(request.body());
+    checker.finish()
+}
+
+/// Returns whether a value is a base64-encoded checksum of an algorithm.
+fn is_checksum(algorithm: ChecksumAlgorithm, value: &str) -> bool {
+    let len = match algorithm {
+        ChecksumAlgorithm::Sha256 => 32,
+        _ => 4,
+    };
+    STANDARD
+        .decode(value)
+        .is_ok_and(|checksum| checksum.len() == len)
+}
+
+/// Returns the `BadDigest` error of a body which doesn't match its checksum.
+pub(crate) fn bad_checksum(algorithm: ChecksumAlgorithm) -> Box<S3Error> {
+    let name = match algorithm {
+        ChecksumAlgorithm::Sha256 => "SHA256",
+        _ => "CRC32C",
+    };
+    error(
+        "BadDigest",
+        format!(
+            "The {} you specified did not match the calculated checksum.",
+            name
+        ),
+    )
+}
+
+fn error(code: &str, message: impl Into<String>) -> Box<S3Error> {
+    Box::new(S3Error::new(StatusCode::BAD_REQUEST, code).with_message(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use s3ers_signature::{
+        chunked::{Checksum, ChecksumAlgorithm},
+        md5::content_md5,
+    };
+
+    use super::check_request;
+
+    fn request(
+        headers: &[(&str, &str)],
+        body: &'static str,
+    ) -> http::Request<Bytes> {
+        let mut request = http::Request::put("/bucket/key");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(Bytes::from(body)).unwrap()
+    }
+
+    fn code(request: &http::Request<Bytes>) -> Option<String> {
+        check_request(request).err().map(|error| error.code.clone())
+    }
+
+    #[test]
+    fn check_digests() {
+        let md5 = content_md5(b"hello");
+        let crc32c = Checksum::compute(ChecksumAlgorithm::Crc32c, b"hello");
+        let sha256 = Checksum::compute(ChecksumAlgorithm::Sha256, b"hello");
+
+        assert_eq!(code(&request(&[], "hello")), None);
+        assert_eq!(code(&request(&[("content-md5", &md5)], "hello")), None);
+        assert_eq!(
+            code(&request(&[("x-amz-checksum-crc32c", &crc32c)], "hello")),
+            None
+        );
+        assert_eq!(
+            code(&request(
+                &[("content-md5", &md5), ("x-amz-checksum-sha256", &sha256)],
+                "hello"
+            )),
+            None
+        );
+        assert_eq!(
+            code(&request(&[("x-amz-checksum-crc32", "AAAAAA==")], "hello")),
+            None
+        );
+
+        let bad = |headers: &[(&str, &str)]| code(&request(headers, "hellO"));
+        assert_eq!(bad(&[("content-md5", &md5)]).unwrap(), "BadDigest");
+        let error = check_request(&request(
+            &[("x-amz-checksum-sha256", &sha256)],
+            "hellO",
+        ))
+        .unwrap_err();
+        assert_eq!(error.code, "BadDigest");
+        assert_eq!(
+            error.message.as_deref(),
+            Some("The SHA256 you specified did not match the calculated checksum.")
+        );
+        assert_eq!(
+            bad(&[("x-amz-checksum-crc32c", &crc32c)]).unwrap(),
+            "BadDigest"
+        );
+
+        assert_eq!(
+            bad(&[("content-encoding", "aws-chunked"), ("content-md5", &md5)]),
+            None
+        );
+    }
+
+    #[test]
+    fn reject_invalid_digests() {
+        let invalid = [
+            (("content-md5", "hello"), "InvalidDigest"),
+            (("content-md5", "aGVsbG8="), "InvalidDigest"),
+            (("x-amz-checksum-crc32c", "hello"), "InvalidRequest"),
+            (("x-amz-checksum-sha256", "AAAAAA=="), "InvalidRequest"),
+        ];
+        for (header, expected) in invalid {
+            let code = code(&request(&[header], "hello"));
+            assert_eq!(code.as_deref(), Some(expected), "{:?}", header);
+        }
+
+        let code = code(&request(
+            &[
+                ("x-amz-checksum-crc32c", "AAAAAA=="),
+                ("x-amz-checksum-crc32", "AAAAAA=="),
+            ],
+            "",
+        ));
+        assert_eq!(code.as_deref(), Some("InvalidRequest"));
+    }
+}
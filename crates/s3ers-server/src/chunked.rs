@@ -0,0 +1,260 @@
+//! Decoding the `aws-chunked` bodies of the uploads of AWS SDKs.
+//!
+//! The AWS SDKs send the bodies of uploads as `aws-chunked` chunks, each
+//! signed on its own, with a checksum of the payload in a trailer after the
+//! last chunk. The [`SigV4Verifier`](crate::auth::SigV4Verifier) decodes
+//! the bodies of the requests it authenticates with [`decode_request`],
+//! checking the signature of their chunks, and the [`Router`](crate::Router)
+//! decodes the other ones before dispatching them, so that handlers get the
+//! payload.
+//!
+//! ```
+//! use bytes::Bytes;
+//! use s3ers_server::chunked::{decode_request, is_aws_chunked};
+//!
+//! let mut request = http::Request::put("/bucket/key")
+//!     .header("content-encoding", "aws-chunked")
+//!     .header("x-amz-content-sha256", "STREAMING-UNSIGNED-PAYLOAD-TRAILER")
+//!     .header("x-amz-decoded-content-length", "5")
+//!     .body(Bytes::from_static(b"5\r\nhello\r\n0\r\n\r\n"))
+//!     .unwrap();
+//! assert!(is_aws_chunked(&request));
+//!
+//! decode_request(&mut request, None).unwrap();
+//! assert!(!is_aws_chunked(&request));
+//! assert_eq!(request.body(), "hello");
+//! assert_eq!(request.headers()["content-length"], "5");
+//! ```
+
+use std::convert::TryFrom;
+
+use bytes::Bytes;
+use http::{
+    header::{CONTENT_ENCODING, CONTENT_LENGTH},
+    HeaderName, HeaderValue, StatusCode,
+};
+use s3ers_api::error::S3Error;
+use s3ers_signature::chunked::{
+    decode_chunked_body, ChunkVerifier, DecodeError,
+    X_AMZ_DECODED_CONTENT_LENGTH, X_AMZ_TRAILER,
+};
+
+use crate::checksum::bad_checksum;
+
+/// Returns whether the body of a request is `aws-chunked`, and has not been
+/// decoded yet.
+pub fn is_aws_chunked<B>(request: &http::Request<B>) -> bool {
+    let headers = request.headers();
+    headers.contains_key(X_AMZ_DECODED_CONTENT_LENGTH)
+        || headers.get_all(CONTENT_ENCODING).iter().any(|value| {
+            value.to_str().is_ok_and(|value| {
+                value.split(',').any(|coding| {
+                    coding.trim().eq_ignore_ascii_case("aws-chunked")
+                })
+            })
+        })
+}
+
+/// Replaces the `aws-chunked` body of a request with its payload.
+///
+/// With a `verifier`, from the
+/// [`VerifiedRequest`](s3ers_signature::verify::VerifiedRequest) of the
+/// request, the signature of the chunks is checked. The payload has to be
+/// `x-amz-decoded-content-length` bytes long, and to match the checksum of
+/// its trailer, which is moved to the headers of the request, like the
+/// `x-amz-checksum-*` header of other uploads. The only trailers accepted
+/// are the `x-amz-checksum-*` ones named by the `x-amz-trailer` header, so
+/// that they can't replace the other headers. `aws-chunked` is removed
+/// from the `Content-Encoding` of the request, and its `Content-Length` set
+/// to the length of the payload.
+pub fn decode_request(
+    request: &mut http::Request<Bytes>,
+    verifier: Option<&mut ChunkVerifier>,
+) -> Result<(), Box<S3Error>> {
+    let decoded =
+        decode_chunked_body(request.body(), verifier).map_err(|error| {
+            match error {
+                DecodeError::SignatureDoesNotMatch => signature_mismatch(),
+                DecodeError::ChecksumMismatch(algorithm) => {
+                    bad_checksum(algorithm)
+                }
+                _ => incomplete_body(),
+            }
+        })?;
+
+    let declared = request
+        .headers()
+        .get_all(X_AMZ_TRAILER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    let mut trailers = Vec::new();
+    for (name, value) in decoded.trailers {
+        if !name.starts_with("x-amz-checksum-") || !declared.contains(&name) {
+            return Err(invalid_trailer(&name));
+        }
+        match (HeaderName::try_from(&name), HeaderValue::try_from(value)) {
+            (Ok(name), Ok(value)) => trailers.push((name, value)),
+            _ => return Err(invalid_trailer(&name)),
+        }
+    }
+
+    let headers = request.headers_mut();
+    if let Some(length) = headers.remove(X_AMZ_DECODED_CONTENT_LENGTH) {
+        let length = length
+            .to_str()
+            .ok()
+            .and_then(|length| length.parse::<usize>().ok());
+        if length != Some(decoded.payload.len()) {
+            return Err(incomplete_body());
+        }
+    }
+    let encoding = headers
+        .get_all(CONTENT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|coding| {
+            !coding.is_empty() && !coding.eq_ignore_ascii_case("aws-chunked")
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    match HeaderValue::try_from(encoding) {
+        Ok(encoding) if !encoding.is_empty() => {
+            headers.insert(CONTENT_ENCODING, encoding);
+        }
+        _ => {
+            headers.remove(CONTENT_ENCODING);
+        }
+    }
+    headers.insert(CONTENT_LENGTH, decoded.payload.len().into());
+    for (name, value) in trailers {
+        headers.insert(name, value);
+    }
+    *request.body_mut() = Bytes::from(decoded.payload);
+    Ok(())
+}
+
+fn signature_mismatch() -> Box<S3Error> {
+    Box::new(
+        S3Error::new(StatusCode::FORBIDDEN, "SignatureDoesNotMatch")
+            .with_message(
+                "The request signature we calculated does not match the \
+                 signature you provided.",
+            ),
+    )
+}
+
+fn invalid_trailer(name: &str) -> Box<S3Error> {
+    Box::new(
+        S3Error::new(StatusCode::BAD_REQUEST, "InvalidRequest").with_message(
+            format!(
+                "The trailer {} was not declared or is not supported.",
+                name
+            ),
+        ),
+    )
+}
+
+fn incomplete_body() -> Box<S3Error> {
+    Box::new(
+        S3Error::new(StatusCode::BAD_REQUEST, "IncompleteBody").with_message(
+            "You did not provide the number of bytes specified by the \
+             Content-Length HTTP header.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{decode_request, is_aws_chunked};
+
+    fn request(headers: &[(&str, &str)], body: &str) -> http::Request<Bytes> {
+        let mut request = http::Request::put("/bucket/key");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(Bytes::from(body.to_owned())).unwrap()
+    }
+
+    #[test]
+    fn decode_requests() {
+        // The CRC-32C check value of "123456789" is 0xe3069283.
+        let mut decoded = request(
+            &[
+                ("content-encoding", "aws-chunked, gzip"),
+                ("x-amz-decoded-content-length", "9"),
+                ("x-amz-trailer", "x-amz-checksum-crc32c"),
+            ],
+            "9\r\n123456789\r\n0\r\nx-amz-checksum-crc32c:4waSgw==\r\n\r\n",
+        );
+        assert!(is_aws_chunked(&decoded));
+        decode_request(&mut decoded, None).unwrap();
+        assert!(!is_aws_chunked(&decoded));
+        assert_eq!(decoded.body(), "123456789");
+        let headers = decoded.headers();
+        assert_eq!(headers["content-encoding"], "gzip");
+        assert_eq!(headers["content-length"], "9");
+        assert_eq!(headers["x-amz-checksum-crc32c"], "4waSgw==");
+
+        let mut plain = request(&[], "hello");
+        assert!(!is_aws_chunked(&plain));
+        decode_request(&mut plain, None).unwrap_err();
+    }
+
+    #[test]
+    fn reject_invalid_bodies() {
+        let invalid = [
+            ("5\r\nhello\r\n0\r\n\r\n", "4", "IncompleteBody"),
+            ("5\r\nhello\r\n0\r\n", "5", "IncompleteBody"),
+            (
+                "5\r\nhello\r\n0\r\nx-amz-checksum-crc32c:AAAAAA==\r\n\r\n",
+                "5",
+                "BadDigest",
+            ),
+        ];
+        for (body, length, code) in invalid {
+            let mut request = request(
+                &[
+                    ("content-encoding", "aws-chunked"),
+                    ("x-amz-decoded-content-length", length),
+                    ("x-amz-trailer", "x-amz-checksum-crc32c"),
+                ],
+                body,
+            );
+            let error = decode_request(&mut request, None).unwrap_err();
+            assert_eq!(error.code, code, "{:?}", body);
+        }
+    }
+
+    #[test]
+    fn reject_undeclared_trailers() {
+        let trailers = [
+            // Not a checksum, though declared.
+            ("x-amz-acl", "x-amz-acl:public-read"),
+            ("content-length", "content-length:1"),
+            // A checksum, but not declared.
+            ("x-amz-checksum-crc32", "x-amz-checksum-crc32c:mnG7TA=="),
+            ("", "x-amz-checksum-crc32c:mnG7TA=="),
+        ];
+        for (declared, trailer) in trailers {
+            let mut request = request(
+                &[
+                    ("content-encoding", "aws-chunked"),
+                    ("x-amz-acl", "private"),
+                    ("x-amz-decoded-content-length", "5"),
+                    ("x-amz-trailer", declared),
+                ],
+                &format!("5\r\nhello\r\n0\r\n{}\r\n\r\n", trailer),
+            );
+            let error = decode_request(&mut request, None).unwrap_err();
+            assert_eq!(error.code, "InvalidRequest", "{}", trailer);
+            assert_eq!(request.headers()["x-amz-acl"], "private");
+        }
+    }
+}
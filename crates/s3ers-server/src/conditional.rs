@@ -0,0 +1,364 @@
+//! Evaluating the conditional headers of requests against objects.
+//!
+//! The [`Preconditions`] of a request, its `If-Match`, `If-None-Match`,
+//! `If-Modified-Since` and `If-Unmodified-Since` headers, are evaluated
+//! against the entity tag and the modification time of the object it is
+//! for with the precedence rules of RFC 7232, deciding whether the request
+//! proceeds, or gets a `304 Not Modified` or `412 Precondition Failed`
+//! response.
+//!
+//! ```
+//! use http::{HeaderMap, HeaderValue, Method};
+//! use s3ers_api::header::HttpDate;
+//! use s3ers_server::conditional::{Evaluation, Preconditions};
+//!
+//! let mut headers = HeaderMap::new();
+//! headers.insert("if-none-match", HeaderValue::from_static("\"etag\""));
+//! let preconditions = Preconditions::from_headers(&headers);
+//!
+//! let modified = HttpDate::now();
+//! let evaluation =
+//!     preconditions.evaluate(&Method::GET, Some("\"etag\""), Some(modified));
+//! assert_eq!(evaluation, Evaluation::NotModified);
+//! assert_eq!(evaluation.into_result().unwrap_err().status, 304);
+//! ```
+
+use std::convert::TryFrom;
+
+use http::{
+    header::{IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_UNMODIFIED_SINCE},
+    HeaderMap, Method, StatusCode,
+};
+use s3ers_api::{error::S3Error, header::HttpDate};
+
+/// The conditional headers of a request.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Preconditions {
+    /// The entity tags of `If-Match`, or `*`.
+    pub if_match: Option<String>,
+
+    /// The entity tags of `If-None-Match`, or `*`.
+    pub if_none_match: Option<String>,
+
+    /// The date of `If-Modified-Since`.
+    pub if_modified_since: Option<HttpDate>,
+
+    /// The date of `If-Unmodified-Since`.
+    pub if_unmodified_since: Option<HttpDate>,
+}
+
+impl Preconditions {
+    /// Reads the conditional headers of a request.
+    ///
+    /// Headers which are not valid, like dates which can't be parsed, are
+    /// ignored, as RFC 7232 requires.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let string = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(ToOwned::to_owned)
+        };
+        let date = |name| {
+            headers
+                .get(name)
+                .and_then(|value| HttpDate::try_from(value).ok())
+        };
+        Self {
+            if_match: string(IF_MATCH),
+            if_none_match: string(IF_NONE_MATCH),
+            if_modified_since: date(IF_MODIFIED_SINCE),
+            if_unmodified_since: date(IF_UNMODIFIED_SINCE),
+        }
+    }
+
+    /// Evaluates the conditions of a request with a method against the
+    /// current object, given by its entity tag and its modification time,
+    /// or `None` if the object doesn't exist.
+    ///
+    /// The conditions are evaluated in the order of RFC 7232: `If-Match`,
+    /// or `If-Unmodified-Since` without it, then `If-None-Match`, or
+    /// `If-Modified-Since` without it for `GET` and `HEAD` requests.
+    pub fn evaluate(
+        &self,
+        method: &Method,
+        etag: Option<&str>,
+        last_modified: Option<HttpDate>,
+    ) -> Evaluation {
+        if let Some(if_match) = &self.if_match {
+            if !etag.is_some_and(|etag| matches(if_match, etag, false)) {
+                return Evaluation::PreconditionFailed("If-Match");
+            }
+        } else if let Some(since) = self.if_unmodified_since {
+            if last_modified.is_some_and(|modified| modified > since) {
+                return Evaluation::PreconditionFailed("If-Unmodified-Since");
+            }
+        }
+
+        let read = method == Method::GET || method == Method::HEAD;
+        if let Some(if_none_match) = &self.if_none_match {
+            if etag.is_some_and(|etag| matches(if_none_match, etag, true)) {
+                return match read {
+                    true => Evaluation::NotModified,
+                    false => Evaluation::PreconditionFailed("If-None-Match"),
+                };
+            }
+        } else if let Some(since) = self.if_modified_since.filter(|_| read) {
+            if last_modified.is_some_and(|modified| modified <= since) {
+                return Evaluation::NotModified;
+            }
+        }
+        Evaluation::Proceed
+    }
+}
+
+/// The decision on a conditional request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Evaluation {
+    /// The conditions hold, or there are none: the request proceeds.
+    Proceed,
+
+    /// The object wasn't changed, and the request gets a
+    /// `304 Not Modified` response.
+    NotModified,
+
+    /// A condition, the one of the given header, doesn't hold, and the
+    /// request gets a `412 Precondition Failed` response.
+    PreconditionFailed(&'static str),
+}
+
+impl Evaluation {
+    /// Returns the `NotModified` or `PreconditionFailed` error of S3 with
+    /// which requests which don't proceed are answered.
+    pub fn into_result(self) -> Result<(), Box<S3Error>> {
+        match self {
+            Self::Proceed => Ok(()),
+            Self::NotModified => Err(Box::new(S3Error::new(
+                StatusCode::NOT_MODIFIED,
+                "NotModified",
+            ))),
+            Self::PreconditionFailed(condition) => {
+                Err(Box::new(precondition_failed(condition)))
+            }
+        }
+    }
+}
+
+pub(crate) fn precondition_failed(condition: &str) -> S3Error {
+    S3Error::new(StatusCode::PRECONDITION_FAILED, "PreconditionFailed")
+        .with_message(
+            "At least one of the pre-conditions you specified did not hold.",
+        )
+        .with_resource(condition)
+}
+
+/// Returns whether the entity tags of a header, or `*`, match the one of an
+/// object.
+///
+/// S3 sends quoted entity tags, but clients sometimes send them without
+/// their quotes. Weak entity tags only match with the weak comparison of
+/// `If-None-Match`.
+fn matches(header: &str, etag: &str, weak: bool) -> bool {
+    let etag = etag.trim().trim_matches('"');
+    header.split(',').map(str::trim).any(|tag| {
+        if tag == "*" {
+            return true;
+        }
+        let tag = match tag.strip_prefix("W/") {
+            Some(_) if !weak => return false,
+            Some(tag) => tag,
+            None => tag,
+        };
+        tag.trim_matches('"') == etag
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use http::{HeaderMap, HeaderValue, Method};
+    use s3ers_api::header::HttpDate;
+
+    use super::{Evaluation, Preconditions};
+
+    const ETAG: &str = "\"d41d8cd98f00b204e9800998ecf8427e\"";
+
+    fn date(secs: u64) -> HttpDate {
+        (UNIX_EPOCH + Duration::from_secs(secs)).into()
+    }
+
+    fn evaluate(
+        headers: &[(&'static str, &'static str)],
+        method: Method,
+        exists: bool,
+    ) -> Evaluation {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(*name, HeaderValue::from_static(value));
+        }
+        let (etag, last_modified) = match exists {
+            // Sat, 01 Jan 2000 00:00:00 GMT
+            true => (Some(ETAG), Some(date(946_684_800))),
+            false => (None, None),
+        };
+        Preconditions::from_headers(&map).evaluate(&method, etag, last_modified)
+    }
+
+    #[test]
+    fn read_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("if-match", HeaderValue::from_static("*"));
+        headers.insert(
+            "if-modified-since",
+            HeaderValue::from_static("Sat, 01 Jan 2000 00:00:00 GMT"),
+        );
+        headers.insert(
+            "if-unmodified-since",
+            HeaderValue::from_static("yesterday"),
+        );
+        let preconditions = Preconditions::from_headers(&headers);
+        assert_eq!(preconditions.if_match.as_deref(), Some("*"));
+        assert_eq!(preconditions.if_none_match, None);
+        assert_eq!(preconditions.if_modified_since, Some(date(946_684_800)));
+        assert_eq!(preconditions.if_unmodified_since, None);
+    }
+
+    #[test]
+    fn evaluate_conditions() {
+        use Evaluation::*;
+
+        let before = "Fri, 31 Dec 1999 00:00:00 GMT";
+        let after = "Sun, 02 Jan 2000 00:00:00 GMT";
+        let cases = [
+            (vec![], Method::GET, true, Proceed),
+            (vec![("if-match", ETAG)], Method::GET, true, Proceed),
+            (
+                vec![("if-match", "\"other\", *")],
+                Method::GET,
+                true,
+                Proceed,
+            ),
+            (
+                vec![("if-match", "d41d8cd98f00b204e9800998ecf8427e")],
+                Method::GET,
+                true,
+                Proceed,
+            ),
+            (
+                vec![("if-match", "W/\"d41d8cd98f00b204e9800998ecf8427e\"")],
+                Method::GET,
+                true,
+                PreconditionFailed("If-Match"),
+            ),
+            (
+                vec![("if-match", "*")],
+                Method::PUT,
+                false,
+                PreconditionFailed("If-Match"),
+            ),
+            (
+                vec![("if-unmodified-since", before)],
+                Method::GET,
+                true,
+                PreconditionFailed("If-Unmodified-Since"),
+            ),
+            (
+                // If-Match takes precedence over If-Unmodified-Since.
+                vec![("if-match", ETAG), ("if-unmodified-since", before)],
+                Method::GET,
+                true,
+                Proceed,
+            ),
+            (
+                vec![("if-unmodified-since", after)],
+                Method::GET,
+                true,
+                Proceed,
+            ),
+            (
+                vec![("if-none-match", ETAG)],
+                Method::HEAD,
+                true,
+                NotModified,
+            ),
+            (
+                vec![(
+                    "if-none-match",
+                    "W/\"d41d8cd98f00b204e9800998ecf8427e\"",
+                )],
+                Method::GET,
+                true,
+                NotModified,
+            ),
+            (
+                vec![("if-none-match", "*")],
+                Method::PUT,
+                true,
+                PreconditionFailed("If-None-Match"),
+            ),
+            (vec![("if-none-match", "*")], Method::PUT, false, Proceed),
+            (
+                vec![("if-modified-since", after)],
+                Method::GET,
+                true,
+                NotModified,
+            ),
+            (
+                vec![("if-modified-since", before)],
+                Method::GET,
+                true,
+                Proceed,
+            ),
+            (
+                vec![("if-modified-since", after)],
+                Method::PUT,
+                true,
+                Proceed,
+            ),
+            (
+                // If-None-Match takes precedence over If-Modified-Since.
+                vec![
+                    ("if-none-match", "\"other\""),
+                    ("if-modified-since", after),
+                ],
+                Method::GET,
+                true,
+                Proceed,
+            ),
+            (
+                // If-Match is evaluated before If-None-Match.
+                vec![("if-match", "\"other\""), ("if-none-match", ETAG)],
+                Method::GET,
+                true,
+                PreconditionFailed("If-Match"),
+            ),
+        ];
+        for (headers, method, exists, expected) in cases {
+            assert_eq!(
+                evaluate(&headers, method.clone(), exists),
+                expected,
+                "{:?} {}",
+                headers,
+                method
+            );
+        }
+    }
+
+    #[test]
+    fn respond_with_errors() {
+        assert!(Evaluation::Proceed.into_result().is_ok());
+        let error = Evaluation::NotModified.into_result().unwrap_err();
+        assert_eq!(
+            (error.status.as_u16(), error.code.as_str()),
+            (304, "NotModified")
+        );
+        let error = Evaluation::PreconditionFailed("If-Match")
+            .into_result()
+            .unwrap_err();
+        assert_eq!(error.status, 412);
+        assert_eq!(error.resource.as_deref(), Some("If-Match"));
+    }
+}
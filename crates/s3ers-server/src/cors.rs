@@ -0,0 +1,475 @@
+//! Answering the CORS requests of browsers with the CORS rules of buckets.
+//!
+//! Servers keep the [`CorsConfiguration`] of each bucket, read from the
+//! `CORSConfiguration` document S3 stores. Requests for which
+//! [`is_preflight`] is true are answered by
+//! [`CorsConfiguration::preflight`] without going to their endpoint, and
+//! the responses to the other requests are given the headers of
+//! [`CorsConfiguration::apply`].
+//!
+//! ```
+//! use bytes::Bytes;
+//! use s3ers_server::cors::{is_preflight, CorsConfiguration, CorsRule};
+//!
+//! let cors = CorsConfiguration::new(vec![CorsRule::new(
+//!     vec!["https://example.com".to_owned()],
+//!     vec!["GET".to_owned()],
+//! )]);
+//! let request = http::Request::builder()
+//!     .method("OPTIONS")
+//!     .uri("/bucket/key")
+//!     .header("origin", "https://example.com")
+//!     .header("access-control-request-method", "GET")
+//!     .body(Bytes::new())
+//!     .unwrap();
+//! assert!(is_preflight(&request));
+//! let response = cors.preflight(&request);
+//! assert_eq!(
+//!     response.headers()["access-control-allow-origin"],
+//!     "https://example.com",
+//! );
+//! ```
+
+use std::convert::TryFrom;
+
+use bytes::Bytes;
+use http::{
+    header::{
+        ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+        ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+        ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE,
+        ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+        VARY,
+    },
+    HeaderMap, HeaderName, HeaderValue, Method,
+};
+use s3ers_api::{error::DeserializationError, xml::Element};
+
+use crate::ErrorResponse;
+
+/// The CORS configuration of a bucket.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CorsConfiguration {
+    /// The rules, of which the first one matching a request applies.
+    pub rules: Vec<CorsRule>,
+}
+
+/// A rule allowing requests from some origins.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CorsRule {
+    /// The ID of the rule.
+    pub id: Option<String>,
+
+    /// The origins allowed, like `https://example.com`, which can contain
+    /// one `*` wildcard, like `https://*.example.com` or `*`.
+    pub allowed_origins: Vec<String>,
+
+    /// The methods allowed, among `GET`, `PUT`, `POST`, `DELETE` and
+    /// `HEAD`.
+    pub allowed_methods: Vec<String>,
+
+    /// The headers that preflight requests can ask to send, which can
+    /// contain one `*` wildcard.
+    pub allowed_headers: Vec<String>,
+
+    /// The headers of responses that browsers let applications read.
+    pub expose_headers: Vec<String>,
+
+    /// How long browsers can cache the response to a preflight request, in
+    /// seconds.
+    pub max_age_seconds: Option<u32>,
+}
+
+impl CorsConfiguration {
+    /// Creates a configuration with the given rules.
+    pub fn new(rules: Vec<CorsRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Parses a `CORSConfiguration` XML document.
+    pub fn from_xml(xml: &[u8]) -> Result<Self, DeserializationError> {
+        let root = Element::parse(xml)?;
+        let rules = root
+            .children("CORSRule")
+            .map(CorsRule::from_xml)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Writes the configuration as a `CORSConfiguration` XML document.
+    pub fn to_xml(&self) -> String {
+        let document = self
+            .rules
+            .iter()
+            .fold(Element::new("CORSConfiguration"), |document, rule| {
+                document.with_child(rule.to_xml())
+            });
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}",
+            document.to_xml()
+        )
+    }
+
+    /// Answers a preflight request, with the `Access-Control-*` headers of
+    /// the first rule allowing its origin, its method and all its headers,
+    /// or a `403 Forbidden` error if there is none, and a `400 Bad Request`
+    /// error if it isn't a preflight request.
+    pub fn preflight<B>(
+        &self,
+        request: &http::Request<B>,
+    ) -> http::Response<Bytes> {
+        let headers = request.headers();
+        let origin = header(headers, &ORIGIN);
+        let method = header(headers, &ACCESS_CONTROL_REQUEST_METHOD);
+        let (origin, method) = match (origin, method) {
+            (Some(origin), Some(method)) => (origin, method),
+            _ => {
+                return ErrorResponse::new("BadRequest")
+                    .with_message(
+                        "Insufficient information. Origin request header \
+                         needed.",
+                    )
+                    .into_http_response()
+            }
+        };
+        let requested_headers: Vec<_> =
+            header(headers, &ACCESS_CONTROL_REQUEST_HEADERS)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .collect();
+        let rule = self.rules.iter().find(|rule| {
+            rule.allows(origin, method)
+                && requested_headers
+                    .iter()
+                    .all(|name| rule.allows_header(name))
+        });
+        let rule = match rule {
+            Some(rule) => rule,
+            None => {
+                return ErrorResponse::new("AccessForbidden")
+                    .with_message(
+                        "CORSResponse: This CORS request is not allowed.",
+                    )
+                    .with_detail("Method", method)
+                    .into_http_response()
+            }
+        };
+
+        let mut response = http::Response::new(Bytes::new());
+        let response_headers = response.headers_mut();
+        rule.insert_headers(origin, response_headers);
+        if !requested_headers.is_empty() {
+            insert(
+                response_headers,
+                ACCESS_CONTROL_ALLOW_HEADERS,
+                &requested_headers.join(", "),
+            );
+        }
+        insert(
+            response_headers,
+            VARY,
+            "Origin, Access-Control-Request-Headers, \
+             Access-Control-Request-Method",
+        );
+        response
+    }
+
+    /// Adds the `Access-Control-*` headers of the first rule allowing the
+    /// origin and the method of a request to its response, if it has an
+    /// origin and there is such a rule.
+    pub fn apply<B, C>(
+        &self,
+        request: &http::Request<B>,
+        response: &mut http::Response<C>,
+    ) {
+        let origin = match header(request.headers(), &ORIGIN) {
+            Some(origin) => origin,
+            None => return,
+        };
+        let method = request.method().as_str();
+        if let Some(rule) =
+            self.rules.iter().find(|rule| rule.allows(origin, method))
+        {
+            let headers = response.headers_mut();
+            rule.insert_headers(origin, headers);
+            headers.append(VARY, HeaderValue::from_static("Origin"));
+        }
+    }
+}
+
+impl CorsRule {
+    /// Creates a rule allowing the given methods from the given origins.
+    pub fn new(
+        allowed_origins: Vec<String>,
+        allowed_methods: Vec<String>,
+    ) -> Self {
+        Self {
+            allowed_origins,
+            allowed_methods,
+            ..Self::default()
+        }
+    }
+
+    fn from_xml(element: &Element) -> Result<Self, DeserializationError> {
+        let texts = |name| {
+            element
+                .children(name)
+                .map(|child| child.text.clone())
+                .collect::<Vec<_>>()
+        };
+        let rule = Self {
+            id: element.child_string("ID"),
+            allowed_origins: texts("AllowedOrigin"),
+            allowed_methods: texts("AllowedMethod"),
+            allowed_headers: texts("AllowedHeader"),
+            expose_headers: texts("ExposeHeader"),
+            max_age_seconds: element.parse_child("MaxAgeSeconds")?,
+        };
+        if rule.allowed_origins.is_empty() {
+            return Err(DeserializationError::Missing(
+                "AllowedOrigin".to_owned(),
+            ));
+        }
+        if rule.allowed_methods.is_empty() {
+            return Err(DeserializationError::Missing(
+                "AllowedMethod".to_owned(),
+            ));
+        }
+        Ok(rule)
+    }
+
+    fn to_xml(&self) -> Element {
+        let mut element = Element::new("CORSRule");
+        if let Some(id) = &self.id {
+            element = element.with_child(Element::with_text("ID", id));
+        }
+        let lists = [
+            ("AllowedHeader", &self.allowed_headers),
+            ("AllowedMethod", &self.allowed_methods),
+            ("AllowedOrigin", &self.allowed_origins),
+            ("ExposeHeader", &self.expose_headers),
+        ];
+        for (name, values) in lists {
+            for value in values {
+                element = element.with_child(Element::with_text(name, value));
+            }
+        }
+        if let Some(max_age) = self.max_age_seconds {
+            element = element.with_child(Element::with_text(
+                "MaxAgeSeconds",
+                max_age.to_string(),
+            ));
+        }
+        element
+    }
+
+    /// Returns whether the rule allows requests with a method from an
+    /// origin.
+    fn allows(&self, origin: &str, method: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| wildcard_match(allowed, origin))
+            && self.allowed_methods.iter().any(|allowed| allowed == method)
+    }
+
+    /// Returns whether the rule allows preflight requests to ask to send a
+    /// header.
+    fn allows_header(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.allowed_headers
+            .iter()
+            .any(|allowed| wildcard_match(&allowed.to_ascii_lowercase(), &name))
+    }
+
+    /// Inserts the headers of the rule in the response to a request from an
+    /// origin.
+    fn insert_headers(&self, origin: &str, headers: &mut HeaderMap) {
+        // Rules allowing any origin allow requests without credentials, the
+        // others echo the origin and allow credentials.
+        if self.allowed_origins.iter().any(|allowed| allowed == "*") {
+            insert(headers, ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+        } else {
+            insert(headers, ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+            insert(headers, ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+        }
+        insert(
+            headers,
+            ACCESS_CONTROL_ALLOW_METHODS,
+            &self.allowed_methods.join(", "),
+        );
+        if !self.expose_headers.is_empty() {
+            insert(
+                headers,
+                ACCESS_CONTROL_EXPOSE_HEADERS,
+                &self.expose_headers.join(", "),
+            );
+        }
+        if let Some(max_age) = self.max_age_seconds {
+            insert(headers, ACCESS_CONTROL_MAX_AGE, &max_age.to_string());
+        }
+    }
+}
+
+/// Returns whether a request is a CORS preflight request, an `OPTIONS`
+/// request with an `Origin` and an `Access-Control-Request-Method` header.
+pub fn is_preflight<B>(request: &http::Request<B>) -> bool {
+    request.method() == Method::OPTIONS
+        && request.headers().contains_key(ORIGIN)
+        && request
+            .headers()
+            .contains_key(ACCESS_CONTROL_REQUEST_METHOD)
+}
+
+/// Returns whether a value matches a pattern with at most one `*` wildcard.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            value.len() >= prefix.len() + suffix.len()
+                && value.starts_with(prefix)
+                && value.ends_with(suffix)
+        }
+        None => pattern == value,
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Inserts a header, unless its value can't be sent, like the ones of
+/// origins with line breaks.
+fn insert(headers: &mut HeaderMap, name: HeaderName, value: &str) {
+    if let Ok(value) = HeaderValue::try_from(value) {
+        headers.insert(name, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use http::{Method, StatusCode};
+
+    use super::{is_preflight, CorsConfiguration};
+
+    const CONFIGURATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<CORSConfiguration>
+  <CORSRule>
+    <AllowedOrigin>https://*.example.com</AllowedOrigin>
+    <AllowedMethod>GET</AllowedMethod>
+    <AllowedMethod>PUT</AllowedMethod>
+    <AllowedHeader>x-amz-*</AllowedHeader>
+    <AllowedHeader>Content-Type</AllowedHeader>
+    <ExposeHeader>ETag</ExposeHeader>
+    <MaxAgeSeconds>3000</MaxAgeSeconds>
+  </CORSRule>
+  <CORSRule>
+    <AllowedOrigin>*</AllowedOrigin>
+    <AllowedMethod>GET</AllowedMethod>
+  </CORSRule>
+</CORSConfiguration>"#;
+
+    fn request(
+        method: Method,
+        headers: &[(&str, &str)],
+    ) -> http::Request<Bytes> {
+        let mut request = http::Request::builder().method(method).uri("/b/k");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(Bytes::new()).unwrap()
+    }
+
+    #[test]
+    fn parse_configuration() {
+        let cors =
+            CorsConfiguration::from_xml(CONFIGURATION.as_bytes()).unwrap();
+        assert_eq!(cors.rules.len(), 2);
+        assert_eq!(cors.rules[0].allowed_headers, ["x-amz-*", "Content-Type"]);
+        assert_eq!(cors.rules[0].max_age_seconds, Some(3000));
+        let xml = cors.to_xml();
+        assert_eq!(CorsConfiguration::from_xml(xml.as_bytes()).unwrap(), cors);
+        assert!(CorsConfiguration::from_xml(
+            b"<CORSConfiguration><CORSRule><AllowedMethod>GET</AllowedMethod>\
+              </CORSRule></CORSConfiguration>"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn answer_preflight_requests() {
+        let cors =
+            CorsConfiguration::from_xml(CONFIGURATION.as_bytes()).unwrap();
+        let preflight = request(
+            Method::OPTIONS,
+            &[
+                ("origin", "https://app.example.com"),
+                ("access-control-request-method", "PUT"),
+                ("access-control-request-headers", "X-Amz-Date, content-type"),
+            ],
+        );
+        assert!(is_preflight(&preflight));
+        let response = cors.preflight(&preflight);
+        let headers = response.headers();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.example.com"
+        );
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+        assert_eq!(headers["access-control-allow-methods"], "GET, PUT");
+        assert_eq!(
+            headers["access-control-allow-headers"],
+            "X-Amz-Date, content-type"
+        );
+        assert_eq!(headers["access-control-max-age"], "3000");
+
+        let preflight = request(
+            Method::OPTIONS,
+            &[
+                ("origin", "https://other.com"),
+                ("access-control-request-method", "GET"),
+            ],
+        );
+        let response = cors.preflight(&preflight);
+        assert_eq!(response.headers()["access-control-allow-origin"], "*");
+        assert!(!response
+            .headers()
+            .contains_key("access-control-allow-credentials"));
+
+        let preflight = request(
+            Method::OPTIONS,
+            &[
+                ("origin", "https://other.com"),
+                ("access-control-request-method", "PUT"),
+            ],
+        );
+        assert_eq!(cors.preflight(&preflight).status(), StatusCode::FORBIDDEN);
+        let preflight = request(Method::OPTIONS, &[]);
+        assert!(!is_preflight(&preflight));
+        assert_eq!(
+            cors.preflight(&preflight).status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn apply_to_responses() {
+        let cors =
+            CorsConfiguration::from_xml(CONFIGURATION.as_bytes()).unwrap();
+        let get = request(Method::GET, &[("origin", "https://a.example.com")]);
+        let mut response = http::Response::new(());
+        cors.apply(&get, &mut response);
+        assert_eq!(response.headers()["access-control-expose-headers"], "ETag");
+        assert_eq!(response.headers()["vary"], "Origin");
+
+        let delete =
+            request(Method::DELETE, &[("origin", "https://a.example.com")]);
+        let mut response = http::Response::new(());
+        cors.apply(&delete, &mut response);
+        assert!(response.headers().is_empty());
+    }
+}
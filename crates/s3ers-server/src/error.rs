@@ -0,0 +1,322 @@
+//! Building the XML error responses of S3.
+
+use std::{collections::BTreeMap, convert::TryFrom, time::Duration};
+
+use bytes::Bytes;
+use http::{
+    header::{CONTENT_TYPE, RETRY_AFTER},
+    HeaderValue, StatusCode,
+};
+use s3ers_api::{error::S3Error, xml::Element};
+
+use crate::RequestId;
+
+/// An S3 error response, with its `Error` XML document.
+///
+/// The status code of a response is the one of its code, like
+/// `404 Not Found` for `NoSuchKey`, unless it is set otherwise.
+///
+/// ```
+/// use s3ers_server::ErrorResponse;
+///
+/// let response = ErrorResponse::new("NoSuchKey")
+///     .with_message("The specified key does not exist.")
+///     .with_resource("/bucket/key")
+///     .with_request_id("4442587FB7D0A2F9")
+///     .into_http_response();
+/// assert_eq!(response.status(), 404);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorResponse {
+    status: StatusCode,
+    code: String,
+    message: Option<String>,
+    resource: Option<String>,
+    request_id: Option<String>,
+    host_id: Option<String>,
+    retry_after: Option<Duration>,
+    bucket_region: Option<String>,
+    details: BTreeMap<String, String>,
+}
+
+impl ErrorResponse {
+    /// Creates the response of an error code, with its status code.
+    pub fn new(code: impl Into<String>) -> Self {
+        let code = code.into();
+        Self {
+            status: Self::status_of(&code),
+            code,
+            message: None,
+            resource: None,
+            request_id: None,
+            host_id: None,
+            retry_after: None,
+            bucket_region: None,
+            details: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the status code S3 returns with an error code, or
+    /// `500 Internal Server Error` for unknown codes.
+    pub fn status_of(code: &str) -> StatusCode {
+        match code {
+            "NotModified" => StatusCode::NOT_MODIFIED,
+            "PermanentRedirect" => StatusCode::MOVED_PERMANENTLY,
+            "TemporaryRedirect" => StatusCode::TEMPORARY_REDIRECT,
+            "AuthorizationHeaderMalformed"
+            | "BadDigest"
+            | "BadRequest"
+            | "CredentialsNotSupported"
+            | "EntityTooLarge"
+            | "EntityTooSmall"
+            | "ExpiredToken"
+            | "IllegalLocationConstraintException"
+            | "IncompleteBody"
+            | "InvalidArgument"
+            | "InvalidBucketName"
+            | "InvalidDigest"
+            | "InvalidEncryptionAlgorithmError"
+            | "InvalidPart"
+            | "InvalidPartOrder"
+            | "InvalidPolicyDocument"
+            | "InvalidRequest"
+            | "InvalidStorageClass"
+            | "InvalidTag"
+            | "InvalidToken"
+            | "KeyTooLongError"
+            | "MalformedPOSTRequest"
+            | "MalformedPolicy"
+            | "MalformedXML"
+            | "MetadataTooLarge"
+            | "MissingRequestBodyError"
+            | "RequestTimeout"
+            | "TooManyBuckets"
+            | "UnexpectedContent"
+            | "XAmzContentSHA256Mismatch" => StatusCode::BAD_REQUEST,
+            "AccessDenied"
+            | "AccessForbidden"
+            | "AccountProblem"
+            | "AllAccessDisabled"
+            | "InvalidAccessKeyId"
+            | "InvalidObjectState"
+            | "InvalidSecurity"
+            | "RequestTimeTooSkewed"
+            | "SignatureDoesNotMatch" => StatusCode::FORBIDDEN,
+            "NoSuchBucket"
+            | "NoSuchBucketPolicy"
+            | "NoSuchCORSConfiguration"
+            | "NoSuchKey"
+            | "NoSuchLifecycleConfiguration"
+            | "NoSuchTagSet"
+            | "NoSuchUpload"
+            | "NoSuchVersion"
+            | "NotFound" => StatusCode::NOT_FOUND,
+            "MethodNotAllowed" => StatusCode::METHOD_NOT_ALLOWED,
+            "BucketAlreadyExists"
+            | "BucketAlreadyOwnedByYou"
+            | "BucketNotEmpty"
+            | "InvalidBucketState"
+            | "OperationAborted"
+            | "RestoreAlreadyInProgress" => StatusCode::CONFLICT,
+            "MissingContentLength" => StatusCode::LENGTH_REQUIRED,
+            "PreconditionFailed" => StatusCode::PRECONDITION_FAILED,
+            "InvalidRange" => StatusCode::RANGE_NOT_SATISFIABLE,
+            "NotImplemented" => StatusCode::NOT_IMPLEMENTED,
+            "ServiceUnavailable" | "SlowDown" => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Sets the status code, in place of the one of the code.
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Sets the human-readable description of the error.
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Sets the bucket or object the error is about.
+    pub fn with_resource(mut self, resource: impl Into<String>) -> Self {
+        self.resource = Some(resource.into());
+        self
+    }
+
+    /// Sets the ID of the request, also sent in the `x-amz-request-id`
+    /// header.
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// Sets the extended ID of the request, also sent in the `x-amz-id-2`
+    /// header.
+    pub fn with_host_id(mut self, host_id: impl Into<String>) -> Self {
+        self.host_id = Some(host_id.into());
+        self
+    }
+
+    /// Sets the IDs of the request the error is for, unless they are set
+    /// already.
+    pub(crate) fn with_ids(mut self, ids: Option<&RequestId>) -> Self {
+        if let Some(ids) = ids {
+            self.request_id
+                .get_or_insert_with(|| ids.request_id.clone());
+            self.host_id.get_or_insert_with(|| ids.host_id.clone());
+        }
+        self
+    }
+
+    /// Adds an element specific to the code, like `Region` for
+    /// `AuthorizationHeaderMalformed`.
+    pub fn with_detail(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.details.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns the status code of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the error code of the response.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Returns the `Error` XML document of the response.
+    pub fn to_xml(&self) -> String {
+        let mut document = Element::new("Error")
+            .with_child(Element::with_text("Code", &self.code));
+        if let Some(message) = &self.message {
+            document =
+                document.with_child(Element::with_text("Message", message));
+        }
+        for (name, value) in &self.details {
+            document = document.with_child(Element::with_text(name, value));
+        }
+        let elements = [
+            ("Resource", &self.resource),
+            ("RequestId", &self.request_id),
+            ("HostId", &self.host_id),
+        ];
+        for (name, value) in elements {
+            if let Some(value) = value {
+                document = document.with_child(Element::with_text(name, value));
+            }
+        }
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}",
+            document.to_xml()
+        )
+    }
+
+    /// Returns the HTTP response, with the XML document as its body.
+    ///
+    /// Values that can't be sent in headers, like request IDs with line
+    /// breaks, are only in the body.
+    pub fn into_http_response(self) -> http::Response<Bytes> {
+        let mut response = http::Response::new(Bytes::from(self.to_xml()));
+        *response.status_mut() = self.status;
+        let headers = response.headers_mut();
+        headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/xml"));
+        let values = [
+            ("x-amz-request-id", self.request_id),
+            ("x-amz-id-2", self.host_id),
+            ("x-amz-bucket-region", self.bucket_region),
+            (
+                RETRY_AFTER.as_str(),
+                self.retry_after.map(|after| after.as_secs().to_string()),
+            ),
+        ];
+        for (name, value) in values {
+            if let Some(Ok(value)) = value.map(HeaderValue::try_from) {
+                headers.insert(name, value);
+            }
+        }
+        response
+    }
+}
+
+/// Keeps the status code of the error, and the values of the headers it
+/// was read from, like `Retry-After`.
+impl From<&S3Error> for ErrorResponse {
+    fn from(error: &S3Error) -> Self {
+        Self {
+            status: error.status,
+            code: error.code.clone(),
+            message: error.message.clone(),
+            resource: error.resource.clone(),
+            request_id: error.request_id.clone(),
+            host_id: error.host_id.clone(),
+            retry_after: error.retry_after,
+            bucket_region: error.bucket_region.clone(),
+            details: error.details.clone(),
+        }
+    }
+}
+
+impl From<S3Error> for ErrorResponse {
+    fn from(error: S3Error) -> Self {
+        Self::from(&error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+    use s3ers_api::error::S3Error;
+
+    use super::ErrorResponse;
+
+    #[test]
+    fn status_of_codes() {
+        assert_eq!(ErrorResponse::status_of("NoSuchUpload"), 404);
+        assert_eq!(ErrorResponse::status_of("SignatureDoesNotMatch"), 403);
+        assert_eq!(ErrorResponse::status_of("InvalidRange"), 416);
+        assert_eq!(ErrorResponse::status_of("SlowDown"), 503);
+        assert_eq!(ErrorResponse::status_of("Unknown"), 500);
+        let response = ErrorResponse::new("NoSuchKey")
+            .with_status(StatusCode::GONE)
+            .into_http_response();
+        assert_eq!(response.status(), StatusCode::GONE);
+    }
+
+    #[test]
+    fn error_document() {
+        let response = ErrorResponse::new("AuthorizationHeaderMalformed")
+            .with_message("The region 'us-east-1' is wrong.")
+            .with_detail("Region", "eu-west-1")
+            .with_resource("/bucket")
+            .with_request_id("4442587FB7D0A2F9")
+            .into_http_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()["x-amz-request-id"], "4442587FB7D0A2F9");
+        assert_eq!(
+            response.body(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Error>\
+             <Code>AuthorizationHeaderMalformed</Code>\
+             <Message>The region &apos;us-east-1&apos; is wrong.</Message>\
+             <Region>eu-west-1</Region><Resource>/bucket</Resource>\
+             <RequestId>4442587FB7D0A2F9</RequestId></Error>"
+        );
+
+        let error = S3Error::from_http_response(&response);
+        assert_eq!(error.code, "AuthorizationHeaderMalformed");
+        assert_eq!(error.details["Region"], "eu-west-1");
+        assert_eq!(
+            ErrorResponse::from(&error).into_http_response().body(),
+            response.body()
+        );
+    }
+}
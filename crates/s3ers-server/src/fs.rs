@@ -0,0 +1,837 @@
+//! An S3 server storing its buckets and objects in a directory of the local
+//! filesystem, with the `fs` feature, as an example implementation of
+//! [`S3Handler`] and a stand-in for S3 during local development.
+//!
+//! ```no_run
+//! use s3ers_server::fs::FsServer;
+//!
+//! let router = FsServer::new("/var/lib/s3").router();
+//! // Serve `router`, with a `hyper::RouterService` for example.
+//! ```
+
+use std::{
+    io::{self, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use http::{Extensions, Method, StatusCode};
+use s3ers_api::{error::S3Error, header::HttpDate};
+use s3ers_s3_api::{
+    bucket::{head_bucket, list_objects_v2},
+    multipart::{
+        abort_multipart_upload, complete_multipart_upload,
+        create_multipart_upload, list_parts, upload_part,
+    },
+    object::{copy_object, get_object, head_object, put_object},
+    WriteCondition,
+};
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+
+use crate::{
+    backend::{self, no_such_bucket, no_such_key},
+    conditional::{precondition_failed, Preconditions},
+    pagination::{max_keys, paginate},
+    range::content_range,
+    Router, S3Handler,
+};
+
+/// The directory of the root where the server keeps its own files.
+const STATE_DIR: &str = ".s3ers";
+
+/// An S3 server storing each bucket in a directory of its root, and each
+/// object in a file of its bucket at the path of its key, so that the
+/// object `a/b` is the file `b` of the directory `a`.
+///
+/// Buckets are the directories of the root whose name doesn't start with a
+/// dot, and are created up front, since there are no endpoints to create
+/// them. The server keeps the entity tags and content types of the objects
+/// it stores, and the multipart uploads in progress, in the `.s3ers`
+/// directory of the root. Files added to buckets by other means are served
+/// too, with an entity tag computed from their content.
+///
+/// Objects are written to temporary files that are then renamed, so that
+/// readers never see partial objects, but conditional writes aren't atomic.
+/// Keys can't have empty, `.` or `..` segments, and an object can't be
+/// stored at a key that other keys start with followed by a `/`, since its
+/// file would be a directory. Versioning, copying parts and listing
+/// multipart uploads aren't implemented.
+#[derive(Clone, Debug)]
+pub struct FsServer {
+    root: PathBuf,
+    next_id: Arc<AtomicU64>,
+}
+
+/// An object stored by the server.
+struct Object {
+    path: PathBuf,
+    len: u64,
+    etag: String,
+    content_type: Option<String>,
+    last_modified: HttpDate,
+}
+
+impl FsServer {
+    /// Creates a server storing its buckets in `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            next_id: Arc::default(),
+        }
+    }
+
+    /// Returns a router handing the requests it receives to the server.
+    pub fn router(&self) -> Router {
+        Router::new().with_handler(self.clone())
+    }
+
+    /// Returns a new ID, unique across restarts of the server.
+    fn next_id(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let count = self.next_id.fetch_add(1, Ordering::Relaxed);
+        format!("{:016x}{:016x}", now, count)
+    }
+
+    /// Returns the directory of a bucket, if it exists.
+    async fn bucket_dir(&self, bucket: &str) -> Result<PathBuf, Box<S3Error>> {
+        if bucket.is_empty() || bucket.starts_with('.') || bucket.contains('/')
+        {
+            return Err(Box::new(no_such_bucket(bucket)));
+        }
+        let path = self.root.join(bucket);
+        match fs::metadata(&path).await {
+            Ok(metadata) if metadata.is_dir() => Ok(path),
+            _ => Err(Box::new(no_such_bucket(bucket))),
+        }
+    }
+
+    /// Returns the path of the metadata of an object.
+    fn metadata_path(&self, bucket: &str, key: &str) -> PathBuf {
+        self.root
+            .join(STATE_DIR)
+            .join("metadata")
+            .join(bucket)
+            .join(key)
+    }
+
+    /// Returns the directory of a multipart upload.
+    fn upload_dir(&self, bucket: &str, upload_id: &str) -> PathBuf {
+        self.root
+            .join(STATE_DIR)
+            .join("uploads")
+            .join(bucket)
+            .join(upload_id)
+    }
+
+    /// Returns an object, if it exists.
+    async fn find_object(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<Object, Box<S3Error>> {
+        let path = self.bucket_dir(bucket).await?.join(check_key(key)?);
+        let metadata = match fs::metadata(&path).await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => return Err(Box::new(no_such_key(bucket, key))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(Box::new(no_such_key(bucket, key)))
+            }
+            Err(err) => return Err(internal_error(err)),
+        };
+        let last_modified =
+            HttpDate::from(metadata.modified().map_err(internal_error)?);
+
+        // The first line of the metadata is the entity tag, and the second
+        // one the content type, if there is one.
+        let stored = fs::read_to_string(self.metadata_path(bucket, key))
+            .await
+            .unwrap_or_default();
+        let mut lines = stored.lines();
+        let etag = match lines.next().filter(|etag| !etag.is_empty()) {
+            Some(etag) => etag.to_owned(),
+            None => {
+                backend::etag(&fs::read(&path).await.map_err(internal_error)?)
+            }
+        };
+        let content_type = lines
+            .next()
+            .filter(|content_type| !content_type.is_empty())
+            .map(str::to_owned);
+        Ok(Object {
+            path,
+            len: metadata.len(),
+            etag,
+            content_type,
+            last_modified,
+        })
+    }
+
+    /// Stores an object and its metadata.
+    async fn store(
+        &self,
+        bucket: &str,
+        key: &str,
+        data: &[u8],
+        etag: &str,
+        content_type: Option<&str>,
+    ) -> Result<(), Box<S3Error>> {
+        let path = self.bucket_dir(bucket).await?.join(check_key(key)?);
+        let metadata =
+            format!("{}\n{}\n", etag, content_type.unwrap_or_default());
+        self.write(&self.metadata_path(bucket, key), metadata.as_bytes())
+            .await?;
+        self.write(&path, data).await
+    }
+
+    /// Writes a file to a temporary path, then renames it to `path`.
+    async fn write(
+        &self,
+        path: &Path,
+        data: &[u8],
+    ) -> Result<(), Box<S3Error>> {
+        let temp_dir = self.root.join(STATE_DIR).join("tmp");
+        fs::create_dir_all(&temp_dir)
+            .await
+            .map_err(internal_error)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(internal_error)?;
+        }
+        let temp = temp_dir.join(self.next_id());
+        fs::write(&temp, data).await.map_err(internal_error)?;
+        if let Err(err) = fs::rename(&temp, path).await {
+            let _ = fs::remove_file(&temp).await;
+            return Err(internal_error(err));
+        }
+        Ok(())
+    }
+
+    /// Checks the condition of a write against the current object.
+    async fn check_condition(
+        &self,
+        bucket: &str,
+        key: &str,
+        condition: Option<&WriteCondition>,
+    ) -> Result<(), Box<S3Error>> {
+        let condition = match condition {
+            Some(condition) => condition,
+            None => return Ok(()),
+        };
+        let current = match self.find_object(bucket, key).await {
+            Ok(object) => Some(object),
+            Err(error) if error.code == "NoSuchKey" => None,
+            Err(error) => return Err(error),
+        };
+        match (condition, current) {
+            (WriteCondition::NotExists, Some(_)) => {
+                Err(Box::new(precondition_failed("If-None-Match")))
+            }
+            (WriteCondition::Matches(_), None) => {
+                Err(Box::new(no_such_key(bucket, key)))
+            }
+            (WriteCondition::Matches(etag), Some(object))
+                if *etag != object.etag =>
+            {
+                Err(Box::new(precondition_failed("If-Match")))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the directory of a multipart upload of an object, and the
+    /// content type of the object.
+    async fn find_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(PathBuf, Option<String>), Box<S3Error>> {
+        self.bucket_dir(bucket).await?;
+        let no_such_upload = || Box::new(backend::no_such_upload(upload_id));
+        if upload_id.is_empty()
+            || !upload_id.bytes().all(|byte| byte.is_ascii_hexdigit())
+        {
+            return Err(no_such_upload());
+        }
+        let dir = self.upload_dir(bucket, upload_id);
+        // The first line of the upload is the content type, and the rest the
+        // key, which can span lines.
+        let upload = fs::read_to_string(dir.join("upload"))
+            .await
+            .map_err(|_| no_such_upload())?;
+        let (content_type, upload_key) =
+            upload.split_once('\n').ok_or_else(no_such_upload)?;
+        if upload_key != key {
+            return Err(no_such_upload());
+        }
+        let content_type = Some(content_type.to_owned())
+            .filter(|content_type| !content_type.is_empty());
+        Ok((dir, content_type))
+    }
+}
+
+#[async_trait]
+impl S3Handler for FsServer {
+    async fn head_bucket(
+        &self,
+        request: head_bucket::Request,
+        _: Extensions,
+    ) -> Result<head_bucket::Response, S3Error> {
+        self.bucket_dir(&request.bucket).await?;
+        Ok(head_bucket::Response::default())
+    }
+
+    async fn list_objects_v2(
+        &self,
+        request: list_objects_v2::Request,
+        _: Extensions,
+    ) -> Result<list_objects_v2::Response, S3Error> {
+        let dir = self.bucket_dir(&request.bucket).await?;
+        let prefix = request.prefix.as_deref().unwrap_or_default();
+        let mut keys = walk(&dir, prefix).await.map_err(internal_error)?;
+        keys.sort();
+
+        // The token is the last key or common prefix of the previous page.
+        let after = request.continuation_token.max(request.start_after);
+        let page = paginate(
+            keys.iter().map(|key| (key.as_str(), ())),
+            request.prefix.as_deref(),
+            request.delimiter.as_deref(),
+            request.max_keys,
+            |entry, _| after.as_deref().is_some_and(|after| entry <= after),
+        );
+
+        let mut response = list_objects_v2::Response::default();
+        response.key_count = page.key_count();
+        for (key, ()) in page.items {
+            let stored = self.find_object(&request.bucket, &key).await?;
+            let mut object = list_objects_v2::Object::new(key, stored.len);
+            object.etag = Some(stored.etag);
+            object.last_modified = Some(stored.last_modified);
+            object.storage_class = Some("STANDARD".to_owned());
+            response.contents.push(object);
+        }
+        response.common_prefixes = page.common_prefixes;
+        response.is_truncated = page.is_truncated;
+        if page.is_truncated {
+            response.next_continuation_token = page.next_marker;
+        }
+        Ok(response)
+    }
+
+    async fn head_object(
+        &self,
+        request: head_object::Request,
+        _: Extensions,
+    ) -> Result<head_object::Response, S3Error> {
+        let object = self.find_object(&request.bucket, &request.key).await?;
+        let mut response = head_object::Response::default();
+        response.content_length = Some(object.len);
+        response.content_type = object.content_type;
+        response.etag = Some(object.etag);
+        response.last_modified = Some(object.last_modified);
+        Ok(response)
+    }
+
+    async fn get_object(
+        &self,
+        request: get_object::Request,
+        _: Extensions,
+    ) -> Result<get_object::Response, S3Error> {
+        let object = self.find_object(&request.bucket, &request.key).await?;
+        let preconditions = Preconditions {
+            if_match: request.if_match.clone(),
+            ..Preconditions::default()
+        };
+        preconditions
+            .evaluate(
+                &Method::GET,
+                Some(&object.etag),
+                Some(object.last_modified),
+            )
+            .into_result()
+            .map_err(|error| *error)?;
+
+        let mut response = get_object::Response::default();
+        let len = object.len;
+        let range = content_range(request.range.as_deref(), len)
+            .map_err(|error| *error)?;
+        response.body = match range {
+            Some(range) => {
+                response.content_range = Some(range.to_string());
+                read_range(&object.path, range.first, range.last)
+                    .await
+                    .map_err(internal_error)?
+            }
+            None => fs::read(&object.path).await.map_err(internal_error)?,
+        };
+        response.content_length = Some(response.body.len() as u64);
+        response.content_type = request
+            .response_overrides
+            .content_type
+            .or(object.content_type);
+        response.etag = Some(object.etag);
+        response.last_modified = Some(object.last_modified);
+        Ok(response)
+    }
+
+    async fn put_object(
+        &self,
+        request: put_object::Request,
+        _: Extensions,
+    ) -> Result<put_object::Response, S3Error> {
+        let put_object::Request {
+            bucket,
+            key,
+            body,
+            content_type,
+            condition,
+            ..
+        } = request;
+        self.check_condition(&bucket, &key, condition.as_ref())
+            .await?;
+        let etag = backend::etag(&body);
+        self.store(&bucket, &key, &body, &etag, content_type.as_deref())
+            .await?;
+        let mut response = put_object::Response::default();
+        response.etag = Some(etag);
+        Ok(response)
+    }
+
+    async fn copy_object(
+        &self,
+        request: copy_object::Request,
+        _: Extensions,
+    ) -> Result<copy_object::Response, S3Error> {
+        if request.source_version_id.is_some() {
+            return Err(not_versioned());
+        }
+        let source = self
+            .find_object(&request.source_bucket, &request.source_key)
+            .await?;
+        let data = fs::read(&source.path).await.map_err(internal_error)?;
+        self.store(
+            &request.bucket,
+            &request.key,
+            &data,
+            &source.etag,
+            source.content_type.as_deref(),
+        )
+        .await?;
+        let mut response = copy_object::Response::default();
+        response.etag = Some(source.etag);
+        Ok(response)
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        request: create_multipart_upload::Request,
+        _: Extensions,
+    ) -> Result<create_multipart_upload::Response, S3Error> {
+        self.bucket_dir(&request.bucket).await?;
+        check_key(&request.key)?;
+        let upload_id = self.next_id();
+        let upload = format!(
+            "{}\n{}",
+            request.content_type.as_deref().unwrap_or_default(),
+            request.key
+        );
+        self.write(
+            &self.upload_dir(&request.bucket, &upload_id).join("upload"),
+            upload.as_bytes(),
+        )
+        .await?;
+        let mut response = create_multipart_upload::Response::default();
+        response.upload_id = upload_id;
+        Ok(response)
+    }
+
+    async fn upload_part(
+        &self,
+        request: upload_part::Request,
+        _: Extensions,
+    ) -> Result<upload_part::Response, S3Error> {
+        let (dir, _) = self
+            .find_upload(&request.bucket, &request.key, &request.upload_id)
+            .await?;
+        backend::check_part_number(request.part_number)?;
+        self.write(&dir.join(request.part_number.to_string()), &request.body)
+            .await?;
+        let mut response = upload_part::Response::default();
+        response.etag = Some(backend::etag(&request.body));
+        Ok(response)
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        request: complete_multipart_upload::Request,
+        _: Extensions,
+    ) -> Result<complete_multipart_upload::Response, S3Error> {
+        self.check_condition(
+            &request.bucket,
+            &request.key,
+            request.condition.as_ref(),
+        )
+        .await?;
+        let (dir, content_type) = self
+            .find_upload(&request.bucket, &request.key, &request.upload_id)
+            .await?;
+        backend::check_parts(&request.parts)?;
+
+        let mut data = Vec::new();
+        for completed in &request.parts {
+            let part = fs::read(dir.join(completed.part_number.to_string()))
+                .await
+                .ok()
+                .filter(|part| {
+                    backend::etag(part).trim_matches('"')
+                        == completed.etag.trim_matches('"')
+                })
+                .ok_or_else(|| backend::invalid_part(completed.part_number))?;
+            data.extend_from_slice(&part);
+        }
+        let etag = backend::multipart_etag(
+            request.parts.iter().map(|part| part.etag.as_str()),
+        );
+        self.store(
+            &request.bucket,
+            &request.key,
+            &data,
+            &etag,
+            content_type.as_deref(),
+        )
+        .await?;
+        fs::remove_dir_all(&dir).await.map_err(internal_error)?;
+
+        let mut response = complete_multipart_upload::Response::default();
+        response.location =
+            Some(format!("/{}/{}", request.bucket, request.key));
+        response.etag = Some(etag);
+        Ok(response)
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        request: abort_multipart_upload::Request,
+        _: Extensions,
+    ) -> Result<abort_multipart_upload::Response, S3Error> {
+        let (dir, _) = self
+            .find_upload(&request.bucket, &request.key, &request.upload_id)
+            .await?;
+        fs::remove_dir_all(&dir).await.map_err(internal_error)?;
+        Ok(abort_multipart_upload::Response::default())
+    }
+
+    async fn list_parts(
+        &self,
+        request: list_parts::Request,
+        _: Extensions,
+    ) -> Result<list_parts::Response, S3Error> {
+        let (dir, _) = self
+            .find_upload(&request.bucket, &request.key, &request.upload_id)
+            .await?;
+        let mut part_numbers = Vec::new();
+        let mut entries = fs::read_dir(&dir).await.map_err(internal_error)?;
+        while let Some(entry) =
+            entries.next_entry().await.map_err(internal_error)?
+        {
+            if let Some(part_number) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u32>().ok())
+            {
+                part_numbers.push(part_number);
+            }
+        }
+        part_numbers.sort_unstable();
+
+        let max = max_keys(request.max_parts);
+        let marker = request.part_number_marker.unwrap_or_default();
+        let mut part_numbers =
+            part_numbers.into_iter().filter(|&number| number > marker);
+        let mut response = list_parts::Response::default();
+        for part_number in part_numbers.by_ref().take(max) {
+            let path = dir.join(part_number.to_string());
+            let data = fs::read(&path).await.map_err(internal_error)?;
+            let modified = fs::metadata(&path)
+                .await
+                .and_then(|metadata| metadata.modified())
+                .map_err(internal_error)?;
+            let mut part =
+                list_parts::Part::new(part_number, data.len() as u64);
+            part.etag = Some(backend::etag(&data));
+            part.last_modified = Some(HttpDate::from(modified));
+            response.parts.push(part);
+        }
+        response.is_truncated = part_numbers.next().is_some();
+        if response.is_truncated {
+            response.next_part_number_marker =
+                response.parts.last().map(|part| part.part_number);
+        }
+        response.storage_class = Some("STANDARD".to_owned());
+        Ok(response)
+    }
+}
+
+/// Checks that a key can be the path of a file in a bucket, returning it.
+fn check_key(key: &str) -> Result<&Path, Box<S3Error>> {
+    let valid = key
+        .split('/')
+        .all(|segment| !matches!(segment, "" | "." | ".."))
+        && !key.contains('\\');
+    if !valid {
+        return Err(Box::new(
+            S3Error::new(StatusCode::BAD_REQUEST, "InvalidArgument")
+                .with_message(
+                    "Keys can't have empty, `.` or `..` segments on this \
+                     server.",
+                )
+                .with_resource(key),
+        ));
+    }
+    Ok(Path::new(key))
+}
+
+/// Returns the keys of the files under a directory that start with
+/// `prefix`, unsorted.
+async fn walk(dir: &Path, prefix: &str) -> io::Result<Vec<String>> {
+    let mut keys = Vec::new();
+    let mut dirs = vec![(dir.to_owned(), String::new())];
+    while let Some((dir, dir_key)) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            // Files whose name isn't UTF-8 can't be requested, so they are
+            // left out.
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            let key = format!("{}{}", dir_key, name);
+            if entry.file_type().await?.is_dir() {
+                let key = key + "/";
+                if key.starts_with(prefix) || prefix.starts_with(&key) {
+                    dirs.push((entry.path(), key));
+                }
+            } else if key.starts_with(prefix) {
+                keys.push(key);
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Reads the bytes from `start` to `end` of a file.
+async fn read_range(path: &Path, start: u64, end: u64) -> io::Result<Vec<u8>> {
+    let mut file = fs::File::open(path).await?;
+    file.seek(SeekFrom::Start(start)).await?;
+    let mut data = vec![0; (end - start + 1) as usize];
+    file.read_exact(&mut data).await?;
+    Ok(data)
+}
+
+fn not_versioned() -> S3Error {
+    S3Error::new(StatusCode::NOT_IMPLEMENTED, "NotImplemented")
+        .with_message("The server doesn't keep versions of objects.")
+}
+
+fn internal_error(err: io::Error) -> Box<S3Error> {
+    Box::new(
+        S3Error::new(StatusCode::INTERNAL_SERVER_ERROR, "InternalError")
+            .with_message(err.to_string()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use http::Extensions;
+    use s3ers_api::{
+        error::FromHttpResponseError, IncomingResponse, OutgoingRequest,
+    };
+    use s3ers_s3_api::{
+        bucket::list_objects_v2,
+        multipart::{
+            complete_multipart_upload, create_multipart_upload, list_parts,
+            upload_part,
+        },
+        object::{copy_object, get_object, head_object, put_object},
+        WriteCondition,
+    };
+
+    use super::FsServer;
+    use crate::{Router, S3Handler};
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+    }
+
+    /// Returns a server with a `bucket` bucket in a new directory.
+    fn server(name: &str) -> FsServer {
+        let root = std::env::temp_dir().join(format!(
+            "s3ers-fs-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("bucket")).unwrap();
+        FsServer::new(root)
+    }
+
+    async fn send<R: OutgoingRequest>(
+        router: &Router,
+        request: R,
+    ) -> Result<R::IncomingResponse, FromHttpResponseError> {
+        let request = request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let response = router.handle(request.map(Bytes::from)).await;
+        R::IncomingResponse::try_from_http_response(response)
+    }
+
+    fn error_code<T>(result: Result<T, FromHttpResponseError>) -> String {
+        match result {
+            Err(FromHttpResponseError::Server(error)) => error.code,
+            _ => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn store_objects() {
+        let server = server("objects");
+        let router = server.router();
+        runtime().block_on(async {
+            let mut put = put_object::Request::new("bucket", "a", "content");
+            put.content_type = Some("text/plain".to_owned());
+            let etag = send(&router, put).await.unwrap().etag;
+            let mut get = get_object::Request::new("bucket", "a");
+            get.range = Some("bytes=1-3".to_owned());
+            let response = send(&router, get).await.unwrap();
+            assert_eq!(response.body, b"ont");
+            assert_eq!(response.content_range.as_deref(), Some("bytes 1-3/7"));
+            assert_eq!(response.etag, etag);
+
+            let mut put = put_object::Request::new("bucket", "a", "other");
+            put.condition = Some(WriteCondition::NotExists);
+            assert_eq!(
+                error_code(send(&router, put).await),
+                "PreconditionFailed"
+            );
+            let copy = copy_object::Request::new("bucket", "a", "bucket", "b");
+            send(&router, copy).await.unwrap();
+            let head = head_object::Request::new("bucket", "b");
+            let response = send(&router, head).await.unwrap();
+            assert_eq!(response.content_type.as_deref(), Some("text/plain"));
+            assert_eq!(response.etag, etag);
+
+            let get = get_object::Request::new("bucket", "c");
+            assert_eq!(error_code(send(&router, get).await), "NoSuchKey");
+            let put = put_object::Request::new(".s3ers", "a", "1");
+            assert_eq!(error_code(send(&router, put).await), "NoSuchBucket");
+        });
+        assert_eq!(
+            std::fs::read(server.root.join("bucket/b")).unwrap(),
+            b"content"
+        );
+        std::fs::remove_dir_all(&server.root).unwrap();
+    }
+
+    #[test]
+    fn list_objects() {
+        let server = server("list");
+        runtime().block_on(async {
+            for key in ["a/1", "a/2", "b", "c/d/1"] {
+                let put = put_object::Request::new("bucket", key, key);
+                server.put_object(put, Extensions::new()).await.unwrap();
+            }
+            let put = put_object::Request::new("bucket", "a/../b", "");
+            let error =
+                server.put_object(put, Extensions::new()).await.unwrap_err();
+            assert_eq!(error.code, "InvalidArgument");
+
+            let mut request = list_objects_v2::Request::new("bucket");
+            request.delimiter = Some("/".to_owned());
+            let response = server
+                .list_objects_v2(request, Extensions::new())
+                .await
+                .unwrap();
+            assert_eq!(response.contents[0].key, "b");
+            assert_eq!(response.common_prefixes, ["a/", "c/"]);
+
+            let mut request = list_objects_v2::Request::new("bucket");
+            request.prefix = Some("c/".to_owned());
+            let response = server
+                .list_objects_v2(request, Extensions::new())
+                .await
+                .unwrap();
+            assert_eq!(response.contents[0].key, "c/d/1");
+            assert_eq!(response.contents[0].size, 5);
+        });
+        std::fs::remove_dir_all(&server.root).unwrap();
+    }
+
+    #[test]
+    fn upload_multipart() {
+        let server = server("multipart");
+        runtime().block_on(async {
+            let create = create_multipart_upload::Request::new("bucket", "a/b");
+            let upload_id = server
+                .create_multipart_upload(create, Extensions::new())
+                .await
+                .unwrap()
+                .upload_id;
+
+            let mut parts = Vec::new();
+            for (part_number, data) in [(1, "first "), (2, "second")] {
+                let request = upload_part::Request::new(
+                    "bucket",
+                    "a/b",
+                    &upload_id,
+                    part_number,
+                    data,
+                );
+                let response = server
+                    .upload_part(request, Extensions::new())
+                    .await
+                    .unwrap();
+                parts.push(complete_multipart_upload::CompletedPart::new(
+                    part_number,
+                    response.etag.unwrap(),
+                ));
+            }
+            let request = list_parts::Request::new("bucket", "a/b", &upload_id);
+            let response =
+                server.list_parts(request, Extensions::new()).await.unwrap();
+            assert_eq!(response.parts.len(), 2);
+
+            let request = complete_multipart_upload::Request::new(
+                "bucket", "a/b", &upload_id, parts,
+            );
+            let response = server
+                .complete_multipart_upload(request, Extensions::new())
+                .await
+                .unwrap();
+            assert!(response.etag.unwrap().ends_with("-2\""));
+            let request = list_parts::Request::new("bucket", "a/b", upload_id);
+            let error = server
+                .list_parts(request, Extensions::new())
+                .await
+                .unwrap_err();
+            assert_eq!(error.code, "NoSuchUpload");
+        });
+        assert_eq!(
+            std::fs::read(server.root.join("bucket/a/b")).unwrap(),
+            b"first second"
+        );
+        std::fs::remove_dir_all(&server.root).unwrap();
+    }
+}
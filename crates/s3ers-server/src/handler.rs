@@ -0,0 +1,237 @@
+//! Implementing an S3-compatible server by implementing a trait with one
+//! method per endpoint.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{Extensions, StatusCode};
+use s3ers_api::{error::S3Error, uri};
+use s3ers_s3_api::{
+    bucket::{head_bucket, list_object_versions, list_objects_v2},
+    multipart::{
+        abort_multipart_upload, complete_multipart_upload,
+        create_multipart_upload, list_multipart_uploads, list_parts,
+        upload_part, upload_part_copy,
+    },
+    object::{copy_object, get_object, head_object, put_object},
+};
+
+use crate::Router;
+
+/// Generates the [`S3Handler`] trait, with a method per endpoint returning
+/// a `NotImplemented` error by default, and [`Router::with_handler`],
+/// routing the requests to each endpoint that pass its guard to its method.
+macro_rules! s3_handler {
+    ($(
+        $(#[$doc:meta])*
+        $method:ident: $endpoint:ident, $guard:expr;
+    )*) => {
+        /// A server handling the requests to the endpoints of
+        /// `s3ers-s3-api`, routed to it by [`Router::with_handler`].
+        ///
+        /// Every method is given the request to its endpoint and the
+        /// extensions of the `http::Request`, like the
+        /// [`Principal`](crate::auth::Principal) of authenticated requests,
+        /// and returns a `NotImplemented` error unless it is implemented, so
+        /// that servers only implement the endpoints they support.
+        #[async_trait]
+        pub trait S3Handler: Send + Sync + 'static {
+            $(
+                $(#[$doc])*
+                async fn $method(
+                    &self,
+                    request: $endpoint::Request,
+                    extensions: Extensions,
+                ) -> Result<$endpoint::Response, S3Error> {
+                    let _ = (request, extensions);
+                    Err(not_implemented())
+                }
+            )*
+        }
+
+        impl Router {
+            /// Routes the requests to the endpoints of `s3ers-s3-api` to
+            /// the methods of `handler`.
+            pub fn with_handler<H: S3Handler>(self, handler: H) -> Self {
+                let handler = Arc::new(handler);
+                let router = self;
+                $(
+                    let router = {
+                        let handler = handler.clone();
+                        router.route_guarded(
+                            $guard,
+                            move |request: $endpoint::Request, extensions| {
+                                let handler = handler.clone();
+                                async move {
+                                    handler.$method(request, extensions).await
+                                }
+                            },
+                        )
+                    };
+                )*
+                router
+            }
+        }
+    };
+}
+
+s3_handler! {
+    /// Checks that a bucket exists and that it can be accessed.
+    head_bucket: head_bucket, None;
+    /// Lists the objects of a bucket, with `?list-type=2`.
+    list_objects_v2: list_objects_v2, None;
+    /// Lists the versions of the objects of a bucket, with `?versions`.
+    list_object_versions: list_object_versions, None;
+    /// Returns the metadata of an object.
+    head_object: head_object, None;
+    /// Returns an object.
+    get_object: get_object, None;
+    /// Stores an object.
+    put_object: put_object, None;
+    /// Copies an object, with an `x-amz-copy-source` header.
+    copy_object: copy_object, Some(is_copy_object);
+    /// Starts a multipart upload, with `?uploads`.
+    create_multipart_upload: create_multipart_upload, None;
+    /// Stores a part of a multipart upload.
+    upload_part: upload_part, Some(is_upload_part);
+    /// Stores a part of a multipart upload copied from an object.
+    upload_part_copy: upload_part_copy, Some(is_upload_part_copy);
+    /// Assembles the parts of a multipart upload into an object.
+    complete_multipart_upload: complete_multipart_upload, Some(has_upload_id);
+    /// Aborts a multipart upload, deleting its parts.
+    abort_multipart_upload: abort_multipart_upload, Some(has_upload_id);
+    /// Lists the parts of a multipart upload.
+    list_parts: list_parts, Some(has_upload_id);
+    /// Lists the multipart uploads in progress in a bucket, with
+    /// `?uploads`.
+    list_multipart_uploads: list_multipart_uploads, None;
+}
+
+/// The error of the endpoints a handler doesn't implement.
+fn not_implemented() -> S3Error {
+    S3Error::new(StatusCode::NOT_IMPLEMENTED, "NotImplemented")
+        .with_message("The request isn't supported by the server.")
+}
+
+/// Returns the value of a query parameter of a request, if it has one.
+fn query_param(request: &http::Request<Bytes>, name: &str) -> Option<String> {
+    uri::parse_query(request.uri().query().unwrap_or_default())
+        .into_iter()
+        .find(|(param, _)| param == name)
+        .map(|(_, value)| value)
+}
+
+fn has_upload_id(request: &http::Request<Bytes>) -> bool {
+    query_param(request, "uploadId").is_some()
+}
+
+fn has_copy_source(request: &http::Request<Bytes>) -> bool {
+    request.headers().contains_key("x-amz-copy-source")
+}
+
+fn is_part(request: &http::Request<Bytes>) -> bool {
+    has_upload_id(request) && query_param(request, "partNumber").is_some()
+}
+
+fn is_copy_object(request: &http::Request<Bytes>) -> bool {
+    has_copy_source(request) && !is_part(request)
+}
+
+fn is_upload_part(request: &http::Request<Bytes>) -> bool {
+    is_part(request) && !has_copy_source(request)
+}
+
+fn is_upload_part_copy(request: &http::Request<Bytes>) -> bool {
+    is_part(request) && has_copy_source(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use futures_executor::block_on;
+    use http::{Extensions, Method, StatusCode};
+    use s3ers_api::error::S3Error;
+    use s3ers_s3_api::{
+        multipart::create_multipart_upload,
+        object::{copy_object, put_object},
+    };
+
+    use super::S3Handler;
+    use crate::Router;
+
+    /// Stores objects without keeping them, and copies none.
+    struct Server;
+
+    #[async_trait]
+    impl S3Handler for Server {
+        async fn put_object(
+            &self,
+            request: put_object::Request,
+            _: Extensions,
+        ) -> Result<put_object::Response, S3Error> {
+            let mut response = put_object::Response::default();
+            response.etag = Some(format!("\"{}\"", request.body.len()));
+            Ok(response)
+        }
+
+        async fn create_multipart_upload(
+            &self,
+            request: create_multipart_upload::Request,
+            _: Extensions,
+        ) -> Result<create_multipart_upload::Response, S3Error> {
+            let mut response = create_multipart_upload::Response::default();
+            response.upload_id = request.key;
+            Ok(response)
+        }
+
+        async fn copy_object(
+            &self,
+            request: copy_object::Request,
+            _: Extensions,
+        ) -> Result<copy_object::Response, S3Error> {
+            Err(S3Error::new(StatusCode::NOT_FOUND, "NoSuchKey")
+                .with_resource(request.source_key))
+        }
+    }
+
+    fn send(
+        router: &Router,
+        method: Method,
+        uri: &str,
+        headers: &[(&str, &str)],
+    ) -> http::Response<Bytes> {
+        let mut request = http::Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        block_on(router.handle(request.body(Bytes::from("hello")).unwrap()))
+    }
+
+    #[test]
+    fn handle_requests() {
+        let router = Router::new().with_handler(Server);
+
+        let response = send(&router, Method::PUT, "/bucket/key", &[]);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["etag"], "\"5\"");
+
+        let copy_source = [("x-amz-copy-source", "/bucket/source")];
+        let response = send(&router, Method::PUT, "/bucket/key", &copy_source);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = send(&router, Method::POST, "/bucket/key?uploads", &[]);
+        assert!(std::str::from_utf8(response.body())
+            .unwrap()
+            .contains("<UploadId>key</UploadId>"));
+
+        let uri = "/bucket/key?partNumber=1&uploadId=key";
+        let response = send(&router, Method::PUT, uri, &[]);
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+        let response = send(&router, Method::GET, "/bucket?list-type=2", &[]);
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+        let response = send(&router, Method::DELETE, "/bucket/key", &[]);
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
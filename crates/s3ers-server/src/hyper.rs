@@ -0,0 +1,138 @@
+//! Running a [`Router`] on hyper, with the `hyper` feature.
+//!
+//! ```no_run
+//! # async fn run(router: s3ers_server::Router) -> Result<(), hyper::Error> {
+//! let addr = ([127, 0, 0, 1], 9000).into();
+//! hyper::Server::bind(&addr)
+//!     .serve(router.into_make_service())
+//!     .await
+//! # }
+//! ```
+
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_util::future::{self, BoxFuture, FutureExt, Ready};
+use hyper::{service::Service, Body};
+
+use crate::{ErrorResponse, Router};
+
+/// A hyper service handling requests with a [`Router`].
+///
+/// The body of requests is buffered before they are handed to the router,
+/// and requests whose body can't be read get an `IncompleteBody` error.
+#[derive(Clone, Debug)]
+pub struct RouterService {
+    router: Arc<Router>,
+}
+
+impl RouterService {
+    /// A service handling requests with `router`.
+    pub fn new(router: Router) -> Self {
+        Self {
+            router: Arc::new(router),
+        }
+    }
+}
+
+impl Service<http::Request<Body>> for RouterService {
+    type Response = http::Response<Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Infallible>>;
+
+    fn poll_ready(
+        &mut self,
+        _: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let router = self.router.clone();
+        async move {
+            let (parts, body) = request.into_parts();
+            let response = match hyper::body::to_bytes(body).await {
+                Ok(body) => {
+                    router.handle(http::Request::from_parts(parts, body)).await
+                }
+                Err(err) => ErrorResponse::new("IncompleteBody")
+                    .with_message(err.to_string())
+                    .into_http_response(),
+            };
+            Ok(response.map(Body::from))
+        }
+        .boxed()
+    }
+}
+
+/// A hyper service making a [`RouterService`] for every connection.
+#[derive(Clone, Debug)]
+pub struct MakeRouterService {
+    service: RouterService,
+}
+
+impl<T> Service<T> for MakeRouterService {
+    type Response = RouterService;
+    type Error = Infallible;
+    type Future = Ready<Result<RouterService, Infallible>>;
+
+    fn poll_ready(
+        &mut self,
+        _: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: T) -> Self::Future {
+        future::ready(Ok(self.service.clone()))
+    }
+}
+
+impl Router {
+    /// Returns a hyper service handling requests with the router.
+    pub fn into_service(self) -> RouterService {
+        RouterService::new(self)
+    }
+
+    /// Returns a hyper service making a service handling requests with the
+    /// router for every connection, to serve it with `hyper::Server`.
+    pub fn into_make_service(self) -> MakeRouterService {
+        MakeRouterService {
+            service: self.into_service(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_executor::block_on;
+    use http::StatusCode;
+    use hyper::{service::Service, Body};
+    use s3ers_s3_api::bucket::head_bucket;
+
+    use crate::Router;
+
+    #[test]
+    fn serve_requests() {
+        let router = Router::new().route(|_: head_bucket::Request, _| async {
+            Ok(head_bucket::Response::default())
+        });
+        let mut service =
+            block_on(router.into_make_service().call(())).unwrap();
+
+        let request = http::Request::head("/bucket").body(Body::empty());
+        let response = block_on(service.call(request.unwrap())).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = http::Request::get("/").body(Body::from("ignored"));
+        let response = block_on(service.call(request.unwrap())).unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+        let body = block_on(hyper::body::to_bytes(response.into_body()));
+        assert!(std::str::from_utf8(&body.unwrap())
+            .unwrap()
+            .contains("<Code>NotImplemented</Code>"));
+    }
+}
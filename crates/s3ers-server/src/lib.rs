@@ -0,0 +1,59 @@
+//! Building blocks for S3-compatible servers.
+//!
+//! The [`Router`] receives the requests of clients, finds the endpoints they
+//! are for from the [`Metadata`](s3ers_api::Metadata) of the endpoints it was
+//! given, converts them to the request types of these endpoints, like the ones
+//! of `s3ers-s3-api`, and hands them to their handlers, converting the
+//! responses or the errors they return back, the latter as an [`ErrorResponse`]
+//! with the `Error` XML document of S3. It accepts path-style and
+//! virtual-hosted-style requests, as set by [`Addressing`], and can
+//! authenticate them with an [`auth::SigV4Verifier`], checking their body
+//! against its digests with [`checksum`] once the `aws-chunked` bodies of
+//! AWS SDKs are decoded with [`chunked`]. The CORS rules of buckets
+//! are applied with [`cors::CorsConfiguration`], and their policies evaluated
+//! with [`policy::Policy`]. Listings are cut into pages, with continuation
+//! tokens, by the helpers of [`pagination`], the ranges of objects served
+//! with the helpers of [`range`], conditional requests evaluated with
+//! [`conditional::Preconditions`], and browser-based uploads read as a
+//! [`post::PostForm`]. Implementing the [`S3Handler`] trait and giving it
+//! to [`Router::with_handler`] routes the requests to all the endpoints of
+//! `s3ers-s3-api` at once. With the `hyper` feature, it can be served by hyper
+//! as a [`hyper::RouterService`], and with the `tower` feature, wrapped in the
+//! `tower` services of [`middleware`], which also give each request its
+//! [`RequestId`]. With the `memory` feature, the
+//! [`memory::MemoryServer`] keeps buckets and objects in memory, to test
+//! clients end to end, and with the `fs` feature, the [`fs::FsServer`] stores
+//! them in a directory, as a stand-in for S3 during local development.
+
+#![warn(missing_docs)]
+
+mod addressing;
+pub mod auth;
+#[cfg(any(feature = "fs", feature = "memory"))]
+mod backend;
+pub mod checksum;
+pub mod chunked;
+pub mod conditional;
+pub mod cors;
+mod error;
+#[cfg(feature = "fs")]
+pub mod fs;
+mod handler;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+#[cfg(feature = "memory")]
+pub mod memory;
+#[cfg(feature = "tower")]
+pub mod middleware;
+pub mod pagination;
+pub mod policy;
+pub mod post;
+pub mod range;
+mod request_id;
+mod router;
+
+pub use addressing::{Addressing, Resource};
+pub use error::ErrorResponse;
+pub use handler::S3Handler;
+pub use request_id::RequestId;
+pub use router::Router;
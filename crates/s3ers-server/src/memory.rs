@@ -0,0 +1,981 @@
+//! An S3 server keeping its buckets and objects in memory, with the `memory`
+//! feature, to test clients end to end without a real server.
+//!
+//! ```
+//! use s3ers_server::memory::MemoryServer;
+//!
+//! let server = MemoryServer::new()
+//!     .with_bucket("bucket")
+//!     .with_versioned_bucket("versioned");
+//! let router = server.router();
+//! // Send requests to `router`, then check `server.object("bucket", "key")`.
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{Extensions, Method, StatusCode};
+use s3ers_api::{error::S3Error, header::HttpDate};
+use s3ers_s3_api::{
+    bucket::{head_bucket, list_object_versions, list_objects_v2},
+    multipart::{
+        abort_multipart_upload, complete_multipart_upload,
+        create_multipart_upload, list_multipart_uploads, list_parts,
+        upload_part, upload_part_copy,
+    },
+    object::{copy_object, get_object, head_object, put_object},
+    WriteCondition,
+};
+
+use crate::{
+    backend::{self, no_such_bucket, no_such_key},
+    conditional::{precondition_failed, Preconditions},
+    pagination::{max_keys, paginate},
+    range::{content_range, invalid_range, ByteRange},
+    Router, S3Handler,
+};
+
+/// An S3 server keeping its buckets, the versions of their objects and
+/// their multipart uploads in memory.
+///
+/// Clones share their state, so that a clone can handle the requests of a
+/// [`Router`] while the original is used to check what they did. Buckets
+/// are created up front, since there are no endpoints to create them.
+///
+/// Entity tags are computed like the ones of S3, with SHA-256 in place of
+/// MD5.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryServer {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    buckets: BTreeMap<String, Bucket>,
+    next_id: u64,
+}
+
+#[derive(Debug, Default)]
+struct Bucket {
+    versioned: bool,
+    /// The versions of the objects, the latest last.
+    objects: BTreeMap<String, Vec<Version>>,
+    /// The multipart uploads in progress, by upload ID.
+    uploads: BTreeMap<String, Upload>,
+}
+
+#[derive(Clone, Debug)]
+struct Version {
+    /// The ID of the version, or `None` in unversioned buckets.
+    version_id: Option<String>,
+    data: Bytes,
+    etag: String,
+    content_type: Option<String>,
+    last_modified: HttpDate,
+}
+
+#[derive(Debug)]
+struct Upload {
+    key: String,
+    content_type: Option<String>,
+    initiated: HttpDate,
+    parts: BTreeMap<u32, Part>,
+}
+
+#[derive(Debug)]
+struct Part {
+    data: Bytes,
+    etag: String,
+    last_modified: HttpDate,
+}
+
+impl MemoryServer {
+    /// Creates a server without buckets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an unversioned bucket, where objects are overwritten.
+    pub fn with_bucket(self, bucket: impl Into<String>) -> Self {
+        self.lock().buckets.entry(bucket.into()).or_default();
+        self
+    }
+
+    /// Creates a versioned bucket, keeping the previous versions of the
+    /// objects that are overwritten.
+    pub fn with_versioned_bucket(self, bucket: impl Into<String>) -> Self {
+        self.lock()
+            .buckets
+            .entry(bucket.into())
+            .or_default()
+            .versioned = true;
+        self
+    }
+
+    /// Returns the current version of an object, if it exists.
+    pub fn object(&self, bucket: &str, key: &str) -> Option<Bytes> {
+        let state = self.lock();
+        let versions = state.buckets.get(bucket)?.objects.get(key)?;
+        Some(versions.last()?.data.clone())
+    }
+
+    /// Returns how many multipart uploads are in progress in a bucket.
+    pub fn upload_count(&self, bucket: &str) -> usize {
+        let state = self.lock();
+        state
+            .buckets
+            .get(bucket)
+            .map_or(0, |bucket| bucket.uploads.len())
+    }
+
+    /// Returns a router handing the requests it receives to the server.
+    pub fn router(&self) -> Router {
+        Router::new().with_handler(self.clone())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The state is consistent between handlers, even ones that panicked.
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl State {
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("{:032x}", self.next_id)
+    }
+
+    /// Stores a new version of an object, returning it.
+    fn store(
+        &mut self,
+        bucket: &str,
+        key: &str,
+        data: Bytes,
+        etag: String,
+        content_type: Option<String>,
+    ) -> Option<Version> {
+        let versioned = self.buckets.get(bucket)?.versioned;
+        let version = Version {
+            version_id: versioned.then(|| self.next_id()),
+            data,
+            etag,
+            content_type,
+            last_modified: HttpDate::now(),
+        };
+        let versions = self
+            .buckets
+            .get_mut(bucket)?
+            .objects
+            .entry(key.to_owned())
+            .or_default();
+        if !versioned {
+            versions.clear();
+        }
+        versions.push(version.clone());
+        Some(version)
+    }
+}
+
+#[async_trait]
+impl S3Handler for MemoryServer {
+    async fn head_bucket(
+        &self,
+        request: head_bucket::Request,
+        _: Extensions,
+    ) -> Result<head_bucket::Response, S3Error> {
+        let state = self.lock();
+        match state.buckets.contains_key(&request.bucket) {
+            true => Ok(head_bucket::Response::default()),
+            false => Err(no_such_bucket(&request.bucket)),
+        }
+    }
+
+    async fn list_objects_v2(
+        &self,
+        request: list_objects_v2::Request,
+        _: Extensions,
+    ) -> Result<list_objects_v2::Response, S3Error> {
+        let state = self.lock();
+        let bucket = state
+            .buckets
+            .get(&request.bucket)
+            .ok_or_else(|| no_such_bucket(&request.bucket))?;
+        // The token is the last key or common prefix of the previous page.
+        let after = request.continuation_token.max(request.start_after);
+        let entries = bucket.objects.iter().filter_map(|(key, versions)| {
+            Some((key.as_str(), versions.last()?))
+        });
+        let page = paginate(
+            entries,
+            request.prefix.as_deref(),
+            request.delimiter.as_deref(),
+            request.max_keys,
+            |entry, _| after.as_deref().is_some_and(|after| entry <= after),
+        );
+
+        let mut response = list_objects_v2::Response::default();
+        response.key_count = page.key_count();
+        response.contents = page
+            .items
+            .into_iter()
+            .map(|(key, version)| {
+                let mut object = list_objects_v2::Object::new(
+                    key,
+                    version.data.len() as u64,
+                );
+                object.etag = Some(version.etag.clone());
+                object.last_modified = Some(version.last_modified);
+                object.storage_class = Some("STANDARD".to_owned());
+                object
+            })
+            .collect();
+        response.common_prefixes = page.common_prefixes;
+        response.is_truncated = page.is_truncated;
+        if page.is_truncated {
+            response.next_continuation_token = page.next_marker;
+        }
+        Ok(response)
+    }
+
+    async fn list_object_versions(
+        &self,
+        request: list_object_versions::Request,
+        _: Extensions,
+    ) -> Result<list_object_versions::Response, S3Error> {
+        let state = self.lock();
+        let bucket = state
+            .buckets
+            .get(&request.bucket)
+            .ok_or_else(|| no_such_bucket(&request.bucket))?;
+        let versions: Vec<_> = bucket
+            .objects
+            .iter()
+            .flat_map(|(key, versions)| {
+                let latest = versions.len() - 1;
+                versions.iter().enumerate().rev().map(move |(i, version)| {
+                    (key.as_str(), (version, i == latest))
+                })
+            })
+            .collect();
+        // Versions are listed after the one of the markers, or after the
+        // key marker if it is gone.
+        let position = request.version_id_marker.as_deref().and_then(|id| {
+            versions.iter().position(|(key, (version, _))| {
+                Some(*key) == request.key_marker.as_deref()
+                    && version.version_id.as_deref().unwrap_or("null") == id
+            })
+        });
+        let page = paginate(
+            versions.iter().copied().enumerate().map(
+                |(i, (key, (version, is_latest)))| {
+                    (key, (i, version, is_latest))
+                },
+            ),
+            request.prefix.as_deref(),
+            request.delimiter.as_deref(),
+            request.max_keys,
+            |entry, (i, _, _)| match position {
+                Some(position) => *i <= position,
+                None => request
+                    .key_marker
+                    .as_deref()
+                    .is_some_and(|marker| entry <= marker),
+            },
+        );
+
+        let mut response = list_object_versions::Response::default();
+        if page.is_truncated {
+            response.next_version_id_marker = page
+                .items
+                .last()
+                .filter(|(key, _)| Some(key) == page.next_marker.as_ref())
+                .map(|(_, (_, version, _))| {
+                    version.version_id.clone().unwrap_or_else(|| "null".into())
+                });
+            response.next_key_marker = page.next_marker;
+        }
+        response.versions = page
+            .items
+            .into_iter()
+            .map(|(key, (_, version, is_latest))| {
+                let mut object = list_object_versions::ObjectVersion::new(key);
+                object.version_id = Some(
+                    version.version_id.clone().unwrap_or_else(|| "null".into()),
+                );
+                object.is_latest = is_latest;
+                object.last_modified = Some(version.last_modified);
+                object.etag = Some(version.etag.clone());
+                object.size = Some(version.data.len() as u64);
+                object.storage_class = Some("STANDARD".to_owned());
+                object
+            })
+            .collect();
+        response.common_prefixes = page.common_prefixes;
+        response.is_truncated = page.is_truncated;
+        Ok(response)
+    }
+
+    async fn head_object(
+        &self,
+        request: head_object::Request,
+        _: Extensions,
+    ) -> Result<head_object::Response, S3Error> {
+        let state = self.lock();
+        let version = find_version(
+            &state,
+            &request.bucket,
+            &request.key,
+            request.version_id.as_deref(),
+        )?;
+        let mut response = head_object::Response::default();
+        response.content_length = Some(version.data.len() as u64);
+        response.content_type = version.content_type.clone();
+        response.etag = Some(version.etag.clone());
+        response.last_modified = Some(version.last_modified);
+        response.version_id = version.version_id.clone();
+        Ok(response)
+    }
+
+    async fn get_object(
+        &self,
+        request: get_object::Request,
+        _: Extensions,
+    ) -> Result<get_object::Response, S3Error> {
+        let state = self.lock();
+        let version = find_version(
+            &state,
+            &request.bucket,
+            &request.key,
+            request.version_id.as_deref(),
+        )?;
+        let preconditions = Preconditions {
+            if_match: request.if_match.clone(),
+            ..Preconditions::default()
+        };
+        preconditions
+            .evaluate(
+                &Method::GET,
+                Some(&version.etag),
+                Some(version.last_modified),
+            )
+            .into_result()
+            .map_err(|error| *error)?;
+
+        let mut response = get_object::Response::default();
+        let len = version.data.len() as u64;
+        let range = content_range(request.range.as_deref(), len)
+            .map_err(|error| *error)?;
+        let data = match range {
+            Some(range) => {
+                response.content_range = Some(range.to_string());
+                version.data.slice(range.to_range())
+            }
+            None => version.data.clone(),
+        };
+        response.content_length = Some(data.len() as u64);
+        response.body = data.to_vec();
+        response.content_type = request
+            .response_overrides
+            .content_type
+            .or_else(|| version.content_type.clone());
+        response.etag = Some(version.etag.clone());
+        response.last_modified = Some(version.last_modified);
+        response.version_id = version.version_id.clone();
+        Ok(response)
+    }
+
+    async fn put_object(
+        &self,
+        request: put_object::Request,
+        _: Extensions,
+    ) -> Result<put_object::Response, S3Error> {
+        let put_object::Request {
+            bucket,
+            key,
+            body,
+            content_type,
+            condition,
+            ..
+        } = request;
+        let mut state = self.lock();
+        check_condition(&state, &bucket, &key, condition.as_ref())?;
+        let etag = backend::etag(&body);
+        let version = state
+            .store(&bucket, &key, Bytes::from(body), etag, content_type)
+            .ok_or_else(|| no_such_bucket(&bucket))?;
+        let mut response = put_object::Response::default();
+        response.etag = Some(version.etag);
+        response.version_id = version.version_id;
+        Ok(response)
+    }
+
+    async fn copy_object(
+        &self,
+        request: copy_object::Request,
+        _: Extensions,
+    ) -> Result<copy_object::Response, S3Error> {
+        let mut state = self.lock();
+        let source = find_version(
+            &state,
+            &request.source_bucket,
+            &request.source_key,
+            request.source_version_id.as_deref(),
+        )?
+        .clone();
+        let version = state
+            .store(
+                &request.bucket,
+                &request.key,
+                source.data,
+                source.etag,
+                source.content_type,
+            )
+            .ok_or_else(|| no_such_bucket(&request.bucket))?;
+        let mut response = copy_object::Response::default();
+        response.etag = Some(version.etag);
+        response.version_id = version.version_id;
+        Ok(response)
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        request: create_multipart_upload::Request,
+        _: Extensions,
+    ) -> Result<create_multipart_upload::Response, S3Error> {
+        let mut state = self.lock();
+        if !state.buckets.contains_key(&request.bucket) {
+            return Err(no_such_bucket(&request.bucket));
+        }
+        let upload_id = state.next_id();
+        let upload = Upload {
+            key: request.key,
+            content_type: request.content_type,
+            initiated: HttpDate::now(),
+            parts: BTreeMap::new(),
+        };
+        if let Some(bucket) = state.buckets.get_mut(&request.bucket) {
+            bucket.uploads.insert(upload_id.clone(), upload);
+        }
+        let mut response = create_multipart_upload::Response::default();
+        response.upload_id = upload_id;
+        Ok(response)
+    }
+
+    async fn upload_part(
+        &self,
+        request: upload_part::Request,
+        _: Extensions,
+    ) -> Result<upload_part::Response, S3Error> {
+        let mut state = self.lock();
+        let upload = find_upload(
+            &mut state,
+            &request.bucket,
+            &request.key,
+            &request.upload_id,
+        )?;
+        let mut response = upload_part::Response::default();
+        response.etag = Some(store_part(
+            upload,
+            request.part_number,
+            Bytes::from(request.body),
+        )?);
+        Ok(response)
+    }
+
+    async fn upload_part_copy(
+        &self,
+        request: upload_part_copy::Request,
+        _: Extensions,
+    ) -> Result<upload_part_copy::Response, S3Error> {
+        let mut state = self.lock();
+        let source = find_version(
+            &state,
+            &request.source_bucket,
+            &request.source_key,
+            request.source_version_id.as_deref(),
+        )?;
+        if let Some(etag) = &request.source_if_match {
+            if *etag != source.etag {
+                return Err(precondition_failed("x-amz-copy-source-if-match"));
+            }
+        }
+        let data = match &request.source_range {
+            Some(range) => {
+                // Unlike `Range`, the range of the source has to be valid.
+                let len = source.data.len() as u64;
+                let range = ByteRange::parse(range)
+                    .ok_or_else(|| invalid_range(range, len))
+                    .and_then(|range| range.resolve(len))
+                    .map_err(|error| *error)?;
+                source.data.slice(range.to_range())
+            }
+            None => source.data.clone(),
+        };
+
+        let upload = find_upload(
+            &mut state,
+            &request.bucket,
+            &request.key,
+            &request.upload_id,
+        )?;
+        let mut response = upload_part_copy::Response::default();
+        response.etag = Some(store_part(upload, request.part_number, data)?);
+        Ok(response)
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        request: complete_multipart_upload::Request,
+        _: Extensions,
+    ) -> Result<complete_multipart_upload::Response, S3Error> {
+        let mut state = self.lock();
+        check_condition(
+            &state,
+            &request.bucket,
+            &request.key,
+            request.condition.as_ref(),
+        )?;
+        let upload = find_upload(
+            &mut state,
+            &request.bucket,
+            &request.key,
+            &request.upload_id,
+        )?;
+        backend::check_parts(&request.parts)?;
+
+        let mut data = Vec::new();
+        for completed in &request.parts {
+            let part = upload
+                .parts
+                .get(&completed.part_number)
+                .filter(|part| {
+                    part.etag.trim_matches('"')
+                        == completed.etag.trim_matches('"')
+                })
+                .ok_or_else(|| backend::invalid_part(completed.part_number))?;
+            data.extend_from_slice(&part.data);
+        }
+        let etag = backend::multipart_etag(
+            request.parts.iter().map(|part| part.etag.as_str()),
+        );
+        let content_type = upload.content_type.clone();
+
+        if let Some(bucket) = state.buckets.get_mut(&request.bucket) {
+            bucket.uploads.remove(&request.upload_id);
+        }
+        let version = state
+            .store(
+                &request.bucket,
+                &request.key,
+                Bytes::from(data),
+                etag,
+                content_type,
+            )
+            .ok_or_else(|| no_such_bucket(&request.bucket))?;
+        let mut response = complete_multipart_upload::Response::default();
+        response.location =
+            Some(format!("/{}/{}", request.bucket, request.key));
+        response.etag = Some(version.etag);
+        response.version_id = version.version_id;
+        Ok(response)
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        request: abort_multipart_upload::Request,
+        _: Extensions,
+    ) -> Result<abort_multipart_upload::Response, S3Error> {
+        let mut state = self.lock();
+        find_upload(
+            &mut state,
+            &request.bucket,
+            &request.key,
+            &request.upload_id,
+        )?;
+        if let Some(bucket) = state.buckets.get_mut(&request.bucket) {
+            bucket.uploads.remove(&request.upload_id);
+        }
+        Ok(abort_multipart_upload::Response::default())
+    }
+
+    async fn list_parts(
+        &self,
+        request: list_parts::Request,
+        _: Extensions,
+    ) -> Result<list_parts::Response, S3Error> {
+        let mut state = self.lock();
+        let upload = find_upload(
+            &mut state,
+            &request.bucket,
+            &request.key,
+            &request.upload_id,
+        )?;
+        let max = max_keys(request.max_parts);
+        let marker = request.part_number_marker.unwrap_or_default();
+        let mut parts = upload.parts.range(marker + 1..);
+
+        let mut response = list_parts::Response::default();
+        for (&part_number, part) in parts.by_ref().take(max) {
+            let mut listed =
+                list_parts::Part::new(part_number, part.data.len() as u64);
+            listed.etag = Some(part.etag.clone());
+            listed.last_modified = Some(part.last_modified);
+            response.parts.push(listed);
+        }
+        response.is_truncated = parts.next().is_some();
+        if response.is_truncated {
+            response.next_part_number_marker =
+                response.parts.last().map(|part| part.part_number);
+        }
+        response.storage_class = Some("STANDARD".to_owned());
+        Ok(response)
+    }
+
+    async fn list_multipart_uploads(
+        &self,
+        request: list_multipart_uploads::Request,
+        _: Extensions,
+    ) -> Result<list_multipart_uploads::Response, S3Error> {
+        let state = self.lock();
+        let bucket = state
+            .buckets
+            .get(&request.bucket)
+            .ok_or_else(|| no_such_bucket(&request.bucket))?;
+        let mut uploads: Vec<_> = bucket
+            .uploads
+            .iter()
+            .map(|(upload_id, upload)| {
+                (upload.key.as_str(), (upload_id, upload))
+            })
+            .collect();
+        uploads.sort_by(|a, b| (a.0, a.1 .0).cmp(&(b.0, b.1 .0)));
+        let key_marker = request.key_marker.as_deref();
+        let upload_id_marker = request.upload_id_marker.as_deref();
+        let page = paginate(
+            uploads,
+            request.prefix.as_deref(),
+            request.delimiter.as_deref(),
+            request.max_uploads,
+            |entry, (upload_id, _)| match (key_marker, upload_id_marker) {
+                (Some(key), Some(upload_id_marker)) => {
+                    entry < key
+                        || (entry == key
+                            && upload_id.as_str() <= upload_id_marker)
+                }
+                (Some(key), None) => entry <= key,
+                (None, _) => false,
+            },
+        );
+
+        let mut response = list_multipart_uploads::Response::default();
+        if page.is_truncated {
+            response.next_upload_id_marker = page
+                .items
+                .last()
+                .filter(|(key, _)| Some(key) == page.next_marker.as_ref())
+                .map(|(_, (upload_id, _))| (*upload_id).clone());
+            response.next_key_marker = page.next_marker;
+        }
+        response.uploads = page
+            .items
+            .into_iter()
+            .map(|(key, (upload_id, upload))| {
+                let mut listed = list_multipart_uploads::Upload::new(
+                    key,
+                    upload_id.as_str(),
+                );
+                listed.initiated = Some(upload.initiated);
+                listed.storage_class = Some("STANDARD".to_owned());
+                listed
+            })
+            .collect();
+        response.common_prefixes = page.common_prefixes;
+        response.is_truncated = page.is_truncated;
+        Ok(response)
+    }
+}
+
+/// Returns a version of an object, or the current one.
+fn find_version<'a>(
+    state: &'a State,
+    bucket: &str,
+    key: &str,
+    version_id: Option<&str>,
+) -> Result<&'a Version, Box<S3Error>> {
+    let versions = state
+        .buckets
+        .get(bucket)
+        .ok_or_else(|| no_such_bucket(bucket))?
+        .objects
+        .get(key);
+    let version = match version_id {
+        Some(version_id) => versions.and_then(|versions| {
+            versions.iter().find(|version| {
+                version.version_id.as_deref().unwrap_or("null") == version_id
+            })
+        }),
+        None => versions.and_then(|versions| versions.last()),
+    };
+    version.ok_or_else(|| match version_id {
+        Some(_) => Box::new(
+            S3Error::new(StatusCode::NOT_FOUND, "NoSuchVersion")
+                .with_message("The specified version does not exist.")
+                .with_resource(format!("/{}/{}", bucket, key)),
+        ),
+        None => Box::new(no_such_key(bucket, key)),
+    })
+}
+
+/// Returns a multipart upload of an object.
+fn find_upload<'a>(
+    state: &'a mut State,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+) -> Result<&'a mut Upload, Box<S3Error>> {
+    state
+        .buckets
+        .get_mut(bucket)
+        .ok_or_else(|| no_such_bucket(bucket))?
+        .uploads
+        .get_mut(upload_id)
+        .filter(|upload| upload.key == key)
+        .ok_or_else(|| Box::new(backend::no_such_upload(upload_id)))
+}
+
+/// Stores a part of a multipart upload, returning its entity tag.
+fn store_part(
+    upload: &mut Upload,
+    part_number: u32,
+    data: Bytes,
+) -> Result<String, Box<S3Error>> {
+    backend::check_part_number(part_number)?;
+    let etag = backend::etag(&data);
+    upload.parts.insert(
+        part_number,
+        Part {
+            data,
+            etag: etag.clone(),
+            last_modified: HttpDate::now(),
+        },
+    );
+    Ok(etag)
+}
+
+/// Checks the condition of a write against the current version of the
+/// object.
+fn check_condition(
+    state: &State,
+    bucket: &str,
+    key: &str,
+    condition: Option<&WriteCondition>,
+) -> Result<(), Box<S3Error>> {
+    let current = match find_version(state, bucket, key, None) {
+        Ok(version) => Some(version),
+        Err(error) if error.code == "NoSuchKey" => None,
+        Err(error) => return Err(error),
+    };
+    match (condition, current) {
+        (Some(WriteCondition::NotExists), Some(_)) => {
+            Err(Box::new(precondition_failed("If-None-Match")))
+        }
+        (Some(WriteCondition::Matches(_)), None) => {
+            Err(Box::new(no_such_key(bucket, key)))
+        }
+        (Some(WriteCondition::Matches(etag)), Some(version))
+            if *etag != version.etag =>
+        {
+            Err(Box::new(precondition_failed("If-Match")))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures_executor::block_on;
+    use http::StatusCode;
+    use s3ers_api::{
+        error::FromHttpResponseError, IncomingResponse, OutgoingRequest,
+        Paginated,
+    };
+    use s3ers_s3_api::{
+        bucket::{list_object_versions, list_objects_v2},
+        multipart::{
+            abort_multipart_upload, complete_multipart_upload,
+            create_multipart_upload, list_multipart_uploads, list_parts,
+            upload_part,
+        },
+        object::{copy_object, get_object, put_object},
+        WriteCondition,
+    };
+
+    use super::MemoryServer;
+    use crate::Router;
+
+    fn send<R: OutgoingRequest>(
+        router: &Router,
+        request: R,
+    ) -> Result<R::IncomingResponse, FromHttpResponseError> {
+        let request = request.try_into_http_request::<Vec<u8>>("").unwrap();
+        let response = block_on(router.handle(request.map(Bytes::from)));
+        R::IncomingResponse::try_from_http_response(response)
+    }
+
+    fn error_code<T>(result: Result<T, FromHttpResponseError>) -> String {
+        match result {
+            Err(FromHttpResponseError::Server(error)) => error.code,
+            _ => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn store_objects() {
+        let server = MemoryServer::new().with_bucket("bucket");
+        let router = server.router();
+
+        let put = send(&router, put_object::Request::new("bucket", "a", "1"));
+        assert!(put.unwrap().version_id.is_none());
+        let mut get = get_object::Request::new("bucket", "a");
+        get.range = Some("bytes=-1".to_owned());
+        let response = send(&router, get).unwrap();
+        assert_eq!(response.body, b"1");
+        assert_eq!(response.content_range.as_deref(), Some("bytes 0-0/1"));
+
+        let mut put = put_object::Request::new("bucket", "a", "2");
+        put.condition = Some(WriteCondition::NotExists);
+        assert_eq!(error_code(send(&router, put)), "PreconditionFailed");
+        let copy = copy_object::Request::new("bucket", "a", "bucket", "b");
+        send(&router, copy).unwrap();
+        assert_eq!(server.object("bucket", "b").unwrap(), "1");
+
+        let get = get_object::Request::new("bucket", "c");
+        assert_eq!(error_code(send(&router, get)), "NoSuchKey");
+        let put = put_object::Request::new("other", "a", "1");
+        assert_eq!(error_code(send(&router, put)), "NoSuchBucket");
+    }
+
+    #[test]
+    fn list_versions() {
+        let server = MemoryServer::new().with_versioned_bucket("bucket");
+        let router = server.router();
+
+        let put = put_object::Request::new("bucket", "key", "first");
+        let first = send(&router, put).unwrap().version_id.unwrap();
+        let put = put_object::Request::new("bucket", "key", "second");
+        send(&router, put).unwrap();
+        assert_eq!(server.object("bucket", "key").unwrap(), "second");
+
+        let mut get = get_object::Request::new("bucket", "key");
+        get.version_id = Some(first.clone());
+        assert_eq!(send(&router, get).unwrap().body, b"first");
+
+        let mut request = list_object_versions::Request::new("bucket");
+        request.max_keys = Some(1);
+        let response = send(&router, request.clone()).unwrap();
+        assert!(response.is_truncated);
+        assert!(response.versions[0].is_latest);
+        let request = request.next_page(&response).unwrap();
+        let response = send(&router, request).unwrap();
+        assert!(!response.is_truncated);
+        assert_eq!(response.versions[0].version_id.as_ref(), Some(&first));
+        assert!(!response.versions[0].is_latest);
+    }
+
+    #[test]
+    fn list_objects() {
+        let server = MemoryServer::new().with_bucket("bucket");
+        let router = server.router();
+        for key in ["a-1", "a-2", "b", "c-1", "d"] {
+            send(&router, put_object::Request::new("bucket", key, key))
+                .unwrap();
+        }
+
+        let mut request = list_objects_v2::Request::new("bucket");
+        request.delimiter = Some("-".to_owned());
+        request.max_keys = Some(2);
+        let mut keys = Vec::new();
+        let mut prefixes = Vec::new();
+        let mut request = Some(request);
+        while let Some(page) = request {
+            let response = send(&router, page.clone()).unwrap();
+            keys.extend(response.contents.iter().map(|o| o.key.clone()));
+            prefixes.extend(response.common_prefixes.iter().cloned());
+            request = page.next_page(&response);
+        }
+        assert_eq!(keys, ["b", "d"]);
+        assert_eq!(prefixes, ["a-", "c-"]);
+
+        let mut request = list_objects_v2::Request::new("bucket");
+        request.prefix = Some("a-".to_owned());
+        let response = send(&router, request).unwrap();
+        assert_eq!(response.key_count, 2);
+        assert_eq!(response.contents[1].key, "a-2");
+        assert_eq!(response.contents[1].size, 3);
+    }
+
+    #[test]
+    fn upload_multipart() {
+        let server = MemoryServer::new().with_bucket("bucket");
+        let router = server.router();
+
+        let create = create_multipart_upload::Request::new("bucket", "key");
+        let upload_id = send(&router, create).unwrap().upload_id;
+        let create = create_multipart_upload::Request::new("bucket", "other");
+        let other = send(&router, create).unwrap().upload_id;
+        let uploads = list_multipart_uploads::Request::new("bucket");
+        assert_eq!(send(&router, uploads).unwrap().uploads.len(), 2);
+
+        let mut parts = Vec::new();
+        for (part_number, data) in [(1, "hello "), (2, "world")] {
+            let part = upload_part::Request::new(
+                "bucket",
+                "key",
+                &upload_id,
+                part_number,
+                data,
+            );
+            let etag = send(&router, part).unwrap().etag.unwrap();
+            parts.push(complete_multipart_upload::CompletedPart::new(
+                part_number,
+                etag,
+            ));
+        }
+        let list = list_parts::Request::new("bucket", "key", &upload_id);
+        let response = send(&router, list).unwrap();
+        assert_eq!(response.parts.len(), 2);
+        assert_eq!(response.parts[1].size, 5);
+
+        let mut reversed = parts.clone();
+        reversed.reverse();
+        let complete = complete_multipart_upload::Request::new(
+            "bucket", "key", &upload_id, reversed,
+        );
+        assert_eq!(error_code(send(&router, complete)), "InvalidPartOrder");
+        let complete = complete_multipart_upload::Request::new(
+            "bucket", "key", &upload_id, parts,
+        );
+        let etag = send(&router, complete).unwrap().etag.unwrap();
+        assert!(etag.ends_with("-2\""));
+        assert_eq!(server.object("bucket", "key").unwrap(), "hello world");
+
+        let abort =
+            abort_multipart_upload::Request::new("bucket", "other", &other);
+        send(&router, abort.clone()).unwrap();
+        assert_eq!(server.upload_count("bucket"), 0);
+        let response = block_on(
+            router.handle(
+                abort
+                    .try_into_http_request::<Vec<u8>>("")
+                    .unwrap()
+                    .map(Bytes::from),
+            ),
+        );
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,448 @@
+//! Middlewares as `tower` services, with the `tower` feature, to be stacked
+//! in front of a [`RouterService`](crate::hyper::RouterService) or of any
+//! other service.
+//!
+//! The layers have the `layer` method of `tower::Layer`, to wrap services
+//! with them.
+
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::future::{BoxFuture, FutureExt};
+use http::{header::CONTENT_LENGTH, StatusCode};
+use http_body::Limited;
+use s3ers_api::error::S3Error;
+use tower_service::Service;
+
+use crate::{
+    auth::{AccessKeyStore, SigV4Verifier},
+    ErrorResponse, RequestId,
+};
+
+/// A layer authenticating requests with a [`SigV4Verifier`] before they
+/// reach the service it wraps.
+#[derive(Debug)]
+pub struct SigV4Layer<S> {
+    verifier: Arc<SigV4Verifier<S>>,
+}
+
+impl<S> SigV4Layer<S> {
+    /// A layer authenticating requests with `verifier`.
+    pub fn new(verifier: SigV4Verifier<S>) -> Self {
+        Self {
+            verifier: Arc::new(verifier),
+        }
+    }
+
+    /// Wraps `inner` in a [`SigV4Service`].
+    pub fn layer<I>(&self, inner: I) -> SigV4Service<S, I> {
+        SigV4Service {
+            verifier: self.verifier.clone(),
+            inner,
+        }
+    }
+}
+
+impl<S> Clone for SigV4Layer<S> {
+    fn clone(&self) -> Self {
+        Self {
+            verifier: self.verifier.clone(),
+        }
+    }
+}
+
+/// A service authenticating requests before handing them to another, and
+/// replying to the ones that fail to authenticate with an S3 error.
+///
+/// Authenticated requests have their [`Principal`](crate::auth::Principal)
+/// in their extensions.
+#[derive(Debug)]
+pub struct SigV4Service<S, I> {
+    verifier: Arc<SigV4Verifier<S>>,
+    inner: I,
+}
+
+impl<S, I: Clone> Clone for SigV4Service<S, I> {
+    fn clone(&self) -> Self {
+        Self {
+            verifier: self.verifier.clone(),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S, I, B> Service<http::Request<Bytes>> for SigV4Service<S, I>
+where
+    S: AccessKeyStore + 'static,
+    I: Service<http::Request<Bytes>, Response = http::Response<B>>
+        + Clone
+        + Send
+        + 'static,
+    I::Future: Send,
+    B: From<Bytes>,
+{
+    type Response = http::Response<B>;
+    type Error = I::Error;
+    type Future = BoxFuture<'static, Result<http::Response<B>, I::Error>>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<Bytes>) -> Self::Future {
+        let verifier = self.verifier.clone();
+        // The inner service that was polled ready is the one called.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        async move {
+            match verifier.verify(&mut request).await {
+                Ok(()) => inner.call(request).await,
+                Err(error) => Ok(ErrorResponse::from(error)
+                    .with_ids(request.extensions().get())
+                    .into_http_response()
+                    .map(B::from)),
+            }
+        }
+        .boxed()
+    }
+}
+
+/// A layer rejecting requests whose body is larger than a limit with an
+/// `EntityTooLarge` error.
+#[derive(Clone, Copy, Debug)]
+pub struct BodyLimitLayer {
+    max_size: usize,
+}
+
+impl BodyLimitLayer {
+    /// A layer limiting bodies to `max_size` bytes.
+    pub fn new(max_size: usize) -> Self {
+        Self { max_size }
+    }
+
+    /// Wraps `inner` in a [`BodyLimitService`].
+    pub fn layer<I>(&self, inner: I) -> BodyLimitService<I> {
+        BodyLimitService {
+            max_size: self.max_size,
+            inner,
+        }
+    }
+}
+
+/// A service limiting the size of the body of requests before handing them
+/// to another.
+///
+/// Requests whose `Content-Length` exceeds the limit are rejected right
+/// away, and the body of the others fails to be read past the limit, as
+/// with [`http_body::Limited`].
+#[derive(Clone, Debug)]
+pub struct BodyLimitService<I> {
+    max_size: usize,
+    inner: I,
+}
+
+impl<I, ReqBody, ResBody> Service<http::Request<ReqBody>>
+    for BodyLimitService<I>
+where
+    I: Service<
+        http::Request<Limited<ReqBody>>,
+        Response = http::Response<ResBody>,
+        Error = Infallible,
+    >,
+    I::Future: Send + 'static,
+    ResBody: From<Bytes> + Send + 'static,
+{
+    type Response = http::Response<ResBody>;
+    type Error = Infallible;
+    type Future =
+        BoxFuture<'static, Result<http::Response<ResBody>, Infallible>>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        let content_length = request
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if content_length.is_some_and(|length| length > self.max_size as u64) {
+            let error =
+                S3Error::new(StatusCode::PAYLOAD_TOO_LARGE, "EntityTooLarge")
+                    .with_message(
+                        "Your proposed upload exceeds the maximum allowed \
+                         size.",
+                    );
+            let response = ErrorResponse::from(error)
+                .into_http_response()
+                .map(ResBody::from);
+            return futures_util::future::ready(Ok(response)).boxed();
+        }
+
+        let max_size = self.max_size;
+        self.inner
+            .call(request.map(|body| Limited::new(body, max_size)))
+            .boxed()
+    }
+}
+
+/// A layer giving [`RequestId`]s to requests and their responses.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequestIdLayer {
+    _private: (),
+}
+
+impl RequestIdLayer {
+    /// A layer generating the IDs of requests with
+    /// [`RequestId::generate`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wraps `inner` in a [`RequestIdService`].
+    pub fn layer<I>(&self, inner: I) -> RequestIdService<I> {
+        RequestIdService { inner }
+    }
+}
+
+/// A service generating the [`RequestId`] of requests before handing them
+/// to another, and sending it in the `x-amz-request-id` and `x-amz-id-2`
+/// headers of their response.
+///
+/// The IDs are inserted in the extensions of requests, so that handlers
+/// can get them, and the [`Router`](crate::Router) and the
+/// [`SigV4Service`] put them in the `Error` document of the errors they
+/// reply with. To be given to all the responses, including the errors of
+/// other middlewares, it has to wrap them.
+#[derive(Clone, Debug)]
+pub struct RequestIdService<I> {
+    inner: I,
+}
+
+impl<I, ReqBody, ResBody> Service<http::Request<ReqBody>>
+    for RequestIdService<I>
+where
+    I: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    I::Future: Send + 'static,
+{
+    type Response = http::Response<ResBody>;
+    type Error = I::Error;
+    type Future = BoxFuture<'static, Result<http::Response<ResBody>, I::Error>>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        let ids = RequestId::generate();
+        request.extensions_mut().insert(ids.clone());
+        let response = self.inner.call(request);
+        async move {
+            let mut response = response.await?;
+            ids.apply(response.headers_mut());
+            Ok(response)
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        convert::Infallible,
+        task::{Context, Poll},
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use bytes::Bytes;
+    use futures_executor::block_on;
+    use futures_util::future::{self, Ready};
+    use http::StatusCode;
+    use http_body::{Body, Full, Limited};
+    use s3ers_signature::{
+        clock::FixedClock, sign_request, Credentials, SigningParams,
+    };
+    use tower_service::Service;
+
+    use super::{BodyLimitLayer, RequestIdLayer, SigV4Layer};
+    use crate::{
+        auth::{Principal, SigV4Verifier},
+        RequestId,
+    };
+
+    /// Replies with the access key ID of the principal of requests.
+    #[derive(Clone)]
+    struct Echo;
+
+    impl Service<http::Request<Bytes>> for Echo {
+        type Response = http::Response<Bytes>;
+        type Error = Infallible;
+        type Future = Ready<Result<http::Response<Bytes>, Infallible>>;
+
+        fn poll_ready(
+            &mut self,
+            _: &mut Context<'_>,
+        ) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<Bytes>) -> Self::Future {
+            let principal = request.extensions().get::<Principal>().unwrap();
+            let body = Bytes::from(principal.access_key_id.clone());
+            future::ready(Ok(http::Response::new(body)))
+        }
+    }
+
+    /// Replies with the size of the body of requests, or `413` if it is too
+    /// large.
+    struct Size;
+
+    impl Service<http::Request<Limited<Full<Bytes>>>> for Size {
+        type Response = http::Response<Bytes>;
+        type Error = Infallible;
+        type Future = Ready<Result<http::Response<Bytes>, Infallible>>;
+
+        fn poll_ready(
+            &mut self,
+            _: &mut Context<'_>,
+        ) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(
+            &mut self,
+            request: http::Request<Limited<Full<Bytes>>>,
+        ) -> Self::Future {
+            let mut body = request.into_body();
+            let mut size = 0;
+            let mut response = http::Response::new(Bytes::new());
+            while let Some(chunk) = block_on(body.data()) {
+                match chunk {
+                    Ok(chunk) => size += chunk.len(),
+                    Err(_) => {
+                        *response.status_mut() = StatusCode::PAYLOAD_TOO_LARGE;
+                        return future::ready(Ok(response));
+                    }
+                }
+            }
+            *response.body_mut() = Bytes::from(size.to_string());
+            future::ready(Ok(response))
+        }
+    }
+
+    /// Replies with the request ID of requests.
+    #[derive(Clone)]
+    struct Id;
+
+    impl Service<http::Request<Bytes>> for Id {
+        type Response = http::Response<Bytes>;
+        type Error = Infallible;
+        type Future = Ready<Result<http::Response<Bytes>, Infallible>>;
+
+        fn poll_ready(
+            &mut self,
+            _: &mut Context<'_>,
+        ) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<Bytes>) -> Self::Future {
+            let id = request.extensions().get::<RequestId>().unwrap();
+            future::ready(Ok(http::Response::new(Bytes::from(id.to_string()))))
+        }
+    }
+
+    #[test]
+    fn authenticate_requests() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_369_353_600);
+        let mut keys = HashMap::new();
+        keys.insert("AKIDEXAMPLE".to_owned(), "secret".to_owned());
+        let verifier = SigV4Verifier::new(keys).with_clock(FixedClock(now));
+        let mut service = SigV4Layer::new(verifier).layer(Echo);
+
+        let mut request =
+            http::Request::get("https://example.com/bucket").body(Vec::new());
+        sign_request(
+            request.as_mut().unwrap(),
+            &Credentials::new("AKIDEXAMPLE", "secret"),
+            &SigningParams::new("us-east-1", "s3", now),
+        )
+        .unwrap();
+        let request = request.unwrap().map(Bytes::from);
+        let response = block_on(service.call(request)).unwrap();
+        assert_eq!(response.body(), "AKIDEXAMPLE");
+
+        let request = http::Request::get("/bucket").body(Bytes::new());
+        let response = block_on(service.call(request.unwrap())).unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn limit_bodies() {
+        let mut service = BodyLimitLayer::new(4).layer(Size);
+        let request = |body: &'static str, length: Option<usize>| {
+            let mut request = http::Request::new(Full::new(Bytes::from(body)));
+            if let Some(length) = length {
+                request
+                    .headers_mut()
+                    .insert("content-length", length.into());
+            }
+            request
+        };
+
+        let response = block_on(service.call(request("1234", Some(4))));
+        assert_eq!(response.unwrap().body(), "4");
+        let response = block_on(service.call(request("12345", Some(5))));
+        let response = response.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(std::str::from_utf8(response.body())
+            .unwrap()
+            .contains("<Code>EntityTooLarge</Code>"));
+        let response = block_on(service.call(request("12345", None)));
+        assert_eq!(response.unwrap().status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn give_ids_to_requests() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_369_353_600);
+        let verifier =
+            SigV4Verifier::new(HashMap::new()).with_clock(FixedClock(now));
+        let mut service =
+            RequestIdLayer::new().layer(SigV4Layer::new(verifier).layer(Echo));
+
+        let request = http::Request::get("/bucket").body(Bytes::new());
+        let response = block_on(service.call(request.unwrap())).unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let request_id = response.headers()["x-amz-request-id"].to_str();
+        let host_id = response.headers()["x-amz-id-2"].to_str().unwrap();
+        let body = std::str::from_utf8(response.body()).unwrap();
+        assert!(body.contains(&format!(
+            "<RequestId>{}</RequestId>",
+            request_id.unwrap()
+        )));
+        assert!(body.contains(&format!("<HostId>{}</HostId>", host_id)));
+
+        let mut service = RequestIdLayer::new().layer(Id);
+        let request = http::Request::get("/bucket").body(Bytes::new());
+        let response = block_on(service.call(request.unwrap())).unwrap();
+        assert_eq!(
+            response.headers()["x-amz-request-id"].as_bytes(),
+            response.body()
+        );
+    }
+}
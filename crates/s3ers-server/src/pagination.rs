@@ -0,0 +1,336 @@
+//! Paginating the listings of servers.
+//!
+//! [`paginate`] cuts the entries of a listing, sorted by key, into a
+//! [`Page`] of at most [`MAX_KEYS`] entries, grouping keys into common
+//! prefixes like S3 does. The position a page ends at, its [`Marker`], is
+//! given to clients as an opaque continuation token with
+//! [`ContinuationTokens`], which signs it so that tokens changed by clients
+//! are rejected.
+//!
+//! ```
+//! use s3ers_server::pagination::{paginate, ContinuationTokens, Marker};
+//!
+//! let tokens = ContinuationTokens::new("secret");
+//! let keys = ["a", "b/1", "b/2", "c"];
+//! let page = paginate(
+//!     keys.iter().map(|key| (*key, ())),
+//!     None,
+//!     Some("/"),
+//!     Some(2),
+//!     |_, _| false,
+//! );
+//! assert_eq!(page.common_prefixes, ["b/"]);
+//! assert!(page.is_truncated);
+//!
+//! let token = tokens.encode(&Marker::new(page.next_marker.unwrap()));
+//! let after = tokens.decode(&token).unwrap().key;
+//! let page = paginate(
+//!     keys.iter().map(|key| (*key, ())),
+//!     None,
+//!     Some("/"),
+//!     Some(2),
+//!     |key, _| key <= after.as_str(),
+//! );
+//! assert_eq!(page.items, [("c".to_owned(), ())]);
+//! assert!(!page.is_truncated);
+//! ```
+
+use std::{error::Error, fmt};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use http::StatusCode;
+use s3ers_api::error::S3Error;
+use serde_json::{json, Value as JsonValue};
+use sha2::Sha256;
+
+/// The most entries listed in a page, and the default.
+pub const MAX_KEYS: u32 = 1000;
+
+/// The length of the signature of tokens, in bytes.
+const SIGNATURE_LEN: usize = 16;
+
+/// A page of a listing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Page<T> {
+    /// The keys listed and their entries.
+    pub items: Vec<(String, T)>,
+
+    /// The common prefixes listed, in place of the keys they contain.
+    pub common_prefixes: Vec<String>,
+
+    /// Whether entries are left after the page.
+    pub is_truncated: bool,
+
+    /// The last key or common prefix of the page, after which the next page
+    /// starts.
+    pub next_marker: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Returns the number of keys and common prefixes of the page, its
+    /// `KeyCount`.
+    pub fn key_count(&self) -> u32 {
+        (self.items.len() + self.common_prefixes.len()) as u32
+    }
+}
+
+/// Lists the entries whose key starts with `prefix`, sorted by key, except
+/// the ones skipped by `skip`, grouping the keys containing `delimiter`
+/// after the prefix into common prefixes, in a page of at most `max_keys`
+/// entries.
+///
+/// `skip` is given the key of entries, or their common prefix, to skip the
+/// ones up to the marker of the previous page.
+pub fn paginate<'a, T>(
+    entries: impl IntoIterator<Item = (&'a str, T)>,
+    prefix: Option<&str>,
+    delimiter: Option<&str>,
+    max_keys: Option<u32>,
+    skip: impl Fn(&str, &T) -> bool,
+) -> Page<T> {
+    let prefix = prefix.unwrap_or_default();
+    let max = self::max_keys(max_keys);
+    let mut page = Page {
+        items: Vec::new(),
+        common_prefixes: Vec::new(),
+        is_truncated: false,
+        next_marker: None,
+    };
+    for (key, entry) in entries {
+        let rest = match key.strip_prefix(prefix) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let common_prefix = delimiter
+            .filter(|delimiter| !delimiter.is_empty())
+            .and_then(|delimiter| {
+                let end = rest.find(delimiter)? + delimiter.len();
+                Some(&key[..prefix.len() + end])
+            });
+        let marker = common_prefix.unwrap_or(key);
+        let grouped = common_prefix.is_some()
+            && page.next_marker.as_deref() == common_prefix;
+        if grouped || skip(marker, &entry) {
+            continue;
+        }
+        if page.items.len() + page.common_prefixes.len() == max {
+            page.is_truncated = true;
+            break;
+        }
+        match common_prefix {
+            Some(common_prefix) => {
+                page.common_prefixes.push(common_prefix.to_owned())
+            }
+            None => page.items.push((key.to_owned(), entry)),
+        }
+        page.next_marker = Some(marker.to_owned());
+    }
+    page
+}
+
+/// Returns the most entries to list in a page, from the `max-keys` of a
+/// request, which is at most [`MAX_KEYS`].
+pub fn max_keys(requested: Option<u32>) -> usize {
+    requested.unwrap_or(MAX_KEYS).min(MAX_KEYS) as usize
+}
+
+/// The position a page of a listing ends at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Marker {
+    /// The last key or common prefix of the page.
+    pub key: String,
+
+    /// The last version of the key listed, for listings of versions.
+    pub version_id: Option<String>,
+}
+
+impl Marker {
+    /// Creates the marker of a key.
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            version_id: None,
+        }
+    }
+
+    /// Sets the last version of the key listed.
+    pub fn with_version_id(mut self, version_id: impl Into<String>) -> Self {
+        self.version_id = Some(version_id.into());
+        self
+    }
+}
+
+/// Encodes markers as the opaque continuation tokens of S3, and decodes
+/// them back.
+///
+/// Tokens are the marker and its HMAC-SHA256 signature with the secret of
+/// the server, in URL-safe base64: the same marker is always encoded as the
+/// same token, and tokens not encoded with the secret fail to decode.
+#[derive(Clone)]
+pub struct ContinuationTokens {
+    secret: Vec<u8>,
+}
+
+impl ContinuationTokens {
+    /// Creates the tokens signed with a secret, which has to be kept by the
+    /// server for its tokens to stay valid.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            secret: secret.as_ref().to_vec(),
+        }
+    }
+
+    /// Encodes a marker as a token.
+    pub fn encode(&self, marker: &Marker) -> String {
+        let payload = match &marker.version_id {
+            Some(version_id) => json!([marker.key, version_id]),
+            None => json!([marker.key]),
+        };
+        let mut token = payload.to_string().into_bytes();
+        let signature = self.sign(&token);
+        token.extend_from_slice(&signature);
+        URL_SAFE_NO_PAD.encode(token)
+    }
+
+    /// Decodes the marker of a token, checking its signature.
+    pub fn decode(&self, token: &str) -> Result<Marker, InvalidToken> {
+        let token = URL_SAFE_NO_PAD.decode(token).map_err(|_| InvalidToken)?;
+        let split =
+            token.len().checked_sub(SIGNATURE_LEN).ok_or(InvalidToken)?;
+        let (payload, signature) = token.split_at(split);
+        self.mac(payload)
+            .verify_truncated_left(signature)
+            .map_err(|_| InvalidToken)?;
+
+        let payload: JsonValue =
+            serde_json::from_slice(payload).map_err(|_| InvalidToken)?;
+        let string = |value: &JsonValue| {
+            value.as_str().map(ToOwned::to_owned).ok_or(InvalidToken)
+        };
+        match payload.as_array().map(Vec::as_slice) {
+            Some([key]) => Ok(Marker::new(string(key)?)),
+            Some([key, version_id]) => {
+                Ok(Marker::new(string(key)?)
+                    .with_version_id(string(version_id)?))
+            }
+            _ => Err(InvalidToken),
+        }
+    }
+
+    fn sign(&self, payload: &[u8]) -> [u8; SIGNATURE_LEN] {
+        let mut signature = [0; SIGNATURE_LEN];
+        signature.copy_from_slice(
+            &self.mac(payload).finalize().into_bytes()[..SIGNATURE_LEN],
+        );
+        signature
+    }
+
+    fn mac(&self, payload: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret)
+            .expect("HMAC accepts keys of any length");
+        mac.update(payload);
+        mac
+    }
+}
+
+impl fmt::Debug for ContinuationTokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContinuationTokens").finish_non_exhaustive()
+    }
+}
+
+/// A continuation token which was not encoded by the server, or was changed
+/// since.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InvalidToken;
+
+impl fmt::Display for InvalidToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the continuation token is invalid")
+    }
+}
+
+impl Error for InvalidToken {}
+
+/// The `InvalidArgument` error S3 returns for invalid tokens.
+impl From<InvalidToken> for S3Error {
+    fn from(_: InvalidToken) -> Self {
+        S3Error::new(StatusCode::BAD_REQUEST, "InvalidArgument")
+            .with_message("The continuation token provided is incorrect")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+
+    use super::{max_keys, paginate, ContinuationTokens, InvalidToken, Marker};
+
+    #[test]
+    fn paginate_entries() {
+        let keys = ["a/1", "a/2", "b", "c/1", "d"];
+        let entries = || keys.iter().map(|key| (*key, ()));
+
+        let page = paginate(entries(), None, Some("/"), Some(2), |_, _| false);
+        assert_eq!(page.items, [("b".to_owned(), ())]);
+        assert_eq!(page.common_prefixes, ["a/"]);
+        assert_eq!(page.key_count(), 2);
+        assert!(page.is_truncated);
+        assert_eq!(page.next_marker.as_deref(), Some("b"));
+
+        let page =
+            paginate(entries(), None, Some("/"), Some(2), |key, _| key <= "b");
+        assert_eq!(page.common_prefixes, ["c/"]);
+        assert_eq!(page.items, [("d".to_owned(), ())]);
+        assert!(!page.is_truncated);
+
+        let page = paginate(entries(), Some("a/"), None, None, |_, _| false);
+        assert_eq!(page.key_count(), 2);
+        assert!(!page.is_truncated);
+        let page = paginate(entries(), None, None, Some(0), |_, _| false);
+        assert_eq!(page.key_count(), 0);
+        assert!(page.is_truncated);
+
+        assert_eq!(max_keys(None), 1000);
+        assert_eq!(max_keys(Some(10)), 10);
+        assert_eq!(max_keys(Some(5000)), 1000);
+    }
+
+    #[test]
+    fn round_trip_tokens() {
+        let tokens = ContinuationTokens::new("secret");
+        let markers = [
+            Marker::new("photos/2006/"),
+            Marker::new("").with_version_id("null"),
+            Marker::new("é/\"[]\0"),
+        ];
+        for marker in markers {
+            let token = tokens.encode(&marker);
+            assert_eq!(token, tokens.encode(&marker));
+            assert!(!token.contains(['+', '/', '=']));
+            assert_eq!(tokens.decode(&token), Ok(marker));
+        }
+    }
+
+    #[test]
+    fn reject_invalid_tokens() {
+        let tokens = ContinuationTokens::new("secret");
+        let token = tokens.encode(&Marker::new("key"));
+        let other =
+            ContinuationTokens::new("other").encode(&Marker::new("key"));
+        assert_eq!(tokens.decode(&other), Err(InvalidToken));
+
+        let mut tampered = URL_SAFE_NO_PAD.decode(&token).unwrap();
+        tampered[3] ^= 1;
+        let tampered = URL_SAFE_NO_PAD.encode(tampered);
+        assert_eq!(tokens.decode(&tampered), Err(InvalidToken));
+
+        for token in ["", "key", "a2V5", "!!!"] {
+            assert_eq!(tokens.decode(token), Err(InvalidToken));
+        }
+    }
+}
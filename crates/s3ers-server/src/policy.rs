@@ -0,0 +1,1152 @@
+//! Bucket policies, the JSON documents granting or denying actions on
+//! resources to principals, and their evaluation.
+//!
+//! Servers keep the [`Policy`] of each bucket, read from the JSON document
+//! S3 stores, and evaluate it against a [`PolicyRequest`] describing each
+//! request they receive, before handling it. The condition keys of requests,
+//! like `aws:SourceIp` or `s3:prefix`, are compared by the string, numeric,
+//! date, boolean, IP address and ARN operators of conditions.
+//!
+//! ```
+//! use s3ers_server::policy::{Decision, Policy, PolicyRequest};
+//!
+//! let policy = Policy::from_json(
+//!     br#"{
+//!         "Version": "2012-10-17",
+//!         "Statement": [{
+//!             "Effect": "Allow",
+//!             "Principal": "*",
+//!             "Action": "s3:GetObject",
+//!             "Resource": "arn:aws:s3:::bucket/public/*"
+//!         }]
+//!     }"#,
+//! )
+//! .unwrap();
+//! let object = "arn:aws:s3:::bucket/public/a";
+//! let request = PolicyRequest::new("s3:GetObject", object);
+//! assert_eq!(policy.evaluate(&request), Decision::Allow);
+//! let request = PolicyRequest::new("s3:PutObject", object);
+//! assert_eq!(policy.evaluate(&request), Decision::ImplicitDeny);
+//! ```
+
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    net::IpAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use s3ers_api::{error::DeserializationError, header::HttpDate, uri};
+use s3ers_signature::Clock;
+use serde_json::{json, Map, Value as JsonValue};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// A policy document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Policy {
+    /// The version of the policy language, like `2012-10-17`.
+    pub version: Option<String>,
+
+    /// The ID of the policy.
+    pub id: Option<String>,
+
+    /// The statements of the policy.
+    pub statements: Vec<Statement>,
+}
+
+/// A statement of a policy, allowing or denying the actions matching its
+/// action patterns on the resources matching its resource patterns, to the
+/// principals it applies to, when its conditions hold.
+///
+/// Patterns can contain `*` wildcards, matching any characters, and `?`
+/// wildcards, matching one. Actions, like `s3:GetObject`, are matched
+/// regardless of case, and resources, like `arn:aws:s3:::bucket/key`, are
+/// not.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Statement {
+    /// The ID of the statement.
+    pub sid: Option<String>,
+
+    /// Whether the statement allows or denies the actions.
+    pub effect: Effect,
+
+    /// The principals the statement applies to, or `None` if it doesn't
+    /// have a `Principal` element.
+    pub principal: Option<Principals>,
+
+    /// The principals the statement applies to all but, or `None` if it
+    /// doesn't have a `NotPrincipal` element.
+    pub not_principal: Option<Principals>,
+
+    /// The patterns of the actions of the statement.
+    pub action: Vec<String>,
+
+    /// The patterns of the actions the statement applies to all but.
+    pub not_action: Vec<String>,
+
+    /// The patterns of the resources of the statement.
+    pub resource: Vec<String>,
+
+    /// The patterns of the resources the statement applies to all but.
+    pub not_resource: Vec<String>,
+
+    /// The conditions that must all hold for the statement to apply.
+    pub conditions: Vec<Condition>,
+}
+
+/// Whether a statement allows or denies actions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    /// The statement allows the actions.
+    Allow,
+
+    /// The statement denies the actions, even if other statements allow
+    /// them.
+    Deny,
+}
+
+/// The principals a statement applies to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Principals {
+    /// Every principal, including anonymous ones, from `"Principal": "*"`.
+    Any,
+
+    /// The principals of each type, like `AWS` or `Service`, from
+    /// `"Principal": {"AWS": ["arn:aws:iam::123456789012:root"]}`. A `*`
+    /// matches every principal.
+    Ids(BTreeMap<String, Vec<String>>),
+}
+
+/// A condition of a statement, comparing the values of a key of the
+/// context of requests, like `s3:prefix`, with its values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Condition {
+    /// The operator of the condition, like `StringEquals`, with its
+    /// `ForAllValues:` or `ForAnyValue:` prefix and its `IfExists` suffix.
+    pub operator: String,
+
+    /// The condition key, like `s3:prefix`.
+    pub key: String,
+
+    /// The values the values of the key are compared with.
+    pub values: Vec<String>,
+}
+
+/// A request, as seen by a policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PolicyRequest {
+    /// The principal sending the request, like
+    /// `arn:aws:iam::123456789012:user/alice`, or `None` if it is
+    /// anonymous.
+    pub principal: Option<String>,
+
+    /// The action of the request, like `s3:GetObject`.
+    pub action: String,
+
+    /// The resource of the request, like `arn:aws:s3:::bucket/key`.
+    pub resource: String,
+
+    /// The values of the condition keys of the request, by key in lower
+    /// case.
+    pub context: BTreeMap<String, Vec<String>>,
+}
+
+/// The decision of a policy about a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    /// A statement allows the request, and none denies it.
+    Allow,
+
+    /// A statement denies the request.
+    ExplicitDeny,
+
+    /// No statement applies to the request, which is denied unless another
+    /// policy allows it.
+    ImplicitDeny,
+}
+
+impl Policy {
+    /// Creates a policy with the given statements.
+    pub fn new(statements: Vec<Statement>) -> Self {
+        Self {
+            version: Some("2012-10-17".to_owned()),
+            id: None,
+            statements,
+        }
+    }
+
+    /// Parses a policy document.
+    pub fn from_json(json: &[u8]) -> Result<Self, DeserializationError> {
+        let json: JsonValue = serde_json::from_slice(json)
+            .map_err(|_| DeserializationError::Invalid("policy".to_owned()))?;
+        let statements = match json.get("Statement") {
+            Some(JsonValue::Array(statements)) => statements.iter().collect(),
+            Some(statement) => vec![statement],
+            None => return Err(missing("Statement")),
+        };
+        Ok(Self {
+            version: string_field(&json, "Version")?,
+            id: string_field(&json, "Id")?,
+            statements: statements
+                .into_iter()
+                .map(Statement::from_json)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Writes the policy document.
+    pub fn to_json(&self) -> String {
+        let mut json = Map::new();
+        if let Some(version) = &self.version {
+            json.insert("Version".to_owned(), json!(version));
+        }
+        if let Some(id) = &self.id {
+            json.insert("Id".to_owned(), json!(id));
+        }
+        let statements = self.statements.iter().map(Statement::to_json);
+        json.insert("Statement".to_owned(), statements.collect());
+        JsonValue::Object(json).to_string()
+    }
+
+    /// Evaluates the policy against a request: a statement denying it
+    /// denies it, and otherwise a statement allowing it allows it.
+    pub fn evaluate(&self, request: &PolicyRequest) -> Decision {
+        let mut decision = Decision::ImplicitDeny;
+        for statement in &self.statements {
+            if !statement.applies_to(request) {
+                continue;
+            }
+            match statement.effect {
+                Effect::Deny => return Decision::ExplicitDeny,
+                Effect::Allow => decision = Decision::Allow,
+            }
+        }
+        decision
+    }
+}
+
+impl Statement {
+    /// Creates a statement with an effect on actions and resources, applying
+    /// to every principal.
+    pub fn new(
+        effect: Effect,
+        action: Vec<String>,
+        resource: Vec<String>,
+    ) -> Self {
+        Self {
+            sid: None,
+            effect,
+            principal: Some(Principals::Any),
+            not_principal: None,
+            action,
+            not_action: Vec::new(),
+            resource,
+            not_resource: Vec::new(),
+            conditions: Vec::new(),
+        }
+    }
+
+    fn from_json(json: &JsonValue) -> Result<Self, DeserializationError> {
+        let effect = match json.get("Effect").and_then(JsonValue::as_str) {
+            Some("Allow") => Effect::Allow,
+            Some("Deny") => Effect::Deny,
+            Some(_) => return Err(invalid("Effect")),
+            None => return Err(missing("Effect")),
+        };
+        let statement = Self {
+            sid: string_field(json, "Sid")?,
+            effect,
+            principal: principals_field(json, "Principal")?,
+            not_principal: principals_field(json, "NotPrincipal")?,
+            action: strings_field(json, "Action")?,
+            not_action: strings_field(json, "NotAction")?,
+            resource: strings_field(json, "Resource")?,
+            not_resource: strings_field(json, "NotResource")?,
+            conditions: conditions_field(json)?,
+        };
+        if statement.action.is_empty() == statement.not_action.is_empty() {
+            return Err(invalid("Action"));
+        }
+        if statement.resource.is_empty() == statement.not_resource.is_empty() {
+            return Err(invalid("Resource"));
+        }
+        Ok(statement)
+    }
+
+    fn to_json(&self) -> JsonValue {
+        let mut json = Map::new();
+        if let Some(sid) = &self.sid {
+            json.insert("Sid".to_owned(), json!(sid));
+        }
+        let effect = match self.effect {
+            Effect::Allow => "Allow",
+            Effect::Deny => "Deny",
+        };
+        json.insert("Effect".to_owned(), json!(effect));
+        let principals = [
+            ("Principal", &self.principal),
+            ("NotPrincipal", &self.not_principal),
+        ];
+        for (name, principals) in principals {
+            match principals {
+                Some(Principals::Any) => {
+                    json.insert(name.to_owned(), json!("*"));
+                }
+                Some(Principals::Ids(ids)) => {
+                    let ids = ids
+                        .iter()
+                        .map(|(kind, ids)| (kind.clone(), strings_json(ids)));
+                    json.insert(name.to_owned(), ids.collect());
+                }
+                None => {}
+            }
+        }
+        let patterns = [
+            ("Action", &self.action),
+            ("NotAction", &self.not_action),
+            ("Resource", &self.resource),
+            ("NotResource", &self.not_resource),
+        ];
+        for (name, patterns) in patterns {
+            if !patterns.is_empty() {
+                json.insert(name.to_owned(), strings_json(patterns));
+            }
+        }
+        if !self.conditions.is_empty() {
+            let mut conditions = Map::new();
+            for condition in &self.conditions {
+                let keys = conditions
+                    .entry(condition.operator.clone())
+                    .or_insert_with(|| JsonValue::Object(Map::new()));
+                if let JsonValue::Object(keys) = keys {
+                    keys.insert(
+                        condition.key.clone(),
+                        strings_json(&condition.values),
+                    );
+                }
+            }
+            json.insert("Condition".to_owned(), JsonValue::Object(conditions));
+        }
+        JsonValue::Object(json)
+    }
+
+    /// Returns whether the statement applies to a request.
+    fn applies_to(&self, request: &PolicyRequest) -> bool {
+        let principal = request.principal.as_deref();
+        let principal_matches = match (&self.principal, &self.not_principal) {
+            (Some(principals), _) => principals.contains(principal),
+            (None, Some(principals)) => !principals.contains(principal),
+            (None, None) => true,
+        };
+        principal_matches
+            && matches_patterns(&self.action, &self.not_action, |pattern| {
+                wildcard_match(pattern, &request.action, true)
+            })
+            && matches_patterns(&self.resource, &self.not_resource, |pattern| {
+                wildcard_match(pattern, &request.resource, false)
+            })
+            && self
+                .conditions
+                .iter()
+                .all(|condition| condition.holds(&request.context))
+    }
+}
+
+impl Principals {
+    /// Returns whether a principal, or an anonymous one, is one of these.
+    fn contains(&self, principal: Option<&str>) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Ids(ids) => ids.values().flatten().any(|id| {
+                id == "*" || principal.is_some_and(|principal| id == principal)
+            }),
+        }
+    }
+}
+
+impl Condition {
+    /// Creates a condition comparing the values of a key with an operator.
+    pub fn new(
+        operator: impl Into<String>,
+        key: impl Into<String>,
+        values: Vec<String>,
+    ) -> Self {
+        Self {
+            operator: operator.into(),
+            key: key.into(),
+            values,
+        }
+    }
+
+    /// Returns whether the condition holds in the context of a request.
+    ///
+    /// Conditions with an unsupported operator, which policy documents are
+    /// rejected for, never hold.
+    fn holds(&self, context: &BTreeMap<String, Vec<String>>) -> bool {
+        let (for_all_values, operator, if_exists) =
+            match parse_operator(&self.operator) {
+                Some(operator) => operator,
+                None => return false,
+            };
+        let values = context
+            .get(&self.key.to_ascii_lowercase())
+            .filter(|values| !values.is_empty());
+        if operator == "Null" {
+            let is_null = values.is_none().to_string();
+            return self.values.contains(&is_null);
+        }
+
+        let (compare, negated) = match comparison(operator) {
+            Some(comparison) => comparison,
+            None => return false,
+        };
+        let values = match values {
+            Some(values) => values,
+            None => return if_exists || negated || for_all_values,
+        };
+        let matches = |value: &String| {
+            self.values.iter().any(|expected| compare(expected, value))
+        };
+        let matched = match for_all_values {
+            true => values.iter().all(matches),
+            false => values.iter().any(matches),
+        };
+        matched != negated
+    }
+}
+
+impl PolicyRequest {
+    /// Creates an anonymous request for an action on a resource.
+    pub fn new(action: impl Into<String>, resource: impl Into<String>) -> Self {
+        Self {
+            principal: None,
+            action: action.into(),
+            resource: resource.into(),
+            context: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the principal sending the request.
+    pub fn with_principal(mut self, principal: impl Into<String>) -> Self {
+        self.principal = Some(principal.into());
+        self
+    }
+
+    /// Adds a value of a condition key to the context of the request.
+    pub fn with_context_value(
+        mut self,
+        key: &str,
+        value: impl Into<String>,
+    ) -> Self {
+        self.context
+            .entry(key.to_ascii_lowercase())
+            .or_default()
+            .push(value.into());
+        self
+    }
+
+    /// Sets the value of a condition key, replacing its previous values.
+    fn with_context(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.context
+            .insert(key.to_ascii_lowercase(), vec![value.into()]);
+        self
+    }
+
+    /// Sets `aws:SourceIp`, the IP address the request was sent from.
+    pub fn with_source_ip(self, ip: IpAddr) -> Self {
+        self.with_context("aws:SourceIp", ip.to_string())
+    }
+
+    /// Sets `aws:SecureTransport`, whether the request was sent over TLS.
+    pub fn with_secure_transport(self, secure: bool) -> Self {
+        self.with_context("aws:SecureTransport", secure.to_string())
+    }
+
+    /// Sets `aws:CurrentTime` and `aws:EpochTime`, the time of the request.
+    pub fn with_current_time(self, now: SystemTime) -> Self {
+        let epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+        self.with_context("aws:CurrentTime", HttpDate::from(now).to_iso8601())
+            .with_context("aws:EpochTime", epoch.as_secs().to_string())
+    }
+
+    /// Sets the condition keys taken from an HTTP request: the S3 keys of
+    /// its query parameters, like `s3:prefix`, and of its headers, like
+    /// `s3:x-amz-acl`, `aws:UserAgent`, `aws:Referer`, the current time of
+    /// `clock`, and `aws:SecureTransport` if its URI has a scheme.
+    ///
+    /// `aws:SourceIp` has to be set with [`with_source_ip`] from the address
+    /// of the connection, and `aws:SecureTransport` with
+    /// [`with_secure_transport`] if the URI has no scheme.
+    ///
+    /// [`with_source_ip`]: Self::with_source_ip
+    /// [`with_secure_transport`]: Self::with_secure_transport
+    pub fn with_http_request<B>(
+        self,
+        request: &http::Request<B>,
+        clock: &impl Clock,
+    ) -> Self {
+        let mut policy_request = self.with_current_time(clock.now());
+        match request.uri().scheme_str() {
+            Some("https") => {
+                policy_request = policy_request.with_secure_transport(true)
+            }
+            Some("http") => {
+                policy_request = policy_request.with_secure_transport(false)
+            }
+            _ => {}
+        }
+
+        let query = uri::parse_query(request.uri().query().unwrap_or_default());
+        for (param, value) in query {
+            let key = match param.as_str() {
+                "prefix" => "s3:prefix",
+                "delimiter" => "s3:delimiter",
+                "max-keys" => "s3:max-keys",
+                "versionId" => "s3:VersionId",
+                _ => continue,
+            };
+            policy_request = policy_request.with_context(key, value);
+        }
+
+        for (name, value) in request.headers() {
+            let value = match value.to_str() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            let key = match name.as_str() {
+                "user-agent" => "aws:UserAgent".to_owned(),
+                "referer" => "aws:Referer".to_owned(),
+                name if S3_HEADER_KEYS.contains(&name) => {
+                    format!("s3:{}", name)
+                }
+                _ => continue,
+            };
+            policy_request = policy_request.with_context(&key, value);
+        }
+        policy_request
+    }
+}
+
+/// The headers of requests whose value is the one of the condition key
+/// with their name prefixed by `s3:`.
+const S3_HEADER_KEYS: &[&str] = &[
+    "x-amz-acl",
+    "x-amz-content-sha256",
+    "x-amz-copy-source",
+    "x-amz-grant-full-control",
+    "x-amz-grant-read",
+    "x-amz-grant-read-acp",
+    "x-amz-grant-write",
+    "x-amz-grant-write-acp",
+    "x-amz-metadata-directive",
+    "x-amz-server-side-encryption",
+    "x-amz-server-side-encryption-aws-kms-key-id",
+    "x-amz-storage-class",
+    "x-amz-website-redirect-location",
+];
+
+/// A comparison of the expected value of a condition with a value of a
+/// request.
+type Compare = fn(&str, &str) -> bool;
+
+/// Splits a condition operator into whether it has the `ForAllValues:`
+/// qualifier, its name and whether it has the `IfExists` suffix, returning
+/// `None` if its qualifier or its name isn't supported.
+fn parse_operator(operator: &str) -> Option<(bool, &str, bool)> {
+    let (for_all_values, operator) = match operator.split_once(':') {
+        Some(("ForAllValues", operator)) => (true, operator),
+        Some(("ForAnyValue", operator)) => (false, operator),
+        Some(_) => return None,
+        None => (false, operator),
+    };
+    let (operator, if_exists) = match operator.strip_suffix("IfExists") {
+        Some(operator) => (operator, true),
+        None => (operator, false),
+    };
+    if operator != "Null" && comparison(operator).is_none() {
+        return None;
+    }
+    Some((for_all_values, operator, if_exists))
+}
+
+/// Returns the comparison of a condition operator, without its prefix and
+/// suffix, and whether it is negated.
+fn comparison(operator: &str) -> Option<(Compare, bool)> {
+    let comparison: (Compare, bool) = match operator {
+        "StringEquals" => (|expected, value| expected == value, false),
+        "StringNotEquals" => (|expected, value| expected == value, true),
+        "StringEqualsIgnoreCase" => (
+            |expected, value| expected.eq_ignore_ascii_case(value),
+            false,
+        ),
+        "StringNotEqualsIgnoreCase" => {
+            (|expected, value| expected.eq_ignore_ascii_case(value), true)
+        }
+        "StringLike" => (
+            |expected, value| wildcard_match(expected, value, false),
+            false,
+        ),
+        "StringNotLike" => (
+            |expected, value| wildcard_match(expected, value, false),
+            true,
+        ),
+        "Bool" => (
+            |expected, value| expected.eq_ignore_ascii_case(value),
+            false,
+        ),
+        "NumericEquals" => (
+            |expected, value| numbers(value, expected) == Some(Ordering::Equal),
+            false,
+        ),
+        "NumericNotEquals" => (
+            |expected, value| numbers(value, expected) == Some(Ordering::Equal),
+            true,
+        ),
+        "NumericLessThan" => (
+            |expected, value| numbers(value, expected) == Some(Ordering::Less),
+            false,
+        ),
+        "NumericLessThanEquals" => (
+            |expected, value| {
+                numbers(value, expected).is_some_and(Ordering::is_le)
+            },
+            false,
+        ),
+        "NumericGreaterThan" => (
+            |expected, value| {
+                numbers(value, expected) == Some(Ordering::Greater)
+            },
+            false,
+        ),
+        "NumericGreaterThanEquals" => (
+            |expected, value| {
+                numbers(value, expected).is_some_and(Ordering::is_ge)
+            },
+            false,
+        ),
+        "DateEquals" => (
+            |expected, value| dates(value, expected) == Some(Ordering::Equal),
+            false,
+        ),
+        "DateNotEquals" => (
+            |expected, value| dates(value, expected) == Some(Ordering::Equal),
+            true,
+        ),
+        "DateLessThan" => (
+            |expected, value| dates(value, expected) == Some(Ordering::Less),
+            false,
+        ),
+        "DateLessThanEquals" => (
+            |expected, value| {
+                dates(value, expected).is_some_and(Ordering::is_le)
+            },
+            false,
+        ),
+        "DateGreaterThan" => (
+            |expected, value| dates(value, expected) == Some(Ordering::Greater),
+            false,
+        ),
+        "DateGreaterThanEquals" => (
+            |expected, value| {
+                dates(value, expected).is_some_and(Ordering::is_ge)
+            },
+            false,
+        ),
+        "IpAddress" => {
+            (|expected, value| ip_in_network(value, expected), false)
+        }
+        "NotIpAddress" => {
+            (|expected, value| ip_in_network(value, expected), true)
+        }
+        "ArnEquals" | "ArnLike" => (
+            |expected, value| wildcard_match(expected, value, false),
+            false,
+        ),
+        "ArnNotEquals" | "ArnNotLike" => (
+            |expected, value| wildcard_match(expected, value, false),
+            true,
+        ),
+        _ => return None,
+    };
+    Some(comparison)
+}
+
+/// Compares two numbers, if they are.
+fn numbers(a: &str, b: &str) -> Option<Ordering> {
+    a.parse::<f64>().ok()?.partial_cmp(&b.parse::<f64>().ok()?)
+}
+
+/// Compares two dates, if they are, either in ISO 8601 or as seconds since
+/// the epoch.
+fn dates(a: &str, b: &str) -> Option<Ordering> {
+    let parse = |date: &str| match date.parse::<i64>() {
+        Ok(secs) => Some(i128::from(secs) * 1_000_000_000),
+        Err(_) => Some(
+            OffsetDateTime::parse(date, &Rfc3339)
+                .ok()?
+                .unix_timestamp_nanos(),
+        ),
+    };
+    Some(parse(a)?.cmp(&parse(b)?))
+}
+
+/// Returns whether an IP address is in a network, like `192.0.2.0/24`, or
+/// is an address.
+fn ip_in_network(ip: &str, network: &str) -> bool {
+    let ip: IpAddr = match ip.parse() {
+        Ok(ip) => ip,
+        Err(_) => return false,
+    };
+    let (address, prefix_len) = match network.split_once('/') {
+        Some((address, prefix_len)) => match prefix_len.parse::<u32>() {
+            Ok(prefix_len) => (address, Some(prefix_len)),
+            Err(_) => return false,
+        },
+        None => (network, None),
+    };
+    // The addresses are compared as 128-bit numbers, with IPv4 addresses in
+    // their upper bits.
+    let (ip, address, bits) = match (ip, address.parse()) {
+        (IpAddr::V4(ip), Ok(IpAddr::V4(address))) => (
+            u128::from(u32::from(ip)) << 96,
+            u128::from(u32::from(address)) << 96,
+            32,
+        ),
+        (IpAddr::V6(ip), Ok(IpAddr::V6(address))) => {
+            (u128::from(ip), u128::from(address), 128)
+        }
+        _ => return false,
+    };
+    let prefix_len = prefix_len.unwrap_or(bits);
+    if prefix_len > bits {
+        return false;
+    }
+    let mask = u128::MAX.checked_shl(128 - prefix_len).unwrap_or(0);
+    ip & mask == address & mask
+}
+
+/// Returns whether a value matches one of `patterns`, or none of
+/// `not_patterns` if there are no `patterns`.
+fn matches_patterns(
+    patterns: &[String],
+    not_patterns: &[String],
+    matches: impl Fn(&str) -> bool,
+) -> bool {
+    match patterns.is_empty() {
+        false => patterns.iter().any(|pattern| matches(pattern)),
+        true => !not_patterns.iter().any(|pattern| matches(pattern)),
+    }
+}
+
+/// Returns whether a value matches a pattern where `*` matches any
+/// characters and `?` matches one.
+fn wildcard_match(pattern: &str, value: &str, ignore_case: bool) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let equal = |a: char, b: char| match ignore_case {
+        true => a.eq_ignore_ascii_case(&b),
+        false => a == b,
+    };
+
+    let (mut p, mut v) = (0, 0);
+    // The position of the last `*` in the pattern, and of the character of
+    // the value it was matched up to.
+    let mut star = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, v));
+                p += 1;
+            }
+            Some(&c) if c == '?' || equal(c, value[v]) => {
+                p += 1;
+                v += 1;
+            }
+            _ => match star {
+                Some((star_p, star_v)) => {
+                    star = Some((star_p, star_v + 1));
+                    p = star_p + 1;
+                    v = star_v + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn missing(name: &str) -> DeserializationError {
+    DeserializationError::Missing(name.to_owned())
+}
+
+fn invalid(name: &str) -> DeserializationError {
+    DeserializationError::Invalid(name.to_owned())
+}
+
+/// Returns a string field of an object, if it has one.
+fn string_field(
+    json: &JsonValue,
+    name: &str,
+) -> Result<Option<String>, DeserializationError> {
+    json.get(name)
+        .map(|value| {
+            value
+                .as_str()
+                .map(ToOwned::to_owned)
+                .ok_or_else(|| invalid(name))
+        })
+        .transpose()
+}
+
+/// Returns a field of an object that is either a string or an array of
+/// strings, empty if it has none.
+fn strings_field(
+    json: &JsonValue,
+    name: &str,
+) -> Result<Vec<String>, DeserializationError> {
+    match json.get(name) {
+        Some(value) => strings(value).ok_or_else(|| invalid(name)),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Returns the values of a string, or of an array of strings, booleans or
+/// numbers.
+fn strings(json: &JsonValue) -> Option<Vec<String>> {
+    let string = |value: &JsonValue| match value {
+        JsonValue::String(value) => Some(value.clone()),
+        JsonValue::Bool(value) => Some(value.to_string()),
+        JsonValue::Number(value) => Some(value.to_string()),
+        _ => None,
+    };
+    match json {
+        JsonValue::Array(values) => values.iter().map(string).collect(),
+        value => Some(vec![string(value)?]),
+    }
+}
+
+/// Writes one value as a string, and other numbers of values as an array.
+fn strings_json(values: &[String]) -> JsonValue {
+    match values {
+        [value] => json!(value),
+        values => json!(values),
+    }
+}
+
+fn principals_field(
+    json: &JsonValue,
+    name: &str,
+) -> Result<Option<Principals>, DeserializationError> {
+    match json.get(name) {
+        None => Ok(None),
+        Some(JsonValue::String(any)) if any == "*" => Ok(Some(Principals::Any)),
+        Some(JsonValue::Object(ids)) => ids
+            .iter()
+            .map(|(kind, ids)| {
+                let ids = strings(ids).ok_or_else(|| invalid(name))?;
+                Ok((kind.clone(), ids))
+            })
+            .collect::<Result<_, _>>()
+            .map(|ids| Some(Principals::Ids(ids))),
+        Some(_) => Err(invalid(name)),
+    }
+}
+
+fn conditions_field(
+    json: &JsonValue,
+) -> Result<Vec<Condition>, DeserializationError> {
+    let operators = match json.get("Condition") {
+        Some(JsonValue::Object(operators)) => operators,
+        Some(_) => return Err(invalid("Condition")),
+        None => return Ok(Vec::new()),
+    };
+    let mut conditions = Vec::new();
+    for (operator, keys) in operators {
+        // Conditions which can't be evaluated would never hold, and the
+        // statements denying requests with them would never apply.
+        if parse_operator(operator).is_none() {
+            return Err(invalid("Condition"));
+        }
+        let keys = keys.as_object().ok_or_else(|| invalid("Condition"))?;
+        for (key, values) in keys {
+            conditions.push(Condition {
+                operator: operator.clone(),
+                key: key.clone(),
+                values: strings(values).ok_or_else(|| invalid("Condition"))?,
+            });
+        }
+    }
+    Ok(conditions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use s3ers_signature::clock::FixedClock;
+
+    use super::{
+        ip_in_network, wildcard_match, Condition, Decision, Effect, Policy,
+        PolicyRequest, Principals, Statement,
+    };
+
+    const POLICY: &str = r#"{
+        "Version": "2012-10-17",
+        "Id": "Policy",
+        "Statement": [
+            {
+                "Sid": "ReadOnly",
+                "Effect": "Allow",
+                "Principal": {"AWS": ["arn:aws:iam::111122223333:user/alice"]},
+                "Action": ["s3:Get*", "s3:ListBucket"],
+                "Resource": ["arn:aws:s3:::bucket", "arn:aws:s3:::bucket/*"]
+            },
+            {
+                "Effect": "Deny",
+                "Principal": "*",
+                "NotAction": "s3:Get*",
+                "Resource": "arn:aws:s3:::bucket/secret/*"
+            },
+            {
+                "Effect": "Allow",
+                "Principal": {"AWS": "*"},
+                "Action": "s3:ListBucket",
+                "Resource": "arn:aws:s3:::bucket",
+                "Condition": {
+                    "StringLike": {"s3:prefix": ["public/*", "shared/*"]}
+                }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn parse_policy() {
+        let policy = Policy::from_json(POLICY.as_bytes()).unwrap();
+        assert_eq!(policy.id.as_deref(), Some("Policy"));
+        assert_eq!(policy.statements.len(), 3);
+        assert_eq!(policy.statements[1].effect, Effect::Deny);
+        assert_eq!(policy.statements[1].principal, Some(Principals::Any));
+        assert_eq!(
+            policy.statements[2].conditions,
+            [Condition::new(
+                "StringLike",
+                "s3:prefix",
+                vec!["public/*".to_owned(), "shared/*".to_owned()]
+            )]
+        );
+        let json = policy.to_json();
+        assert_eq!(Policy::from_json(json.as_bytes()).unwrap(), policy);
+
+        let statement = Statement::new(
+            Effect::Allow,
+            vec!["s3:GetObject".to_owned()],
+            vec!["arn:aws:s3:::bucket/*".to_owned()],
+        );
+        let json = Policy::new(vec![statement.clone()]).to_json();
+        let policy = Policy::from_json(json.as_bytes()).unwrap();
+        assert_eq!(policy.statements, [statement]);
+
+        let invalid = [
+            r#"{"Statement": {"Effect": "Allow", "Action": "s3:*"}}"#,
+            r#"{"Statement": {"Effect": "Maybe", "Action": "*", "Resource": "*"}}"#,
+            r#"{"Version": "2012-10-17"}"#,
+            "[]",
+            // A statement denying requests with a condition that can't be
+            // evaluated would never apply.
+            r#"{"Statement": {"Effect": "Deny", "Action": "*", "Resource": "*",
+                "Principal": "*",
+                "Condition": {"BinaryEquals": {"s3:prefix": "YQ=="}}}}"#,
+            r#"{"Statement": {"Effect": "Deny", "Action": "*", "Resource": "*",
+                "Principal": "*",
+                "Condition": {"StringEqual": {"s3:prefix": "a"}}}}"#,
+            r#"{"Statement": {"Effect": "Allow", "Action": "*", "Resource": "*",
+                "Condition": {"ForEach:StringEquals": {"s3:prefix": "a"}}}}"#,
+        ];
+        for json in invalid {
+            assert!(Policy::from_json(json.as_bytes()).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn evaluate_policy() {
+        let policy = Policy::from_json(POLICY.as_bytes()).unwrap();
+        let alice = "arn:aws:iam::111122223333:user/alice";
+        let decision = |request: PolicyRequest| policy.evaluate(&request);
+
+        let request =
+            PolicyRequest::new("s3:GetObject", "arn:aws:s3:::bucket/a")
+                .with_principal(alice);
+        assert_eq!(decision(request), Decision::Allow);
+        let request =
+            PolicyRequest::new("S3:getobject", "arn:aws:s3:::bucket/a")
+                .with_principal(alice);
+        assert_eq!(decision(request), Decision::Allow);
+        let request =
+            PolicyRequest::new("s3:GetObject", "arn:aws:s3:::Bucket/a")
+                .with_principal(alice);
+        assert_eq!(decision(request), Decision::ImplicitDeny);
+        let request =
+            PolicyRequest::new("s3:GetObject", "arn:aws:s3:::bucket/a");
+        assert_eq!(decision(request), Decision::ImplicitDeny);
+        let request =
+            PolicyRequest::new("s3:PutObject", "arn:aws:s3:::bucket/secret/a")
+                .with_principal(alice);
+        assert_eq!(decision(request), Decision::ExplicitDeny);
+
+        let request =
+            PolicyRequest::new("s3:ListBucket", "arn:aws:s3:::bucket")
+                .with_context_value("s3:prefix", "public/a");
+        assert_eq!(decision(request), Decision::Allow);
+        let request =
+            PolicyRequest::new("s3:ListBucket", "arn:aws:s3:::bucket")
+                .with_context_value("S3:Prefix", "private/a");
+        assert_eq!(decision(request), Decision::ImplicitDeny);
+        let request =
+            PolicyRequest::new("s3:ListBucket", "arn:aws:s3:::bucket");
+        assert_eq!(decision(request), Decision::ImplicitDeny);
+    }
+
+    #[test]
+    fn evaluate_conditions() {
+        let holds = |operator: &str, values: &[&str], context: &[&str]| {
+            let condition = Condition::new(
+                operator,
+                "s3:x-amz-acl",
+                values.iter().map(|value| value.to_string()).collect(),
+            );
+            let request = context.iter().fold(
+                PolicyRequest::new("s3:PutObject", "*"),
+                |request, value| {
+                    request.with_context_value("s3:x-amz-acl", *value)
+                },
+            );
+            condition.holds(&request.context)
+        };
+
+        assert!(holds("StringEquals", &["private"], &["private"]));
+        assert!(!holds("StringEquals", &["private"], &[]));
+        assert!(holds("StringEqualsIfExists", &["private"], &[]));
+        assert!(holds("StringNotEquals", &["public-read"], &["private"]));
+        assert!(holds("StringNotEquals", &["public-read"], &[]));
+        assert!(holds("StringEqualsIgnoreCase", &["PRIVATE"], &["private"]));
+        assert!(!holds("StringNotLike", &["public-*"], &["public-read"]));
+        assert!(holds("ForAnyValue:StringEquals", &["a"], &["a", "b"]));
+        assert!(!holds("ForAllValues:StringEquals", &["a"], &["a", "b"]));
+        assert!(holds("ForAllValues:StringEquals", &["a"], &[]));
+        assert!(holds("Null", &["true"], &[]));
+        assert!(holds("Null", &["false"], &["private"]));
+        assert!(!holds("Unknown", &["private"], &["private"]));
+    }
+
+    #[test]
+    fn evaluate_typed_conditions() {
+        let policy = Policy::from_json(
+            br#"{
+                "Statement": [
+                    {
+                        "Effect": "Deny",
+                        "Principal": "*",
+                        "Action": "s3:*",
+                        "Resource": "*",
+                        "Condition": {"Bool": {"aws:SecureTransport": false}}
+                    },
+                    {
+                        "Effect": "Allow",
+                        "Principal": "*",
+                        "Action": "s3:ListBucket",
+                        "Resource": "*",
+                        "Condition": {
+                            "IpAddress": {
+                                "aws:SourceIp": [
+                                    "192.0.2.0/24",
+                                    "2001:db8::/32"
+                                ]
+                            },
+                            "NumericLessThanEquals": {"s3:max-keys": 10},
+                            "DateLessThan": {
+                                "aws:CurrentTime": "2030-01-01T00:00:00Z"
+                            }
+                        }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let request = |uri: &str, ip: &str| {
+            let request = http::Request::get(uri).body(()).unwrap();
+            PolicyRequest::new("s3:ListBucket", "arn:aws:s3:::bucket")
+                .with_http_request(&request, &FixedClock(now))
+                .with_source_ip(ip.parse().unwrap())
+        };
+
+        let allowed = request("https://s3/bucket?max-keys=10", "192.0.2.7");
+        assert_eq!(allowed.context["aws:epochtime"], ["1700000000"]);
+        assert_eq!(policy.evaluate(&allowed), Decision::Allow);
+        let allowed = request("https://s3/bucket?max-keys=5", "2001:db8::1");
+        assert_eq!(policy.evaluate(&allowed), Decision::Allow);
+        let denied = request("http://s3/bucket?max-keys=10", "192.0.2.7");
+        assert_eq!(policy.evaluate(&denied), Decision::ExplicitDeny);
+        let denied = request("https://s3/bucket?max-keys=10", "198.51.100.1");
+        assert_eq!(policy.evaluate(&denied), Decision::ImplicitDeny);
+        let denied = request("https://s3/bucket?max-keys=11", "192.0.2.7");
+        assert_eq!(policy.evaluate(&denied), Decision::ImplicitDeny);
+        let denied = request("https://s3/bucket", "192.0.2.7")
+            .with_current_time(UNIX_EPOCH + Duration::from_secs(1_900_000_000));
+        assert_eq!(policy.evaluate(&denied), Decision::ImplicitDeny);
+    }
+
+    #[test]
+    fn context_of_http_requests() {
+        let request = http::Request::put("/bucket/key?versionId=1")
+            .header("x-amz-acl", "public-read")
+            .header("user-agent", "s3ers")
+            .header("x-amz-meta-a", "b")
+            .body(())
+            .unwrap();
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let request = PolicyRequest::new("s3:PutObject", "*")
+            .with_http_request(&request, &FixedClock(now));
+        assert_eq!(request.context["s3:x-amz-acl"], ["public-read"]);
+        assert_eq!(request.context["aws:useragent"], ["s3ers"]);
+        assert_eq!(request.context["s3:versionid"], ["1"]);
+        assert_eq!(request.context["aws:epochtime"], ["1700000000"]);
+        assert!(!request.context.contains_key("aws:securetransport"));
+        assert!(!request.context.contains_key("s3:x-amz-meta-a"));
+
+        let condition = Condition::new(
+            "StringNotEquals",
+            "s3:x-amz-acl",
+            vec!["public-read".to_owned()],
+        );
+        assert!(!condition.holds(&request.context));
+    }
+
+    #[test]
+    fn match_ip_addresses() {
+        assert!(ip_in_network("192.0.2.7", "192.0.2.0/24"));
+        assert!(ip_in_network("192.0.2.7", "192.0.2.7"));
+        assert!(ip_in_network("192.0.2.7", "0.0.0.0/0"));
+        assert!(!ip_in_network("192.0.3.7", "192.0.2.0/24"));
+        assert!(!ip_in_network("192.0.2.7", "192.0.2.0/33"));
+        assert!(!ip_in_network("2001:db8::1", "192.0.2.0/24"));
+        assert!(ip_in_network("2001:db8::1", "2001:db8::/32"));
+        assert!(!ip_in_network("2001:db9::1", "2001:db8::/32"));
+    }
+
+    #[test]
+    fn match_wildcards() {
+        assert!(wildcard_match("*", "", false));
+        assert!(wildcard_match("a*c", "abbbc", false));
+        assert!(wildcard_match("a?c", "abc", false));
+        assert!(!wildcard_match("a?c", "ac", false));
+        assert!(wildcard_match("*/*.jpg", "a/b/c.jpg", false));
+        assert!(!wildcard_match("*.jpg", "a.jpeg", false));
+        assert!(wildcard_match("S3:Get*", "s3:GetObject", true));
+        assert!(!wildcard_match("S3:Get*", "s3:GetObject", false));
+    }
+}
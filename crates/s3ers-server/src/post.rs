@@ -0,0 +1,310 @@
+//! Receiving the browser-based uploads of objects with HTTP POST.
+//!
+//! Browsers upload files to a bucket with a `multipart/form-data` form,
+//! signed with a policy like the ones of
+//! [`PostPolicy`](s3ers_signature::post_policy::PostPolicy). Servers read
+//! the [`PostForm`] of the requests for which [`is_post_upload`] is true,
+//! check its signature and its policy with
+//! [`SigV4Verifier::verify_post`](crate::auth::SigV4Verifier::verify_post),
+//! and store its file at its [`key`](PostForm::key).
+//!
+//! ```
+//! use bytes::Bytes;
+//! use s3ers_server::post::{is_post_upload, PostForm};
+//!
+//! let body = "--boundary\r\n\
+//!             Content-Disposition: form-data; name=\"key\"\r\n\
+//!             \r\n\
+//!             uploads/${filename}\r\n\
+//!             --boundary\r\n\
+//!             Content-Disposition: form-data; name=\"file\"; \
+//!             filename=\"photo.jpg\"\r\n\
+//!             Content-Type: image/jpeg\r\n\
+//!             \r\n\
+//!             data\r\n\
+//!             --boundary--\r\n";
+//! let request = http::Request::post("/bucket")
+//!     .header("content-type", "multipart/form-data; boundary=boundary")
+//!     .body(Bytes::from(body))
+//!     .unwrap();
+//! assert!(is_post_upload(&request));
+//!
+//! let form = PostForm::from_http_request(&request).unwrap();
+//! assert_eq!(form.key().unwrap(), "uploads/photo.jpg");
+//! assert_eq!(form.file, "data");
+//! ```
+
+use bytes::Bytes;
+use http::{header::CONTENT_TYPE, Method, StatusCode};
+use s3ers_api::error::S3Error;
+
+/// The form of a browser-based upload.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PostForm {
+    /// The fields sent before the file, in order.
+    ///
+    /// The fields sent after the file are ignored, like S3 does.
+    pub fields: Vec<(String, String)>,
+
+    /// The content of the uploaded file.
+    pub file: Bytes,
+
+    /// The name of the uploaded file on the computer of the user.
+    pub filename: Option<String>,
+}
+
+impl PostForm {
+    /// Reads the form of a `multipart/form-data` request.
+    ///
+    /// Requests which are not well-formed `multipart/form-data`, or have no
+    /// `file` field, are rejected with the errors of S3.
+    pub fn from_http_request(
+        request: &http::Request<Bytes>,
+    ) -> Result<Self, Box<S3Error>> {
+        let boundary = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .and_then(boundary)
+            .ok_or_else(malformed)?;
+        let mut form = Self::default();
+        let mut has_file = false;
+        for part in parts(request.body(), &boundary).ok_or_else(malformed)? {
+            if part.name.eq_ignore_ascii_case("file") {
+                form.file = part.content;
+                form.filename = part.filename;
+                has_file = true;
+                break;
+            }
+            let value = String::from_utf8(part.content.to_vec())
+                .map_err(|_| malformed())?;
+            form.fields.push((part.name, value));
+        }
+        if !has_file {
+            return Err(Box::new(
+                S3Error::new(StatusCode::BAD_REQUEST, "InvalidArgument")
+                    .with_message(
+                        "POST requires exactly one file upload per request.",
+                    ),
+            ));
+        }
+        Ok(form)
+    }
+
+    /// Returns the value of a field, whose names are case-insensitive.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the key of the uploaded object, the `key` field with
+    /// `${filename}` replaced by the name of the file.
+    pub fn key(&self) -> Result<String, Box<S3Error>> {
+        let key = self.field("key").ok_or_else(|| {
+            Box::new(
+                S3Error::new(StatusCode::BAD_REQUEST, "InvalidArgument")
+                    .with_message(
+                        "Bucket POST must contain a field named 'key'.",
+                    ),
+            )
+        })?;
+        Ok(key.replace("${filename}", self.filename.as_deref().unwrap_or("")))
+    }
+}
+
+/// Returns whether a request is a browser-based upload, a `POST` request
+/// with a `multipart/form-data` body.
+pub fn is_post_upload<B>(request: &http::Request<B>) -> bool {
+    request.method() == Method::POST
+        && request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .and_then(boundary)
+            .is_some()
+}
+
+/// A part of a `multipart/form-data` body.
+struct Part {
+    name: String,
+    filename: Option<String>,
+    content: Bytes,
+}
+
+/// Returns the boundary of a `multipart/form-data` content type.
+fn boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';');
+    let media_type = params.next()?.trim();
+    if !media_type.eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params
+        .find_map(|param| {
+            let (name, value) = param.trim().split_once('=')?;
+            name.eq_ignore_ascii_case("boundary").then_some(value)
+        })
+        .map(|boundary| boundary.trim_matches('"').to_owned())
+        .filter(|boundary| !boundary.is_empty())
+}
+
+/// Returns the parts of a `multipart/form-data` body, or `None` if it is
+/// malformed.
+fn parts(body: &Bytes, boundary: &str) -> Option<Vec<Part>> {
+    let delimiter = format!("\r\n--{}", boundary).into_bytes();
+    // The first delimiter may not be preceded by a line break.
+    let mut position = match body.starts_with(&delimiter[2..]) {
+        true => delimiter.len() - 2,
+        false => find(body, &delimiter)? + delimiter.len(),
+    };
+    let mut parts = Vec::new();
+    while !body[position..].starts_with(b"--") {
+        if !body[position..].starts_with(b"\r\n") {
+            return None;
+        }
+        let start = position + 2;
+        let headers_len = find(&body[start..], b"\r\n\r\n")?;
+        let headers =
+            std::str::from_utf8(&body[start..][..headers_len]).ok()?;
+        let content_start = start + headers_len + 4;
+        let content_len = find(&body[content_start..], &delimiter)?;
+        position = content_start + content_len + delimiter.len();
+
+        let (name, filename) = disposition(headers)?;
+        parts.push(Part {
+            name,
+            filename,
+            content: body.slice(content_start..content_start + content_len),
+        });
+    }
+    Some(parts)
+}
+
+/// Returns the name and the filename of the `Content-Disposition` header of
+/// a part.
+fn disposition(headers: &str) -> Option<(String, Option<String>)> {
+    let disposition = headers.split("\r\n").find_map(|header| {
+        let (name, value) = header.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("content-disposition")
+            .then_some(value)
+    })?;
+    let mut params = disposition.split(';');
+    if !params.next()?.trim().eq_ignore_ascii_case("form-data") {
+        return None;
+    }
+    let (mut name, mut filename) = (None, None);
+    for param in params {
+        let (param, value) = match param.trim().split_once('=') {
+            Some(param) => param,
+            None => continue,
+        };
+        let value = value.trim_matches('"').to_owned();
+        match param.to_ascii_lowercase().as_str() {
+            "name" => name = Some(value),
+            "filename" => filename = Some(value),
+            _ => {}
+        }
+    }
+    Some((name?, filename))
+}
+
+/// Returns the position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn malformed() -> Box<S3Error> {
+    Box::new(
+        S3Error::new(StatusCode::BAD_REQUEST, "MalformedPOSTRequest")
+            .with_message(
+                "The body of your POST request is not well-formed \
+                 multipart/form-data.",
+            ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{is_post_upload, PostForm};
+
+    fn request(content_type: &str, body: &str) -> http::Request<Bytes> {
+        http::Request::post("/bucket")
+            .header("content-type", content_type)
+            .body(Bytes::from(body.to_owned()))
+            .unwrap()
+    }
+
+    #[test]
+    fn read_forms() {
+        let body = "preamble\r\n\
+                    --b\r\n\
+                    Content-Disposition: form-data; name=\"Key\"\r\n\
+                    \r\n\
+                    a\r\nb\r\n\
+                    --b\r\n\
+                    content-disposition: form-data; name=\"acl\"\r\n\
+                    \r\n\
+                    private\r\n\
+                    --b\r\n\
+                    Content-Disposition: form-data; name=\"file\"\r\n\
+                    \r\n\
+                    \r\n\
+                    --b\r\n\
+                    Content-Disposition: form-data; name=\"after\"\r\n\
+                    \r\n\
+                    ignored\r\n\
+                    --b--";
+        let request = request("Multipart/Form-Data; boundary=\"b\"", body);
+        assert!(is_post_upload(&request));
+        let form = PostForm::from_http_request(&request).unwrap();
+        assert_eq!(
+            form.fields,
+            [
+                ("Key".to_owned(), "a\r\nb".to_owned()),
+                ("acl".to_owned(), "private".to_owned()),
+            ]
+        );
+        assert_eq!(form.field("ACL"), Some("private"));
+        assert_eq!(form.key().unwrap(), "a\r\nb");
+        assert_eq!(form.file, "");
+        assert_eq!(form.filename, None);
+    }
+
+    #[test]
+    fn reject_invalid_forms() {
+        let content_type = "multipart/form-data; boundary=b";
+        let invalid = [
+            ("", "MalformedPOSTRequest"),
+            ("--b\r\n\r\nvalue\r\n--b--", "MalformedPOSTRequest"),
+            (
+                "--b\r\nContent-Disposition: form-data; name=\"key\"\r\n\r\n",
+                "MalformedPOSTRequest",
+            ),
+            (
+                "--b\r\nContent-Disposition: form-data; name=\"key\"\r\n\r\n\
+                 key\r\n--b--",
+                "InvalidArgument",
+            ),
+        ];
+        for (body, code) in invalid {
+            let request = request(content_type, body);
+            let error = PostForm::from_http_request(&request).unwrap_err();
+            assert_eq!(error.code, code, "{:?}", body);
+        }
+
+        let request = request("text/plain", "--b--");
+        assert!(!is_post_upload(&request));
+        let error = PostForm::from_http_request(&request).unwrap_err();
+        assert_eq!(error.code, "MalformedPOSTRequest");
+
+        let form = PostForm::default();
+        assert_eq!(form.key().unwrap_err().code, "InvalidArgument");
+    }
+}
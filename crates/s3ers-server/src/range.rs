@@ -0,0 +1,316 @@
+//! Serving the ranges of objects requested with a `Range` header.
+//!
+//! [`ByteRange::parse`] reads the range of a `Range` header, which is
+//! resolved against the size of an object into its [`ContentRange`], or a
+//! `416 Range Not Satisfiable` `InvalidRange` error, with
+//! [`ByteRange::resolve`]. Like S3, servers ignore the `Range` headers which
+//! can't be parsed, or have several ranges, and return the whole object.
+//! [`range_response`] does all of this for objects in memory.
+//!
+//! ```
+//! use bytes::Bytes;
+//! use s3ers_server::range::range_response;
+//!
+//! let request = http::Request::get("/bucket/key")
+//!     .header("range", "bytes=-3")
+//!     .body(())
+//!     .unwrap();
+//! let response = range_response(&request, Bytes::from_static(b"hello"));
+//! assert_eq!(response.status(), 206);
+//! assert_eq!(response.headers()["content-range"], "bytes 2-4/5");
+//! assert_eq!(response.body(), "llo");
+//! ```
+
+use std::{convert::TryFrom, fmt, ops::Range};
+
+use bytes::Bytes;
+use http::{
+    header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, RANGE},
+    HeaderValue, StatusCode,
+};
+use s3ers_api::error::S3Error;
+
+use crate::ErrorResponse;
+
+/// A range of bytes of a `Range` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    /// The bytes from the first to the last one, inclusive, like
+    /// `bytes=0-9`.
+    Bounded {
+        /// The position of the first byte.
+        first: u64,
+        /// The position of the last byte, which may be after the end of the
+        /// object.
+        last: u64,
+    },
+
+    /// The bytes from a position to the end, like `bytes=10-`.
+    From(u64),
+
+    /// The last bytes, like `bytes=-10`.
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Parses a `Range` header, returning `None` if it isn't a single range
+    /// of bytes.
+    pub fn parse(header: &str) -> Option<Self> {
+        let (unit, range) = header.trim().split_once('=')?;
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+        let (first, last) = range.trim().split_once('-')?;
+        let number =
+            |value: &str| match value.bytes().all(|b| b.is_ascii_digit()) {
+                true => value.parse::<u64>().ok(),
+                false => None,
+            };
+        match (first.trim(), last.trim()) {
+            ("", "") => None,
+            ("", suffix) => number(suffix).map(Self::Suffix),
+            (first, "") => number(first).map(Self::From),
+            (first, last) => {
+                let (first, last) = (number(first)?, number(last)?);
+                (first <= last).then_some(Self::Bounded { first, last })
+            }
+        }
+    }
+
+    /// Returns the bytes of the range in an object of `len` bytes, clamped
+    /// to its end, or an `InvalidRange` error if it has none of them.
+    pub fn resolve(self, len: u64) -> Result<ContentRange, Box<S3Error>> {
+        let (first, last) = match self {
+            Self::Bounded { first, last } => (first, last),
+            Self::From(first) => (first, u64::MAX),
+            Self::Suffix(0) => return Err(invalid_range(self, len)),
+            Self::Suffix(suffix) => (len.saturating_sub(suffix), u64::MAX),
+        };
+        if first >= len {
+            return Err(invalid_range(self, len));
+        }
+        Ok(ContentRange {
+            first,
+            last: last.min(len - 1),
+            complete_length: len,
+        })
+    }
+}
+
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bounded { first, last } => {
+                write!(f, "bytes={}-{}", first, last)
+            }
+            Self::From(first) => write!(f, "bytes={}-", first),
+            Self::Suffix(suffix) => write!(f, "bytes=-{}", suffix),
+        }
+    }
+}
+
+/// The bytes of an object sent in a `206 Partial Content` response, and
+/// their `Content-Range`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ContentRange {
+    /// The position of the first byte.
+    pub first: u64,
+
+    /// The position of the last byte, inclusive.
+    pub last: u64,
+
+    /// The size of the whole object.
+    pub complete_length: u64,
+}
+
+impl ContentRange {
+    /// Returns the number of bytes of the range, its `Content-Length`.
+    pub fn content_length(&self) -> u64 {
+        self.last - self.first + 1
+    }
+
+    /// Returns the range of the positions of the bytes, to slice the object.
+    pub fn to_range(&self) -> Range<usize> {
+        self.first as usize..self.last as usize + 1
+    }
+}
+
+/// Formats the value of the `Content-Range` header, like `bytes 0-9/443`.
+impl fmt::Display for ContentRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bytes {}-{}/{}",
+            self.first, self.last, self.complete_length
+        )
+    }
+}
+
+/// Returns the range of an object of `len` bytes requested by a `Range`
+/// header, or `None` if the header is missing or is ignored.
+pub fn content_range(
+    range: Option<&str>,
+    len: u64,
+) -> Result<Option<ContentRange>, Box<S3Error>> {
+    match range.and_then(ByteRange::parse) {
+        Some(range) => range.resolve(len).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Returns the response to a `GET` request for an object, with the range
+/// of the object requested by the `Range` header of the request.
+///
+/// The response is `206 Partial Content` with the `Content-Range` of the
+/// range, `200 OK` with the whole object if there is no range, or a
+/// `416 Range Not Satisfiable` `InvalidRange` error, with the size of the
+/// object in its `Content-Range`, if the object has none of its bytes.
+pub fn range_response<B>(
+    request: &http::Request<B>,
+    object: Bytes,
+) -> http::Response<Bytes> {
+    let len = object.len() as u64;
+    let range = request
+        .headers()
+        .get(RANGE)
+        .and_then(|range| range.to_str().ok());
+    let mut response = match content_range(range, len) {
+        Ok(Some(range)) => {
+            let mut response =
+                http::Response::new(object.slice(range.to_range()));
+            *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            if let Ok(value) = HeaderValue::try_from(range.to_string()) {
+                response.headers_mut().insert(CONTENT_RANGE, value);
+            }
+            response
+        }
+        Ok(None) => http::Response::new(object),
+        Err(error) => {
+            let mut response = ErrorResponse::from(*error).into_http_response();
+            if let Ok(value) = HeaderValue::try_from(format!("bytes */{}", len))
+            {
+                response.headers_mut().insert(CONTENT_RANGE, value);
+            }
+            return response;
+        }
+    };
+    let content_length = response.body().len();
+    let headers = response.headers_mut();
+    headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    headers.insert(CONTENT_LENGTH, content_length.into());
+    response
+}
+
+/// Returns the `InvalidRange` error of a range an object of `len` bytes has
+/// none of the bytes of.
+pub(crate) fn invalid_range(
+    range: impl fmt::Display,
+    len: u64,
+) -> Box<S3Error> {
+    let mut error =
+        S3Error::new(StatusCode::RANGE_NOT_SATISFIABLE, "InvalidRange")
+            .with_message("The requested range is not satisfiable");
+    error
+        .details
+        .insert("RangeRequested".to_owned(), range.to_string());
+    error
+        .details
+        .insert("ActualObjectSize".to_owned(), len.to_string());
+    Box::new(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{content_range, range_response, ByteRange};
+
+    #[test]
+    fn parse_ranges() {
+        let ranges = [
+            ("bytes=0-9", Some(ByteRange::Bounded { first: 0, last: 9 })),
+            ("Bytes = 10-", Some(ByteRange::From(10))),
+            ("bytes=-10", Some(ByteRange::Suffix(10))),
+            ("bytes=5-2", None),
+            ("bytes=-", None),
+            ("bytes=0-1,4-5", None),
+            ("bytes=+1-2", None),
+            ("items=0-9", None),
+            ("0-9", None),
+        ];
+        for (header, expected) in ranges {
+            assert_eq!(ByteRange::parse(header), expected, "{}", header);
+            if let Some(range) = expected {
+                assert_eq!(ByteRange::parse(&range.to_string()), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn resolve_ranges() {
+        let resolve = |range: &str, len| {
+            content_range(Some(range), len)
+                .map(|range| range.map(|range| range.to_string()))
+                .map_err(|error| error.code.clone())
+        };
+        let ok = |range: &str| Ok(Some(range.to_owned()));
+        assert_eq!(resolve("bytes=0-9", 443), ok("bytes 0-9/443"));
+        assert_eq!(resolve("bytes=400-999", 443), ok("bytes 400-442/443"));
+        assert_eq!(resolve("bytes=440-", 443), ok("bytes 440-442/443"));
+        assert_eq!(resolve("bytes=-3", 443), ok("bytes 440-442/443"));
+        assert_eq!(resolve("bytes=-1000", 443), ok("bytes 0-442/443"));
+        assert_eq!(resolve("bytes=9-0", 443), Ok(None));
+        assert_eq!(content_range(None, 443).unwrap(), None);
+
+        for range in ["bytes=443-", "bytes=443-500", "bytes=-0", "bytes=0-"] {
+            let len = if range == "bytes=0-" { 0 } else { 443 };
+            assert_eq!(
+                resolve(range, len).unwrap_err(),
+                "InvalidRange",
+                "{}",
+                range
+            );
+        }
+
+        let range = content_range(Some("bytes=2-4"), 10).unwrap().unwrap();
+        assert_eq!(range.content_length(), 3);
+        assert_eq!(range.to_range(), 2..5);
+    }
+
+    #[test]
+    fn respond_with_ranges() {
+        let response = |range: Option<&str>| {
+            let mut request = http::Request::get("/bucket/key");
+            if let Some(range) = range {
+                request = request.header("range", range);
+            }
+            range_response(
+                &request.body(()).unwrap(),
+                Bytes::from_static(b"hello"),
+            )
+        };
+
+        let partial = response(Some("bytes=1-2"));
+        assert_eq!(partial.status(), 206);
+        assert_eq!(partial.headers()["content-range"], "bytes 1-2/5");
+        assert_eq!(partial.headers()["content-length"], "2");
+        assert_eq!(partial.body(), "el");
+
+        for range in [None, Some("bytes=0-1,3-4")] {
+            let full = response(range);
+            assert_eq!(full.status(), 200);
+            assert!(full.headers().get("content-range").is_none());
+            assert_eq!(full.headers()["accept-ranges"], "bytes");
+            assert_eq!(full.headers()["content-length"], "5");
+            assert_eq!(full.body(), "hello");
+        }
+
+        let unsatisfiable = response(Some("bytes=5-"));
+        assert_eq!(unsatisfiable.status(), 416);
+        assert_eq!(unsatisfiable.headers()["content-range"], "bytes */5");
+        let body = std::str::from_utf8(unsatisfiable.body()).unwrap();
+        assert!(body.contains("<Code>InvalidRange</Code>"));
+        assert!(body.contains("<ActualObjectSize>5</ActualObjectSize>"));
+    }
+}
@@ -0,0 +1,114 @@
+//! The IDs S3 gives to each request.
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use http::{HeaderMap, HeaderValue};
+use sha2::{Digest, Sha256};
+
+/// The number of IDs generated by the process, to tell apart the ones
+/// generated at the same time.
+static GENERATED: AtomicU64 = AtomicU64::new(0);
+
+/// The IDs of a request, sent in the `x-amz-request-id` and `x-amz-id-2`
+/// headers of its response and in the `Error` document of errors.
+///
+/// The [`RequestIdService`](crate::middleware::RequestIdService) inserts
+/// them in the extensions of requests, so that handlers can get them, and
+/// they are displayed as the request ID, to be logged.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct RequestId {
+    /// The ID of the request, sent in `x-amz-request-id`.
+    pub request_id: String,
+
+    /// The extended ID of the request, sent in `x-amz-id-2`.
+    pub host_id: String,
+}
+
+impl RequestId {
+    /// Creates the IDs of a request.
+    pub fn new(
+        request_id: impl Into<String>,
+        host_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            request_id: request_id.into(),
+            host_id: host_id.into(),
+        }
+    }
+
+    /// Generates unique IDs that look like the ones of S3: 16 hexadecimal
+    /// digits, and base64.
+    pub fn generate() -> Self {
+        let generated = GENERATED.fetch_add(1, Ordering::Relaxed);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let digest = Sha256::new()
+            .chain_update(now.as_nanos().to_be_bytes())
+            .chain_update(generated.to_be_bytes())
+            .chain_update(std::process::id().to_be_bytes())
+            .finalize();
+        Self::new(
+            hex::encode_upper(&digest[..8]),
+            STANDARD.encode(&digest[8..]),
+        )
+    }
+
+    /// Sets the `x-amz-request-id` and `x-amz-id-2` headers of a response,
+    /// unless it has them already.
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        for (name, value) in [
+            ("x-amz-request-id", &self.request_id),
+            ("x-amz-id-2", &self.host_id),
+        ] {
+            if headers.contains_key(name) {
+                continue;
+            }
+            if let Ok(value) = HeaderValue::try_from(value) {
+                headers.insert(name, value);
+            }
+        }
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.request_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderMap, HeaderValue};
+
+    use super::RequestId;
+
+    #[test]
+    fn generate_ids() {
+        let id = RequestId::generate();
+        assert_eq!(id.request_id.len(), 16);
+        assert!(id
+            .request_id
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b.is_ascii_uppercase()));
+        assert_eq!(id.host_id.len(), 32);
+        assert_eq!(id.to_string(), id.request_id);
+        assert_ne!(RequestId::generate(), id);
+    }
+
+    #[test]
+    fn apply_ids() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-amz-id-2", HeaderValue::from_static("kept"));
+        RequestId::new("4442587FB7D0A2F9", "host").apply(&mut headers);
+        assert_eq!(headers["x-amz-request-id"], "4442587FB7D0A2F9");
+        assert_eq!(headers["x-amz-id-2"], "kept");
+    }
+}
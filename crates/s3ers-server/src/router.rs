@@ -0,0 +1,580 @@
+use std::{fmt, future::Future, sync::Arc};
+
+use bytes::{Bytes, BytesMut};
+use futures_util::future::{BoxFuture, FutureExt};
+use http::{Extensions, Method, StatusCode};
+use s3ers_api::{
+    error::S3Error, uri, IncomingRequest, OutgoingResponse, Scope,
+};
+
+use crate::{
+    auth::{AccessKeyStore, SigV4Verifier, Verify},
+    checksum::check_request,
+    chunked::{decode_request, is_aws_chunked},
+    Addressing, ErrorResponse, RequestId,
+};
+
+/// A handler of requests to an endpoint, converting them.
+type BoxHandler = Arc<
+    dyn Fn(
+            http::Request<Bytes>,
+            Vec<String>,
+        )
+            -> BoxFuture<'static, Result<http::Response<Bytes>, S3Error>>
+        + Send
+        + Sync,
+>;
+
+/// A check of a request, besides its method and its path, telling apart the
+/// endpoints that share them, like `PutObject` and `CopyObject`.
+pub(crate) type Guard = fn(&http::Request<Bytes>) -> bool;
+
+/// Dispatches the requests received by a server to the handlers of their
+/// endpoints.
+///
+/// Requests are matched against the method and the path of the
+/// [`Metadata`](s3ers_api::Metadata) of the endpoints. The parameters of
+/// paths, like `:bucket`, match a whole path segment, greedy ones, like the
+/// `*key` of `/:bucket/*key`, match the rest of the path, slashes included,
+/// and their query, like `?uploads` or `?list-type=2`, must be in the query
+/// of requests. When
+/// several endpoints match a request, the one with the most query
+/// parameters handles it, or the one routed first.
+///
+/// Requests are path-style unless the router is given an [`Addressing`]
+/// with a base domain, in which case virtual-hosted-style requests are
+/// routed like the same requests in path-style.
+///
+/// Requests are authenticated by a [`SigV4Verifier`] if the router is given
+/// one, before they are dispatched, and their body is checked against their
+/// `Content-MD5` and `x-amz-checksum-*` headers.
+///
+/// Requests that can't be converted to the request type of their endpoint,
+/// and handlers that fail, get an S3 error response, with the [`RequestId`]
+/// in the extensions of the request if there is one.
+#[derive(Clone, Default)]
+pub struct Router {
+    routes: Vec<Route>,
+    addressing: Addressing,
+    verifier: Option<Arc<dyn Verify>>,
+}
+
+#[derive(Clone)]
+struct Route {
+    name: &'static str,
+    method: Method,
+    scope: Scope,
+    pattern: Pattern,
+    guard: Option<Guard>,
+    handler: BoxHandler,
+}
+
+impl Router {
+    /// Creates a router without routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how the bucket of requests is found.
+    pub fn with_addressing(mut self, addressing: Addressing) -> Self {
+        self.addressing = addressing;
+        self
+    }
+
+    /// Authenticates requests with `verifier` before dispatching them.
+    pub fn with_verifier<S: AccessKeyStore + 'static>(
+        mut self,
+        verifier: SigV4Verifier<S>,
+    ) -> Self {
+        self.verifier = Some(Arc::new(verifier));
+        self
+    }
+
+    /// Routes the requests to the endpoint of `R` to `handler`, which is
+    /// given the request and the extensions of the `http::Request`, like
+    /// the ones inserted by middlewares.
+    pub fn route<R, H, Fut>(self, handler: H) -> Self
+    where
+        R: IncomingRequest + Send + 'static,
+        H: Fn(R, Extensions) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R::OutgoingResponse, S3Error>>
+            + Send
+            + 'static,
+    {
+        self.route_guarded(None, handler)
+    }
+
+    /// Routes the requests to the endpoint of `R` that pass `guard` to
+    /// `handler`.
+    ///
+    /// Among the routes matching a request with as many query parameters,
+    /// the ones with a guard are preferred.
+    pub(crate) fn route_guarded<R, H, Fut>(
+        mut self,
+        guard: Option<Guard>,
+        handler: H,
+    ) -> Self
+    where
+        R: IncomingRequest + Send + 'static,
+        H: Fn(R, Extensions) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R::OutgoingResponse, S3Error>>
+            + Send
+            + 'static,
+    {
+        let handler = Arc::new(handler);
+        let handler: BoxHandler = Arc::new(move |request, path_args| {
+            let (mut parts, body) = request.into_parts();
+            let extensions = std::mem::take(&mut parts.extensions);
+            let request = http::Request::from_parts(parts, body);
+            let request = R::try_from_http_request(request, &path_args);
+            let handler = handler.clone();
+            async move {
+                let request = request.map_err(|err| {
+                    S3Error::new(StatusCode::BAD_REQUEST, "InvalidRequest")
+                        .with_message(err.to_string())
+                })?;
+                let response = handler(request, extensions)
+                    .await?
+                    .try_into_http_response::<BytesMut>()
+                    .map_err(|err| {
+                        S3Error::new(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            "InternalError",
+                        )
+                        .with_message(err.to_string())
+                    })?;
+                Ok(response.map(BytesMut::freeze))
+            }
+            .boxed()
+        });
+
+        self.routes.push(Route {
+            name: R::METADATA.name,
+            method: R::METADATA.method,
+            scope: R::METADATA.scope,
+            pattern: Pattern::parse(R::METADATA.path),
+            guard,
+            handler,
+        });
+        self
+    }
+
+    /// Handles a request, returning the response of its handler, or an
+    /// error response.
+    pub async fn handle(
+        &self,
+        request: http::Request<Bytes>,
+    ) -> http::Response<Bytes> {
+        let head = request.method() == Method::HEAD;
+        let ids = request.extensions().get::<RequestId>().cloned();
+        let mut response = match self.authenticate_and_dispatch(request).await {
+            Ok(response) => response,
+            Err(error) => ErrorResponse::from(error)
+                .with_ids(ids.as_ref())
+                .into_http_response(),
+        };
+        if head {
+            *response.body_mut() = Bytes::new();
+        }
+        response
+    }
+
+    async fn authenticate_and_dispatch(
+        &self,
+        mut request: http::Request<Bytes>,
+    ) -> Result<http::Response<Bytes>, S3Error> {
+        if let Some(verifier) = &self.verifier {
+            verifier.verify(&mut request).await?;
+        }
+        if is_aws_chunked(&request) {
+            decode_request(&mut request, None).map_err(|error| *error)?;
+        }
+        check_request(&request).map_err(|error| *error)?;
+        // Signatures are checked against the URI the request was sent to.
+        self.addressing.to_path_style(&mut request);
+        self.dispatch(request).await
+    }
+
+    async fn dispatch(
+        &self,
+        request: http::Request<Bytes>,
+    ) -> Result<http::Response<Bytes>, S3Error> {
+        let query = uri::parse_query(request.uri().query().unwrap_or_default());
+        let mut path_matched = None;
+        let mut matched: Option<(&Route, Vec<String>)> = None;
+        for route in &self.routes {
+            let path_args =
+                match route.pattern.matches(request.uri().path(), &query) {
+                    Some(path_args) => path_args,
+                    None => continue,
+                };
+            if route.guard.is_some_and(|guard| !guard(&request)) {
+                continue;
+            }
+            path_matched.get_or_insert(route.scope);
+            if route.method != request.method() {
+                continue;
+            }
+            let more_specific = matched.as_ref().is_none_or(|(best, _)| {
+                route.specificity() > best.specificity()
+            });
+            if more_specific {
+                matched = Some((route, path_args));
+            }
+        }
+
+        match matched {
+            Some((route, path_args)) => {
+                (route.handler)(request, path_args).await
+            }
+            None => match path_matched {
+                Some(scope) => Err(method_not_allowed(request.method(), scope)),
+                None => Err(S3Error::new(
+                    StatusCode::NOT_IMPLEMENTED,
+                    "NotImplemented",
+                )
+                .with_message("The request isn't supported by the server.")),
+            },
+        }
+    }
+}
+
+/// Returns the `MethodNotAllowed` error of a request to a resource that
+/// has no endpoint for its method, with the method and the type of the
+/// resource, like S3.
+fn method_not_allowed(method: &Method, scope: Scope) -> S3Error {
+    let resource_type = match scope {
+        Scope::Service => "SERVICE",
+        Scope::Bucket => "BUCKET",
+        Scope::Object => "OBJECT",
+    };
+    let mut error =
+        S3Error::new(StatusCode::METHOD_NOT_ALLOWED, "MethodNotAllowed")
+            .with_message(
+                "The specified method is not allowed against this resource.",
+            );
+    error
+        .details
+        .insert("Method".to_owned(), method.as_str().to_owned());
+    error
+        .details
+        .insert("ResourceType".to_owned(), resource_type.to_owned());
+    error
+}
+
+impl Route {
+    /// How specific the route is, to prefer it to less specific routes
+    /// matching the same requests.
+    fn specificity(&self) -> (usize, bool) {
+        (self.pattern.query.len(), self.guard.is_some())
+    }
+}
+
+impl fmt::Debug for Router {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Router")
+            .field(
+                "routes",
+                &self
+                    .routes
+                    .iter()
+                    .map(|route| route.name)
+                    .collect::<Vec<_>>(),
+            )
+            .field("addressing", &self.addressing)
+            .field("verifier", &self.verifier.is_some())
+            .finish()
+    }
+}
+
+/// The path of an endpoint, like `/:bucket/*key` or `/:bucket?uploads`.
+#[derive(Clone, Debug)]
+struct Pattern {
+    segments: Vec<Segment>,
+    query: Vec<(String, Option<String>)>,
+}
+
+#[derive(Clone, Debug)]
+enum Segment {
+    Literal(String),
+    Param,
+    /// A parameter matching the rest of the path, which ends the pattern.
+    Greedy,
+}
+
+impl Pattern {
+    fn parse(path: &str) -> Self {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        let segments = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| match segment.chars().next() {
+                Some(':') => Segment::Param,
+                Some('*') => Segment::Greedy,
+                _ => Segment::Literal(segment.to_owned()),
+            })
+            .collect::<Vec<_>>();
+        assert!(
+            segments
+                .iter()
+                .rev()
+                .skip(1)
+                .all(|segment| !matches!(segment, Segment::Greedy)),
+            "greedy parameters end the paths of endpoints, unlike in {}",
+            path
+        );
+        let query = query
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .map(|param| match param.split_once('=') {
+                Some((name, value)) => {
+                    (name.to_owned(), Some(value.to_owned()))
+                }
+                None => (param.to_owned(), None),
+            })
+            .collect();
+        Self { segments, query }
+    }
+
+    /// Returns the arguments of the path of a request if it matches the
+    /// pattern.
+    ///
+    /// A trailing slash is ignored, like the one of `/bucket/`, unless it
+    /// is matched by a greedy parameter, like the one of the key `photos/`.
+    fn matches(
+        &self,
+        path: &str,
+        query: &[(String, String)],
+    ) -> Option<Vec<String>> {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let greedy = matches!(self.segments.last(), Some(Segment::Greedy));
+        let mut segments = match path {
+            "" => Vec::new(),
+            path if greedy => {
+                // The last segment takes the rest of the path.
+                path.splitn(self.segments.len(), '/').collect::<Vec<_>>()
+            }
+            path => path.split('/').collect::<Vec<_>>(),
+        };
+        if segments.len() == self.segments.len() + 1
+            && segments.last() == Some(&"")
+        {
+            segments.pop();
+        }
+        if segments.len() != self.segments.len() {
+            return None;
+        }
+
+        let mut path_args = Vec::new();
+        for (segment, pattern) in segments.into_iter().zip(&self.segments) {
+            let segment = uri::decode(segment);
+            match pattern {
+                Segment::Literal(literal) if *literal == segment => {}
+                Segment::Param | Segment::Greedy if !segment.is_empty() => {
+                    path_args.push(segment)
+                }
+                _ => return None,
+            }
+        }
+
+        let query_matches = self.query.iter().all(|(name, value)| {
+            query.iter().any(|(param, param_value)| {
+                param == name
+                    && value.as_ref().is_none_or(|value| value == param_value)
+            })
+        });
+        query_matches.then_some(path_args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures_executor::block_on;
+    use http::{Method, StatusCode};
+    use s3ers_api::{
+        error::{FromHttpRequestError, IntoHttpError, S3Error},
+        AuthScheme, IncomingRequest, Metadata, OutgoingResponse, Scope,
+    };
+    use s3ers_s3_api::{bucket::head_bucket, object::head_object};
+
+    use super::Router;
+    use crate::Addressing;
+
+    /// The `ListMultipartUploads` endpoint, without its parameters.
+    struct ListUploads(String);
+
+    struct Uploads;
+
+    impl IncomingRequest for ListUploads {
+        const METADATA: Metadata = Metadata {
+            description: "Test endpoint",
+            method: Method::GET,
+            name: "ListUploads",
+            path: "/:bucket?uploads",
+            scope: Scope::Bucket,
+            authentication: AuthScheme::AwsSignatureV4,
+            requires_content_md5: false,
+            flexible_checksums: false,
+        };
+
+        type OutgoingResponse = Uploads;
+
+        fn try_from_http_request<T: AsRef<[u8]>>(
+            _request: http::Request<T>,
+            path_args: &[String],
+        ) -> Result<Self, FromHttpRequestError> {
+            Ok(Self(path_args[0].clone()))
+        }
+    }
+
+    impl OutgoingResponse for Uploads {
+        fn try_into_http_response<T: Default + bytes::BufMut>(
+            self,
+        ) -> Result<http::Response<T>, IntoHttpError> {
+            let mut body = T::default();
+            body.put_slice(b"<ListMultipartUploadsResult/>");
+            Ok(http::Response::new(body))
+        }
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route(|request: head_object::Request, _| async move {
+                if request.key != "my image.jpg" {
+                    return Err(S3Error::new(
+                        StatusCode::NOT_FOUND,
+                        "NoSuchKey",
+                    )
+                    .with_resource(request.key));
+                }
+                let mut response = head_object::Response::default();
+                response.content_length = Some(42);
+                Ok(response)
+            })
+            .route(|_: head_bucket::Request, _| async {
+                Ok(head_bucket::Response::default())
+            })
+            .route(|request: ListUploads, _| async move {
+                assert_eq!(request.0, "bucket");
+                Ok(Uploads)
+            })
+    }
+
+    fn send(
+        router: &Router,
+        method: Method,
+        uri: &str,
+    ) -> http::Response<Bytes> {
+        let request = http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Bytes::new())
+            .unwrap();
+        block_on(router.handle(request))
+    }
+
+    #[test]
+    fn route_requests() {
+        let router = router();
+
+        let response = send(&router, Method::HEAD, "/bucket/my%20image.jpg");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-length"], "42");
+
+        let response = send(&router, Method::HEAD, "/bucket/other.jpg");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.body().is_empty());
+
+        assert_eq!(
+            send(&router, Method::HEAD, "/bucket/").status(),
+            StatusCode::OK
+        );
+        let response = send(&router, Method::GET, "/bucket?uploads");
+        assert_eq!(response.body(), "<ListMultipartUploadsResult/>");
+
+        let response = send(&router, Method::DELETE, "/bucket");
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let body = std::str::from_utf8(response.body()).unwrap();
+        assert!(body.contains("<Method>DELETE</Method>"));
+        assert!(body.contains("<ResourceType>BUCKET</ResourceType>"));
+        let response = send(&router, Method::GET, "/");
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+        assert!(std::str::from_utf8(response.body())
+            .unwrap()
+            .contains("<Code>NotImplemented</Code>"));
+    }
+
+    #[test]
+    fn route_nested_keys() {
+        // Responds with the length of the key, to check it.
+        let router = Router::new().route(
+            |request: head_object::Request, _| async move {
+                let mut response = head_object::Response::default();
+                response.content_length = Some(request.key.len() as u64);
+                Ok(response)
+            },
+        );
+        let keys = [
+            (
+                "/bucket/photos/2006/my%20image.jpg",
+                "photos/2006/my image.jpg",
+            ),
+            ("/bucket/photos/", "photos/"),
+            ("/bucket/a//b", "a//b"),
+            ("/bucket/a%2Fb", "a/b"),
+        ];
+        for (uri, key) in keys {
+            let response = send(&router, Method::HEAD, uri);
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            assert_eq!(
+                response.headers()["content-length"],
+                key.len().to_string().as_str(),
+                "{}",
+                uri
+            );
+        }
+        let response = send(&router, Method::HEAD, "/bucket/");
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+    }
+
+    #[test]
+    fn check_request_digests() {
+        let router = router();
+        let request = |content_md5: &str| {
+            http::Request::get("/bucket?uploads")
+                .header("content-md5", content_md5)
+                .body(Bytes::from_static(b"hello"))
+                .unwrap()
+        };
+
+        let response =
+            block_on(router.handle(request("XUFAKrxLKna5cZ2REBfFkg==")));
+        assert_eq!(response.status(), StatusCode::OK);
+        let response =
+            block_on(router.handle(request("1B2M2Y8AsgTpgAmY7PhCfg==")));
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(std::str::from_utf8(response.body())
+            .unwrap()
+            .contains("<Code>BadDigest</Code>"));
+    }
+
+    #[test]
+    fn route_virtual_hosted_requests() {
+        let router = router()
+            .with_addressing(Addressing::with_base_domain("s3.example.com"));
+        let request = http::Request::get("/?uploads")
+            .header("host", "bucket.s3.example.com")
+            .body(Bytes::new())
+            .unwrap();
+        let response = block_on(router.handle(request));
+        assert_eq!(response.body(), "<ListMultipartUploadsResult/>");
+
+        let request = http::Request::head("/my%20image.jpg")
+            .header("host", "bucket.s3.example.com")
+            .body(Bytes::new())
+            .unwrap();
+        let response = block_on(router.handle(request));
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
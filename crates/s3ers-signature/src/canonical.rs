@@ -0,0 +1,237 @@
+use std::{collections::BTreeMap, fmt};
+
+use http::{header::AUTHORIZATION, HeaderMap, Method, Uri};
+use percent_encoding::percent_decode_str;
+
+use crate::{util::URI_ENCODE_SET, Error, PathEncoding, SigningSettings};
+
+/// The canonical form of a request, as defined by Signature Version 4.
+#[derive(Debug)]
+pub(crate) struct CanonicalRequest {
+    method: Method,
+    path: String,
+    query: String,
+    headers: BTreeMap<String, String>,
+    payload_hash: String,
+}
+
+impl CanonicalRequest {
+    pub(crate) fn new(
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        payload_hash: &str,
+    ) -> Result<Self, Error> {
+        Self::from_parts(
+            method,
+            uri.path(),
+            uri.query().unwrap_or(""),
+            headers,
+            payload_hash,
+        )
+    }
+
+    pub(crate) fn from_parts(
+        method: &Method,
+        path: &str,
+        query: &str,
+        headers: &HeaderMap,
+        payload_hash: &str,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            method: method.clone(),
+            path: s3_canonical_path(path),
+            query: canonical_query(query),
+            headers: canonical_headers(headers)?,
+            payload_hash: payload_hash.to_owned(),
+        })
+    }
+
+    /// Returns the lowercase names of the headers.
+    pub(crate) fn header_names(&self) -> impl Iterator<Item = &str> {
+        self.headers.keys().map(String::as_str)
+    }
+
+    /// Only keeps the given headers, returning the name of the first one
+    /// that is missing from the request, if any.
+    pub(crate) fn retain_headers<'a>(
+        &mut self,
+        names: &'a [String],
+    ) -> Result<(), &'a str> {
+        if let Some(missing) =
+            names.iter().find(|name| !self.headers.contains_key(*name))
+        {
+            return Err(missing);
+        }
+        self.headers.retain(|name, _| names.contains(name));
+        Ok(())
+    }
+
+    /// Applies the signing settings, which select the signed headers and
+    /// how the path is canonicalized.
+    pub(crate) fn apply_settings(&mut self, settings: &SigningSettings) {
+        let selection = settings.signed_headers;
+        self.headers.retain(|name, _| selection.includes(name));
+
+        if settings.path_encoding == PathEncoding::Generic {
+            self.path = generic_canonical_path(&self.path);
+        }
+    }
+
+    /// The `;`-separated list of the names of the signed headers.
+    pub(crate) fn signed_headers(&self) -> String {
+        self.headers
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+impl fmt::Display for CanonicalRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.method)?;
+        writeln!(f, "{}", self.path)?;
+        writeln!(f, "{}", self.query)?;
+        for (name, value) in &self.headers {
+            writeln!(f, "{}:{}", name, value)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{}", self.signed_headers())?;
+        write!(f, "{}", self.payload_hash)
+    }
+}
+
+/// Encodes every segment of a path exactly once, as S3 expects.
+///
+/// The path isn't normalized: empty, `.` and `..` segments are part of S3
+/// keys. Segments are decoded first, so that characters the URI already
+/// encodes aren't encoded twice, and characters it doesn't, like `$` or `+`,
+/// are.
+fn s3_canonical_path(path: &str) -> String {
+    if path.is_empty() {
+        return "/".to_owned();
+    }
+    path.split('/').map(reencode).collect::<Vec<_>>().join("/")
+}
+
+/// Normalizes a path canonicalized for S3, and encodes its segments a second
+/// time, as the services other than S3 expect.
+fn generic_canonical_path(path: &str) -> String {
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    let mut canonical = String::new();
+    for segment in &segments {
+        canonical.push('/');
+        canonical.extend(percent_encoding::utf8_percent_encode(
+            segment,
+            URI_ENCODE_SET,
+        ));
+    }
+    if canonical.is_empty() || path.ends_with('/') {
+        canonical.push('/');
+    }
+    canonical
+}
+
+/// Sorts the query parameters by name then value, and encodes them with the
+/// SigV4 rules regardless of how they were encoded in the URI.
+pub(crate) fn canonical_query(query: &str) -> String {
+    let mut params: Vec<(String, String)> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = match pair.find('=') {
+                Some(i) => (&pair[..i], &pair[i + 1..]),
+                None => (pair, ""),
+            };
+            (reencode(name), reencode(value))
+        })
+        .collect();
+    params.sort();
+
+    params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn reencode(component: &str) -> String {
+    let decoded = percent_decode_str(component).collect::<Vec<_>>();
+    percent_encoding::percent_encode(&decoded, URI_ENCODE_SET).to_string()
+}
+
+/// Lowercases and sorts header names, trims values and collapses their
+/// inner whitespace, and joins the values of repeated headers with commas
+/// in their order in the request.
+fn canonical_headers(
+    headers: &HeaderMap,
+) -> Result<BTreeMap<String, String>, Error> {
+    let mut canonical = BTreeMap::<String, String>::new();
+
+    for (name, value) in headers {
+        if name == AUTHORIZATION {
+            continue;
+        }
+
+        let value = std::str::from_utf8(value.as_bytes())
+            .map_err(|_| Error::InvalidHeaderValue(name.to_string()))?
+            .split_ascii_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let value = value.as_str();
+
+        canonical
+            .entry(name.as_str().to_owned())
+            .and_modify(|values| {
+                values.push(',');
+                values.push_str(value);
+            })
+            .or_insert_with(|| value.to_owned());
+    }
+
+    Ok(canonical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generic_canonical_path, s3_canonical_path};
+
+    #[test]
+    fn s3_paths() {
+        assert_eq!(s3_canonical_path(""), "/");
+        assert_eq!(s3_canonical_path("/"), "/");
+        assert_eq!(s3_canonical_path("/bucket/a//b/"), "/bucket/a//b/");
+        assert_eq!(s3_canonical_path("/bucket/./a/../b"), "/bucket/./a/../b");
+        assert_eq!(s3_canonical_path("/bucket/a+b"), "/bucket/a%2Bb");
+        assert_eq!(s3_canonical_path("/bucket/a$b"), "/bucket/a%24b");
+        assert_eq!(s3_canonical_path("/bucket/a%24b"), "/bucket/a%24b");
+        assert_eq!(s3_canonical_path("/bucket/a%2fb"), "/bucket/a%2Fb");
+        assert_eq!(
+            s3_canonical_path("/bucket/%E2%82%AC%20-_.~"),
+            "/bucket/%E2%82%AC%20-_.~"
+        );
+    }
+
+    #[test]
+    fn generic_paths() {
+        let canonical = |path| generic_canonical_path(&s3_canonical_path(path));
+
+        assert_eq!(canonical("/"), "/");
+        assert_eq!(canonical("/example/.."), "/");
+        assert_eq!(canonical("/./"), "/");
+        assert_eq!(canonical("/a/./b/../c//d"), "/a/c/d");
+        assert_eq!(canonical("/example/"), "/example/");
+        assert_eq!(canonical("/example%20space"), "/example%2520space");
+    }
+}
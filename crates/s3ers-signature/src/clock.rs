@@ -0,0 +1,127 @@
+//! Sources of the time requests are signed at.
+//!
+//! Servers reject signatures made too far from their own time, so a client
+//! whose clock drifted can keep working by correcting its time with the
+//! `Date` header of the `RequestTimeTooSkewed` error and signing again.
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+
+/// A source of the current time.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+/// The system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that is stopped at a given time, for reproducible signatures.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+/// A clock corrected by the difference between another clock and the
+/// server's.
+#[derive(Debug)]
+pub struct SkewCorrectedClock<C = SystemClock> {
+    inner: C,
+
+    /// The time of the server minus the time of the inner clock, in
+    /// milliseconds.
+    offset_ms: AtomicI64,
+}
+
+impl<C: Clock> SkewCorrectedClock<C> {
+    /// Creates a clock without any correction yet.
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            offset_ms: AtomicI64::new(0),
+        }
+    }
+
+    /// Corrects the clock given the time of the server, usually from the
+    /// `Date` header of a response.
+    ///
+    /// The precision of HTTP dates is one second, which is well within the
+    /// 15 minutes of skew servers allow.
+    pub fn correct(&self, server_time: SystemTime) {
+        let local = self.inner.now();
+        let offset_ms = match server_time.duration_since(local) {
+            Ok(ahead) => millis(ahead),
+            Err(err) => -millis(err.duration()),
+        };
+        self.offset_ms.store(offset_ms, Ordering::Relaxed);
+    }
+}
+
+impl Default for SkewCorrectedClock<SystemClock> {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl<C: Clock> Clock for SkewCorrectedClock<C> {
+    fn now(&self) -> SystemTime {
+        let now = self.inner.now();
+        let offset_ms = self.offset_ms.load(Ordering::Relaxed);
+        let offset = Duration::from_millis(offset_ms.unsigned_abs());
+        if offset_ms >= 0 {
+            now + offset
+        } else {
+            now - offset
+        }
+    }
+}
+
+fn millis(duration: Duration) -> i64 {
+    i64::try_from(duration.as_millis()).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{Clock, FixedClock, SkewCorrectedClock};
+
+    #[test]
+    fn correct_skew() {
+        let local = UNIX_EPOCH + Duration::from_secs(1_369_353_600);
+        let clock = SkewCorrectedClock::new(FixedClock(local));
+        assert_eq!(clock.now(), local);
+
+        let ahead = local + Duration::from_secs(20 * 60);
+        clock.correct(ahead);
+        assert_eq!(clock.now(), ahead);
+
+        let behind = local - Duration::from_secs(30 * 60);
+        clock.correct(behind);
+        assert_eq!(clock.now(), behind);
+    }
+}
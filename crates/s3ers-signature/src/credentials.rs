@@ -0,0 +1,69 @@
+use std::{fmt, time::SystemTime};
+
+/// AWS credentials used to sign requests.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    /// The access key ID.
+    pub access_key_id: String,
+
+    /// The secret access key.
+    pub secret_access_key: String,
+
+    /// The session token of temporary credentials, sent in the
+    /// `x-amz-security-token` header or query parameter.
+    pub session_token: Option<String>,
+
+    /// When temporary credentials stop being valid.
+    pub expiry: Option<SystemTime>,
+}
+
+impl Credentials {
+    /// Creates a new `Credentials` from an access key ID and a secret access
+    /// key.
+    pub fn new(
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+    ) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: None,
+            expiry: None,
+        }
+    }
+
+    /// Sets the session token of temporary credentials.
+    pub fn with_session_token(
+        mut self,
+        session_token: impl Into<String>,
+    ) -> Self {
+        self.session_token = Some(session_token.into());
+        self
+    }
+
+    /// Sets the time temporary credentials stop being valid at.
+    pub fn with_expiry(mut self, expiry: SystemTime) -> Self {
+        self.expiry = Some(expiry);
+        self
+    }
+
+    /// Whether the credentials are expired at the given time. Credentials
+    /// without an expiry never expire.
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        self.expiry.is_some_and(|expiry| expiry <= now)
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"** redacted **")
+            .field(
+                "session_token",
+                &self.session_token.as_ref().map(|_| "** redacted **"),
+            )
+            .field("expiry", &self.expiry)
+            .finish()
+    }
+}
@@ -0,0 +1,50 @@
+use std::{error::Error as StdError, fmt, time::Duration};
+
+/// An error that occurred while signing a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The request has neither a `Host` header nor an absolute URI.
+    MissingHost,
+
+    /// A header value can't be used in a signature because it isn't valid
+    /// UTF-8.
+    InvalidHeaderValue(String),
+
+    /// The expiration of a presigned request is zero or longer than
+    /// [`MAX_PRESIGNED_EXPIRATION`](crate::MAX_PRESIGNED_EXPIRATION).
+    InvalidExpiration(Duration),
+
+    /// Adding the signature to the URI made it invalid.
+    InvalidUri,
+
+    /// The signature version isn't supported, because the feature enabling
+    /// it, named here, isn't enabled.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHost => {
+                f.write_str("request has no Host header and no absolute URI")
+            }
+            Self::InvalidHeaderValue(name) => {
+                write!(f, "value of header `{}` is not valid UTF-8", name)
+            }
+            Self::InvalidExpiration(expiration) => write!(
+                f,
+                "invalid presigned request expiration: {}s",
+                expiration.as_secs()
+            ),
+            Self::InvalidUri => f.write_str("signed URI is invalid"),
+            Self::Unsupported(feature) => write!(
+                f,
+                "signature version not supported without the `{}` feature",
+                feature
+            ),
+        }
+    }
+}
+
+impl StdError for Error {}
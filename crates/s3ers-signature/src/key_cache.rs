@@ -0,0 +1,117 @@
+//! A cache of derived signing keys.
+//!
+//! Deriving a signing key takes four HMACs, but the key only changes with the
+//! date, region and service of the scope, so it can be reused by every
+//! request signed with the same credentials on the same day.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use crate::sign::{derive_signing_key, Scope};
+
+/// How many keys are kept, enough for a few credentials used with a few
+/// regions and services.
+const CAPACITY: usize = 64;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    secret_access_key: String,
+    date: String,
+    region: String,
+    service: String,
+}
+
+/// A bounded map of signing keys.
+#[derive(Default)]
+pub(crate) struct SigningKeyCache {
+    keys: Mutex<HashMap<CacheKey, Arc<[u8]>>>,
+}
+
+impl SigningKeyCache {
+    /// Returns the signing key for a scope, deriving it if it isn't cached.
+    pub(crate) fn get(
+        &self,
+        secret_access_key: &str,
+        scope: &Scope,
+    ) -> Arc<[u8]> {
+        let key = CacheKey {
+            secret_access_key: secret_access_key.to_owned(),
+            date: scope.date.clone(),
+            region: scope.region.clone(),
+            service: scope.service.clone(),
+        };
+
+        // A poisoned lock only means another thread panicked while holding
+        // it, and the map is still consistent.
+        let mut keys = self.keys.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(signing_key) = keys.get(&key) {
+            return signing_key.clone();
+        }
+
+        if keys.len() >= CAPACITY {
+            // Keys of past days won't be used anymore. If every key is
+            // current, start over rather than tracking their usage.
+            keys.retain(|cached, _| cached.date == scope.date);
+            if keys.len() >= CAPACITY {
+                keys.clear();
+            }
+        }
+
+        let signing_key: Arc<[u8]> =
+            derive_signing_key(secret_access_key, scope).into();
+        keys.insert(key, signing_key.clone());
+        signing_key
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.keys.lock().unwrap().len()
+    }
+}
+
+/// The cache shared by every signature of the process.
+pub(crate) fn shared() -> &'static SigningKeyCache {
+    static CACHE: OnceLock<SigningKeyCache> = OnceLock::new();
+    CACHE.get_or_init(SigningKeyCache::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SigningKeyCache, CAPACITY};
+    use crate::sign::{derive_signing_key, Scope};
+
+    fn scope(date: &str, region: &str) -> Scope {
+        Scope {
+            date: date.to_owned(),
+            region: region.to_owned(),
+            service: "s3".to_owned(),
+        }
+    }
+
+    #[test]
+    fn cached_key_matches_derived_key() {
+        let cache = SigningKeyCache::default();
+        let scope = scope("20130524", "us-east-1");
+
+        let first = cache.get("secret", &scope);
+        let second = cache.get("secret", &scope);
+
+        assert_eq!(&*first, &derive_signing_key("secret", &scope)[..]);
+        assert!(std::sync::Arc::ptr_eq(&first, &second));
+        assert_ne!(&*cache.get("other secret", &scope), &*first);
+    }
+
+    #[test]
+    fn evict_past_days() {
+        let cache = SigningKeyCache::default();
+        for i in 0..CAPACITY {
+            cache.get("secret", &scope("20130524", &format!("region-{}", i)));
+        }
+        assert_eq!(cache.len(), CAPACITY);
+
+        cache.get("secret", &scope("20130525", "us-east-1"));
+        assert_eq!(cache.len(), 1);
+    }
+}
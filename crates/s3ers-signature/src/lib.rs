@@ -0,0 +1,32 @@
+//! AWS request signing for s3ers.
+//!
+//! This crate implements AWS Signature Version 4 as used by S3 and
+//! S3-compatible servers. It works on [`http`] types only, so that both the
+//! s3ers client and servers built with s3ers can use it.
+
+#![warn(missing_docs)]
+
+mod canonical;
+pub mod chunked;
+pub mod clock;
+mod credentials;
+mod error;
+mod key_cache;
+pub mod md5;
+pub mod post_policy;
+mod presign;
+mod sign;
+mod util;
+#[cfg(feature = "sigv2")]
+pub mod v2;
+pub mod verify;
+
+pub use clock::{Clock, SystemClock};
+pub use credentials::Credentials;
+pub use error::Error;
+pub use presign::{presign_request, MAX_PRESIGNED_EXPIRATION};
+pub use sign::{
+    sign_request, sign_request_with_payload_hash, PathEncoding,
+    PayloadChecksumKind, PayloadSigning, SignedHeaders, SigningOutput,
+    SigningParams, SigningSettings, UNSIGNED_PAYLOAD,
+};
@@ -0,0 +1,186 @@
+//! MD5 digests, for the `Content-MD5` header some S3 endpoints require.
+//!
+//! MD5 isn't used for signing, only to detect corrupted bodies, so it is
+//! implemented here rather than pulled in as a dependency.
+
+use std::convert::TryInto;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+/// The per-round shift amounts.
+const SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20,
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4,
+    11, 16, 23, 4, 11, 16, 23, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6,
+    10, 15, 21,
+];
+
+/// The integer parts of the sines of the rounds, as in RFC 1321.
+const SINES: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a,
+    0xa8304613, 0xfd469501, 0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be,
+    0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821, 0xf61e2562, 0xc040b340,
+    0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8,
+    0x676f02d9, 0x8d2a4c8a, 0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c,
+    0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70, 0x289b7ec6, 0xeaa127fa,
+    0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92,
+    0xffeff47d, 0x85845dd1, 0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1,
+    0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// An MD5 digest being computed.
+#[derive(Clone, Debug)]
+pub struct Md5 {
+    state: [u32; 4],
+    buffer: [u8; 64],
+    buffered: usize,
+    length: u64,
+}
+
+impl Md5 {
+    /// Starts a digest.
+    pub fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            buffer: [0; 64],
+            buffered: 0,
+            length: 0,
+        }
+    }
+
+    /// Adds data to the digest.
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+
+        if self.buffered > 0 {
+            let n = data.len().min(64 - self.buffered);
+            self.buffer[self.buffered..self.buffered + n]
+                .copy_from_slice(&data[..n]);
+            self.buffered += n;
+            data = &data[n..];
+            if self.buffered < 64 {
+                return;
+            }
+            let block = self.buffer;
+            self.compress(&block);
+            self.buffered = 0;
+        }
+
+        let mut blocks = data.chunks_exact(64);
+        for block in &mut blocks {
+            self.compress(block.try_into().unwrap());
+        }
+        let rest = blocks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    /// Returns the digest of the data.
+    pub fn finalize(mut self) -> [u8; 16] {
+        let bit_length = self.length.wrapping_mul(8);
+        let padding = if self.buffered < 56 {
+            56 - self.buffered
+        } else {
+            120 - self.buffered
+        };
+        let mut trailer = vec![0; padding + 8];
+        trailer[0] = 0x80;
+        trailer[padding..].copy_from_slice(&bit_length.to_le_bytes());
+        self.update(&trailer);
+
+        let mut digest = [0; 16];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut words = [0u32; 16];
+        for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        let [mut a, mut b, mut c, mut d] = self.state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(SINES[i])
+                .wrapping_add(words[g])
+                .rotate_left(SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+impl Default for Md5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the value of the `Content-MD5` header of a body, its
+/// base64-encoded MD5 digest.
+pub fn content_md5(body: &[u8]) -> String {
+    let mut md5 = Md5::new();
+    md5.update(body);
+    STANDARD.encode(md5.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_md5, Md5};
+
+    fn hex_digest(data: &[u8]) -> String {
+        let mut md5 = Md5::new();
+        md5.update(data);
+        hex::encode(md5.finalize())
+    }
+
+    #[test]
+    fn digests() {
+        // The test suite of RFC 1321.
+        assert_eq!(hex_digest(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex_digest(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            hex_digest(b"message digest"),
+            "f96b697d7cb7938d525a2f31aaf161d0"
+        );
+        assert_eq!(
+            hex_digest(
+                b"12345678901234567890123456789012345678901234567890123456789\
+                  012345678901234567890"
+            ),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+
+        let mut md5 = Md5::new();
+        for chunk in b"abcdefghijklmnopqrstuvwxyz".chunks(5) {
+            md5.update(chunk);
+        }
+        assert_eq!(
+            hex::encode(md5.finalize()),
+            "c3fcd3d76192e4007dfb496cca67e13b"
+        );
+    }
+
+    #[test]
+    fn content_md5_header() {
+        assert_eq!(content_md5(b""), "1B2M2Y8AsgTpgAmY7PhCfg==");
+    }
+}
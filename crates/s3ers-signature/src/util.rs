@@ -0,0 +1,58 @@
+use std::time::SystemTime;
+
+use hmac::{Hmac, Mac};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC};
+use sha2::{Digest, Sha256};
+use time::{format_description::FormatItem, macros::format_description};
+
+/// The characters that must be percent-encoded in URI components, that is
+/// everything except the unreserved characters of RFC 3986.
+pub(crate) const URI_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+const AMZ_DATE: &[FormatItem<'static>] =
+    format_description!("[year][month][day]T[hour][minute][second]Z");
+
+const SCOPE_DATE: &[FormatItem<'static>] =
+    format_description!("[year][month][day]");
+
+pub(crate) fn sha256_hex(data: impl AsRef<[u8]>) -> String {
+    hex::encode(Sha256::digest(data.as_ref()))
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length.
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Formats a time as expected by the `x-amz-date` header.
+pub(crate) fn format_amz_date(time: SystemTime) -> String {
+    time::OffsetDateTime::from(time).format(AMZ_DATE).unwrap()
+}
+
+/// Formats the date part of a credential scope.
+pub(crate) fn format_scope_date(time: SystemTime) -> String {
+    time::OffsetDateTime::from(time).format(SCOPE_DATE).unwrap()
+}
+
+/// Parses a time in the format of the `x-amz-date` header.
+pub(crate) fn parse_amz_date(s: &str) -> Option<SystemTime> {
+    time::PrimitiveDateTime::parse(s, AMZ_DATE)
+        .ok()
+        .map(|datetime| datetime.assume_utc().into())
+}
+
+/// Compares two strings in constant time, to avoid leaking how much of a
+/// signature is correct.
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}